@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use starknet_api::core::{ContractAddress, Nonce};
+use starknet_types_core::felt::Felt;
 use thiserror::Error;
 
 use crate::blockifier::config::TransactionExecutorConfig;
@@ -151,4 +152,19 @@ impl<S: StateReader> StatefulValidator<S> {
             .expect(BLOCK_STATE_ACCESS_ERR)
             .get_nonce_at(account_address)?)
     }
+
+    /// Returns the balance of the given account in the given fee token, as the (low, high) words
+    /// of a 256-bit integer.
+    pub fn get_fee_token_balance(
+        &mut self,
+        account_address: ContractAddress,
+        fee_token_address: ContractAddress,
+    ) -> StatefulValidatorResult<(Felt, Felt)> {
+        Ok(self
+            .tx_executor
+            .block_state
+            .as_ref()
+            .expect(BLOCK_STATE_ACCESS_ERR)
+            .get_fee_token_balance(account_address, fee_token_address)?)
+    }
 }
@@ -4,6 +4,7 @@ use log::warn;
 
 use crate::block_committer::errors::BlockCommitmentError;
 use crate::block_committer::input::{Config, ConfigImpl, ContractAddress, Input, StateDiff};
+use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::forest::FilledForest;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, Nonce};
 use crate::patricia_merkle_tree::node_data::leaf::ContractState;
@@ -15,12 +16,33 @@ use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
 use crate::patricia_merkle_tree::updated_skeleton_tree::skeleton_forest::UpdatedSkeletonForest;
 use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::Storage;
 
 type BlockCommitmentResult<T> = Result<T, BlockCommitmentError>;
 
 pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<FilledForest> {
+    commit_block_with_storage(
+        &MapStorage::from(input.storage),
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+}
+
+/// Commits the state diff of a block on top of the forest with the given roots, whose nodes are
+/// read from the given storage. The new nodes are not written to the storage; see
+/// [FilledForest::write_to_storage].
+pub async fn commit_block_with_storage(
+    storage: &impl Storage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<FilledForest> {
     let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
-        get_all_modified_indices(&input.state_diff);
+        get_all_modified_indices(state_diff);
     let forest_sorted_indices = ForestSortedIndices {
         storage_tries_sorted_indices: storage_tries_indices
             .iter_mut()
@@ -29,33 +51,33 @@ pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<Fil
         contracts_trie_sorted_indices: SortedLeafIndices::new(&mut contracts_trie_indices),
         classes_trie_sorted_indices: SortedLeafIndices::new(&mut classes_trie_indices),
     };
-    let actual_storage_updates = input.state_diff.actual_storage_updates();
-    let actual_classes_updates = input.state_diff.actual_classes_updates();
+    let actual_storage_updates = state_diff.actual_storage_updates();
+    let actual_classes_updates = state_diff.actual_classes_updates();
     let (mut original_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
-        MapStorage::from(input.storage),
-        input.contracts_trie_root_hash,
-        input.classes_trie_root_hash,
+        storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
         &actual_storage_updates,
         &actual_classes_updates,
         &forest_sorted_indices,
-        &input.config,
+        config,
     )?;
 
-    if input.config.warn_on_trivial_modifications() {
+    if config.warn_on_trivial_modifications() {
         check_trivial_nonce_and_class_hash_updates(
             &original_contracts_trie_leaves,
-            &input.state_diff.address_to_class_hash,
-            &input.state_diff.address_to_nonce,
+            &state_diff.address_to_class_hash,
+            &state_diff.address_to_nonce,
         );
     }
 
     let updated_forest = UpdatedSkeletonForest::create(
         &mut original_forest,
-        &input.state_diff.skeleton_classes_updates(),
-        &input.state_diff.skeleton_storage_updates(),
+        &state_diff.skeleton_classes_updates(),
+        &state_diff.skeleton_storage_updates(),
         &original_contracts_trie_leaves,
-        &input.state_diff.address_to_class_hash,
-        &input.state_diff.address_to_nonce,
+        &state_diff.address_to_class_hash,
+        &state_diff.address_to_nonce,
     )?;
 
     Ok(FilledForest::create::<TreeHashFunctionImpl>(
@@ -63,8 +85,8 @@ pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<Fil
         actual_storage_updates,
         actual_classes_updates,
         &original_contracts_trie_leaves,
-        &input.state_diff.address_to_class_hash,
-        &input.state_diff.address_to_nonce,
+        &state_diff.address_to_class_hash,
+        &state_diff.address_to_nonce,
     )
     .await?)
 }
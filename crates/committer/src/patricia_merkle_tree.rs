@@ -2,6 +2,7 @@ pub mod errors;
 pub mod filled_tree;
 pub mod node_data;
 pub mod original_skeleton_tree;
+pub mod proof;
 pub mod types;
 pub mod updated_skeleton_tree;
 
@@ -19,6 +19,7 @@ use crate::patricia_merkle_tree::types::NodeIndex;
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::ForestHashFunction;
 use crate::patricia_merkle_tree::updated_skeleton_tree::skeleton_forest::UpdatedSkeletonForest;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
+use crate::storage::errors::StorageResult;
 use crate::storage::storage_trait::Storage;
 
 pub struct FilledForest {
@@ -28,7 +29,9 @@ pub struct FilledForest {
 }
 
 impl FilledForest {
-    pub fn write_to_storage(&self, storage: &mut impl Storage) {
+    /// Writes the new nodes of all the trees of the forest to the storage, in a single atomic
+    /// write.
+    pub fn write_to_storage(&self, storage: &mut impl Storage) -> StorageResult<()> {
         // Serialize all trees to one hash map.
         let new_db_objects = self
             .storage_tries
@@ -39,7 +42,7 @@ impl FilledForest {
             .collect();
 
         // Store the new hash map
-        storage.mset(new_db_objects);
+        storage.mset(new_db_objects)
     }
 
     pub fn get_contract_root_hash(&self) -> HashOutput {
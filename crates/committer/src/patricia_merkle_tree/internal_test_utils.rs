@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use ethnum::U256;
 use rand::rngs::ThreadRng;
 use rstest::{fixture, rstest};
 
+use crate::block_committer::commit::commit_block;
+use crate::block_committer::input::{ConfigImpl, Input, StateDiff};
 use crate::felt::Felt;
 use crate::generate_trie_config;
 use crate::hash::hash_trait::HashOutput;
@@ -25,6 +28,7 @@ use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
 use crate::storage::db_object::{DBObject, Deserializable};
+use crate::storage::map_storage::MapStorage;
 use crate::storage::storage_trait::StorageValue;
 
 #[derive(Debug, PartialEq, Clone, Copy, Default, Eq)]
@@ -169,3 +173,22 @@ pub(crate) fn as_fully_indexed(
         .map(|index| small_tree_index_to_full(index, SubTreeHeight::new(subtree_height)))
         .collect()
 }
+
+/// Commits the state diff on top of an empty forest. Returns the storage holding the new forest,
+/// and the root hashes of its contracts trie and classes trie.
+pub(crate) async fn commit_to_empty_forest(
+    state_diff: StateDiff,
+) -> (MapStorage, HashOutput, HashOutput) {
+    let forest = commit_block(Input {
+        storage: HashMap::new(),
+        state_diff,
+        contracts_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        classes_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        config: ConfigImpl::new(false, log::LevelFilter::Debug),
+    })
+    .await
+    .expect("Failed to commit the state diff.");
+    let mut storage = MapStorage::default();
+    forest.write_to_storage(&mut storage).unwrap();
+    (storage, forest.get_contract_root_hash(), forest.get_compiled_class_root_hash())
+}
@@ -287,7 +287,7 @@ fn test_create_original_skeleton_forest(
         classes_trie_sorted_indices: SortedLeafIndices::new(&mut classes_trie_indices),
     };
     let (actual_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
-        MapStorage::from(input.storage),
+        &MapStorage::from(input.storage),
         input.contracts_trie_root_hash,
         input.classes_trie_root_hash,
         &input.state_diff.actual_storage_updates(),
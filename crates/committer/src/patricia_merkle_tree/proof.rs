@@ -0,0 +1,4 @@
+pub mod errors;
+pub mod fetch;
+pub mod types;
+pub mod verify;
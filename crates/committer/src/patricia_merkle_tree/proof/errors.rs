@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::errors::{DeserializationError, StorageError};
+
+#[derive(Debug, Error)]
+pub enum ProofError {
+    #[error("Failed to deserialize the storage value: {0:?} while fetching a proof.")]
+    Deserialization(#[from] DeserializationError),
+    #[error("Unable to read from storage the storage key: {0:?} while fetching a proof.")]
+    StorageRead(#[from] StorageError),
+    #[error("The proof is missing the node with hash {0:?}.")]
+    MissingNode(HashOutput),
+    #[error("The hash of the proof node {0:?} does not match the node.")]
+    NodeHashMismatch(HashOutput),
+    #[error("The leaf at index {0:?} does not match the proof.")]
+    LeafMismatch(NodeIndex),
+}
+
+pub type ProofResult<T> = Result<T, ProofError>;
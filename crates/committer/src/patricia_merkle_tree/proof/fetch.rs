@@ -0,0 +1,149 @@
+use crate::block_committer::input::{ContractAddress, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, FilledNode};
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::proof::errors::ProofResult;
+use crate::patricia_merkle_tree::proof::types::{
+    ContractStorageKeys,
+    ContractsProof,
+    MerkleNode,
+    MerkleProof,
+    StorageProof,
+};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::errors::StorageError;
+use crate::storage::storage_trait::{create_db_key, StarknetPrefix, Storage};
+
+#[cfg(test)]
+#[path = "fetch_test.rs"]
+pub mod fetch_test;
+
+/// Fetches from storage the proofs of the given classes, contracts and storage entries, against
+/// the given roots of the contracts trie and the classes trie. The storage tries are proven
+/// against the storage roots of their contracts, whose leaves are proven in the contracts proof.
+pub fn fetch_storage_proof(
+    storage: &impl Storage,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    class_hashes: &[ClassHash],
+    contract_addresses: &[ContractAddress],
+    contracts_storage_keys: &[ContractStorageKeys],
+) -> ProofResult<StorageProof> {
+    let class_indices: Vec<NodeIndex> =
+        class_hashes.iter().map(NodeIndex::from_class_hash).collect();
+    let (classes_proof, _) =
+        fetch_tree_proof::<CompiledClassHash>(storage, classes_trie_root_hash, &class_indices)?;
+
+    // The contracts of the storage entries are proven as well, to prove their storage roots.
+    let contract_indices: Vec<NodeIndex> = contract_addresses
+        .iter()
+        .chain(contracts_storage_keys.iter().map(|keys| &keys.contract_address))
+        .map(NodeIndex::from_contract_address)
+        .collect();
+    let (contracts_nodes, mut contract_leaves) =
+        fetch_tree_proof::<ContractState>(storage, contracts_trie_root_hash, &contract_indices)?;
+    let storage_tries_leaves = contract_leaves.split_off(contract_addresses.len());
+
+    let contracts_storage_proofs = contracts_storage_keys
+        .iter()
+        .zip(storage_tries_leaves)
+        .map(|(keys, contract_leaf)| {
+            let storage_indices: Vec<NodeIndex> =
+                keys.storage_keys.iter().map(NodeIndex::from_starknet_storage_key).collect();
+            let (storage_proof, _) = fetch_tree_proof::<StarknetStorageValue>(
+                storage,
+                contract_leaf.storage_root_hash,
+                &storage_indices,
+            )?;
+            Ok(storage_proof)
+        })
+        .collect::<ProofResult<Vec<MerkleProof>>>()?;
+
+    Ok(StorageProof {
+        classes_proof,
+        contracts_proof: ContractsProof {
+            nodes: contracts_nodes,
+            contract_leaves_data: contract_leaves,
+        },
+        contracts_storage_proofs,
+    })
+}
+
+/// Fetches the inner nodes on the paths from the root to the given leaves, and the leaves
+/// themselves, in the order of the given indices. A path stops early at an edge which leads away
+/// from its leaf, in which case the leaf is empty.
+pub(crate) fn fetch_tree_proof<L: Leaf>(
+    storage: &impl Storage,
+    root_hash: HashOutput,
+    leaf_indices: &[NodeIndex],
+) -> ProofResult<(MerkleProof, Vec<L>)> {
+    let mut proof = MerkleProof::default();
+    let mut leaves = Vec::with_capacity(leaf_indices.len());
+    for leaf_index in leaf_indices {
+        let leaf = fetch_path(storage, root_hash, leaf_index, &mut proof)?;
+        leaves.push(leaf);
+    }
+    Ok((proof, leaves))
+}
+
+/// Adds the inner nodes on the path to the leaf to the proof, and returns the leaf.
+fn fetch_path<L: Leaf>(
+    storage: &impl Storage,
+    root_hash: HashOutput,
+    leaf_index: &NodeIndex,
+    proof: &mut MerkleProof,
+) -> ProofResult<L> {
+    if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+        return Ok(L::default());
+    }
+    let mut index = NodeIndex::ROOT;
+    let mut hash = root_hash;
+    while index != *leaf_index {
+        // Paths to different leaves share their top nodes, which are fetched only once.
+        let node = match proof.nodes.get(&hash) {
+            Some(node) => node.clone(),
+            None => {
+                let node = match fetch_node::<L>(storage, hash, false)? {
+                    NodeData::Binary(binary_data) => MerkleNode::Binary(binary_data),
+                    NodeData::Edge(edge_data) => MerkleNode::Edge(edge_data),
+                    NodeData::Leaf(_) => unreachable!("Inner nodes are deserialized as such."),
+                };
+                proof.nodes.insert(hash, node.clone());
+                node
+            }
+        };
+        (index, hash) = match node {
+            MerkleNode::Binary(BinaryData { left_hash, right_hash }) => {
+                let [left_index, right_index] = index.get_children_indices();
+                if left_index.is_ancestor_of(leaf_index) {
+                    (left_index, left_hash)
+                } else {
+                    (right_index, right_hash)
+                }
+            }
+            MerkleNode::Edge(EdgeData { bottom_hash, path_to_bottom }) => {
+                let bottom_index = path_to_bottom.bottom_index(index);
+                if !bottom_index.is_ancestor_of(leaf_index) {
+                    return Ok(L::default());
+                }
+                (bottom_index, bottom_hash)
+            }
+        };
+    }
+    match fetch_node::<L>(storage, hash, true)? {
+        NodeData::Leaf(leaf) => Ok(leaf),
+        _ => unreachable!("Leaves are deserialized as such."),
+    }
+}
+
+fn fetch_node<L: Leaf>(
+    storage: &impl Storage,
+    hash: HashOutput,
+    is_leaf: bool,
+) -> ProofResult<NodeData<L>> {
+    let prefix = if is_leaf { L::prefix() } else { StarknetPrefix::InnerNode.to_storage_prefix() };
+    let db_key = create_db_key(prefix, &hash.0.to_bytes_be());
+    let value = storage.get(&db_key)?.ok_or(StorageError::MissingKey(db_key))?;
+    Ok(FilledNode::<L>::deserialize(hash, &value, is_leaf)?.data)
+}
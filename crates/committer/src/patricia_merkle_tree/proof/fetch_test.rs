@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use crate::block_committer::input::{
+    ContractAddress,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff,
+};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::commit_to_empty_forest;
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::proof::fetch::fetch_storage_proof;
+use crate::patricia_merkle_tree::proof::types::{
+    ContractStorageKeys,
+    ContractsProof,
+    MerkleNode,
+    StorageProof,
+};
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction,
+    TreeHashFunctionImpl,
+};
+use crate::storage::map_storage::MapStorage;
+
+fn address(value: u128) -> ContractAddress {
+    ContractAddress(Felt::from(value))
+}
+
+fn storage_keys(contract_address: u128, keys: &[u128]) -> ContractStorageKeys {
+    ContractStorageKeys {
+        contract_address: address(contract_address),
+        storage_keys: keys.iter().map(|key| StarknetStorageKey(Felt::from(*key))).collect(),
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_storage_proof() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        commit_to_empty_forest(StateDiff {
+            address_to_class_hash: HashMap::from([
+                (address(1), ClassHash(Felt::from(10_u128))),
+                (address(2), ClassHash(Felt::from(10_u128))),
+                (address(6), ClassHash(Felt::from(11_u128))),
+            ]),
+            address_to_nonce: HashMap::from([(address(1), Nonce(Felt::ONE))]),
+            class_hash_to_compiled_class_hash: HashMap::from([
+                (ClassHash(Felt::from(10_u128)), CompiledClassHash(Felt::from(20_u128))),
+                (ClassHash(Felt::from(11_u128)), CompiledClassHash(Felt::from(21_u128))),
+            ]),
+            storage_updates: HashMap::from([(
+                address(1),
+                HashMap::from([
+                    (StarknetStorageKey(Felt::TWO), StarknetStorageValue(Felt::from(30_u128))),
+                    (StarknetStorageKey(Felt::THREE), StarknetStorageValue(Felt::from(31_u128))),
+                ]),
+            )]),
+        })
+        .await;
+
+    let proof = fetch_storage_proof(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[ClassHash(Felt::from(10_u128)), ClassHash(Felt::from(12_u128))],
+        // Contract 3 is not deployed.
+        &[address(1), address(3)],
+        &[storage_keys(1, &[3, 1]), storage_keys(3, &[1])],
+    )
+    .unwrap();
+
+    let contract_leaves_data = &proof.contracts_proof.contract_leaves_data;
+    assert_eq!(contract_leaves_data.len(), 2);
+    assert_eq!(contract_leaves_data[0].nonce, Nonce(Felt::ONE));
+    assert_eq!(contract_leaves_data[0].class_hash, ClassHash(Felt::from(10_u128)));
+    assert_ne!(contract_leaves_data[0].storage_root_hash, HashOutput::ROOT_OF_EMPTY_TREE);
+    assert_eq!(contract_leaves_data[1], ContractState::default());
+
+    // The proofs start at the roots of their tries.
+    assert!(proof.classes_proof.nodes.contains_key(&classes_trie_root_hash));
+    assert!(proof.contracts_proof.nodes.nodes.contains_key(&contracts_trie_root_hash));
+    assert_eq!(proof.contracts_storage_proofs.len(), 2);
+    assert!(
+        proof.contracts_storage_proofs[0]
+            .nodes
+            .contains_key(&contract_leaves_data[0].storage_root_hash)
+    );
+    // The storage trie of a contract which is not deployed is empty.
+    assert!(proof.contracts_storage_proofs[1].nodes.is_empty());
+    // Only inner nodes are part of a proof. The two stored values are the children of a single
+    // binary node, which is reached from the root through an edge. The edge leads away from the
+    // missing key.
+    assert_eq!(proof.contracts_storage_proofs[0].nodes.len(), 2);
+}
+
+#[tokio::test]
+async fn test_fetch_storage_proof_of_empty_forest() {
+    let proof = fetch_storage_proof(
+        &MapStorage::default(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &[ClassHash(Felt::ONE)],
+        &[address(1)],
+        &[storage_keys(1, &[1])],
+    )
+    .unwrap();
+
+    assert_eq!(
+        proof,
+        StorageProof {
+            contracts_storage_proofs: vec![Default::default()],
+            contracts_proof: ContractsProof {
+                nodes: Default::default(),
+                contract_leaves_data: vec![ContractState::default()],
+            },
+            ..Default::default()
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_storage_proof_serialization() {
+    let class_hash = ClassHash(Felt::from(10_u128));
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        commit_to_empty_forest(StateDiff {
+            address_to_class_hash: HashMap::from([(address(1), class_hash)]),
+            ..Default::default()
+        })
+        .await;
+
+    let proof = fetch_storage_proof(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[],
+        &[address(1)],
+        &[],
+    )
+    .unwrap();
+
+    // The only contract is reached from the root through a single edge.
+    let contract_state = ContractState {
+        nonce: Nonce::default(),
+        storage_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        class_hash,
+    };
+    let leaf_hash = <TreeHashFunctionImpl as TreeHashFunction<ContractState>>::compute_leaf_hash(
+        &contract_state,
+    );
+    let Some(MerkleNode::Edge(root)) =
+        proof.contracts_proof.nodes.nodes.get(&contracts_trie_root_hash)
+    else {
+        panic!("Expected the root to be an edge node, got {:?}", proof.contracts_proof.nodes);
+    };
+    assert_eq!(root.bottom_hash, leaf_hash);
+
+    assert_eq!(
+        serde_json::to_value(&proof).unwrap(),
+        json!({
+            "classes_proof": [],
+            "contracts_proof": {
+                "nodes": [{
+                    "node_hash": contracts_trie_root_hash.0,
+                    "node": {"path": "0x1", "length": 251, "child": leaf_hash.0},
+                }],
+                "contract_leaves_data": [{
+                    "nonce": "0x0",
+                    "class_hash": "0xa",
+                    "storage_root": "0x0",
+                }],
+            },
+            "contracts_storage_proofs": [],
+        })
+    );
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Serializer};
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+
+/// An inner node on the path from the root of a tree to a leaf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MerkleNode {
+    Binary(BinaryData),
+    Edge(EdgeData),
+}
+
+impl<L: Leaf> From<&MerkleNode> for NodeData<L> {
+    fn from(node: &MerkleNode) -> Self {
+        match node {
+            MerkleNode::Binary(binary_data) => NodeData::Binary(binary_data.clone()),
+            MerkleNode::Edge(edge_data) => NodeData::Edge(*edge_data),
+        }
+    }
+}
+
+/// The inner nodes on the paths from the root of a tree to a set of its leaves, by their hashes.
+/// A path which ends at an edge leading away from the leaf proves that the leaf is empty.
+/// Serialized as the `NODE_HASH_TO_NODE_MAPPING` of the `starknet_getStorageProof` RPC method.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub nodes: HashMap<HashOutput, MerkleNode>,
+}
+
+/// The proof of a set of contracts in the contracts trie, along with their leaves.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ContractsProof {
+    pub nodes: MerkleProof,
+    /// The leaves of the requested contracts, in the order of the request. The leaf of a contract
+    /// which is not deployed is empty.
+    #[serde(serialize_with = "serialize_contract_leaves")]
+    pub contract_leaves_data: Vec<ContractState>,
+}
+
+/// The proofs of classes, contracts and storage entries against the roots of the forest, in the
+/// format of the result of the `starknet_getStorageProof` RPC method (without its global roots).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StorageProof {
+    pub classes_proof: MerkleProof,
+    pub contracts_proof: ContractsProof,
+    /// The proofs of the storage tries of the requested contracts, in the order of the request.
+    pub contracts_storage_proofs: Vec<MerkleProof>,
+}
+
+/// The storage keys of a contract to prove.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractStorageKeys {
+    pub contract_address: ContractAddress,
+    pub storage_keys: Vec<StarknetStorageKey>,
+}
+
+#[derive(Serialize)]
+struct NodeHashToNode {
+    node_hash: Felt,
+    node: SerializedMerkleNode,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SerializedMerkleNode {
+    Binary { left: Felt, right: Felt },
+    Edge { path: Felt, length: u8, child: Felt },
+}
+
+#[derive(Serialize)]
+struct SerializedContractLeaf {
+    nonce: Felt,
+    class_hash: Felt,
+    storage_root: Felt,
+}
+
+impl Serialize for MerkleProof {
+    /// Serializes the nodes as a list, ordered by their hashes.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut nodes: Vec<NodeHashToNode> = self
+            .nodes
+            .iter()
+            .map(|(node_hash, node)| NodeHashToNode {
+                node_hash: node_hash.0,
+                node: match node {
+                    MerkleNode::Binary(BinaryData { left_hash, right_hash }) => {
+                        SerializedMerkleNode::Binary { left: left_hash.0, right: right_hash.0 }
+                    }
+                    MerkleNode::Edge(EdgeData { bottom_hash, path_to_bottom }) => {
+                        SerializedMerkleNode::Edge {
+                            path: Felt::from(&path_to_bottom.path),
+                            length: path_to_bottom.length.into(),
+                            child: bottom_hash.0,
+                        }
+                    }
+                },
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.node_hash);
+        nodes.serialize(serializer)
+    }
+}
+
+fn serialize_contract_leaves<S>(leaves: &[ContractState], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    leaves
+        .iter()
+        .map(|leaf| SerializedContractLeaf {
+            nonce: leaf.nonce.0,
+            class_hash: leaf.class_hash.0,
+            storage_root: leaf.storage_root_hash.0,
+        })
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
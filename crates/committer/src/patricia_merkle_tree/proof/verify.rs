@@ -0,0 +1,120 @@
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash};
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::proof::errors::{ProofError, ProofResult};
+use crate::patricia_merkle_tree::proof::types::{MerkleNode, MerkleProof};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction,
+    TreeHashFunctionImpl,
+};
+
+#[cfg(test)]
+#[path = "verify_test.rs"]
+pub mod verify_test;
+
+/// Verifies that the contract has the given leaf in the contracts trie with the given root. An
+/// empty leaf is verified to be missing from the trie.
+pub fn verify_contract_proof(
+    contracts_trie_root_hash: HashOutput,
+    proof: &MerkleProof,
+    contract_address: &ContractAddress,
+    contract_state: &ContractState,
+) -> ProofResult<()> {
+    verify_leaf::<ContractState>(
+        contracts_trie_root_hash,
+        proof,
+        &NodeIndex::from_contract_address(contract_address),
+        contract_state,
+    )
+}
+
+/// Verifies that the class has the given compiled class hash in the classes trie with the given
+/// root. An empty compiled class hash is verified to be missing from the trie.
+pub fn verify_class_proof(
+    classes_trie_root_hash: HashOutput,
+    proof: &MerkleProof,
+    class_hash: &ClassHash,
+    compiled_class_hash: &CompiledClassHash,
+) -> ProofResult<()> {
+    verify_leaf::<CompiledClassHash>(
+        classes_trie_root_hash,
+        proof,
+        &NodeIndex::from_class_hash(class_hash),
+        compiled_class_hash,
+    )
+}
+
+/// Verifies that the storage key has the given value in the storage trie with the given root. A
+/// zero value is verified to be missing from the trie.
+pub fn verify_storage_proof(
+    storage_root_hash: HashOutput,
+    proof: &MerkleProof,
+    storage_key: &StarknetStorageKey,
+    storage_value: &StarknetStorageValue,
+) -> ProofResult<()> {
+    verify_leaf::<StarknetStorageValue>(
+        storage_root_hash,
+        proof,
+        &NodeIndex::from_starknet_storage_key(storage_key),
+        storage_value,
+    )
+}
+
+/// Follows the path from the root to the leaf through the nodes of the proof, checking the hash of
+/// each node along the way.
+fn verify_leaf<L: Leaf>(
+    root_hash: HashOutput,
+    proof: &MerkleProof,
+    leaf_index: &NodeIndex,
+    leaf: &L,
+) -> ProofResult<()>
+where
+    TreeHashFunctionImpl: TreeHashFunction<L>,
+{
+    let verify_empty = || match leaf.is_empty() {
+        true => Ok(()),
+        false => Err(ProofError::LeafMismatch(*leaf_index)),
+    };
+    if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+        return verify_empty();
+    }
+
+    let mut index = NodeIndex::ROOT;
+    let mut hash = root_hash;
+    while index != *leaf_index {
+        let node = proof.nodes.get(&hash).ok_or(ProofError::MissingNode(hash))?;
+        let node_data = NodeData::<L>::from(node);
+        if <TreeHashFunctionImpl as TreeHashFunction<L>>::compute_node_hash(&node_data) != hash {
+            return Err(ProofError::NodeHashMismatch(hash));
+        }
+        (index, hash) = match node {
+            MerkleNode::Binary(BinaryData { left_hash, right_hash }) => {
+                let [left_index, right_index] = index.get_children_indices();
+                if left_index.is_ancestor_of(leaf_index) {
+                    (left_index, *left_hash)
+                } else {
+                    (right_index, *right_hash)
+                }
+            }
+            MerkleNode::Edge(EdgeData { bottom_hash, path_to_bottom }) => {
+                let bottom_index = path_to_bottom.bottom_index(index);
+                // An edge which leads away from the leaf proves that the leaf is empty.
+                if !bottom_index.is_ancestor_of(leaf_index) {
+                    return verify_empty();
+                }
+                (bottom_index, *bottom_hash)
+            }
+        };
+    }
+
+    // Empty leaves are not part of the tree, so reaching the leaf proves that it is not empty.
+    if leaf.is_empty()
+        || <TreeHashFunctionImpl as TreeHashFunction<L>>::compute_leaf_hash(leaf) != hash
+    {
+        return Err(ProofError::LeafMismatch(*leaf_index));
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::input::{
+    ContractAddress,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff,
+};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::commit_to_empty_forest;
+use crate::patricia_merkle_tree::node_data::inner_node::BinaryData;
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::proof::errors::ProofError;
+use crate::patricia_merkle_tree::proof::fetch::fetch_storage_proof;
+use crate::patricia_merkle_tree::proof::types::{ContractStorageKeys, MerkleNode, StorageProof};
+use crate::patricia_merkle_tree::proof::verify::{
+    verify_class_proof,
+    verify_contract_proof,
+    verify_storage_proof,
+};
+
+const CONTRACT_ADDRESS: ContractAddress = ContractAddress(Felt::ONE);
+const CLASS_HASH: ClassHash = ClassHash(Felt::THREE);
+
+/// Commits a forest with a single contract, and fetches the proof of the given class hash,
+/// contract address and storage key. Returns the proof and the roots of the contracts trie and
+/// the classes trie.
+async fn fetch_proof(
+    class_hash: ClassHash,
+    contract_address: ContractAddress,
+    storage_key: StarknetStorageKey,
+) -> (StorageProof, HashOutput, HashOutput) {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        commit_to_empty_forest(StateDiff {
+            address_to_class_hash: HashMap::from([(CONTRACT_ADDRESS, CLASS_HASH)]),
+            address_to_nonce: HashMap::from([(CONTRACT_ADDRESS, Nonce(Felt::TWO))]),
+            class_hash_to_compiled_class_hash: HashMap::from([
+                (CLASS_HASH, CompiledClassHash(Felt::from(4_u128))),
+                (ClassHash(Felt::from(5_u128)), CompiledClassHash(Felt::from(6_u128))),
+            ]),
+            storage_updates: HashMap::from([(
+                CONTRACT_ADDRESS,
+                HashMap::from([
+                    (StarknetStorageKey(Felt::ONE), StarknetStorageValue(Felt::from(7_u128))),
+                    (StarknetStorageKey(Felt::from(8_u128)), StarknetStorageValue(Felt::ONE)),
+                ]),
+            )]),
+        })
+        .await;
+    let proof = fetch_storage_proof(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[class_hash],
+        &[contract_address],
+        &[ContractStorageKeys { contract_address, storage_keys: vec![storage_key] }],
+    )
+    .unwrap();
+    (proof, contracts_trie_root_hash, classes_trie_root_hash)
+}
+
+#[rstest]
+#[case::members(
+    CLASS_HASH,
+    CompiledClassHash(Felt::from(4_u128)),
+    StarknetStorageKey(Felt::ONE),
+    StarknetStorageValue(Felt::from(7_u128))
+)]
+#[case::non_members(
+    ClassHash(Felt::from(9_u128)),
+    CompiledClassHash::default(),
+    StarknetStorageKey(Felt::TWO),
+    StarknetStorageValue::default()
+)]
+#[tokio::test]
+async fn test_verify_fetched_proof(
+    #[case] class_hash: ClassHash,
+    #[case] compiled_class_hash: CompiledClassHash,
+    #[case] storage_key: StarknetStorageKey,
+    #[case] storage_value: StarknetStorageValue,
+) {
+    let (proof, contracts_trie_root_hash, classes_trie_root_hash) =
+        fetch_proof(class_hash, CONTRACT_ADDRESS, storage_key).await;
+    let contract_state = &proof.contracts_proof.contract_leaves_data[0];
+
+    verify_class_proof(
+        classes_trie_root_hash,
+        &proof.classes_proof,
+        &class_hash,
+        &compiled_class_hash,
+    )
+    .unwrap();
+    verify_contract_proof(
+        contracts_trie_root_hash,
+        &proof.contracts_proof.nodes,
+        &CONTRACT_ADDRESS,
+        contract_state,
+    )
+    .unwrap();
+    verify_storage_proof(
+        contract_state.storage_root_hash,
+        &proof.contracts_storage_proofs[0],
+        &storage_key,
+        &storage_value,
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_proof_of_missing_contract() {
+    let missing_address = ContractAddress(Felt::from(9_u128));
+    let (proof, contracts_trie_root_hash, _) =
+        fetch_proof(CLASS_HASH, missing_address, StarknetStorageKey(Felt::ONE)).await;
+
+    assert_eq!(proof.contracts_proof.contract_leaves_data, vec![ContractState::default()]);
+    verify_contract_proof(
+        contracts_trie_root_hash,
+        &proof.contracts_proof.nodes,
+        &missing_address,
+        &ContractState::default(),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_rejects_wrong_leaves() {
+    let storage_key = StarknetStorageKey(Felt::ONE);
+    let (proof, _, classes_trie_root_hash) =
+        fetch_proof(CLASS_HASH, CONTRACT_ADDRESS, storage_key).await;
+    let storage_root_hash = proof.contracts_proof.contract_leaves_data[0].storage_root_hash;
+    let storage_proof = &proof.contracts_storage_proofs[0];
+
+    // A wrong value.
+    assert!(matches!(
+        verify_storage_proof(
+            storage_root_hash,
+            storage_proof,
+            &storage_key,
+            &StarknetStorageValue(Felt::from(8_u128))
+        ),
+        Err(ProofError::LeafMismatch(_))
+    ));
+    // A stored value claimed to be missing.
+    assert!(matches!(
+        verify_storage_proof(
+            storage_root_hash,
+            storage_proof,
+            &storage_key,
+            &StarknetStorageValue::default()
+        ),
+        Err(ProofError::LeafMismatch(_))
+    ));
+    // A proof against another root.
+    assert!(matches!(
+        verify_class_proof(
+            storage_root_hash,
+            &proof.classes_proof,
+            &CLASS_HASH,
+            &CompiledClassHash(Felt::from(4_u128))
+        ),
+        Err(ProofError::MissingNode(_))
+    ));
+    assert!(
+        verify_class_proof(
+            classes_trie_root_hash,
+            &proof.classes_proof,
+            &CLASS_HASH,
+            &CompiledClassHash(Felt::from(4_u128))
+        )
+        .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_verify_rejects_tampered_proof() {
+    let (mut proof, _, classes_trie_root_hash) =
+        fetch_proof(CLASS_HASH, CONTRACT_ADDRESS, StarknetStorageKey(Felt::ONE)).await;
+    let compiled_class_hash = CompiledClassHash(Felt::from(4_u128));
+
+    // The paths to the two classes split at a binary node, which is on the path to each of them.
+    let (binary_hash, binary_data) = proof
+        .classes_proof
+        .nodes
+        .iter()
+        .find_map(|(hash, node)| match node {
+            MerkleNode::Binary(binary_data) => Some((*hash, binary_data.clone())),
+            MerkleNode::Edge(_) => None,
+        })
+        .unwrap();
+    proof.classes_proof.nodes.insert(
+        binary_hash,
+        MerkleNode::Binary(BinaryData {
+            left_hash: binary_data.right_hash,
+            right_hash: binary_data.left_hash,
+        }),
+    );
+    assert!(matches!(
+        verify_class_proof(
+            classes_trie_root_hash,
+            &proof.classes_proof,
+            &CLASS_HASH,
+            &compiled_class_hash
+        ),
+        Err(ProofError::NodeHashMismatch(hash)) if hash == binary_hash
+    ));
+
+    proof.classes_proof.nodes.remove(&binary_hash);
+    assert!(matches!(
+        verify_class_proof(
+            classes_trie_root_hash,
+            &proof.classes_proof,
+            &CLASS_HASH,
+            &compiled_class_hash
+        ),
+        Err(ProofError::MissingNode(hash)) if hash == binary_hash
+    ));
+}
@@ -86,6 +86,14 @@ impl NodeIndex {
         Self::BITS - self.leading_zeros()
     }
 
+    /// Returns true iff the node is the given node or one of its ancestors.
+    pub(crate) fn is_ancestor_of(&self, descendant: &NodeIndex) -> bool {
+        let descendant_bit_length = descendant.bit_length();
+        let bit_length = self.bit_length();
+        bit_length <= descendant_bit_length
+            && *descendant >> (descendant_bit_length - bit_length) == *self
+    }
+
     /// Get the LCA (Lowest Common Ancestor) of the two nodes.
     pub(crate) fn get_lca(&self, other: &NodeIndex) -> NodeIndex {
         if self == other {
@@ -74,6 +74,19 @@ fn test_get_lca(#[case] node_index: U256, #[case] other: U256, #[case] expected:
     assert_eq!(lca, expected);
 }
 
+#[rstest]
+#[case(1, 1, true)]
+#[case(1, 13, true)]
+#[case(3, 13, true)]
+#[case(2, 13, false)]
+#[case(6, 13, true)]
+#[case(13, 6, false)]
+fn test_is_ancestor_of(#[case] node_index: u8, #[case] other: u8, #[case] expected: bool) {
+    let node_index = NodeIndex::new(node_index.into());
+    let other = NodeIndex::new(other.into());
+    assert_eq!(node_index.is_ancestor_of(&other), expected);
+}
+
 #[rstest]
 fn test_get_lca_big(mut random: ThreadRng) {
     let lca =
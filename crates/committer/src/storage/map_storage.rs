@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use serde::Serialize;
 
+use crate::storage::errors::StorageResult;
 use crate::storage::storage_trait::{Storage, StorageKey, StorageValue};
 
 #[derive(Serialize, Debug, Default)]
@@ -11,24 +12,25 @@ pub struct MapStorage {
 }
 
 impl Storage for MapStorage {
-    fn get(&self, key: &StorageKey) -> Option<&StorageValue> {
-        self.storage.get(key)
+    fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        Ok(self.storage.get(key).cloned())
     }
 
-    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue> {
-        self.storage.insert(key, value)
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> StorageResult<Option<StorageValue>> {
+        Ok(self.storage.insert(key, value))
     }
 
-    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<&StorageValue>> {
-        keys.iter().map(|key| self.get(key)).collect::<Vec<_>>()
+    fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>> {
+        keys.iter().map(|key| self.get(key)).collect()
     }
 
-    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) {
+    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()> {
         self.storage.extend(key_to_value);
+        Ok(())
     }
 
-    fn delete(&mut self, key: &StorageKey) -> Option<StorageValue> {
-        self.storage.remove(key)
+    fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        Ok(self.storage.remove(key))
     }
 }
 
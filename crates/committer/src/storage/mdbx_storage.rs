@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use libmdbx::{Database, Geometry, Table, Transaction, TransactionKind, WriteFlags, WriteMap};
+use lru::LruCache;
+
+use crate::storage::errors::StorageResult;
+use crate::storage::storage_trait::{StarknetPrefix, Storage, StorageKey, StorageValue};
+
+#[cfg(test)]
+#[path = "mdbx_storage_test.rs"]
+pub mod mdbx_storage_test;
+
+type InnerNodeCache = LruCache<StorageKey, StorageValue>;
+
+/// The configuration of an [MdbxStorage].
+#[derive(Clone, Debug)]
+pub struct MdbxStorageConfig {
+    /// The directory of the database. Created if it does not exist.
+    pub path: PathBuf,
+    /// The minimal size of the database file, in bytes.
+    pub min_size: usize,
+    /// The maximal size of the database file, in bytes.
+    pub max_size: usize,
+    /// The step by which the database file grows, in bytes.
+    pub growth_step: isize,
+    /// The number of inner nodes kept in the read cache.
+    pub inner_node_cache_size: NonZeroUsize,
+}
+
+impl MdbxStorageConfig {
+    /// Returns the configuration of a database in the given directory, with the default sizes.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            min_size: 1 << 20,    // 1MB
+            max_size: 1 << 40,    // 1TB
+            growth_step: 1 << 32, // 4GB
+            inner_node_cache_size: NonZeroUsize::new(1 << 20).expect("The size is positive."),
+        }
+    }
+}
+
+/// A persistent storage over an MDBX database. Each write is a single transaction, so a block's
+/// forest written with [Storage::mset] is either fully stored or not stored at all.
+/// Inner nodes are stored by their hashes and never change, so the recently read and written ones
+/// are kept in an in-memory cache.
+pub struct MdbxStorage {
+    db: Database<WriteMap>,
+    inner_node_cache: Mutex<InnerNodeCache>,
+}
+
+impl MdbxStorage {
+    /// Opens the database in the directory of the given configuration, or creates it.
+    pub fn open(config: &MdbxStorageConfig) -> StorageResult<Self> {
+        let db = Database::<WriteMap>::new()
+            .set_geometry(Geometry {
+                size: Some(config.min_size..config.max_size),
+                growth_step: Some(config.growth_step),
+                ..Default::default()
+            })
+            .open(&config.path)?;
+        Ok(Self { db, inner_node_cache: Mutex::new(LruCache::new(config.inner_node_cache_size)) })
+    }
+
+    fn inner_node_cache(&self) -> MutexGuard<'_, InnerNodeCache> {
+        self.inner_node_cache.lock().expect("The inner node cache lock should not be poisoned.")
+    }
+
+    /// Reads the value of the key in the given transaction, through the inner node cache.
+    fn read<K: TransactionKind>(
+        &self,
+        txn: &Transaction<'_, K, WriteMap>,
+        table: &Table<'_>,
+        key: &StorageKey,
+    ) -> StorageResult<Option<StorageValue>> {
+        if !is_inner_node(key) {
+            return Ok(txn.get::<Vec<u8>>(table, &key.0)?.map(StorageValue));
+        }
+        if let Some(value) = self.inner_node_cache().get(key) {
+            return Ok(Some(value.clone()));
+        }
+        let value = txn.get::<Vec<u8>>(table, &key.0)?.map(StorageValue);
+        if let Some(value) = &value {
+            self.inner_node_cache().put(key.clone(), value.clone());
+        }
+        Ok(value)
+    }
+}
+
+impl Storage for MdbxStorage {
+    fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        let txn = self.db.begin_ro_txn()?;
+        let table = txn.open_table(None)?;
+        self.read(&txn, &table, key)
+    }
+
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> StorageResult<Option<StorageValue>> {
+        let txn = self.db.begin_rw_txn()?;
+        let table = txn.open_table(None)?;
+        let previous_value = txn.get::<Vec<u8>>(&table, &key.0)?.map(StorageValue);
+        txn.put(&table, &key.0, &value.0, WriteFlags::UPSERT)?;
+        txn.commit()?;
+        if is_inner_node(&key) {
+            self.inner_node_cache().put(key, value);
+        }
+        Ok(previous_value)
+    }
+
+    fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>> {
+        // All the values are read from the same snapshot of the database.
+        let txn = self.db.begin_ro_txn()?;
+        let table = txn.open_table(None)?;
+        keys.iter().map(|key| self.read(&txn, &table, key)).collect()
+    }
+
+    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()> {
+        // A transaction which is dropped before it is committed is aborted.
+        let txn = self.db.begin_rw_txn()?;
+        let table = txn.open_table(None)?;
+        for (key, value) in key_to_value.iter() {
+            txn.put(&table, &key.0, &value.0, WriteFlags::UPSERT)?;
+        }
+        txn.commit()?;
+
+        // The new inner nodes are the first to be read when the next block is committed.
+        let mut inner_node_cache = self.inner_node_cache();
+        for (key, value) in key_to_value.into_iter().filter(|(key, _)| is_inner_node(key)) {
+            inner_node_cache.put(key, value);
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        let txn = self.db.begin_rw_txn()?;
+        let table = txn.open_table(None)?;
+        let previous_value = txn.get::<Vec<u8>>(&table, &key.0)?.map(StorageValue);
+        if previous_value.is_some() {
+            txn.del(&table, &key.0, None)?;
+        }
+        txn.commit()?;
+        self.inner_node_cache().pop(key);
+        Ok(previous_value)
+    }
+}
+
+fn is_inner_node(key: &StorageKey) -> bool {
+    key.0.starts_with(StarknetPrefix::InnerNode.to_bytes())
+}
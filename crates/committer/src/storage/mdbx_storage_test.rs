@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use log::LevelFilter;
+use pretty_assertions::assert_eq;
+use tempfile::{tempdir, TempDir};
+
+use crate::block_committer::commit::{commit_block, commit_block_with_storage};
+use crate::block_committer::input::{
+    ConfigImpl,
+    ContractAddress,
+    Input,
+    StarknetStorageKey,
+    StarknetStorageValue,
+    StateDiff,
+};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::commit_to_empty_forest;
+use crate::storage::mdbx_storage::{MdbxStorage, MdbxStorageConfig};
+use crate::storage::storage_trait::{
+    create_db_key,
+    StarknetPrefix,
+    Storage,
+    StorageKey,
+    StorageValue,
+};
+
+fn open_test_storage() -> (MdbxStorage, MdbxStorageConfig, TempDir) {
+    let dir = tempdir().unwrap();
+    let config = MdbxStorageConfig {
+        max_size: 1 << 30,    // 1GB
+        growth_step: 1 << 20, // 1MB
+        ..MdbxStorageConfig::new(dir.path().to_path_buf())
+    };
+    (MdbxStorage::open(&config).unwrap(), config, dir)
+}
+
+fn inner_node_key(suffix: u8) -> StorageKey {
+    create_db_key(StarknetPrefix::InnerNode.to_storage_prefix(), &[suffix])
+}
+
+fn leaf_key(suffix: u8) -> StorageKey {
+    create_db_key(StarknetPrefix::StorageLeaf.to_storage_prefix(), &[suffix])
+}
+
+#[test]
+fn test_set_get_and_delete() {
+    let (mut storage, _config, _dir) = open_test_storage();
+
+    for key in [inner_node_key(1), leaf_key(1)] {
+        assert_eq!(storage.get(&key).unwrap(), None);
+        assert_eq!(storage.set(key.clone(), StorageValue(vec![1])).unwrap(), None);
+        assert_eq!(storage.get(&key).unwrap(), Some(StorageValue(vec![1])));
+        assert_eq!(
+            storage.set(key.clone(), StorageValue(vec![2])).unwrap(),
+            Some(StorageValue(vec![1]))
+        );
+        assert_eq!(storage.get(&key).unwrap(), Some(StorageValue(vec![2])));
+        assert_eq!(storage.delete(&key).unwrap(), Some(StorageValue(vec![2])));
+        // A deleted inner node is not read from the cache.
+        assert_eq!(storage.get(&key).unwrap(), None);
+        assert_eq!(storage.delete(&key).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_mset_is_persistent() {
+    let (mut storage, config, _dir) = open_test_storage();
+    storage
+        .mset(HashMap::from([
+            (inner_node_key(1), StorageValue(vec![1])),
+            (inner_node_key(2), StorageValue(vec![2])),
+            (leaf_key(1), StorageValue(vec![3])),
+        ]))
+        .unwrap();
+    drop(storage);
+
+    // The values are read from the database, as the cache of a reopened storage is empty.
+    let storage = MdbxStorage::open(&config).unwrap();
+    assert_eq!(
+        storage.mget(&[leaf_key(1), inner_node_key(3), inner_node_key(1)]).unwrap(),
+        vec![Some(StorageValue(vec![3])), None, Some(StorageValue(vec![1]))]
+    );
+    assert_eq!(storage.get(&inner_node_key(2)).unwrap(), Some(StorageValue(vec![2])));
+}
+
+#[tokio::test]
+async fn test_commit_blocks_on_storage() {
+    let contract_address = ContractAddress(Felt::ONE);
+    let class_hash = ClassHash(Felt::TWO);
+    let first_state_diff = StateDiff {
+        address_to_class_hash: HashMap::from([(contract_address, class_hash)]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash,
+            CompiledClassHash(Felt::THREE),
+        )]),
+        storage_updates: HashMap::from([(
+            contract_address,
+            HashMap::from([(StarknetStorageKey(Felt::ONE), StarknetStorageValue(Felt::TWO))]),
+        )]),
+        ..Default::default()
+    };
+    let second_state_diff = StateDiff {
+        address_to_nonce: HashMap::from([(contract_address, Nonce(Felt::ONE))]),
+        storage_updates: HashMap::from([(
+            contract_address,
+            HashMap::from([
+                (StarknetStorageKey(Felt::ONE), StarknetStorageValue(Felt::THREE)),
+                (StarknetStorageKey(Felt::TWO), StarknetStorageValue(Felt::ONE)),
+            ]),
+        )]),
+        ..Default::default()
+    };
+    let config = ConfigImpl::new(false, LevelFilter::Debug);
+
+    // Commit the blocks one after the other, reopening the database in between.
+    let (mut storage, storage_config, _dir) = open_test_storage();
+    let mut contracts_trie_root_hash = HashOutput::ROOT_OF_EMPTY_TREE;
+    let mut classes_trie_root_hash = HashOutput::ROOT_OF_EMPTY_TREE;
+    for state_diff in [&first_state_diff, &second_state_diff] {
+        let forest = commit_block_with_storage(
+            &storage,
+            state_diff,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &config,
+        )
+        .await
+        .unwrap();
+        forest.write_to_storage(&mut storage).unwrap();
+        contracts_trie_root_hash = forest.get_contract_root_hash();
+        classes_trie_root_hash = forest.get_compiled_class_root_hash();
+        drop(storage);
+        storage = MdbxStorage::open(&storage_config).unwrap();
+    }
+
+    // Commit the same blocks in memory.
+    let (map_storage, first_contracts_trie_root_hash, first_classes_trie_root_hash) =
+        commit_to_empty_forest(first_state_diff).await;
+    let expected_forest = commit_block(Input {
+        storage: map_storage.storage,
+        state_diff: second_state_diff,
+        contracts_trie_root_hash: first_contracts_trie_root_hash,
+        classes_trie_root_hash: first_classes_trie_root_hash,
+        config,
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(contracts_trie_root_hash, expected_forest.get_contract_root_hash());
+    assert_eq!(classes_trie_root_hash, expected_forest.get_compiled_class_root_hash());
+}
@@ -3,32 +3,31 @@ use std::collections::HashMap;
 use serde::{Serialize, Serializer};
 
 use crate::felt::Felt;
+use crate::storage::errors::StorageResult;
 
-#[derive(Debug, Eq, Hash, PartialEq)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct StorageKey(pub Vec<u8>);
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct StorageValue(pub Vec<u8>);
 
-pub trait Storage: From<HashMap<StorageKey, StorageValue>> {
+pub trait Storage {
     /// Returns value from storage, if it exists.
-    fn get(&self, key: &StorageKey) -> Option<&StorageValue>;
+    fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>>;
 
     /// Sets value in storage. If key already exists, its value is overwritten and the old value is
     /// returned.
-    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue>;
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> StorageResult<Option<StorageValue>>;
 
     /// Returns values from storage in same order of given keys. Value is None for keys that do not
     /// exist.
-    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<&StorageValue>>;
+    fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>>;
 
-    /// Sets values in storage.
-    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>);
+    /// Sets values in storage atomically: either all of them are written, or none of them.
+    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()>;
 
     /// Deletes value from storage and returns its value if it exists. Returns None if not.
-    fn delete(&mut self, key: &StorageKey) -> Option<StorageValue>;
+    fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>>;
 }
 
 // TODO(Aviv, 17/07/2024); Split between Storage prefix representation (trait) and node
@@ -1,5 +1,14 @@
+#[cfg(feature = "mdbx")]
+use std::path::PathBuf;
+
 use committer::block_committer::commit::commit_block;
+#[cfg(feature = "mdbx")]
+use committer::block_committer::commit::commit_block_with_storage;
 use committer::block_committer::input::{Config, ConfigImpl, Input};
+#[cfg(feature = "mdbx")]
+use committer::storage::mdbx_storage::{MdbxStorage, MdbxStorageConfig};
+#[cfg(feature = "mdbx")]
+use committer::storage::storage_trait::Storage;
 
 use crate::filled_tree_output::filled_forest::SerializedForest;
 use crate::parse_input::read::{parse_input, write_to_file};
@@ -17,3 +26,30 @@ pub async fn commit(input: Input<ConfigImpl>, output_path: String) {
     let output = serialized_filled_forest.forest_to_output();
     write_to_file(&output_path, &output);
 }
+
+/// Commits the given block on top of the forest stored in the database at the given directory, and
+/// writes the new nodes to the database. The facts in the storage of the input are written to the
+/// database first, e.g., to initialize it. Only the new root hashes are written to the output.
+#[cfg(feature = "mdbx")]
+pub async fn parse_and_commit_to_db(input_string: &str, db_path: PathBuf, output_path: String) {
+    let input = parse_input(input_string).expect("Failed to parse the given input.");
+    // Set the given log level.
+    log::set_max_level(input.config.logger_level());
+    let mut storage =
+        MdbxStorage::open(&MdbxStorageConfig::new(db_path)).expect("Failed to open the database.");
+    storage.mset(input.storage).expect("Failed to write the given storage to the database.");
+    let filled_forest = commit_block_with_storage(
+        &storage,
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+    .expect("Failed to commit the given block.");
+    filled_forest
+        .write_to_storage(&mut storage)
+        .expect("Failed to write the block to the database.");
+    let output = SerializedForest(filled_forest).forest_to_root_hashes_output();
+    write_to_file(&output_path, &output);
+}
@@ -15,10 +15,19 @@ pub struct Output {
     compiled_class_root_hash: String,
 }
 
+/// The new root hashes of a forest whose nodes were written to a database.
+#[derive(Debug, Serialize)]
+pub struct RootHashesOutput {
+    // New contract storage root.
+    contract_storage_root_hash: String,
+    // New compiled class root.
+    compiled_class_root_hash: String,
+}
+
 impl SerializedForest {
     pub fn forest_to_output(&self) -> Output {
         let mut storage = MapStorage::default();
-        self.0.write_to_storage(&mut storage);
+        self.0.write_to_storage(&mut storage).expect("Failed to write to a map storage.");
         let contract_storage_root_hash = self.0.get_contract_root_hash().0;
         let compiled_class_root_hash = self.0.get_compiled_class_root_hash().0;
         Output {
@@ -27,4 +36,11 @@ impl SerializedForest {
             compiled_class_root_hash: compiled_class_root_hash.to_hex(),
         }
     }
+
+    pub fn forest_to_root_hashes_output(&self) -> RootHashesOutput {
+        RootHashesOutput {
+            contract_storage_root_hash: self.0.get_contract_root_hash().0.to_hex(),
+            compiled_class_root_hash: self.0.get_compiled_class_root_hash().0.to_hex(),
+        }
+    }
 }
@@ -1,6 +1,11 @@
+#[cfg(feature = "mdbx")]
+use std::path::PathBuf;
+
 use clap::{Args, Parser, Subcommand};
 use committer_cli::block_hash::{BlockCommitmentsInput, BlockHashInput};
 use committer_cli::commands::parse_and_commit;
+#[cfg(feature = "mdbx")]
+use committer_cli::commands::parse_and_commit_to_db;
 use committer_cli::parse_input::read::{load_from_stdin, read_from_stdin, write_to_file};
 use committer_cli::tests::python_tests::PythonTest;
 use simplelog::{ColorChoice, Config, LevelFilter, TermLogger, TerminalMode};
@@ -41,6 +46,18 @@ enum Command {
         #[clap(long, short = 'o', default_value = "stdout")]
         output_path: String,
     },
+    /// Given a state diff, commits it on top of the state tree stored in a database, and stores
+    /// the new commitment in the database.
+    #[cfg(feature = "mdbx")]
+    CommitToDb {
+        /// File path to output.
+        #[clap(long, short = 'o', default_value = "stdout")]
+        output_path: String,
+
+        /// The directory of the database.
+        #[clap(long)]
+        db_path: PathBuf,
+    },
     PythonTest {
         /// File path to output.
         #[clap(long, short = 'o', default_value = "stdout")]
@@ -76,6 +93,11 @@ async fn main() {
             parse_and_commit(&read_from_stdin(), output_path).await;
         }
 
+        #[cfg(feature = "mdbx")]
+        Command::CommitToDb { output_path, db_path } => {
+            parse_and_commit_to_db(&read_from_stdin(), db_path, output_path).await;
+        }
+
         Command::PythonTest { output_path, test_name } => {
             // Create PythonTest from test_name.
             let test = PythonTest::try_from(test_name)
@@ -29,7 +29,7 @@ use committer::patricia_merkle_tree::node_data::leaf::ContractState;
 use committer::patricia_merkle_tree::types::SubTreeHeight;
 use committer::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
 use committer::storage::db_object::DBObject;
-use committer::storage::errors::{DeserializationError, SerializationError};
+use committer::storage::errors::{DeserializationError, SerializationError, StorageError};
 use committer::storage::map_storage::MapStorage;
 use committer::storage::storage_trait::{Storage, StorageKey, StorageValue};
 use ethnum::U256;
@@ -98,6 +98,8 @@ pub enum PythonTestError {
     SerializationError(#[from] SerializationError),
     #[error(transparent)]
     FilledForest(#[from] FilledForestError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
 }
 
 /// Implements conversion from a string to a `PythonTest`.
@@ -523,7 +525,7 @@ pub(crate) fn storage_serialize_test() -> Result<String, PythonTestError> {
     for i in 0..=99_u128 {
         let key = StorageKey(Felt::from(i).to_bytes_be().to_vec());
         let value = StorageValue(Felt::from(i).to_bytes_be().to_vec());
-        storage.set(key, value);
+        storage.set(key, value)?;
     }
 
     Ok(serde_json::to_string(&storage)?)
@@ -572,7 +574,7 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
     };
 
     // Store the binary node in the storage.
-    rust_fact_storage.set(binary_rust.db_key(), binary_rust.serialize());
+    rust_fact_storage.set(binary_rust.db_key(), binary_rust.serialize())?;
 
     // Parse the edge node data from the input.
     let edge_json = get_or_key_not_found(&data, "edge")?;
@@ -593,7 +595,7 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
     };
 
     // Store the edge node in the storage.
-    rust_fact_storage.set(edge_rust.db_key(), edge_rust.serialize());
+    rust_fact_storage.set(edge_rust.db_key(), edge_rust.serialize())?;
 
     // Parse the storage leaf data from the input.
     let storage_leaf_json = get_or_key_not_found(&data, "storage")?;
@@ -609,7 +611,7 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
     };
 
     // Store the storage leaf node in the storage.
-    rust_fact_storage.set(storage_leaf_rust.db_key(), storage_leaf_rust.serialize());
+    rust_fact_storage.set(storage_leaf_rust.db_key(), storage_leaf_rust.serialize())?;
 
     // Parse the contract state leaf data from the input.
     let contract_state_leaf = get_or_key_not_found(&data, "contract_state_leaf")?;
@@ -634,7 +636,8 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
     };
 
     // Store the contract state leaf node in the storage.
-    rust_fact_storage.set(contract_state_leaf_rust.db_key(), contract_state_leaf_rust.serialize());
+    rust_fact_storage
+        .set(contract_state_leaf_rust.db_key(), contract_state_leaf_rust.serialize())?;
 
     // Parse the compiled class leaf data from the input.
     let compiled_class_leaf = get_or_key_not_found(&data, "contract_class_leaf")?;
@@ -651,7 +654,8 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
     };
 
     // Store the compiled class leaf node in the storage.
-    rust_fact_storage.set(compiled_class_leaf_rust.db_key(), compiled_class_leaf_rust.serialize());
+    rust_fact_storage
+        .set(compiled_class_leaf_rust.db_key(), compiled_class_leaf_rust.serialize())?;
 
     // Serialize the storage to a JSON string and handle serialization errors.
     Ok(serde_json::to_string(&rust_fact_storage)?)
@@ -11,12 +11,29 @@ use validator::Validate;
 
 use crate::compiler_version::VersionId;
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize, Validate, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
 pub struct GatewayConfig {
     pub network_config: GatewayNetworkConfig,
     pub stateless_tx_validator_config: StatelessTransactionValidatorConfig,
     pub stateful_tx_validator_config: StatefulTransactionValidatorConfig,
     pub compiler_config: GatewayCompilerConfig,
+    #[validate]
+    pub rate_limiter_config: RateLimiterConfig,
+    // The maximal number of transactions in a batch; each counts as a request of its client.
+    pub max_batch_size: usize,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            network_config: GatewayNetworkConfig::default(),
+            stateless_tx_validator_config: StatelessTransactionValidatorConfig::default(),
+            stateful_tx_validator_config: StatefulTransactionValidatorConfig::default(),
+            compiler_config: GatewayCompilerConfig::default(),
+            rate_limiter_config: RateLimiterConfig::default(),
+            max_batch_size: 100,
+        }
+    }
 }
 
 impl SerializeConfig for GatewayConfig {
@@ -32,6 +49,13 @@ impl SerializeConfig for GatewayConfig {
                 "stateful_tx_validator_config",
             ),
             append_sub_config_name(self.compiler_config.dump(), "compiler_config"),
+            append_sub_config_name(self.rate_limiter_config.dump(), "rate_limiter_config"),
+            BTreeMap::from_iter([ser_param(
+                "max_batch_size",
+                &self.max_batch_size,
+                "Maximal number of transactions in a batch submitted to the gateway.",
+                ParamPrivacyInput::Public,
+            )]),
         ]
         .into_iter()
         .flatten()
@@ -66,6 +90,67 @@ impl Default for GatewayNetworkConfig {
     }
 }
 
+/// Limits the rate of transactions submitted to the gateway, by the client that submits them and by
+/// their sender address.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct RateLimiterConfig {
+    #[validate]
+    pub per_client_ip: TokenBucketConfig,
+    #[validate]
+    pub per_sender_address: TokenBucketConfig,
+}
+
+impl Default for RateLimiterConfig {
+    fn default() -> Self {
+        Self {
+            per_client_ip: TokenBucketConfig { capacity: 100, refill_rate_per_second: 20 },
+            per_sender_address: TokenBucketConfig { capacity: 20, refill_rate_per_second: 5 },
+        }
+    }
+}
+
+impl SerializeConfig for RateLimiterConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        vec![
+            append_sub_config_name(self.per_client_ip.dump(), "per_client_ip"),
+            append_sub_config_name(self.per_sender_address.dump(), "per_sender_address"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// A token bucket: each request takes a token, and tokens are refilled at a constant rate, up to
+/// the bucket's capacity.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct TokenBucketConfig {
+    // The maximal number of requests in a burst.
+    #[validate(range(min = 1))]
+    pub capacity: u32,
+    #[validate(range(min = 1))]
+    pub refill_rate_per_second: u32,
+}
+
+impl SerializeConfig for TokenBucketConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "capacity",
+                &self.capacity,
+                "Maximal number of requests in a burst.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "refill_rate_per_second",
+                &self.refill_rate_per_second,
+                "Number of requests per second allowed on average.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
 pub struct StatelessTransactionValidatorConfig {
     // If true, validates that the resource bounds are not zero.
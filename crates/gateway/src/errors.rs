@@ -35,10 +35,13 @@ use thiserror::Error;
 use tokio::task::JoinError;
 
 use crate::compiler_version::{VersionId, VersionIdError};
+use crate::rate_limiter::RateLimitKey;
 
 /// Errors directed towards the end-user, as a result of gateway requests.
 #[derive(Debug, Error)]
 pub enum GatewayError {
+    #[error("Batch of {batch_size} transactions exceeds the maximal size of {max_batch_size}.")]
+    BatchTooLarge { batch_size: usize, max_batch_size: usize },
     #[error(transparent)]
     CompilationError(#[from] CompilationUtilError),
     #[error(
@@ -54,6 +57,10 @@ pub enum GatewayError {
     InternalServerError(#[from] JoinError),
     #[error("Error sending message: {0}")]
     MessageSendError(String),
+    #[error("Too many requests from {0}; try again later.")]
+    RateLimitExceeded(RateLimitKey),
+    #[error(transparent)]
+    StarknetApiError(#[from] StarknetApiError),
     #[error(transparent)]
     StatefulTransactionValidatorError(#[from] StatefulTransactionValidatorError),
     #[error(transparent)]
@@ -68,8 +75,13 @@ impl IntoResponse for GatewayError {
     // TODO(Arni, 1/5/2024): Be more fine tuned about the error response. Not all Gateway errors
     // are internal server errors.
     fn into_response(self) -> Response {
+        let status_code = match self {
+            GatewayError::BatchTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            GatewayError::RateLimitExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         let body = self.to_string();
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        (status_code, body).into_response()
     }
 }
 
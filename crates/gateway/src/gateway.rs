@@ -3,24 +3,36 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use axum::extract::State;
+use axum::extract::{ConnectInfo, State};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use starknet_api::rpc_transaction::RpcTransaction;
 use starknet_api::transaction::TransactionHash;
 use starknet_mempool_infra::component_runner::{ComponentStartError, ComponentStarter};
-use starknet_mempool_types::communication::SharedMempoolClient;
-use starknet_mempool_types::mempool_types::{Account, MempoolInput};
+use starknet_mempool_types::communication::{
+    MempoolClient,
+    MempoolClientError,
+    SharedMempoolClient,
+};
+use starknet_mempool_types::errors::MempoolError;
+use starknet_mempool_types::mempool_types::{Account, AccountStateReader, MempoolInput};
+use tokio::task::JoinHandle;
 use tracing::{info, instrument};
 
 use crate::compilation::GatewayCompiler;
 use crate::config::{GatewayConfig, GatewayNetworkConfig, RpcStateReaderConfig};
 use crate::errors::{GatewayError, GatewayResult, GatewayRunError};
+use crate::rate_limiter::RateLimiter;
 use crate::rpc_state_reader::RpcStateReaderFactory;
-use crate::state_reader::StateReaderFactory;
+use crate::state_reader::{MempoolAccountStateReader, StateReaderFactory};
 use crate::stateful_transaction_validator::StatefulTransactionValidator;
 use crate::stateless_transaction_validator::StatelessTransactionValidator;
-use crate::utils::{external_tx_to_thin_tx, get_sender_address};
+use crate::transaction_status::{
+    AddTransactionReceipt,
+    TransactionStatus,
+    TransactionStatusRecords,
+};
+use crate::utils::{calculate_tx_hash, external_tx_to_thin_tx, get_sender_address};
 
 #[cfg(test)]
 #[path = "gateway_test.rs"]
@@ -38,6 +50,9 @@ pub struct AppState {
     pub state_reader_factory: Arc<dyn StateReaderFactory>,
     pub gateway_compiler: GatewayCompiler,
     pub mempool_client: SharedMempoolClient,
+    pub tx_statuses: Arc<TransactionStatusRecords>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub max_batch_size: usize,
 }
 
 impl Gateway {
@@ -57,6 +72,9 @@ impl Gateway {
             state_reader_factory,
             gateway_compiler,
             mempool_client,
+            tx_statuses: Arc::new(TransactionStatusRecords::default()),
+            rate_limiter: Arc::new(RateLimiter::new(config.rate_limiter_config)),
+            max_batch_size: config.max_batch_size,
         };
         Gateway { config, app_state }
     }
@@ -67,14 +85,17 @@ impl Gateway {
         let addr = SocketAddr::new(ip, port);
         let app = self.app();
 
-        // Create a server that runs forever.
-        Ok(axum::Server::bind(&addr).serve(app.into_make_service()).await?)
+        // Create a server that runs forever. The address of each client is kept for rate limiting.
+        let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+        Ok(axum::Server::bind(&addr).serve(make_service).await?)
     }
 
     pub fn app(&self) -> Router {
         Router::new()
             .route("/is_alive", get(is_alive))
             .route("/add_tx", post(add_tx))
+            .route("/add_txs", post(add_txs))
+            .route("/tx_status", post(get_tx_status))
             .with_state(self.app_state.clone())
     }
 }
@@ -89,28 +110,222 @@ async fn is_alive() -> GatewayResult<String> {
 #[instrument(skip(app_state))]
 async fn add_tx(
     State(app_state): State<AppState>,
+    ConnectInfo(client_addr): ConnectInfo<SocketAddr>,
     Json(tx): Json<RpcTransaction>,
-) -> GatewayResult<Json<TransactionHash>> {
-    let mempool_input = tokio::task::spawn_blocking(move || {
-        process_tx(
-            app_state.stateless_tx_validator,
-            app_state.stateful_tx_validator.as_ref(),
-            app_state.state_reader_factory.as_ref(),
-            app_state.gateway_compiler,
-            tx,
-        )
+) -> GatewayResult<Json<AddTransactionReceipt>> {
+    app_state.rate_limiter.check_client_ip(client_addr.ip())?;
+    let tx_statuses = app_state.tx_statuses.clone();
+    let mempool_client = app_state.mempool_client.clone();
+
+    let tx_hash = receive_tx(&app_state, &tx)?;
+    let processing_task =
+        tokio::task::spawn_blocking(move || process_received_tx(&app_state, tx_hash, tx));
+    let mempool_input = join_processing_task(&tx_statuses, tx_hash, processing_task).await?;
+    let add_tx_output = match mempool_client.add_tx(mempool_input).await {
+        Ok(add_tx_output) => add_tx_output,
+        Err(e) => {
+            let error = GatewayError::MessageSendError(e.to_string());
+            tx_statuses.update(tx_hash, TransactionStatus::Rejected { error: error.to_string() });
+            return Err(error);
+        }
+    };
+
+    // TODO: Also return `ContractAddress` for deploy and `ClassHash` for Declare.
+    Ok(Json(record_acceptance(&tx_statuses, tx_hash, add_tx_output.replaced_tx_hash)))
+}
+
+/// Validates the given transactions in parallel, and adds the valid ones to the mempool in a
+/// single request. Returns the outcome of each transaction, in the order they were given.
+#[instrument(skip(app_state, txs))]
+async fn add_txs(
+    State(app_state): State<AppState>,
+    ConnectInfo(client_addr): ConnectInfo<SocketAddr>,
+    Json(txs): Json<Vec<RpcTransaction>>,
+) -> GatewayResult<Json<Vec<Result<AddTransactionReceipt, String>>>> {
+    if txs.len() > app_state.max_batch_size {
+        return Err(GatewayError::BatchTooLarge {
+            batch_size: txs.len(),
+            max_batch_size: app_state.max_batch_size,
+        });
+    }
+    // Each transaction of a batch counts as a request of its client, and each valid one also as a
+    // request of its sender.
+    app_state.rate_limiter.check_client_ip_batch(client_addr.ip(), txs.len())?;
+    let tx_statuses = app_state.tx_statuses.clone();
+    let mempool_client = app_state.mempool_client.clone();
+
+    let processing_tasks: Vec<_> = txs
+        .into_iter()
+        .map(|tx| -> GatewayResult<_> {
+            let tx_hash = receive_tx(&app_state, &tx)?;
+            let app_state = app_state.clone();
+            let processing_task =
+                tokio::task::spawn_blocking(move || process_received_tx(&app_state, tx_hash, tx));
+            Ok((tx_hash, processing_task))
+        })
+        .collect();
+    let mut processed_txs = Vec::with_capacity(processing_tasks.len());
+    let mut mempool_inputs = Vec::new();
+    for processing_task in processing_tasks {
+        let processed_tx = match processing_task {
+            Ok((tx_hash, processing_task)) => {
+                join_processing_task(&tx_statuses, tx_hash, processing_task).await
+            }
+            Err(error) => Err(error),
+        };
+        match processed_tx {
+            Ok(mempool_input) => {
+                processed_txs.push(Ok(mempool_input.tx.tx_hash));
+                mempool_inputs.push(mempool_input);
+            }
+            Err(error) => processed_txs.push(Err(error.to_string())),
+        }
+    }
+
+    let add_tx_results = if mempool_inputs.is_empty() {
+        Vec::new()
+    } else {
+        let accepted_tx_hashes: Vec<_> =
+            mempool_inputs.iter().map(|mempool_input| mempool_input.tx.tx_hash).collect();
+        match mempool_client.add_txs(mempool_inputs).await {
+            Ok(add_tx_results) => add_tx_results,
+            Err(e) => {
+                let error = GatewayError::MessageSendError(e.to_string());
+                for tx_hash in accepted_tx_hashes {
+                    let status = TransactionStatus::Rejected { error: error.to_string() };
+                    tx_statuses.update(tx_hash, status);
+                }
+                return Err(error);
+            }
+        }
+    };
+
+    let mut add_tx_results = add_tx_results.into_iter();
+    let receipts = processed_txs
+        .into_iter()
+        .map(|processed_tx| {
+            let tx_hash = processed_tx?;
+            let add_tx_result =
+                add_tx_results.next().expect("The mempool should return a result per transaction.");
+            match add_tx_result {
+                Ok(add_tx_output) => {
+                    Ok(record_acceptance(&tx_statuses, tx_hash, add_tx_output.replaced_tx_hash))
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    let status = TransactionStatus::Rejected { error: error.clone() };
+                    tx_statuses.update(tx_hash, status);
+                    Err(error)
+                }
+            }
+        })
+        .collect();
+
+    Ok(Json(receipts))
+}
+
+#[instrument(skip(app_state))]
+async fn get_tx_status(
+    State(app_state): State<AppState>,
+    Json(tx_hash): Json<TransactionHash>,
+) -> GatewayResult<Json<TransactionStatus>> {
+    let status = match app_state.tx_statuses.get(tx_hash) {
+        Some(TransactionStatus::Queued) => {
+            get_mempool_tx_status(app_state.mempool_client.as_ref(), tx_hash).await?
+        }
+        Some(status) => status,
+        None => TransactionStatus::NotReceived,
+    };
+
+    Ok(Json(status))
+}
+
+/// Records the receipt of the given transaction, and returns its hash.
+fn receive_tx(app_state: &AppState, tx: &RpcTransaction) -> GatewayResult<TransactionHash> {
+    let chain_id = &app_state.stateful_tx_validator.config.chain_info.chain_id;
+    let tx_hash = calculate_tx_hash(tx, chain_id)?;
+    app_state.tx_statuses.update(tx_hash, TransactionStatus::Received);
+    Ok(tx_hash)
+}
+
+/// Validates the given received transaction, recording its rejection if it is invalid.
+fn process_received_tx(
+    app_state: &AppState,
+    tx_hash: TransactionHash,
+    tx: RpcTransaction,
+) -> GatewayResult<MempoolInput> {
+    process_tx(
+        app_state.stateless_tx_validator.clone(),
+        app_state.stateful_tx_validator.as_ref(),
+        app_state.state_reader_factory.as_ref(),
+        app_state.gateway_compiler.clone(),
+        tx,
+    )
+    .and_then(|mempool_input| {
+        // The sender is charged only for valid transactions, so that others can't exhaust its
+        // limit by submitting invalid transactions in its name.
+        app_state.rate_limiter.check_sender_address(mempool_input.account.sender_address)?;
+        Ok(mempool_input)
+    })
+    .map_err(|error| {
+        let status = TransactionStatus::Rejected { error: error.to_string() };
+        app_state.tx_statuses.update(tx_hash, status);
+        error
     })
-    .await??;
+}
 
-    let tx_hash = mempool_input.tx.tx_hash;
+/// Waits for the processing of the given received transaction, recording its rejection if the
+/// processing task failed, e.g., panicked.
+async fn join_processing_task(
+    tx_statuses: &TransactionStatusRecords,
+    tx_hash: TransactionHash,
+    processing_task: JoinHandle<GatewayResult<MempoolInput>>,
+) -> GatewayResult<MempoolInput> {
+    processing_task.await.unwrap_or_else(|join_error| {
+        let error = GatewayError::InternalServerError(join_error);
+        tx_statuses.update(tx_hash, TransactionStatus::Rejected { error: error.to_string() });
+        Err(error)
+    })
+}
 
-    app_state
-        .mempool_client
-        .add_tx(mempool_input)
+/// Records that the given transaction was accepted by the mempool, and returns its receipt.
+fn record_acceptance(
+    tx_statuses: &TransactionStatusRecords,
+    tx_hash: TransactionHash,
+    replaced_tx_hash: Option<TransactionHash>,
+) -> AddTransactionReceipt {
+    tx_statuses.update(tx_hash, TransactionStatus::Queued);
+    if let Some(replaced_tx_hash) = replaced_tx_hash {
+        tx_statuses
+            .update(replaced_tx_hash, TransactionStatus::Replaced { replacing_tx_hash: tx_hash });
+    }
+    AddTransactionReceipt { tx_hash, status: TransactionStatus::Queued, replaced_tx_hash }
+}
+
+/// Returns the status of a transaction that was accepted by the mempool.
+async fn get_mempool_tx_status(
+    mempool_client: &dyn MempoolClient,
+    tx_hash: TransactionHash,
+) -> GatewayResult<TransactionStatus> {
+    match mempool_client.get_tx(tx_hash).await {
+        Ok(_) => return Ok(TransactionStatus::Queued),
+        Err(MempoolClientError::MempoolError(MempoolError::TransactionNotFound { .. })) => {}
+        Err(e) => return Err(GatewayError::MessageSendError(e.to_string())),
+    }
+
+    let drop_reason = mempool_client
+        .get_drop_reason(tx_hash)
         .await
         .map_err(|e| GatewayError::MessageSendError(e.to_string()))?;
-    // TODO: Also return `ContractAddress` for deploy and `ClassHash` for Declare.
-    Ok(Json(tx_hash))
+    if let Some(reason) = drop_reason {
+        return Ok(TransactionStatus::Dropped { reason });
+    }
+
+    let is_included = mempool_client
+        .is_tx_included(tx_hash)
+        .await
+        .map_err(|e| GatewayError::MessageSendError(e.to_string()))?;
+    Ok(if is_included { TransactionStatus::Included } else { TransactionStatus::Taken })
 }
 
 fn process_tx(
@@ -134,12 +349,15 @@ fn process_tx(
     };
 
     let validator = stateful_tx_validator.instantiate_validator(state_reader_factory)?;
-    let tx_hash = stateful_tx_validator.run_validate(&tx, optional_class_info, validator)?;
+    let validate_info = stateful_tx_validator.run_validate(&tx, optional_class_info, validator)?;
 
     // TODO(Arni): Add the Sierra and the Casm to the mempool input.
     Ok(MempoolInput {
-        tx: external_tx_to_thin_tx(&tx, tx_hash),
-        account: Account { sender_address: get_sender_address(&tx), ..Default::default() },
+        tx: external_tx_to_thin_tx(&tx, validate_info.tx_hash),
+        account: Account {
+            sender_address: get_sender_address(&tx),
+            state: validate_info.account_state,
+        },
     })
 }
 
@@ -153,6 +371,19 @@ pub fn create_gateway(
     Gateway::new(config, state_reader_factory, gateway_compiler, mempool_client)
 }
 
+/// Creates a reader of the account states in the latest block, with the balances of the fee token
+/// that transactions are validated against.
+pub fn create_account_state_reader(
+    config: &GatewayConfig,
+    rpc_state_reader_config: RpcStateReaderConfig,
+) -> impl AccountStateReader {
+    let state_reader_factory = RpcStateReaderFactory { config: rpc_state_reader_config };
+    MempoolAccountStateReader {
+        state_reader: state_reader_factory.get_state_reader_from_latest_block(),
+        fee_token_address: config.stateful_tx_validator_config.chain_info.strk_fee_token_address,
+    }
+}
+
 #[async_trait]
 impl ComponentStarter for Gateway {
     async fn start(&mut self) -> Result<(), ComponentStartError> {
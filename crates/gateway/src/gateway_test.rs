@@ -1,29 +1,48 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 
+use assert_matches::assert_matches;
 use axum::body::{Bytes, HttpBody};
-use axum::extract::State;
+use axum::extract::{ConnectInfo, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use blockifier::context::ChainInfo;
-use blockifier::test_utils::CairoVersion;
+use blockifier::test_utils::{CairoVersion, BALANCE};
 use mempool_test_utils::starknet_api_test_utils::invoke_tx;
 use mockall::predicate::eq;
 use starknet_api::core::ContractAddress;
 use starknet_api::rpc_transaction::RpcTransaction;
-use starknet_api::transaction::TransactionHash;
-use starknet_mempool_types::communication::MockMempoolClient;
-use starknet_mempool_types::mempool_types::{Account, AccountState, MempoolInput, ThinTransaction};
+use starknet_api::transaction::{Fee, TransactionHash};
+use starknet_api::felt;
+use starknet_mempool_types::communication::{MempoolClientError, MockMempoolClient};
+use starknet_mempool_types::errors::MempoolError;
+use starknet_mempool_types::mempool_types::{
+    Account,
+    AccountState,
+    AddTransactionOutput,
+    DropReason,
+    MempoolInput,
+    ThinTransaction,
+};
 
 use crate::compilation::GatewayCompiler;
 use crate::config::{
     GatewayCompilerConfig,
+    RateLimiterConfig,
     StatefulTransactionValidatorConfig,
     StatelessTransactionValidatorConfig,
+    TokenBucketConfig,
 };
-use crate::gateway::{add_tx, AppState, SharedMempoolClient};
+use crate::gateway::{add_tx, add_txs, get_tx_status, AppState, SharedMempoolClient};
+use crate::rate_limiter::RateLimiter;
 use crate::state_reader_test_utils::{local_test_state_reader_factory, TestStateReaderFactory};
 use crate::stateful_transaction_validator::StatefulTransactionValidator;
 use crate::stateless_transaction_validator::StatelessTransactionValidator;
+use crate::transaction_status::{
+    AddTransactionReceipt,
+    TransactionStatus,
+    TransactionStatusRecords,
+};
 use crate::utils::{external_tx_to_account_tx, get_tx_hash};
 
 pub fn app_state(
@@ -47,9 +66,16 @@ pub fn app_state(
         gateway_compiler: GatewayCompiler { config: GatewayCompilerConfig {} },
         state_reader_factory: Arc::new(state_reader_factory),
         mempool_client,
+        tx_statuses: Arc::new(TransactionStatusRecords::default()),
+        rate_limiter: Arc::new(RateLimiter::new(RateLimiterConfig::default())),
+        max_batch_size: 10,
     }
 }
 
+fn client_addr() -> ConnectInfo<SocketAddr> {
+    ConnectInfo(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080))
+}
+
 type SenderAddress = ContractAddress;
 
 fn create_tx() -> (RpcTransaction, SenderAddress) {
@@ -73,20 +99,225 @@ async fn test_add_tx() {
         .expect_add_tx()
         .once()
         .with(eq(MempoolInput {
-            tx: ThinTransaction { sender_address, tx_hash, tip: *tx.tip(), nonce: *tx.nonce() },
-            account: Account { sender_address, state: AccountState { nonce: *tx.nonce() } },
+            tx: ThinTransaction {
+                sender_address,
+                tx_hash,
+                tip: *tx.tip(),
+                nonce: *tx.nonce(),
+                resource_bounds: tx.resource_bounds().clone(),
+            },
+            account: Account {
+                sender_address,
+                state: AccountState { nonce: *tx.nonce(), balance: Fee(BALANCE) },
+            },
         }))
-        .return_once(|_| Ok(()));
+        .return_once(|_| Ok(AddTransactionOutput::default()));
     let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
     let app_state = app_state(Arc::new(mock_mempool_client), state_reader_factory);
+    let tx_statuses = app_state.tx_statuses.clone();
 
-    let response = add_tx(State(app_state), tx.into()).await.into_response();
+    let response = add_tx(State(app_state), client_addr(), tx.into()).await.into_response();
 
     let status_code = response.status();
     let response_bytes = &to_bytes(response).await;
 
     assert_eq!(status_code, StatusCode::OK, "{response_bytes:?}");
-    assert_eq!(tx_hash, serde_json::from_slice(response_bytes).unwrap());
+    let expected_receipt =
+        AddTransactionReceipt { tx_hash, status: TransactionStatus::Queued, replaced_tx_hash: None };
+    assert_eq!(expected_receipt, serde_json::from_slice(response_bytes).unwrap());
+    assert_eq!(tx_statuses.get(tx_hash), Some(TransactionStatus::Queued));
+}
+
+#[tokio::test]
+async fn test_add_tx_records_rejection() {
+    let (mut tx, _) = create_tx();
+    // Fails the stateless validation, which requires a positive L1 gas bound.
+    match &mut tx {
+        RpcTransaction::Invoke(starknet_api::rpc_transaction::RpcInvokeTransaction::V3(
+            invoke_tx,
+        )) => invoke_tx.resource_bounds.l1_gas.max_amount = 0,
+        _ => panic!("Unexpected transaction type"),
+    }
+    let tx_hash = calculate_hash(&tx);
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let app_state = app_state(Arc::new(MockMempoolClient::new()), state_reader_factory);
+    let tx_statuses = app_state.tx_statuses.clone();
+
+    let response = add_tx(State(app_state), client_addr(), tx.into()).await.into_response();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_matches!(tx_statuses.get(tx_hash), Some(TransactionStatus::Rejected { .. }));
+}
+
+#[tokio::test]
+async fn test_add_txs_returns_result_per_tx() {
+    let (tx, _) = create_tx();
+    let tx_hash = calculate_hash(&tx);
+    let mut invalid_tx = tx.clone();
+    // Fails the stateless validation, which requires a positive L1 gas bound.
+    match &mut invalid_tx {
+        RpcTransaction::Invoke(starknet_api::rpc_transaction::RpcInvokeTransaction::V3(
+            invoke_tx,
+        )) => invoke_tx.resource_bounds.l1_gas.max_amount = 0,
+        _ => panic!("Unexpected transaction type"),
+    }
+    let invalid_tx_hash = calculate_hash(&invalid_tx);
+
+    let mut mock_mempool_client = MockMempoolClient::new();
+    mock_mempool_client
+        .expect_add_txs()
+        .once()
+        .withf(move |mempool_inputs| {
+            mempool_inputs.len() == 1 && mempool_inputs[0].tx.tx_hash == tx_hash
+        })
+        .return_once(|_| Ok(vec![Ok(AddTransactionOutput::default())]));
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let app_state = app_state(Arc::new(mock_mempool_client), state_reader_factory);
+    let tx_statuses = app_state.tx_statuses.clone();
+
+    let receipts = add_txs(State(app_state), client_addr(), vec![invalid_tx, tx].into())
+        .await
+        .unwrap()
+        .0;
+
+    let expected_receipt =
+        AddTransactionReceipt { tx_hash, status: TransactionStatus::Queued, replaced_tx_hash: None };
+    assert_matches!(receipts.as_slice(), [Err(_), Ok(receipt)] if *receipt == expected_receipt);
+    assert_eq!(tx_statuses.get(tx_hash), Some(TransactionStatus::Queued));
+    assert_matches!(tx_statuses.get(invalid_tx_hash), Some(TransactionStatus::Rejected { .. }));
+}
+
+#[tokio::test]
+async fn test_add_txs_rejects_batch_too_large() {
+    let (tx, _) = create_tx();
+    let tx_hash = calculate_hash(&tx);
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let mut app_state = app_state(Arc::new(MockMempoolClient::new()), state_reader_factory);
+    app_state.max_batch_size = 1;
+    let tx_statuses = app_state.tx_statuses.clone();
+
+    let response =
+        add_txs(State(app_state), client_addr(), vec![tx.clone(), tx].into()).await.into_response();
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(tx_statuses.get(tx_hash), None);
+}
+
+#[tokio::test]
+async fn test_add_txs_rate_limited_per_tx() {
+    let (tx, _) = create_tx();
+    let tx_hash = calculate_hash(&tx);
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let mut app_state = app_state(Arc::new(MockMempoolClient::new()), state_reader_factory);
+    let single_request = TokenBucketConfig { capacity: 1, refill_rate_per_second: 0 };
+    app_state.rate_limiter = Arc::new(RateLimiter::new(RateLimiterConfig {
+        per_client_ip: single_request,
+        ..Default::default()
+    }));
+    let tx_statuses = app_state.tx_statuses.clone();
+
+    let response =
+        add_txs(State(app_state), client_addr(), vec![tx.clone(), tx].into()).await.into_response();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(tx_statuses.get(tx_hash), None);
+}
+
+#[tokio::test]
+async fn test_add_tx_rate_limited_by_sender_address() {
+    let (tx, _) = create_tx();
+    let mut mock_mempool_client = MockMempoolClient::new();
+    mock_mempool_client.expect_add_tx().once().return_once(|_| Ok(AddTransactionOutput::default()));
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let mut app_state = app_state(Arc::new(mock_mempool_client), state_reader_factory);
+    let single_request = TokenBucketConfig { capacity: 1, refill_rate_per_second: 1 };
+    app_state.rate_limiter = Arc::new(RateLimiter::new(RateLimiterConfig {
+        per_sender_address: single_request,
+        ..Default::default()
+    }));
+
+    let response =
+        add_tx(State(app_state.clone()), client_addr(), tx.clone().into()).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = add_tx(State(app_state), client_addr(), tx.into()).await.into_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_invalid_txs_do_not_exhaust_sender_rate_limit() {
+    let (tx, _) = create_tx();
+    let tx_hash = calculate_hash(&tx);
+    let mut invalid_tx = tx.clone();
+    // Fails the stateless validation, which requires a positive L1 gas bound.
+    match &mut invalid_tx {
+        RpcTransaction::Invoke(starknet_api::rpc_transaction::RpcInvokeTransaction::V3(
+            invoke_tx,
+        )) => invoke_tx.resource_bounds.l1_gas.max_amount = 0,
+        _ => panic!("Unexpected transaction type"),
+    }
+    let mut mock_mempool_client = MockMempoolClient::new();
+    mock_mempool_client.expect_add_tx().once().return_once(|_| Ok(AddTransactionOutput::default()));
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let mut app_state = app_state(Arc::new(mock_mempool_client), state_reader_factory);
+    let single_request = TokenBucketConfig { capacity: 1, refill_rate_per_second: 1 };
+    app_state.rate_limiter = Arc::new(RateLimiter::new(RateLimiterConfig {
+        per_sender_address: single_request,
+        ..Default::default()
+    }));
+    let tx_statuses = app_state.tx_statuses.clone();
+
+    // Invalid transactions naming the sender don't take its tokens.
+    for _ in 0..3 {
+        let response = add_tx(State(app_state.clone()), client_addr(), invalid_tx.clone().into())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let response = add_tx(State(app_state), client_addr(), tx.into()).await.into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(tx_statuses.get(tx_hash), Some(TransactionStatus::Queued));
+}
+
+#[tokio::test]
+async fn test_get_tx_status() {
+    let not_received_tx_hash = TransactionHash(felt!(1_u8));
+    let queued_tx_hash = TransactionHash(felt!(2_u8));
+    let dropped_tx_hash = TransactionHash(felt!(3_u8));
+    let included_tx_hash = TransactionHash(felt!(4_u8));
+    let taken_tx_hash = TransactionHash(felt!(5_u8));
+
+    let mut mock_mempool_client = MockMempoolClient::new();
+    mock_mempool_client.expect_get_tx().returning(move |tx_hash| {
+        if tx_hash == queued_tx_hash {
+            return Ok(ThinTransaction { tx_hash, ..Default::default() });
+        }
+        Err(MempoolClientError::MempoolError(MempoolError::TransactionNotFound { tx_hash }))
+    });
+    mock_mempool_client.expect_get_drop_reason().returning(move |tx_hash| {
+        Ok((tx_hash == dropped_tx_hash).then_some(DropReason::Evicted))
+    });
+    mock_mempool_client
+        .expect_is_tx_included()
+        .returning(move |tx_hash| Ok(tx_hash == included_tx_hash));
+    let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
+    let app_state = app_state(Arc::new(mock_mempool_client), state_reader_factory);
+    for tx_hash in [queued_tx_hash, dropped_tx_hash, included_tx_hash, taken_tx_hash] {
+        app_state.tx_statuses.update(tx_hash, TransactionStatus::Queued);
+    }
+
+    let expected_statuses = [
+        (not_received_tx_hash, TransactionStatus::NotReceived),
+        (queued_tx_hash, TransactionStatus::Queued),
+        (dropped_tx_hash, TransactionStatus::Dropped { reason: DropReason::Evicted }),
+        (included_tx_hash, TransactionStatus::Included),
+        (taken_tx_hash, TransactionStatus::Taken),
+    ];
+    for (tx_hash, expected_status) in expected_statuses {
+        let response = get_tx_status(State(app_state.clone()), tx_hash.into()).await.unwrap();
+        assert_eq!(response.0, expected_status);
+    }
 }
 
 async fn to_bytes(res: Response) -> Bytes {
@@ -4,6 +4,7 @@ mod compiler_version;
 pub mod config;
 pub mod errors;
 pub mod gateway;
+pub mod rate_limiter;
 mod rpc_objects;
 mod rpc_state_reader;
 #[cfg(test)]
@@ -15,4 +16,5 @@ mod stateful_transaction_validator;
 mod stateless_transaction_validator;
 #[cfg(test)]
 mod test_utils;
+pub mod transaction_status;
 mod utils;
@@ -0,0 +1,203 @@
+use std::collections::{hash_map, BTreeMap, HashMap};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use starknet_api::core::ContractAddress;
+
+use crate::config::{RateLimiterConfig, TokenBucketConfig};
+use crate::errors::{GatewayError, GatewayResult};
+
+#[cfg(test)]
+#[path = "rate_limiter_test.rs"]
+mod rate_limiter_test;
+
+// The number of buckets kept per key type; beyond it, the least recently accessed buckets are
+// discarded. Such buckets are mostly full, hence equivalent to new ones.
+const MAX_BUCKETS: usize = 100_000;
+
+/// The key by which a request was rate limited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateLimitKey {
+    ClientIp(IpAddr),
+    SenderAddress(ContractAddress),
+}
+
+impl Display for RateLimitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitKey::ClientIp(ip) => write!(f, "client IP {ip}"),
+            RateLimitKey::SenderAddress(address) => {
+                write!(f, "sender address {:#x}", address.0.key())
+            }
+        }
+    }
+}
+
+/// Limits the rate of requests by the IP of the client submitting them and by the sender address
+/// of their transactions.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_client_ip: TokenBuckets<IpAddr>,
+    per_sender_address: TokenBuckets<ContractAddress>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimiterConfig) -> Self {
+        Self {
+            per_client_ip: TokenBuckets::new(config.per_client_ip),
+            per_sender_address: TokenBuckets::new(config.per_sender_address),
+        }
+    }
+
+    pub fn check_client_ip(&self, client_ip: IpAddr) -> GatewayResult<()> {
+        self.check_client_ip_at(client_ip, Instant::now())
+    }
+
+    /// Checks a batch of requests of the given client, each counting as a separate request. Either
+    /// all of them are allowed, or none.
+    pub fn check_client_ip_batch(&self, client_ip: IpAddr, batch_size: usize) -> GatewayResult<()> {
+        self.check_client_ip_batch_at(client_ip, batch_size, Instant::now())
+    }
+
+    pub fn check_sender_address(&self, sender_address: ContractAddress) -> GatewayResult<()> {
+        self.check_sender_address_at(sender_address, Instant::now())
+    }
+
+    fn check_client_ip_at(&self, client_ip: IpAddr, now: Instant) -> GatewayResult<()> {
+        self.check_client_ip_batch_at(client_ip, 1, now)
+    }
+
+    fn check_client_ip_batch_at(
+        &self,
+        client_ip: IpAddr,
+        batch_size: usize,
+        now: Instant,
+    ) -> GatewayResult<()> {
+        if self.per_client_ip.try_acquire_many(client_ip, batch_size, now) {
+            return Ok(());
+        }
+        Err(GatewayError::RateLimitExceeded(RateLimitKey::ClientIp(client_ip)))
+    }
+
+    fn check_sender_address_at(
+        &self,
+        sender_address: ContractAddress,
+        now: Instant,
+    ) -> GatewayResult<()> {
+        if self.per_sender_address.try_acquire(sender_address, now) {
+            return Ok(());
+        }
+        Err(GatewayError::RateLimitExceeded(RateLimitKey::SenderAddress(sender_address)))
+    }
+}
+
+#[derive(Debug)]
+struct TokenBuckets<K> {
+    config: TokenBucketConfig,
+    max_buckets: usize,
+    buckets: Mutex<BucketsByKey<K>>,
+}
+
+impl<K: Copy + Eq + Hash> TokenBuckets<K> {
+    fn new(config: TokenBucketConfig) -> Self {
+        Self::with_max_buckets(config, MAX_BUCKETS)
+    }
+
+    fn with_max_buckets(config: TokenBucketConfig, max_buckets: usize) -> Self {
+        Self { config, max_buckets, buckets: Mutex::new(BucketsByKey::default()) }
+    }
+
+    /// Takes a token from the bucket of the given key, if it has any.
+    fn try_acquire(&self, key: K, now: Instant) -> bool {
+        self.try_acquire_many(key, 1, now)
+    }
+
+    /// Takes the given number of tokens from the bucket of the given key, if it has enough.
+    fn try_acquire_many(&self, key: K, n_tokens: usize, now: Instant) -> bool {
+        // Larger numbers exceed any capacity.
+        let n_tokens = f64::from(u32::try_from(n_tokens).unwrap_or(u32::MAX));
+        let mut buckets = self.buckets.lock().expect("Token buckets should not be poisoned.");
+        let capacity = f64::from(self.config.capacity);
+        let bucket = buckets.access(key, self.max_buckets, || TokenBucket {
+            tokens: capacity,
+            last_refill: now,
+            access_id: 0,
+        });
+        bucket.refill(&self.config, now);
+        if bucket.tokens < n_tokens {
+            return false;
+        }
+        bucket.tokens -= n_tokens;
+        true
+    }
+}
+
+/// The token buckets of the keys, along with the order in which they were last accessed.
+#[derive(Debug)]
+struct BucketsByKey<K> {
+    buckets: HashMap<K, TokenBucket>,
+    // The keys by ascending access ID, i.e., from the least recently accessed.
+    keys_by_access: BTreeMap<u64, K>,
+    next_access_id: u64,
+}
+
+impl<K> Default for BucketsByKey<K> {
+    fn default() -> Self {
+        Self { buckets: HashMap::new(), keys_by_access: BTreeMap::new(), next_access_id: 0 }
+    }
+}
+
+impl<K: Copy + Eq + Hash> BucketsByKey<K> {
+    /// Returns the bucket of the given key, marked as the most recently accessed. A missing bucket
+    /// is created, after discarding the least recently accessed bucket if there are `max_buckets`.
+    fn access(
+        &mut self,
+        key: K,
+        max_buckets: usize,
+        new_bucket: impl FnOnce() -> TokenBucket,
+    ) -> &mut TokenBucket {
+        if !self.buckets.contains_key(&key) && self.buckets.len() >= max_buckets {
+            if let Some((_, least_recent_key)) = self.keys_by_access.pop_first() {
+                self.buckets.remove(&least_recent_key);
+            }
+        }
+
+        let access_id = self.next_access_id;
+        self.next_access_id += 1;
+        let bucket = match self.buckets.entry(key) {
+            hash_map::Entry::Occupied(entry) => {
+                let bucket = entry.into_mut();
+                self.keys_by_access.remove(&bucket.access_id);
+                bucket
+            }
+            hash_map::Entry::Vacant(entry) => entry.insert(new_bucket()),
+        };
+        bucket.access_id = access_id;
+        self.keys_by_access.insert(access_id, key);
+        bucket
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+    // The ID of the last access to the bucket, ordering it among the other buckets.
+    access_id: u64,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, config: &TokenBucketConfig, now: Instant) {
+        self.tokens = self.tokens_at(config, now);
+        self.last_refill = self.last_refill.max(now);
+    }
+
+    fn tokens_at(&self, config: &TokenBucketConfig, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        let refilled = self.tokens + elapsed * f64::from(config.refill_rate_per_second);
+        refilled.min(f64::from(config.capacity))
+    }
+}
@@ -0,0 +1,119 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+use assert_matches::assert_matches;
+use starknet_api::core::{ContractAddress, PatriciaKey};
+use starknet_api::{contract_address, felt, patricia_key};
+use validator::Validate;
+
+use crate::config::{RateLimiterConfig, TokenBucketConfig};
+use crate::errors::GatewayError;
+use crate::rate_limiter::{RateLimitKey, RateLimiter, TokenBuckets};
+
+const CLIENT_IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+fn rate_limiter(capacity: u32, refill_rate_per_second: u32) -> RateLimiter {
+    let bucket = TokenBucketConfig { capacity, refill_rate_per_second };
+    RateLimiter::new(RateLimiterConfig { per_client_ip: bucket, per_sender_address: bucket })
+}
+
+#[test]
+fn test_burst_up_to_capacity() {
+    let rate_limiter = rate_limiter(3, 1);
+    let now = Instant::now();
+
+    for _ in 0..3 {
+        rate_limiter.check_client_ip_at(CLIENT_IP, now).unwrap();
+    }
+
+    assert_matches!(
+        rate_limiter.check_client_ip_at(CLIENT_IP, now),
+        Err(GatewayError::RateLimitExceeded(RateLimitKey::ClientIp(CLIENT_IP)))
+    );
+}
+
+#[test]
+fn test_tokens_refill_over_time() {
+    let rate_limiter = rate_limiter(2, 2);
+    let now = Instant::now();
+    for _ in 0..2 {
+        rate_limiter.check_client_ip_at(CLIENT_IP, now).unwrap();
+    }
+    assert!(rate_limiter.check_client_ip_at(CLIENT_IP, now).is_err());
+
+    // Half a second refills a single token.
+    let later = now + Duration::from_millis(500);
+    rate_limiter.check_client_ip_at(CLIENT_IP, later).unwrap();
+    assert!(rate_limiter.check_client_ip_at(CLIENT_IP, later).is_err());
+
+    // Refilling does not exceed the capacity.
+    let much_later = later + Duration::from_secs(60);
+    for _ in 0..2 {
+        rate_limiter.check_client_ip_at(CLIENT_IP, much_later).unwrap();
+    }
+    assert!(rate_limiter.check_client_ip_at(CLIENT_IP, much_later).is_err());
+}
+
+#[test]
+fn test_buckets_are_per_key() {
+    let rate_limiter = rate_limiter(1, 0);
+    let now = Instant::now();
+    let sender_address = contract_address!("0x1");
+    let other_sender_address = contract_address!("0x2");
+
+    rate_limiter.check_sender_address_at(sender_address, now).unwrap();
+    assert_matches!(
+        rate_limiter.check_sender_address_at(sender_address, now),
+        Err(GatewayError::RateLimitExceeded(RateLimitKey::SenderAddress(address)))
+            if address == sender_address
+    );
+
+    rate_limiter.check_sender_address_at(other_sender_address, now).unwrap();
+    // Client IPs and sender addresses are limited separately.
+    rate_limiter.check_client_ip_at(CLIENT_IP, now).unwrap();
+}
+
+#[test]
+fn test_batch_takes_token_per_request() {
+    let rate_limiter = rate_limiter(3, 0);
+    let now = Instant::now();
+
+    rate_limiter.check_client_ip_batch_at(CLIENT_IP, 2, now).unwrap();
+    // A batch is rejected as a whole, taking no tokens.
+    assert_matches!(
+        rate_limiter.check_client_ip_batch_at(CLIENT_IP, 2, now),
+        Err(GatewayError::RateLimitExceeded(RateLimitKey::ClientIp(CLIENT_IP)))
+    );
+    rate_limiter.check_client_ip_at(CLIENT_IP, now).unwrap();
+}
+
+#[test]
+fn test_least_recently_accessed_buckets_are_discarded() {
+    let config = TokenBucketConfig { capacity: 1, refill_rate_per_second: 0 };
+    let token_buckets = TokenBuckets::with_max_buckets(config, 2);
+    let now = Instant::now();
+
+    assert!(token_buckets.try_acquire(1, now));
+    assert!(token_buckets.try_acquire(2, now));
+    // Accessing the bucket of the first key makes the second the least recently accessed.
+    assert!(!token_buckets.try_acquire(1, now));
+
+    // Discards the bucket of the second key, which is then recreated with its full capacity.
+    assert!(token_buckets.try_acquire(3, now));
+    assert!(!token_buckets.try_acquire(1, now));
+    assert!(token_buckets.try_acquire(2, now));
+}
+
+#[test]
+fn test_config_rejects_empty_buckets() {
+    let valid = TokenBucketConfig { capacity: 1, refill_rate_per_second: 1 };
+    assert_matches!(valid.validate(), Ok(()));
+    for invalid in [
+        TokenBucketConfig { capacity: 0, refill_rate_per_second: 1 },
+        TokenBucketConfig { capacity: 1, refill_rate_per_second: 0 },
+    ] {
+        assert_matches!(invalid.validate(), Err(_));
+        let config = RateLimiterConfig { per_sender_address: invalid, ..Default::default() };
+        assert_matches!(config.validate(), Err(_));
+    }
+}
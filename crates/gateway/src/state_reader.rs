@@ -7,8 +7,12 @@ use mockall::automock;
 use starknet_api::block::BlockNumber;
 use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
 use starknet_api::state::StorageKey;
+use starknet_mempool_types::errors::MempoolError;
+use starknet_mempool_types::mempool_types::{AccountState, AccountStateReader, MempoolResult};
 use starknet_types_core::felt::Felt;
 
+use crate::stateful_transaction_validator::balance_to_fee;
+
 pub trait MempoolStateReader: BlockifierStateReader + Send + Sync {
     fn get_block_info(&self) -> Result<BlockInfo, StateError>;
 }
@@ -19,6 +23,28 @@ pub trait StateReaderFactory: Send + Sync {
     fn get_state_reader(&self, block_number: BlockNumber) -> Box<dyn MempoolStateReader>;
 }
 
+/// Reads the states of accounts, as tracked by the mempool, from the given state reader.
+pub struct MempoolAccountStateReader {
+    pub state_reader: Box<dyn MempoolStateReader>,
+    // The fee token whose balance is tracked.
+    pub fee_token_address: ContractAddress,
+}
+
+impl AccountStateReader for MempoolAccountStateReader {
+    fn get_account_state(&self, address: ContractAddress) -> MempoolResult<AccountState> {
+        let to_mempool_error = |error: StateError| MempoolError::AccountStateReadError {
+            address,
+            error: error.to_string(),
+        };
+        let nonce = self.state_reader.get_nonce_at(address).map_err(to_mempool_error)?;
+        let balance = self
+            .state_reader
+            .get_fee_token_balance(address, self.fee_token_address)
+            .map_err(to_mempool_error)?;
+        Ok(AccountState { nonce, balance: balance_to_fee(balance) })
+    }
+}
+
 // By default, a Box<dyn Trait> does not implement the trait of the object it contains.
 // Therefore, for using the Box<dyn MempoolStateReader>, that the StateReaderFactory creates,
 // we need to implement the MempoolStateReader trait for Box<dyn MempoolStateReader>.
@@ -11,9 +11,11 @@ use blockifier::transaction::account_transaction::AccountTransaction;
 use blockifier::versioned_constants::VersionedConstants;
 #[cfg(test)]
 use mockall::automock;
+use num_traits::ToPrimitive;
 use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::rpc_transaction::{RpcInvokeTransaction, RpcTransaction};
-use starknet_api::transaction::TransactionHash;
+use starknet_api::transaction::{Fee, TransactionHash};
+use starknet_mempool_types::mempool_types::AccountState;
 use starknet_types_core::felt::Felt;
 
 use crate::config::StatefulTransactionValidatorConfig;
@@ -31,6 +33,14 @@ pub struct StatefulTransactionValidator {
 
 type BlockifierStatefulValidator = StatefulValidator<Box<dyn MempoolStateReader>>;
 
+/// The outcome of a successful stateful validation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidateInfo {
+    pub tx_hash: TransactionHash,
+    // The state of the sender account, as read during the validation.
+    pub account_state: AccountState,
+}
+
 // TODO(yair): move the trait to Blockifier.
 #[cfg_attr(test, automock)]
 pub trait StatefulTransactionValidatorTrait {
@@ -44,6 +54,12 @@ pub trait StatefulTransactionValidatorTrait {
         &mut self,
         account_address: ContractAddress,
     ) -> BlockifierStatefulValidatorResult<Nonce>;
+
+    fn get_fee_token_balance(
+        &mut self,
+        account_address: ContractAddress,
+        fee_token_address: ContractAddress,
+    ) -> BlockifierStatefulValidatorResult<(Felt, Felt)>;
 }
 
 impl StatefulTransactionValidatorTrait for BlockifierStatefulValidator {
@@ -61,6 +77,14 @@ impl StatefulTransactionValidatorTrait for BlockifierStatefulValidator {
     ) -> BlockifierStatefulValidatorResult<Nonce> {
         self.get_nonce(account_address)
     }
+
+    fn get_fee_token_balance(
+        &mut self,
+        account_address: ContractAddress,
+        fee_token_address: ContractAddress,
+    ) -> BlockifierStatefulValidatorResult<(Felt, Felt)> {
+        self.get_fee_token_balance(account_address, fee_token_address)
+    }
 }
 
 impl StatefulTransactionValidator {
@@ -69,17 +93,24 @@ impl StatefulTransactionValidator {
         external_tx: &RpcTransaction,
         optional_class_info: Option<ClassInfo>,
         mut validator: V,
-    ) -> StatefulTransactionValidatorResult<TransactionHash> {
+    ) -> StatefulTransactionValidatorResult<ValidateInfo> {
         let account_tx = external_tx_to_account_tx(
             external_tx,
             optional_class_info,
             &self.config.chain_info.chain_id,
         )?;
         let tx_hash = get_tx_hash(&account_tx);
-        let account_nonce = validator.get_nonce(get_sender_address(external_tx))?;
+        let sender_address = get_sender_address(external_tx);
+        let account_nonce = validator.get_nonce(sender_address)?;
+        // All supported transactions pay their fees in STRK.
+        let balance = validator
+            .get_fee_token_balance(sender_address, self.config.chain_info.strk_fee_token_address)?;
         let skip_validate = skip_stateful_validations(external_tx, account_nonce);
         validator.validate(account_tx, skip_validate)?;
-        Ok(tx_hash)
+        Ok(ValidateInfo {
+            tx_hash,
+            account_state: AccountState { nonce: account_nonce, balance: balance_to_fee(balance) },
+        })
     }
 
     pub fn instantiate_validator(
@@ -125,6 +156,15 @@ fn skip_stateful_validations(tx: &RpcTransaction, account_nonce: Nonce) -> bool
     }
 }
 
+// Converts a fee-token balance, given as the (low, high) words of a 256-bit integer, to a fee
+// amount; balances that do not fit are capped.
+pub(crate) fn balance_to_fee((low, high): (Felt, Felt)) -> Fee {
+    match (high == Felt::ZERO).then(|| low.to_u128()).flatten() {
+        Some(balance) => Fee(balance),
+        None => Fee(u128::MAX),
+    }
+}
+
 pub fn get_latest_block_info(
     state_reader_factory: &dyn StateReaderFactory,
 ) -> StatefulTransactionValidatorResult<BlockInfo> {
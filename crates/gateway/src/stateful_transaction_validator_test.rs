@@ -1,6 +1,6 @@
 use blockifier::blockifier::stateful_validator::StatefulValidatorError;
 use blockifier::context::BlockContext;
-use blockifier::test_utils::CairoVersion;
+use blockifier::test_utils::{CairoVersion, BALANCE};
 use blockifier::transaction::errors::{TransactionFeeError, TransactionPreValidationError};
 use mempool_test_utils::invoke_tx_args;
 use mempool_test_utils::starknet_api_test_utils::{
@@ -18,7 +18,8 @@ use rstest::{fixture, rstest};
 use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::felt;
 use starknet_api::rpc_transaction::RpcTransaction;
-use starknet_api::transaction::TransactionHash;
+use starknet_api::transaction::{Fee, TransactionHash};
+use starknet_mempool_types::mempool_types::AccountState;
 use starknet_types_core::felt::Felt;
 
 use crate::compilation::GatewayCompiler;
@@ -27,8 +28,10 @@ use crate::errors::{StatefulTransactionValidatorError, StatefulTransactionValida
 use crate::state_reader::{MockStateReaderFactory, StateReaderFactory};
 use crate::state_reader_test_utils::local_test_state_reader_factory;
 use crate::stateful_transaction_validator::{
+    balance_to_fee,
     MockStatefulTransactionValidatorTrait,
     StatefulTransactionValidator,
+    ValidateInfo,
 };
 
 #[fixture]
@@ -51,9 +54,12 @@ fn stateful_validator(block_context: BlockContext) -> StatefulTransactionValidat
 #[rstest]
 #[case::valid_tx(
     invoke_tx(CairoVersion::Cairo1),
-    Ok(TransactionHash(felt!(
-        "0x152b8dd0c30e95fa3a4ee7a9398fcfc46fb00c048b4fdcfa9958c64d65899b8"
-    )))
+    Ok(ValidateInfo {
+        tx_hash: TransactionHash(felt!(
+            "0x152b8dd0c30e95fa3a4ee7a9398fcfc46fb00c048b4fdcfa9958c64d65899b8"
+        )),
+        account_state: AccountState { nonce: Nonce(Felt::ZERO), balance: Fee(BALANCE) },
+    })
 )]
 #[case::invalid_tx(
     invoke_tx(CairoVersion::Cairo1),
@@ -71,7 +77,7 @@ fn stateful_validator(block_context: BlockContext) -> StatefulTransactionValidat
 )]
 fn test_stateful_tx_validator(
     #[case] external_tx: RpcTransaction,
-    #[case] expected_result: StatefulTransactionValidatorResult<TransactionHash>,
+    #[case] expected_result: StatefulTransactionValidatorResult<ValidateInfo>,
     stateful_validator: StatefulTransactionValidator,
 ) {
     let optional_class_info = match &external_tx {
@@ -92,11 +98,21 @@ fn test_stateful_tx_validator(
         _ => panic!("Expecting StatefulTransactionValidatorError::StatefulValidatorError"),
     });
     mock_validator.expect_get_nonce().returning(|_| Ok(Nonce(Felt::ZERO)));
+    mock_validator
+        .expect_get_fee_token_balance()
+        .returning(|_, _| Ok((Felt::from(BALANCE), Felt::ZERO)));
 
     let result = stateful_validator.run_validate(&external_tx, optional_class_info, mock_validator);
     assert_eq!(format!("{:?}", result), expected_result_msg);
 }
 
+#[rstest]
+#[case::fits_in_u128(Felt::from(BALANCE), Felt::ZERO, Fee(BALANCE))]
+#[case::high_word_is_set(Felt::ZERO, Felt::ONE, Fee(u128::MAX))]
+fn test_balance_to_fee(#[case] low: Felt, #[case] high: Felt, #[case] expected_fee: Fee) {
+    assert_eq!(balance_to_fee((low, high)), expected_fee);
+}
+
 #[test]
 fn test_instantiate_validator() {
     let state_reader_factory = local_test_state_reader_factory(CairoVersion::Cairo1, false);
@@ -169,6 +185,7 @@ fn test_skip_stateful_validation(
         // TODO(yair): get the sender addres from the external_tx.
         .withf(move |contract_address| *contract_address == sender_address)
         .returning(move |_| Ok(sender_nonce));
+    mock_validator.expect_get_fee_token_balance().returning(|_, _| Ok((Felt::ZERO, Felt::ZERO)));
     mock_validator
         .expect_validate()
         .withf(move |_, skip_validate| *skip_validate == should_skip_validate)
@@ -0,0 +1,85 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use starknet_api::transaction::TransactionHash;
+use starknet_mempool_types::mempool_types::DropReason;
+
+#[cfg(test)]
+#[path = "transaction_status_test.rs"]
+mod transaction_status_test;
+
+// The number of transactions whose status is remembered; beyond it, the oldest records are
+// discarded.
+const MAX_TX_STATUS_RECORDS: usize = 100_000;
+
+/// The status of a transaction submitted to the gateway.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TransactionStatus {
+    /// Not received by the gateway, or received too long ago to be remembered.
+    NotReceived,
+    /// Received, and being validated.
+    Received,
+    /// Rejected by the validations of the gateway, or by the mempool.
+    Rejected { error: String },
+    /// Held in the mempool, waiting to be sequenced.
+    Queued,
+    /// Replaced in the mempool by a transaction with the same sender and nonce.
+    Replaced { replacing_tx_hash: TransactionHash },
+    /// Dropped from the mempool without being included in a block.
+    Dropped { reason: DropReason },
+    /// Taken from the mempool for block creation, but not yet known to be in a committed block.
+    Taken,
+    /// Included in a committed block.
+    Included,
+}
+
+/// The outcome of successfully submitting a transaction to the gateway.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddTransactionReceipt {
+    pub tx_hash: TransactionHash,
+    pub status: TransactionStatus,
+    // The hash of the transaction that was replaced by the submitted one (fee escalation), if any.
+    pub replaced_tx_hash: Option<TransactionHash>,
+}
+
+/// Records the status of the transactions submitted to the gateway, up to their acceptance by the
+/// mempool; from then on, their status is tracked by the mempool.
+#[derive(Debug, Default)]
+pub struct TransactionStatusRecords(Mutex<StatusRecords>);
+
+#[derive(Debug, Default)]
+struct StatusRecords {
+    statuses: HashMap<TransactionHash, TransactionStatus>,
+    // The recorded transactions, from the earliest submitted to the latest.
+    order: VecDeque<TransactionHash>,
+}
+
+impl TransactionStatusRecords {
+    /// Records the status of the given transaction. Resubmitting a transaction that was accepted
+    /// by the mempool does not affect its status.
+    pub fn update(&self, tx_hash: TransactionHash, status: TransactionStatus) {
+        let mut records = self.records();
+        let is_resubmission =
+            matches!(status, TransactionStatus::Received | TransactionStatus::Rejected { .. });
+        match records.statuses.get(&tx_hash) {
+            Some(TransactionStatus::Queued) if is_resubmission => return,
+            Some(_) => {}
+            None => records.order.push_back(tx_hash),
+        }
+        records.statuses.insert(tx_hash, status);
+
+        if records.order.len() > MAX_TX_STATUS_RECORDS {
+            let oldest_tx_hash = records.order.pop_front().expect("Records should not be empty.");
+            records.statuses.remove(&oldest_tx_hash);
+        }
+    }
+
+    pub fn get(&self, tx_hash: TransactionHash) -> Option<TransactionStatus> {
+        self.records().statuses.get(&tx_hash).cloned()
+    }
+
+    fn records(&self) -> MutexGuard<'_, StatusRecords> {
+        self.0.lock().expect("Transaction status records should not be poisoned.")
+    }
+}
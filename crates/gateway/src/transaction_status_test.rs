@@ -0,0 +1,23 @@
+use starknet_api::felt;
+use starknet_api::transaction::TransactionHash;
+
+use crate::transaction_status::{TransactionStatus, TransactionStatusRecords};
+
+#[test]
+fn test_resubmission_does_not_affect_accepted_tx() {
+    let records = TransactionStatusRecords::default();
+    let tx_hash = TransactionHash(felt!(1_u8));
+    records.update(tx_hash, TransactionStatus::Received);
+    records.update(tx_hash, TransactionStatus::Queued);
+
+    let rejection = TransactionStatus::Rejected { error: "Duplicate transaction".to_string() };
+    for status in [TransactionStatus::Received, rejection] {
+        records.update(tx_hash, status);
+        assert_eq!(records.get(tx_hash), Some(TransactionStatus::Queued));
+    }
+
+    let replacement =
+        TransactionStatus::Replaced { replacing_tx_hash: TransactionHash(felt!(2_u8)) };
+    records.update(tx_hash, replacement.clone());
+    assert_eq!(records.get(tx_hash), Some(replacement));
+}
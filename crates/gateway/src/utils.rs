@@ -8,8 +8,11 @@ use blockifier::transaction::transactions::{
 use starknet_api::core::{calculate_contract_address, ChainId, ClassHash, ContractAddress, Nonce};
 use starknet_api::rpc_transaction::{
     RpcDeclareTransaction,
+    RpcDeclareTransactionV3,
     RpcDeployAccountTransaction,
+    RpcDeployAccountTransactionV3,
     RpcInvokeTransaction,
+    RpcInvokeTransactionV3,
     RpcTransaction,
 };
 use starknet_api::transaction::{
@@ -23,6 +26,7 @@ use starknet_api::transaction::{
     TransactionHash,
     TransactionHasher,
 };
+use starknet_api::StarknetApiError;
 use starknet_mempool_types::mempool_types::ThinTransaction;
 
 use crate::errors::StatefulTransactionValidatorResult;
@@ -61,6 +65,7 @@ pub fn external_tx_to_thin_tx(
         nonce: *external_tx.nonce(),
         sender_address: get_sender_address(external_tx),
         tx_hash,
+        resource_bounds: external_tx.resource_bounds().clone(),
     }
 }
 
@@ -90,20 +95,7 @@ pub fn external_tx_to_account_tx(
 ) -> StatefulTransactionValidatorResult<AccountTransaction> {
     match external_tx {
         RpcTransaction::Declare(RpcDeclareTransaction::V3(tx)) => {
-            let declare_tx = DeclareTransaction::V3(DeclareTransactionV3 {
-                class_hash: ClassHash::default(), /* FIXME(yael 15/4/24): call the starknet-api
-                                                   * function once ready */
-                resource_bounds: tx.resource_bounds.clone().into(),
-                tip: tx.tip,
-                signature: tx.signature.clone(),
-                nonce: tx.nonce,
-                compiled_class_hash: tx.compiled_class_hash,
-                sender_address: tx.sender_address,
-                nonce_data_availability_mode: tx.nonce_data_availability_mode,
-                fee_data_availability_mode: tx.fee_data_availability_mode,
-                paymaster_data: tx.paymaster_data.clone(),
-                account_deployment_data: tx.account_deployment_data.clone(),
-            });
+            let declare_tx = rpc_declare_tx_to_declare_tx(tx);
             let tx_hash = declare_tx.calculate_transaction_hash(chain_id, &declare_tx.version())?;
             let class_info =
                 optional_class_info.expect("declare transaction should contain class info");
@@ -111,18 +103,7 @@ pub fn external_tx_to_account_tx(
             Ok(AccountTransaction::Declare(declare_tx))
         }
         RpcTransaction::DeployAccount(RpcDeployAccountTransaction::V3(tx)) => {
-            let deploy_account_tx = DeployAccountTransaction::V3(DeployAccountTransactionV3 {
-                resource_bounds: tx.resource_bounds.clone().into(),
-                tip: tx.tip,
-                signature: tx.signature.clone(),
-                nonce: tx.nonce,
-                class_hash: tx.class_hash,
-                contract_address_salt: tx.contract_address_salt,
-                constructor_calldata: tx.constructor_calldata.clone(),
-                nonce_data_availability_mode: tx.nonce_data_availability_mode,
-                fee_data_availability_mode: tx.fee_data_availability_mode,
-                paymaster_data: tx.paymaster_data.clone(),
-            });
+            let deploy_account_tx = rpc_deploy_account_tx_to_deploy_account_tx(tx);
             let contract_address = calculate_contract_address(
                 deploy_account_tx.contract_address_salt(),
                 deploy_account_tx.class_hash(),
@@ -139,18 +120,7 @@ pub fn external_tx_to_account_tx(
             Ok(AccountTransaction::DeployAccount(deploy_account_tx))
         }
         RpcTransaction::Invoke(RpcInvokeTransaction::V3(tx)) => {
-            let invoke_tx = InvokeTransaction::V3(InvokeTransactionV3 {
-                resource_bounds: tx.resource_bounds.clone().into(),
-                tip: tx.tip,
-                signature: tx.signature.clone(),
-                nonce: tx.nonce,
-                sender_address: tx.sender_address,
-                calldata: tx.calldata.clone(),
-                nonce_data_availability_mode: tx.nonce_data_availability_mode,
-                fee_data_availability_mode: tx.fee_data_availability_mode,
-                paymaster_data: tx.paymaster_data.clone(),
-                account_deployment_data: tx.account_deployment_data.clone(),
-            });
+            let invoke_tx = rpc_invoke_tx_to_invoke_tx(tx);
             let tx_hash = invoke_tx.calculate_transaction_hash(chain_id, &invoke_tx.version())?;
             let invoke_tx = BlockifierInvokeTransaction::new(invoke_tx, tx_hash);
             Ok(AccountTransaction::Invoke(invoke_tx))
@@ -158,6 +128,76 @@ pub fn external_tx_to_account_tx(
     }
 }
 
+/// Calculates the hash of the given transaction, without validating it.
+pub fn calculate_tx_hash(
+    external_tx: &RpcTransaction,
+    chain_id: &ChainId,
+) -> Result<TransactionHash, StarknetApiError> {
+    match external_tx {
+        RpcTransaction::Declare(RpcDeclareTransaction::V3(tx)) => {
+            let declare_tx = rpc_declare_tx_to_declare_tx(tx);
+            declare_tx.calculate_transaction_hash(chain_id, &declare_tx.version())
+        }
+        RpcTransaction::DeployAccount(RpcDeployAccountTransaction::V3(tx)) => {
+            let deploy_account_tx = rpc_deploy_account_tx_to_deploy_account_tx(tx);
+            deploy_account_tx.calculate_transaction_hash(chain_id, &deploy_account_tx.version())
+        }
+        RpcTransaction::Invoke(RpcInvokeTransaction::V3(tx)) => {
+            let invoke_tx = rpc_invoke_tx_to_invoke_tx(tx);
+            invoke_tx.calculate_transaction_hash(chain_id, &invoke_tx.version())
+        }
+    }
+}
+
+fn rpc_declare_tx_to_declare_tx(tx: &RpcDeclareTransactionV3) -> DeclareTransaction {
+    DeclareTransaction::V3(DeclareTransactionV3 {
+        class_hash: ClassHash::default(), /* FIXME(yael 15/4/24): call the starknet-api
+                                           * function once ready */
+        resource_bounds: tx.resource_bounds.clone().into(),
+        tip: tx.tip,
+        signature: tx.signature.clone(),
+        nonce: tx.nonce,
+        compiled_class_hash: tx.compiled_class_hash,
+        sender_address: tx.sender_address,
+        nonce_data_availability_mode: tx.nonce_data_availability_mode,
+        fee_data_availability_mode: tx.fee_data_availability_mode,
+        paymaster_data: tx.paymaster_data.clone(),
+        account_deployment_data: tx.account_deployment_data.clone(),
+    })
+}
+
+fn rpc_deploy_account_tx_to_deploy_account_tx(
+    tx: &RpcDeployAccountTransactionV3,
+) -> DeployAccountTransaction {
+    DeployAccountTransaction::V3(DeployAccountTransactionV3 {
+        resource_bounds: tx.resource_bounds.clone().into(),
+        tip: tx.tip,
+        signature: tx.signature.clone(),
+        nonce: tx.nonce,
+        class_hash: tx.class_hash,
+        contract_address_salt: tx.contract_address_salt,
+        constructor_calldata: tx.constructor_calldata.clone(),
+        nonce_data_availability_mode: tx.nonce_data_availability_mode,
+        fee_data_availability_mode: tx.fee_data_availability_mode,
+        paymaster_data: tx.paymaster_data.clone(),
+    })
+}
+
+fn rpc_invoke_tx_to_invoke_tx(tx: &RpcInvokeTransactionV3) -> InvokeTransaction {
+    InvokeTransaction::V3(InvokeTransactionV3 {
+        resource_bounds: tx.resource_bounds.clone().into(),
+        tip: tx.tip,
+        signature: tx.signature.clone(),
+        nonce: tx.nonce,
+        sender_address: tx.sender_address,
+        calldata: tx.calldata.clone(),
+        nonce_data_availability_mode: tx.nonce_data_availability_mode,
+        fee_data_availability_mode: tx.fee_data_availability_mode,
+        paymaster_data: tx.paymaster_data.clone(),
+        account_deployment_data: tx.account_deployment_data.clone(),
+    })
+}
+
 // TODO(yael 9/5/54): Remove once we we transition to InternalTransaction
 pub fn get_tx_hash(tx: &AccountTransaction) -> TransactionHash {
     match tx {
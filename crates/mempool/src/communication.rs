@@ -1,31 +1,46 @@
 use std::net::IpAddr;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use starknet_api::core::ContractAddress;
+use starknet_api::transaction::TransactionHash;
 use starknet_mempool_infra::component_definitions::ComponentRequestHandler;
-use starknet_mempool_infra::component_runner::ComponentStarter;
-use starknet_mempool_infra::component_server::{LocalComponentServer, RemoteComponentServer};
+use starknet_mempool_infra::component_runner::{ComponentStartError, ComponentStarter};
+use starknet_mempool_infra::component_server::{
+    LocalActiveComponentServer,
+    RemoteActiveComponentServer,
+};
 use starknet_mempool_types::communication::{
     MempoolRequest,
     MempoolRequestAndResponseSender,
     MempoolResponse,
 };
-use starknet_mempool_types::mempool_types::{MempoolInput, MempoolResult, ThinTransaction};
+use starknet_mempool_types::mempool_types::{
+    AddTransactionOutput,
+    DropReason,
+    MempoolInput,
+    MempoolResult,
+    MempoolStats,
+    ThinTransaction,
+};
 use tokio::sync::mpsc::Receiver;
+use tokio::sync::Mutex;
+use tracing::error;
 
 use crate::mempool::Mempool;
 
 pub type MempoolServer =
-    LocalComponentServer<MempoolCommunicationWrapper, MempoolRequest, MempoolResponse>;
+    LocalActiveComponentServer<MempoolCommunicationWrapper, MempoolRequest, MempoolResponse>;
 
 pub type RemoteMempoolServer =
-    RemoteComponentServer<MempoolCommunicationWrapper, MempoolRequest, MempoolResponse>;
+    RemoteActiveComponentServer<MempoolCommunicationWrapper, MempoolRequest, MempoolResponse>;
 
 pub fn create_mempool_server(
     mempool: Mempool,
     rx_mempool: Receiver<MempoolRequestAndResponseSender>,
 ) -> MempoolServer {
     let communication_wrapper = MempoolCommunicationWrapper::new(mempool);
-    LocalComponentServer::new(communication_wrapper, rx_mempool)
+    LocalActiveComponentServer::new(communication_wrapper, rx_mempool)
 }
 
 pub fn create_remote_mempool_server(
@@ -34,25 +49,58 @@ pub fn create_remote_mempool_server(
     port: u16,
 ) -> RemoteMempoolServer {
     let communication_wrapper = MempoolCommunicationWrapper::new(mempool);
-    RemoteComponentServer::new(communication_wrapper, ip_address, port)
+    RemoteActiveComponentServer::new(communication_wrapper, ip_address, port)
 }
 
 /// Wraps the mempool to enable inbound async communication from other components.
+/// The mempool is shared with the component's background task, which removes expired
+/// transactions.
+#[derive(Clone)]
 pub struct MempoolCommunicationWrapper {
-    mempool: Mempool,
+    mempool: Arc<Mutex<Mempool>>,
 }
 
 impl MempoolCommunicationWrapper {
     pub fn new(mempool: Mempool) -> Self {
-        MempoolCommunicationWrapper { mempool }
+        MempoolCommunicationWrapper { mempool: Arc::new(Mutex::new(mempool)) }
+    }
+
+    async fn add_tx(&self, mempool_input: MempoolInput) -> MempoolResult<AddTransactionOutput> {
+        self.mempool.lock().await.add_tx(mempool_input)
+    }
+
+    async fn add_txs(
+        &self,
+        mempool_inputs: Vec<MempoolInput>,
+    ) -> MempoolResult<Vec<MempoolResult<AddTransactionOutput>>> {
+        Ok(self.mempool.lock().await.add_txs(mempool_inputs))
+    }
+
+    async fn get_txs(&self, n_txs: usize) -> MempoolResult<Vec<ThinTransaction>> {
+        self.mempool.lock().await.get_txs(n_txs)
+    }
+
+    async fn get_tx(&self, tx_hash: TransactionHash) -> MempoolResult<ThinTransaction> {
+        self.mempool.lock().await.get_tx(tx_hash)
+    }
+
+    async fn get_account_txs(
+        &self,
+        address: ContractAddress,
+    ) -> MempoolResult<Vec<ThinTransaction>> {
+        Ok(self.mempool.lock().await.get_account_txs(address))
+    }
+
+    async fn get_stats(&self) -> MempoolResult<MempoolStats> {
+        Ok(self.mempool.lock().await.stats())
     }
 
-    fn add_tx(&mut self, mempool_input: MempoolInput) -> MempoolResult<()> {
-        self.mempool.add_tx(mempool_input)
+    async fn get_drop_reason(&self, tx_hash: TransactionHash) -> MempoolResult<Option<DropReason>> {
+        Ok(self.mempool.lock().await.get_drop_reason(tx_hash))
     }
 
-    fn get_txs(&mut self, n_txs: usize) -> MempoolResult<Vec<ThinTransaction>> {
-        self.mempool.get_txs(n_txs)
+    async fn is_tx_included(&self, tx_hash: TransactionHash) -> MempoolResult<bool> {
+        Ok(self.mempool.lock().await.is_included(tx_hash))
     }
 }
 
@@ -61,14 +109,43 @@ impl ComponentRequestHandler<MempoolRequest, MempoolResponse> for MempoolCommuni
     async fn handle_request(&mut self, request: MempoolRequest) -> MempoolResponse {
         match request {
             MempoolRequest::AddTransaction(mempool_input) => {
-                MempoolResponse::AddTransaction(self.add_tx(mempool_input))
+                MempoolResponse::AddTransaction(self.add_tx(mempool_input).await)
+            }
+            MempoolRequest::AddTransactions(mempool_inputs) => {
+                MempoolResponse::AddTransactions(self.add_txs(mempool_inputs).await)
             }
             MempoolRequest::GetTransactions(n_txs) => {
-                MempoolResponse::GetTransactions(self.get_txs(n_txs))
+                MempoolResponse::GetTransactions(self.get_txs(n_txs).await)
+            }
+            MempoolRequest::GetTransaction(tx_hash) => {
+                MempoolResponse::GetTransaction(self.get_tx(tx_hash).await)
+            }
+            MempoolRequest::GetAccountTransactions(address) => {
+                MempoolResponse::GetAccountTransactions(self.get_account_txs(address).await)
+            }
+            MempoolRequest::GetStats => MempoolResponse::GetStats(self.get_stats().await),
+            MempoolRequest::GetDropReason(tx_hash) => {
+                MempoolResponse::GetDropReason(self.get_drop_reason(tx_hash).await)
+            }
+            MempoolRequest::IsTransactionIncluded(tx_hash) => {
+                MempoolResponse::IsTransactionIncluded(self.is_tx_included(tx_hash).await)
             }
         }
     }
 }
 
 #[async_trait]
-impl ComponentStarter for MempoolCommunicationWrapper {}
+impl ComponentStarter for MempoolCommunicationWrapper {
+    /// Periodically removes expired transactions from the mempool. A failed removal is retried on
+    /// the next sweep.
+    async fn start(&mut self) -> Result<(), ComponentStartError> {
+        let sweep_interval = self.mempool.lock().await.config().expired_txs_sweep_interval;
+        let mut interval = tokio::time::interval(sweep_interval);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.mempool.lock().await.remove_expired_txs() {
+                error!("Failed removing expired transactions: {err}.");
+            }
+        }
+    }
+}
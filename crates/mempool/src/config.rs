@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use papyrus_config::converters::deserialize_seconds_to_duration;
+use papyrus_config::dumping::{ser_optional_param, ser_param, SerializeConfig};
+use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+/// The mempool configuration.
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, PartialEq)]
+pub struct MempoolConfig {
+    // If true, a transaction may replace a pending transaction with the same sender and nonce.
+    pub enable_fee_escalation: bool,
+    // The minimal increase, in percentage, of the tip and of each resource bound's max price per
+    // unit, required for a transaction to replace an existing one.
+    pub fee_escalation_percentage: u8,
+    // The maximal number of transactions held in the mempool.
+    #[validate(range(min = 1))]
+    pub max_txs: usize,
+    // The maximal number of transactions of a single account held in the mempool.
+    #[validate(range(min = 1))]
+    pub max_txs_per_account: usize,
+    // The maximal total size, in bytes, of the transactions held in the mempool.
+    #[validate(range(min = 1))]
+    pub max_total_size_in_bytes: usize,
+    // The time a transaction is held in the mempool before it expires and is dropped.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    #[validate(custom = "validate_non_zero_duration")]
+    pub transaction_ttl: Duration,
+    // The interval between consecutive sweeps of expired transactions.
+    #[serde(deserialize_with = "deserialize_seconds_to_duration")]
+    #[validate(custom = "validate_non_zero_duration")]
+    pub expired_txs_sweep_interval: Duration,
+    // The file journaling the accepted transactions and committed nonces, from which the mempool
+    // is restored after a restart. If not set, the mempool starts empty.
+    pub journal_path: Option<PathBuf>,
+}
+
+fn validate_non_zero_duration(duration: &Duration) -> Result<(), ValidationError> {
+    if duration.is_zero() {
+        return Err(ValidationError::new("The duration must be positive."));
+    }
+    Ok(())
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            enable_fee_escalation: true,
+            fee_escalation_percentage: 10,
+            max_txs: 100_000,
+            max_txs_per_account: 64,
+            max_total_size_in_bytes: 64 * 1024 * 1024,
+            transaction_ttl: Duration::from_secs(60 * 60),
+            expired_txs_sweep_interval: Duration::from_secs(60),
+            journal_path: None,
+        }
+    }
+}
+
+impl SerializeConfig for MempoolConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = BTreeMap::from_iter([
+            ser_param(
+                "enable_fee_escalation",
+                &self.enable_fee_escalation,
+                "If true, a transaction with the same sender and nonce as a pending transaction \
+                 replaces it, given it pays sufficiently higher fees.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "fee_escalation_percentage",
+                &self.fee_escalation_percentage,
+                "Minimal increase, in percentage, of the tip and the resource bounds' prices \
+                 required for replacing a pending transaction.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_txs",
+                &self.max_txs,
+                "Maximal number of transactions in the mempool; when reached, transactions with \
+                 the lowest priority are evicted.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_txs_per_account",
+                &self.max_txs_per_account,
+                "Maximal number of transactions of a single account in the mempool.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "max_total_size_in_bytes",
+                &self.max_total_size_in_bytes,
+                "Maximal total size, in bytes, of the transactions in the mempool; when reached, \
+                 transactions with the lowest priority are evicted.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "transaction_ttl",
+                &self.transaction_ttl.as_secs(),
+                "Time (seconds) a transaction is held in the mempool before it expires.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "expired_txs_sweep_interval",
+                &self.expired_txs_sweep_interval.as_secs(),
+                "Interval (seconds) between consecutive removals of expired transactions.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(ser_optional_param(
+            &self.journal_path,
+            PathBuf::from("./data/mempool_journal"),
+            "journal_path",
+            "File journaling the mempool's transactions, from which it is restored after a \
+             restart.",
+            ParamPrivacyInput::Public,
+        ));
+        config
+    }
+}
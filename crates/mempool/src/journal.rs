@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::core::{ContractAddress, Nonce};
+use starknet_api::transaction::TransactionHash;
+use starknet_mempool_types::errors::MempoolError;
+use starknet_mempool_types::mempool_types::{MempoolInput, MempoolResult};
+
+/// A change to the content of the mempool, recorded in its journal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JournalEntry {
+    /// A transaction accepted by the mempool, and the time it was received.
+    AddTx { input: MempoolInput, received_at: SystemTime },
+    /// A transaction dropped from the mempool, i.e., evicted or expired.
+    DropTx(TransactionHash),
+    /// The nonces of the accounts whose transactions were committed in a block.
+    CommitBlock(HashMap<ContractAddress, Nonce>),
+}
+
+/// An append-only file, holding one JSON-serialized entry per line, from which the mempool is
+/// restored after a restart.
+#[derive(Debug)]
+pub struct MempoolJournal {
+    path: PathBuf,
+    file: File,
+    n_entries: usize,
+}
+
+impl MempoolJournal {
+    /// Reads the entries of the journal at the given path; a missing journal has no entries.
+    /// A partially written last entry, e.g., due to a crash, is ignored.
+    pub fn read(path: &Path) -> MempoolResult<Vec<JournalEntry>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(journal_error(error)),
+        };
+
+        let lines =
+            BufReader::new(file).lines().collect::<io::Result<Vec<_>>>().map_err(journal_error)?;
+        let n_lines = lines.len();
+        let mut entries = Vec::with_capacity(n_lines);
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if i + 1 == n_lines => break,
+                Err(error) => return Err(journal_error(error)),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Creates a journal holding the given entries at the given path, replacing the existing one.
+    pub fn create(
+        path: &Path,
+        entries: impl IntoIterator<Item = JournalEntry>,
+    ) -> MempoolResult<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(journal_error)?;
+        }
+        // Written aside and then renamed, so that a crash does not lose the existing journal.
+        let tmp_path = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path).map_err(journal_error)?);
+        let mut n_entries = 0;
+        for entry in entries {
+            write_entry(&mut writer, &entry)?;
+            n_entries += 1;
+        }
+        writer.into_inner().map_err(journal_error)?.sync_all().map_err(journal_error)?;
+        fs::rename(&tmp_path, path).map_err(journal_error)?;
+
+        let file = OpenOptions::new().append(true).open(path).map_err(journal_error)?;
+        Ok(MempoolJournal { path: path.to_path_buf(), file, n_entries })
+    }
+
+    /// Appends an entry to the journal. Returns once the entry is durable.
+    pub fn append(&mut self, entry: &JournalEntry) -> MempoolResult<()> {
+        write_entry(&mut self.file, entry)?;
+        self.n_entries += 1;
+        self.file.sync_data().map_err(journal_error)
+    }
+
+    /// Replaces the content of the journal with the given entries.
+    pub fn compact(
+        &mut self,
+        entries: impl IntoIterator<Item = JournalEntry>,
+    ) -> MempoolResult<()> {
+        *self = MempoolJournal::create(&self.path, entries)?;
+        Ok(())
+    }
+
+    /// Returns the number of entries in the journal.
+    pub fn n_entries(&self) -> usize {
+        self.n_entries
+    }
+}
+
+fn write_entry(writer: &mut impl Write, entry: &JournalEntry) -> MempoolResult<()> {
+    let mut line = serde_json::to_vec(entry).map_err(journal_error)?;
+    line.push(b'\n');
+    writer.write_all(&line).map_err(journal_error)
+}
+
+fn journal_error(error: impl ToString) -> MempoolError {
+    MempoolError::JournalError(error.to_string())
+}
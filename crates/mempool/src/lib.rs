@@ -1,5 +1,10 @@
 pub mod communication;
+pub mod config;
+pub mod journal;
 pub mod mempool;
+pub mod metrics;
 pub(crate) mod transaction_queue;
 // TODO(Mohammad): change to `pub(crate)`.
 pub mod transaction_pool;
+pub(crate) mod tx_records;
+pub mod utils;
@@ -1,25 +1,39 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+use std::time::Instant;
 
 use starknet_api::core::{ContractAddress, Nonce};
-use starknet_api::transaction::{Tip, TransactionHash};
+use starknet_api::transaction::{Fee, Tip, TransactionHash};
 use starknet_mempool_types::errors::MempoolError;
 use starknet_mempool_types::mempool_types::{
     Account,
     AccountState,
+    AccountStateReader,
+    AddTransactionOutput,
+    DropReason,
     MempoolInput,
     MempoolResult,
+    MempoolStats,
     ThinTransaction,
 };
 
-use crate::transaction_pool::TransactionPool;
+use crate::config::MempoolConfig;
+use crate::journal::{JournalEntry, MempoolJournal};
+use crate::metrics::{MEMPOOL_EVICTED_TXS, MEMPOOL_EXPIRED_TXS};
+use crate::transaction_pool::{tx_size_in_bytes, TransactionPool};
 use crate::transaction_queue::TransactionQueue;
+use crate::tx_records::{DroppedTransactions, IncludedTransactions};
+use crate::utils::{Clock, InstantClock};
 
 #[cfg(test)]
 #[path = "mempool_test.rs"]
 pub mod mempool_test;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Mempool {
+    config: MempoolConfig,
+    clock: Arc<dyn Clock>,
     // TODO: add docstring explaining visibility and coupling of the fields.
     // All transactions currently held in the mempool.
     tx_pool: TransactionPool,
@@ -27,14 +41,68 @@ pub struct Mempool {
     tx_queue: TransactionQueue,
     // Represents the current state of the mempool during block creation.
     mempool_state: HashMap<ContractAddress, AccountState>,
+    // Transactions taken for block creation since the last committed block.
+    staged_txs: Vec<TransactionReference>,
+    // Transactions dropped from the mempool without being included in a block.
+    dropped_txs: DroppedTransactions,
+    // Transactions taken from the mempool and included in a committed block.
+    included_txs: IncludedTransactions,
+    // Records the changes to the mempool's content, if configured.
+    journal: Option<MempoolJournal>,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Mempool {
+            config: MempoolConfig::default(),
+            clock: Arc::new(InstantClock),
+            tx_pool: TransactionPool::default(),
+            tx_queue: TransactionQueue::default(),
+            mempool_state: HashMap::default(),
+            staged_txs: Vec::default(),
+            dropped_txs: DroppedTransactions::default(),
+            included_txs: IncludedTransactions::default(),
+            journal: None,
+        }
+    }
 }
 
 impl Mempool {
-    pub fn new(inputs: impl IntoIterator<Item = MempoolInput>) -> MempoolResult<Self> {
+    /// Creates a mempool with the given configuration. If a journal is configured, the mempool is
+    /// restored from it: its transactions are re-added with the latest state of their accounts and
+    /// the time they were originally received, and those that were dropped or became stale are
+    /// not. The journal is then compacted to the restored transactions.
+    pub fn new(
+        config: MempoolConfig,
+        account_state_reader: &dyn AccountStateReader,
+    ) -> MempoolResult<Self> {
+        Mempool::new_with_clock(config, Arc::new(InstantClock), account_state_reader)
+    }
+
+    fn new_with_clock(
+        config: MempoolConfig,
+        clock: Arc<dyn Clock>,
+        account_state_reader: &dyn AccountStateReader,
+    ) -> MempoolResult<Self> {
+        let journal_path = config.journal_path.clone();
+        let mut mempool = Mempool { clock, ..Mempool::with_config(config) };
+        let Some(journal_path) = journal_path else {
+            return Ok(mempool);
+        };
+
+        let restored_entries =
+            mempool.restore(MempoolJournal::read(&journal_path)?, account_state_reader)?;
+        mempool.journal = Some(MempoolJournal::create(&journal_path, restored_entries)?);
+
+        Ok(mempool)
+    }
+
+    pub fn with_txs(inputs: impl IntoIterator<Item = MempoolInput>) -> MempoolResult<Self> {
         let mut mempool = Mempool::empty();
 
         for input in inputs {
-            mempool.insert_tx(input)?;
+            let received_at = mempool.clock.now();
+            mempool.insert_tx(input, received_at)?;
         }
         Ok(mempool)
     }
@@ -43,6 +111,14 @@ impl Mempool {
         Mempool::default()
     }
 
+    pub fn with_config(config: MempoolConfig) -> Self {
+        Mempool { config, ..Mempool::default() }
+    }
+
+    pub fn config(&self) -> &MempoolConfig {
+        &self.config
+    }
+
     /// Returns an iterator of the current eligible transactions for sequencing, ordered by their
     /// priority.
     pub fn iter(&self) -> impl Iterator<Item = &TransactionReference> {
@@ -75,20 +151,105 @@ impl Mempool {
         for tx in &eligible_txs {
             self.mempool_state.entry(tx.sender_address).or_default().nonce = tx.nonce;
         }
+        self.staged_txs.extend(eligible_tx_references);
 
         Ok(eligible_txs)
     }
 
     /// Adds a new transaction to the mempool.
-    /// TODO: support fee escalation and transactions with future nonces.
-    /// TODO: check Account nonce and balance.
-    pub fn add_tx(&mut self, input: MempoolInput) -> MempoolResult<()> {
+    /// If a transaction with the same sender and nonce is already pending, the new transaction
+    /// replaces it, provided that fee escalation is enabled and that its tip and resource bounds
+    /// are sufficiently higher; the hash of the replaced transaction is returned.
+    /// If the mempool is full, the transactions with the lowest priority (by tip, then age) among
+    /// those with the highest nonce of their account are evicted to make room for the new
+    /// transaction, unless it ranks below all of them.
+    /// The account's balance must cover the maximal fees of all its pending transactions.
+    /// TODO: support transactions with future nonces.
+    pub fn add_tx(&mut self, input: MempoolInput) -> MempoolResult<AddTransactionOutput> {
+        self.add_tx_received_at(input, self.clock.now())
+    }
+
+    /// Adds the given transaction to the mempool, as received at the given time.
+    fn add_tx_received_at(
+        &mut self,
+        input: MempoolInput,
+        received_at: Instant,
+    ) -> MempoolResult<AddTransactionOutput> {
         self.validate_input(&input)?;
-        self.insert_tx(input)
+        let journal_entry = self.journal.is_some().then(|| JournalEntry::AddTx {
+            input: input.clone(),
+            received_at: self.clock.to_system_time(received_at),
+        });
+        let output = self.insert_tx(input, received_at)?;
+        if let Some(journal_entry) = journal_entry {
+            self.append_to_journal(&journal_entry)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Adds the given transactions in order, as if by successive calls to `add_tx`; a rejected
+    /// transaction does not affect the others.
+    pub fn add_txs(
+        &mut self,
+        inputs: Vec<MempoolInput>,
+    ) -> Vec<MempoolResult<AddTransactionOutput>> {
+        inputs.into_iter().map(|input| self.add_tx(input)).collect()
+    }
+
+    /// Removes the transactions received more than the configured time-to-live ago, whether they
+    /// are eligible for sequencing or are waiting for a nonce gap to be filled.
+    pub fn remove_expired_txs(&mut self) -> MempoolResult<()> {
+        let Some(expiry_threshold) = self.clock.now().checked_sub(self.config.transaction_ttl)
+        else {
+            return Ok(());
+        };
+
+        let expired_txs: Vec<TransactionReference> =
+            self.tx_pool.iter_received_before(expiry_threshold).copied().collect();
+        for tx in expired_txs {
+            self.drop_tx(tx, DropReason::Expired)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_tx(&self, tx_hash: TransactionHash) -> MempoolResult<ThinTransaction> {
+        self.tx_pool.get_by_tx_hash(tx_hash).cloned()
+    }
+
+    /// Returns the pending transactions of the given account, including those waiting for a
+    /// missing nonce, sorted by ascending nonce.
+    pub fn get_account_txs(&self, address: ContractAddress) -> Vec<ThinTransaction> {
+        self.tx_pool.account_txs(address).cloned().collect()
+    }
+
+    pub fn stats(&self) -> MempoolStats {
+        MempoolStats {
+            n_queued_txs: self.tx_queue.len(),
+            n_pool_txs: self.tx_pool.n_txs(),
+            n_accounts_with_nonce_gaps: self
+                .tx_pool
+                .accounts()
+                .filter(|&address| self.has_nonce_gap(address))
+                .count(),
+        }
+    }
+
+    /// Returns the reason the given transaction was dropped from the mempool, if it was.
+    pub fn get_drop_reason(&self, tx_hash: TransactionHash) -> Option<DropReason> {
+        self.dropped_txs.get(tx_hash)
+    }
+
+    /// Returns whether the given transaction was taken from the mempool and then included in a
+    /// committed block. Only the latest inclusions since the mempool started are remembered.
+    pub fn is_included(&self, tx_hash: TransactionHash) -> bool {
+        self.included_txs.get(tx_hash).is_some()
     }
 
     /// Update the mempool's internal state according to the committed block (resolves nonce gaps,
-    /// updates account balances).
+    /// removes the committed transactions). Account balances are not tracked by the mempool; they
+    /// are given with each added transaction.
     // TODO: the part about resolving nonce gaps is incorrect if we delete txs in get_txs and then
     // push back.
     // state_changes: a map that associates each account address with the state of the committed
@@ -97,7 +258,7 @@ impl Mempool {
         &mut self,
         state_changes: HashMap<ContractAddress, AccountState>,
     ) -> MempoolResult<()> {
-        for (&address, AccountState { nonce }) in &state_changes {
+        for (&address, AccountState { nonce, .. }) in &state_changes {
             let next_nonce = nonce.try_increment().map_err(|_| MempoolError::FeltOutOfRange)?;
 
             // Align the queue with the committed nonces.
@@ -127,14 +288,134 @@ impl Mempool {
 
         self.mempool_state.clear();
 
-        Ok(())
+        // A staged transaction is included if the committed nonce of its account reached it.
+        for tx in std::mem::take(&mut self.staged_txs) {
+            if state_changes
+                .get(&tx.sender_address)
+                .is_some_and(|account_state| tx.nonce <= account_state.nonce)
+            {
+                self.included_txs.insert(tx.tx_hash, ());
+            }
+        }
+
+        let committed_nonces =
+            state_changes.iter().map(|(&address, account_state)| (address, account_state.nonce));
+        self.append_to_journal(&JournalEntry::CommitBlock(committed_nonces.collect()))?;
+        self.compact_journal_if_needed()
+    }
+
+    /// Re-adds the journaled transactions that were neither committed nor dropped, with the
+    /// latest state of their accounts and the time they were originally received. Transactions
+    /// that expired, or that fail validation against the latest state, e.g., whose nonce was
+    /// already used or which their account can no longer afford, are dropped.
+    /// Returns the journal entries of the restored transactions.
+    fn restore(
+        &mut self,
+        journal_entries: Vec<JournalEntry>,
+        account_state_reader: &dyn AccountStateReader,
+    ) -> MempoolResult<Vec<JournalEntry>> {
+        // The added transactions by the order they were added, with those dropped later removed.
+        let mut added_txs = Vec::new();
+        let mut added_tx_indices = HashMap::new();
+        let mut committed_nonces = HashMap::new();
+        for journal_entry in journal_entries {
+            match journal_entry {
+                JournalEntry::AddTx { input, received_at } => {
+                    added_tx_indices.insert(input.tx.tx_hash, added_txs.len());
+                    added_txs.push(Some((input, received_at)));
+                }
+                JournalEntry::DropTx(tx_hash) => {
+                    if let Some(index) = added_tx_indices.remove(&tx_hash) {
+                        added_txs[index] = None;
+                    }
+                }
+                JournalEntry::CommitBlock(nonces) => committed_nonces.extend(nonces),
+            }
+        }
+
+        let expiry_threshold = self.clock.now().checked_sub(self.config.transaction_ttl);
+        let mut account_states = HashMap::new();
+        let mut restored_entries = Vec::new();
+        for (MempoolInput { tx, account }, received_at) in added_txs.into_iter().flatten() {
+            let address = tx.sender_address;
+            if committed_nonces
+                .get(&address)
+                .is_some_and(|&committed_nonce| tx.nonce <= committed_nonce)
+            {
+                continue;
+            }
+
+            // A transaction received before the clock's earliest representable time is expired.
+            let Some(received_at_instant) = self.clock.to_instant(received_at) else {
+                continue;
+            };
+            if expiry_threshold.is_some_and(|threshold| received_at_instant < threshold) {
+                continue;
+            }
+
+            let state = match account_states.entry(address) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    *entry.insert(account_state_reader.get_account_state(address)?)
+                }
+            };
+            let input = MempoolInput { tx, account: Account { state, ..account } };
+            if self.add_tx_received_at(input.clone(), received_at_instant).is_ok() {
+                restored_entries.push(JournalEntry::AddTx { input, received_at });
+            }
+        }
+
+        // Transactions may have been replaced or evicted by later ones.
+        restored_entries.retain(|entry| match entry {
+            JournalEntry::AddTx { input, .. } => {
+                self.tx_pool.get_by_tx_hash(input.tx.tx_hash).is_ok()
+            }
+            _ => false,
+        });
+        Ok(restored_entries)
     }
 
-    fn insert_tx(&mut self, input: MempoolInput) -> MempoolResult<()> {
-        let MempoolInput { tx, account: Account { sender_address, state: AccountState { nonce } } } =
-            input;
+    fn insert_tx(
+        &mut self,
+        input: MempoolInput,
+        received_at: Instant,
+    ) -> MempoolResult<AddTransactionOutput> {
+        let MempoolInput {
+            tx,
+            account: Account { sender_address, state: AccountState { nonce, balance } },
+        } = input;
+        let tx_reference = TransactionReference::new(&tx);
+
+        // Validated before any modification, so that a replacement or an eviction is applied to the
+        // pool and the queue as a whole, and only if the transaction is inserted.
+        if self.tx_pool.get_by_tx_hash(tx.tx_hash).is_ok() {
+            return Err(MempoolError::DuplicateTransaction { tx_hash: tx.tx_hash });
+        }
+        let replaced_tx = self.get_replaced_tx(&tx)?;
+        self.validate_balance(&tx, balance, replaced_tx)?;
+        // A replacement does not add to the mempool's size.
+        let txs_to_evict = match replaced_tx {
+            Some(_) => Vec::new(),
+            None => self.get_txs_to_evict(&tx)?,
+        };
+
+        if let Some(replaced_tx) = replaced_tx {
+            self.tx_pool.remove(replaced_tx.tx_hash)?;
+        }
+        for tx_to_evict in txs_to_evict {
+            self.drop_tx(tx_to_evict, DropReason::Evicted)?;
+        }
 
-        self.tx_pool.insert(tx)?;
+        self.tx_pool.insert(tx, received_at)?;
+        self.dropped_txs.remove(tx_reference.tx_hash);
+
+        // The replacing transaction takes the place of the replaced one in the queue.
+        if let Some(replaced_tx) = replaced_tx {
+            if self.tx_queue.get_nonce(sender_address) == Some(replaced_tx.nonce) {
+                self.tx_queue.remove(sender_address);
+                self.tx_queue.insert(tx_reference);
+            }
+        }
 
         // Maybe close nonce gap.
         if self.tx_queue.get_nonce(sender_address).is_none() {
@@ -144,13 +425,173 @@ impl Mempool {
             }
         }
 
+        Ok(AddTransactionOutput { replaced_tx_hash: replaced_tx.map(|tx| tx.tx_hash) })
+    }
+
+    /// Returns the pending transaction that the given transaction replaces, i.e., the one with the
+    /// same sender and nonce, if such exists.
+    /// Fails if the given transaction does not qualify for replacing it.
+    fn get_replaced_tx(&self, tx: &ThinTransaction) -> MempoolResult<Option<TransactionReference>> {
+        let ThinTransaction { sender_address: address, nonce, tx_hash, .. } = *tx;
+        let Some(existing_tx_reference) = self.tx_pool.get_by_address_and_nonce(address, nonce)
+        else {
+            return Ok(None);
+        };
+
+        if !self.config.enable_fee_escalation {
+            return Err(MempoolError::DuplicateNonce { address, nonce });
+        }
+
+        let existing_tx = self.tx_pool.get_by_tx_hash(existing_tx_reference.tx_hash)?;
+        if !self.is_sufficient_fee_escalation(existing_tx, tx) {
+            return Err(MempoolError::InsufficientFeeEscalation { address, nonce, tx_hash });
+        }
+
+        Ok(Some(*existing_tx_reference))
+    }
+
+    /// Verifies that the given balance covers the maximal fees of the given transaction and of the
+    /// other pending transactions of its account, excluding the one it replaces.
+    fn validate_balance(
+        &self,
+        tx: &ThinTransaction,
+        balance: Fee,
+        replaced_tx: Option<TransactionReference>,
+    ) -> MempoolResult<()> {
+        let replaced_tx_hash = replaced_tx.map(|replaced_tx| replaced_tx.tx_hash);
+        let total_max_fee = self
+            .tx_pool
+            .account_txs(tx.sender_address)
+            .filter(|pending_tx| Some(pending_tx.tx_hash) != replaced_tx_hash)
+            .fold(tx.max_fee().0, |total, pending_tx| total.saturating_add(pending_tx.max_fee().0));
+
+        if total_max_fee > balance.0 {
+            return Err(MempoolError::InsufficientBalance {
+                address: tx.sender_address,
+                tx_hash: tx.tx_hash,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the transactions to evict for the given transaction to fit in the mempool; each is
+    /// the lowest priority transaction that may be evicted after the preceding evictions.
+    /// Only the transaction with the highest nonce of an account is evicted, so that evictions do
+    /// not open nonce gaps; in particular, a queued transaction is evicted only if it is the only
+    /// transaction of its account.
+    /// Fails if its account reached its transaction limit, or if the mempool is full and the given
+    /// transaction does not rank above enough transactions to make room for it.
+    fn get_txs_to_evict(&self, tx: &ThinTransaction) -> MempoolResult<Vec<TransactionReference>> {
+        let ThinTransaction { sender_address: address, tx_hash, tip, .. } = *tx;
+        if self.tx_pool.n_account_txs(address) >= self.config.max_txs_per_account {
+            return Err(MempoolError::AccountTransactionLimitExceeded { address, tx_hash });
+        }
+
+        let mut n_txs = self.tx_pool.n_txs() + 1;
+        let mut size_in_bytes = self.tx_pool.size_in_bytes() + tx_size_in_bytes(tx);
+        let mut txs_to_evict = Vec::new();
+        // The lowest nonce evicted from each account.
+        let mut evicted_nonces = HashMap::new();
+        while n_txs > self.config.max_txs || size_in_bytes > self.config.max_total_size_in_bytes {
+            // Rescanned after each eviction, as evicting the last transaction of an account makes
+            // its preceding transaction a candidate, which may rank lower than those scanned.
+            // The given transaction is the newest, so it ranks below transactions with an equal
+            // tip.
+            let Some(tx_to_evict) = self
+                .tx_pool
+                .iter_by_ascending_priority()
+                .take_while(|candidate| candidate.tip < tip)
+                .find(|candidate| self.is_last_account_tx(candidate, tx, &evicted_nonces))
+            else {
+                return Err(MempoolError::MempoolFull { tx_hash });
+            };
+
+            n_txs -= 1;
+            size_in_bytes -= self.tx_pool.get_size_in_bytes(tx_to_evict.tx_hash)?;
+            evicted_nonces.insert(tx_to_evict.sender_address, tx_to_evict.nonce);
+            txs_to_evict.push(*tx_to_evict);
+        }
+
+        Ok(txs_to_evict)
+    }
+
+    /// Returns whether the given transaction has the highest nonce of its account, once the given
+    /// evictions are applied and the incoming transaction is added. Evicted transactions are not
+    /// considered the last of their account.
+    fn is_last_account_tx(
+        &self,
+        tx: &TransactionReference,
+        incoming_tx: &ThinTransaction,
+        evicted_nonces: &HashMap<ContractAddress, Nonce>,
+    ) -> bool {
+        let address = tx.sender_address;
+        if address == incoming_tx.sender_address && tx.nonce < incoming_tx.nonce {
+            return false;
+        }
+
+        // Evictions of an account are by descending nonce, so its evicted transactions are
+        // exactly those from its lowest evicted nonce onwards.
+        let evicted_nonce = evicted_nonces.get(&address);
+        if evicted_nonce.is_some_and(|&evicted_nonce| tx.nonce >= evicted_nonce) {
+            return false;
+        }
+
+        match self.tx_pool.get_next_account_tx(address, tx.nonce) {
+            Some(next_tx) => evicted_nonce == Some(&next_tx.nonce),
+            None => true,
+        }
+    }
+
+    /// Removes the given transaction from the mempool, and records the reason. Later transactions
+    /// of its account are kept, and become eligible once the nonce gap is filled.
+    fn drop_tx(&mut self, tx: TransactionReference, reason: DropReason) -> MempoolResult<()> {
+        let TransactionReference { sender_address, nonce, tx_hash, .. } = tx;
+        self.tx_pool.remove(tx_hash)?;
+        self.append_to_journal(&JournalEntry::DropTx(tx_hash))?;
+        if self.tx_queue.get_nonce(sender_address) == Some(nonce) {
+            self.tx_queue.remove(sender_address);
+        }
+
+        self.dropped_txs.insert(tx_hash, reason);
+        match reason {
+            DropReason::Evicted => metrics::increment_counter!(MEMPOOL_EVICTED_TXS),
+            DropReason::Expired => metrics::increment_counter!(MEMPOOL_EXPIRED_TXS),
+        }
+
         Ok(())
     }
 
+    /// A transaction may replace another if its tip and the max price per unit of each of its
+    /// resources are higher by at least the configured percentage.
+    fn is_sufficient_fee_escalation(
+        &self,
+        existing_tx: &ThinTransaction,
+        incoming_tx: &ThinTransaction,
+    ) -> bool {
+        let percentage = u128::from(self.config.fee_escalation_percentage);
+        // Compares `incoming >= existing * (100 + percentage) / 100` without division.
+        let is_escalated = |existing: u128, incoming: u128| {
+            incoming.saturating_mul(100) >= existing.saturating_mul(100 + percentage)
+        };
+
+        let (existing_bounds, incoming_bounds) =
+            (&existing_tx.resource_bounds, &incoming_tx.resource_bounds);
+        is_escalated(existing_tx.tip.0.into(), incoming_tx.tip.0.into())
+            && is_escalated(
+                existing_bounds.l1_gas.max_price_per_unit,
+                incoming_bounds.l1_gas.max_price_per_unit,
+            )
+            && is_escalated(
+                existing_bounds.l2_gas.max_price_per_unit,
+                incoming_bounds.l2_gas.max_price_per_unit,
+            )
+    }
+
     fn validate_input(&self, input: &MempoolInput) -> MempoolResult<()> {
         // Check nonce against mempool state.
         let MempoolInput { tx, account } = input;
-        if let Some(AccountState { nonce }) = self.mempool_state.get(&tx.sender_address) {
+        if let Some(AccountState { nonce, .. }) = self.mempool_state.get(&tx.sender_address) {
             if nonce >= &tx.nonce {
                 return Err(MempoolError::DuplicateNonce {
                     address: tx.sender_address,
@@ -160,7 +601,7 @@ impl Mempool {
         }
 
         // Check nonce against given account state.
-        let Account { state: AccountState { nonce }, .. } = account;
+        let Account { state: AccountState { nonce, .. }, .. } = account;
         if nonce > &tx.nonce {
             return Err(MempoolError::DuplicateNonce {
                 address: tx.sender_address,
@@ -171,11 +612,54 @@ impl Mempool {
         Ok(())
     }
 
+    /// An account has a nonce gap if its pending transactions do not form a consecutive sequence
+    /// of nonces, starting at its queued transaction.
+    fn has_nonce_gap(&self, address: ContractAddress) -> bool {
+        let mut expected_nonce = self.tx_queue.get_nonce(address);
+        for tx in self.tx_pool.account_txs(address) {
+            if expected_nonce != Some(tx.nonce) {
+                return true;
+            }
+            expected_nonce = tx.nonce.try_increment().ok();
+        }
+
+        false
+    }
+
+    fn append_to_journal(&mut self, journal_entry: &JournalEntry) -> MempoolResult<()> {
+        match &mut self.journal {
+            Some(journal) => journal.append(journal_entry),
+            None => Ok(()),
+        }
+    }
+
+    /// Compacts the journal to the transactions in the mempool, once most of its entries are
+    /// obsolete. This keeps its size proportional to the mempool's, at an amortized constant cost
+    /// per entry. The account states of the transactions are not kept, as they are read anew when
+    /// the mempool is restored.
+    fn compact_journal_if_needed(&mut self) -> MempoolResult<()> {
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
+        };
+        if journal.n_entries() <= 2 * self.tx_pool.n_txs() {
+            return Ok(());
+        }
+
+        let clock = &self.clock;
+        journal.compact(self.tx_pool.iter_by_arrival().map(|(received_at, tx)| {
+            let account = Account { sender_address: tx.sender_address, ..Default::default() };
+            JournalEntry::AddTx {
+                input: MempoolInput { tx: tx.clone(), account },
+                received_at: clock.to_system_time(received_at),
+            }
+        }))
+    }
+
     fn enqueue_next_eligible_txs(&mut self, txs: &[TransactionReference]) -> MempoolResult<()> {
         for tx in txs {
             let current_account_state = Account {
                 sender_address: tx.sender_address,
-                state: AccountState { nonce: tx.nonce },
+                state: AccountState { nonce: tx.nonce, ..Default::default() },
             };
 
             if let Some(next_tx_reference) =
@@ -1,20 +1,35 @@
 use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
 use rstest::{fixture, rstest};
 use starknet_api::core::{ContractAddress, Nonce, PatriciaKey};
 use starknet_api::hash::StarkHash;
-use starknet_api::transaction::{Tip, TransactionHash};
+use starknet_api::rpc_transaction::ResourceBoundsMapping;
+use starknet_api::transaction::{Fee, ResourceBounds, Tip, TransactionHash};
 use starknet_api::{contract_address, felt, patricia_key};
 use starknet_mempool_types::errors::MempoolError;
-use starknet_mempool_types::mempool_types::{Account, AccountState, ThinTransaction};
+use starknet_mempool_types::mempool_types::{
+    Account,
+    AccountState,
+    AccountStateReader,
+    AddTransactionOutput,
+    DropReason,
+    MempoolResult,
+    MempoolStats,
+    ThinTransaction,
+};
 use starknet_types_core::felt::Felt;
 
+use crate::config::MempoolConfig;
+use crate::journal::{JournalEntry, MempoolJournal};
 use crate::mempool::{Mempool, MempoolInput, TransactionReference};
-use crate::transaction_pool::TransactionPool;
+use crate::transaction_pool::{tx_size_in_bytes, TransactionPool};
 use crate::transaction_queue::TransactionQueue;
+use crate::utils::Clock;
 
 /// Represents the internal state of the mempool.
 /// Enables customized (and potentially inconsistent) creation for unit testing.
@@ -94,6 +109,7 @@ impl<T> From<MempoolState<T>> for Mempool {
             tx_queue: tx_queue.unwrap_or_default(),
             // TODO: Add implementation when needed.
             mempool_state: Default::default(),
+            ..Mempool::default()
         }
     }
 }
@@ -112,7 +128,7 @@ impl FromIterator<ThinTransaction> for TransactionPool {
     fn from_iter<T: IntoIterator<Item = ThinTransaction>>(txs: T) -> Self {
         let mut pool = Self::default();
         for tx in txs {
-            pool.insert(tx).unwrap();
+            pool.insert(tx, Instant::now()).unwrap();
         }
         pool
     }
@@ -128,9 +144,47 @@ impl FromIterator<TransactionReference> for TransactionQueue {
     }
 }
 
+/// A clock whose time advances only when explicitly requested.
+#[derive(Debug)]
+struct FakeClock {
+    now: Mutex<(Instant, SystemTime)>,
+}
+
+impl FakeClock {
+    fn new() -> Self {
+        let system_now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        FakeClock { now: Mutex::new((Instant::now(), system_now)) }
+    }
+
+    fn advance(&self, duration: Duration) {
+        let (now, system_now) = &mut *self.now.lock().unwrap();
+        *now += duration;
+        *system_now += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.lock().unwrap().0
+    }
+
+    fn system_now(&self) -> SystemTime {
+        self.now.lock().unwrap().1
+    }
+}
+
+/// Reads the account states from a fixed map; missing accounts have the default state.
+struct FakeAccountStateReader(HashMap<ContractAddress, AccountState>);
+
+impl AccountStateReader for FakeAccountStateReader {
+    fn get_account_state(&self, address: ContractAddress) -> MempoolResult<AccountState> {
+        Ok(self.0.get(&address).copied().unwrap_or_default())
+    }
+}
+
 #[track_caller]
 fn add_tx(mempool: &mut Mempool, input: &MempoolInput) {
-    assert_eq!(mempool.add_tx(input.clone()), Ok(()));
+    assert_eq!(mempool.add_tx(input.clone()), Ok(AddTransactionOutput::default()));
 }
 
 /// Creates a valid input for mempool's `add_tx` with optional default values.
@@ -140,20 +194,33 @@ fn add_tx(mempool: &mut Mempool, input: &MempoolInput) {
 /// 3. add_tx_input!(tip: 1, tx_hash: 2, sender_address: 3_u8)
 /// 4. add_tx_input!(tx_hash: 1, tx_nonce: 1, account_nonce: 0)
 /// 5. add_tx_input!(tip: 1, tx_hash: 2)
+/// 6. add_tx_input!(tip: 1, tx_hash: 2, sender_address: 3_u8, tx_nonce: 4, account_nonce: 3,
+///    max_l1_gas_price: 5)
 macro_rules! add_tx_input {
     (tip: $tip:expr, tx_hash: $tx_hash:expr, sender_address: $sender_address:expr,
-        tx_nonce: $tx_nonce:expr, account_nonce: $account_nonce:expr) => {{
+        tx_nonce: $tx_nonce:expr, account_nonce: $account_nonce:expr,
+        max_l1_gas_price: $max_l1_gas_price:expr) => {{
         let sender_address = contract_address!($sender_address);
         let account_nonce = Nonce(felt!($account_nonce));
-        let account = Account { sender_address, state: AccountState {nonce: account_nonce}};
+        let account = Account { sender_address, state: AccountState {nonce: account_nonce, ..Default::default()}};
+        let resource_bounds = ResourceBoundsMapping {
+            l1_gas: ResourceBounds { max_amount: 0, max_price_per_unit: $max_l1_gas_price },
+            ..Default::default()
+        };
         let tx = ThinTransaction {
             tip: Tip($tip),
             tx_hash: TransactionHash(StarkHash::from($tx_hash)),
             sender_address,
             nonce: Nonce(felt!($tx_nonce)),
+            resource_bounds,
         };
         MempoolInput { tx, account }
     }};
+    (tip: $tip:expr, tx_hash: $tx_hash:expr, sender_address: $sender_address:expr,
+        tx_nonce: $tx_nonce:expr, account_nonce: $account_nonce:expr) => {
+        add_tx_input!(tip: $tip, tx_hash: $tx_hash, sender_address: $sender_address,
+            tx_nonce: $tx_nonce, account_nonce: $account_nonce, max_l1_gas_price: 0)
+    };
     (tx_hash: $tx_hash:expr, sender_address: $sender_address:expr, tx_nonce: $tx_nonce:expr, account_nonce: $account_nonce:expr) => {
         add_tx_input!(tip: 0, tx_hash: $tx_hash, sender_address: $sender_address, tx_nonce: $tx_nonce, account_nonce: $account_nonce)
     };
@@ -343,13 +410,31 @@ fn test_add_tx_multi_nonce_success(mut mempool: Mempool) {
     expected_mempool_state.assert_eq_mempool_state(&mempool);
 }
 
+#[rstest]
+fn test_add_txs_rejects_each_tx_separately(mut mempool: Mempool) {
+    // Setup.
+    let input = add_tx_input!(tip: 50, tx_hash: 1, sender_address: "0x0");
+    let other_input = add_tx_input!(tip: 100, tx_hash: 2, sender_address: "0x1");
+
+    // Test.
+    let results = mempool.add_txs(vec![input.clone(), input.clone(), other_input.clone()]);
+
+    // Assert: only the duplicate transaction is rejected.
+    assert_matches!(
+        results.as_slice(),
+        [Ok(_), Err(MempoolError::DuplicateTransaction { .. }), Ok(_)]
+    );
+    let expected_mempool_state = MempoolState::with_pool([input.tx, other_input.tx]);
+    expected_mempool_state.assert_eq_pool_state(&mempool);
+}
+
 #[test]
 fn test_new_with_duplicate_tx() {
     let input = add_tx_input!(tip: 0, tx_hash: 1);
     let same_input = input.clone();
 
     assert!(matches!(
-        Mempool::new([input, same_input]),
+        Mempool::with_txs([input, same_input]),
         Err(MempoolError::DuplicateTransaction { .. })
     ));
 }
@@ -372,6 +457,396 @@ fn test_add_tx_with_duplicate_tx(mut mempool: Mempool) {
     expected_mempool_state.assert_eq_pool_state(&mempool);
 }
 
+#[rstest]
+fn test_add_tx_fee_escalation_replaces_queued_tx(mut mempool: Mempool) {
+    // Setup.
+    let input = add_tx_input!(tip: 100, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 100);
+    let escalated_input = add_tx_input!(tip: 110, tx_hash: 2, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 110);
+    add_tx(&mut mempool, &input);
+
+    // Test.
+    let output = mempool.add_tx(escalated_input.clone()).unwrap();
+
+    // Assert: the replaced transaction is reported, and removed from both the pool and the queue.
+    assert_eq!(output.replaced_tx_hash, Some(input.tx.tx_hash));
+    let expected_queue_txs = [TransactionReference::new(&escalated_input.tx)];
+    let expected_mempool_state = MempoolState::new([escalated_input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_fee_escalation_replaces_non_queued_tx(mut mempool: Mempool) {
+    // Setup: a transaction with a nonce gap, which is not queued.
+    let input = add_tx_input!(tip: 100, tx_hash: 1, sender_address: "0x0", tx_nonce: 1_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 100);
+    let escalated_input = add_tx_input!(tip: 200, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 200);
+    add_tx(&mut mempool, &input);
+
+    // Test.
+    let output = mempool.add_tx(escalated_input.clone()).unwrap();
+
+    // Assert.
+    assert_eq!(output.replaced_tx_hash, Some(input.tx.tx_hash));
+    let expected_mempool_state = MempoolState::new([escalated_input.tx], []);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+#[case::tip_not_escalated(109, 110)]
+#[case::resource_bounds_not_escalated(110, 109)]
+#[case::nothing_escalated(100, 100)]
+fn test_add_tx_insufficient_fee_escalation(
+    mut mempool: Mempool,
+    #[case] tip: u64,
+    #[case] max_l1_gas_price: u128,
+) {
+    // Setup.
+    let input = add_tx_input!(tip: 100, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 100);
+    let escalated_input = add_tx_input!(tip: tip, tx_hash: 2, sender_address: "0x0",
+        tx_nonce: 0_u8, account_nonce: 0_u8, max_l1_gas_price: max_l1_gas_price);
+    add_tx(&mut mempool, &input);
+
+    // Test.
+    assert_matches!(
+        mempool.add_tx(escalated_input),
+        Err(MempoolError::InsufficientFeeEscalation { .. })
+    );
+
+    // Assert: the original transaction remains.
+    let expected_queue_txs = [TransactionReference::new(&input.tx)];
+    let expected_mempool_state = MempoolState::new([input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_fee_escalation_disabled() {
+    // Setup.
+    let mut mempool = Mempool::with_config(MempoolConfig {
+        enable_fee_escalation: false,
+        ..MempoolConfig::default()
+    });
+    let input = add_tx_input!(tip: 100, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 100);
+    let escalated_input = add_tx_input!(tip: 200, tx_hash: 2, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: 200);
+    add_tx(&mut mempool, &input);
+
+    // Test and assert.
+    assert_matches!(mempool.add_tx(escalated_input), Err(MempoolError::DuplicateNonce { .. }));
+}
+
+#[rstest]
+#[case::max_txs(MempoolConfig { max_txs: 2, ..MempoolConfig::default() })]
+#[case::max_total_size_in_bytes(MempoolConfig {
+    // Fits the transaction with tip 20 and one of those with tip 10 below.
+    max_total_size_in_bytes: tx_size_in_bytes(&add_tx_input!(tip: 10, tx_hash: 1).tx)
+        + tx_size_in_bytes(&add_tx_input!(tip: 20, tx_hash: 3).tx),
+    ..MempoolConfig::default()
+})]
+fn test_add_tx_evicts_lowest_priority_tx(#[case] config: MempoolConfig) {
+    // Setup.
+    let mut mempool = Mempool::with_config(config);
+    let input_tip_10 = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0");
+    // Newer than the above, with an equal tip, hence of a lower priority.
+    let input_tip_10_newer = add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x1");
+    let input_tip_20 = add_tx_input!(tip: 20, tx_hash: 3, sender_address: "0x2");
+    for input in [&input_tip_10, &input_tip_10_newer] {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test.
+    add_tx(&mut mempool, &input_tip_20);
+
+    // Assert: the transaction with the lowest priority is evicted from both the pool and the
+    // queue.
+    assert_eq!(mempool.get_drop_reason(input_tip_10_newer.tx.tx_hash), Some(DropReason::Evicted));
+    let expected_queue_txs = [&input_tip_20.tx, &input_tip_10.tx].map(TransactionReference::new);
+    let expected_pool_txs = [input_tip_10.tx, input_tip_20.tx];
+    let expected_mempool_state = MempoolState::new(expected_pool_txs, expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+#[case::lower_tip(5)]
+#[case::equal_tip(10)]
+fn test_add_tx_rejected_when_mempool_full(#[case] tip: u64) {
+    // Setup.
+    let mut mempool =
+        Mempool::with_config(MempoolConfig { max_txs: 1, ..MempoolConfig::default() });
+    let input = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0");
+    let low_priority_input = add_tx_input!(tip: tip, tx_hash: 2, sender_address: "0x1");
+    add_tx(&mut mempool, &input);
+
+    // Test.
+    assert_matches!(mempool.add_tx(low_priority_input), Err(MempoolError::MempoolFull { .. }));
+
+    // Assert: the existing transaction remains.
+    let expected_queue_txs = [TransactionReference::new(&input.tx)];
+    let expected_mempool_state = MempoolState::new([input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_evicts_only_last_account_tx() {
+    // Setup.
+    let mut mempool =
+        Mempool::with_config(MempoolConfig { max_txs: 4, ..MempoolConfig::default() });
+    let input_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8);
+    let input_nonce_1 = add_tx_input!(tip: 2, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8,
+        account_nonce: 0_u8);
+    let input_nonce_2 = add_tx_input!(tip: 4, tx_hash: 3, sender_address: "0x0", tx_nonce: 2_u8,
+        account_nonce: 0_u8);
+    let other_account_input = add_tx_input!(tip: 3, tx_hash: 4, sender_address: "0x1");
+    for input in [&input_nonce_0, &input_nonce_1, &input_nonce_2, &other_account_input] {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test.
+    let input_tip_10 = add_tx_input!(tip: 10, tx_hash: 5, sender_address: "0x2");
+    add_tx(&mut mempool, &input_tip_10);
+
+    // Assert: the transactions with lower nonces of the first account rank lower, but are kept,
+    // so that no nonce gap is opened.
+    assert_eq!(mempool.get_drop_reason(other_account_input.tx.tx_hash), Some(DropReason::Evicted));
+
+    // Test.
+    let input_tip_20 = add_tx_input!(tip: 20, tx_hash: 6, sender_address: "0x3");
+    add_tx(&mut mempool, &input_tip_20);
+
+    // Assert.
+    assert_eq!(mempool.get_drop_reason(input_nonce_2.tx.tx_hash), Some(DropReason::Evicted));
+    let expected_queue_txs =
+        [&input_tip_20.tx, &input_tip_10.tx, &input_nonce_0.tx].map(TransactionReference::new);
+    let expected_pool_txs = [input_nonce_0.tx, input_nonce_1.tx, input_tip_10.tx, input_tip_20.tx];
+    let expected_mempool_state = MempoolState::new(expected_pool_txs, expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_evicts_preceding_account_tx_after_its_last_tx() {
+    // Setup.
+    let input_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8);
+    let input_nonce_1 = add_tx_input!(tip: 2, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8,
+        account_nonce: 0_u8);
+    // Larger than each of the above, so that both must be evicted to make room for it.
+    let large_input = add_tx_input!(tip: 3, tx_hash: 3, sender_address: "0x1", tx_nonce: 0_u8,
+        account_nonce: 0_u8, max_l1_gas_price: u128::MAX);
+    let mut mempool = Mempool::with_config(MempoolConfig {
+        max_total_size_in_bytes: tx_size_in_bytes(&input_nonce_0.tx)
+            + tx_size_in_bytes(&input_nonce_1.tx),
+        ..MempoolConfig::default()
+    });
+    for input in [&input_nonce_0, &input_nonce_1] {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test.
+    add_tx(&mut mempool, &large_input);
+
+    // Assert: the transaction with the lowest nonce ranks lowest, and is evicted once the
+    // transaction following it is.
+    for input in [&input_nonce_0, &input_nonce_1] {
+        assert_eq!(mempool.get_drop_reason(input.tx.tx_hash), Some(DropReason::Evicted));
+    }
+    let expected_queue_txs = [TransactionReference::new(&large_input.tx)];
+    let expected_mempool_state = MempoolState::new([large_input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_does_not_evict_preceding_txs_of_its_account() {
+    // Setup.
+    let mut mempool =
+        Mempool::with_config(MempoolConfig { max_txs: 1, ..MempoolConfig::default() });
+    let input_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8,
+        account_nonce: 0_u8);
+    let input_nonce_1 = add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8,
+        account_nonce: 0_u8);
+    add_tx(&mut mempool, &input_nonce_0);
+
+    // Test and assert.
+    assert_matches!(mempool.add_tx(input_nonce_1), Err(MempoolError::MempoolFull { .. }));
+    assert_eq!(mempool.get_drop_reason(input_nonce_0.tx.tx_hash), None);
+}
+
+#[rstest]
+fn test_add_tx_duplicate_does_not_evict() {
+    // Setup.
+    let mut mempool =
+        Mempool::with_config(MempoolConfig { max_txs: 2, ..MempoolConfig::default() });
+    let input = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0");
+    let low_priority_input = add_tx_input!(tip: 5, tx_hash: 2, sender_address: "0x1");
+    for input in [&input, &low_priority_input] {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test.
+    let duplicate_input = add_tx_input!(tip: 20, tx_hash: 1, sender_address: "0x2");
+    assert_matches!(
+        mempool.add_tx(duplicate_input),
+        Err(MempoolError::DuplicateTransaction { .. })
+    );
+
+    // Assert: no transaction is evicted.
+    let expected_queue_txs = [&input.tx, &low_priority_input.tx].map(TransactionReference::new);
+    let expected_mempool_state =
+        MempoolState::new([input.tx, low_priority_input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_account_transaction_limit_exceeded() {
+    // Setup.
+    let mut mempool =
+        Mempool::with_config(MempoolConfig { max_txs_per_account: 1, ..MempoolConfig::default() });
+    let input_nonce_0 = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let input_nonce_1 = add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8, account_nonce: 0_u8);
+    add_tx(&mut mempool, &input_nonce_0);
+
+    // Test and assert: other accounts are not affected.
+    assert_matches!(
+        mempool.add_tx(input_nonce_1),
+        Err(MempoolError::AccountTransactionLimitExceeded { .. })
+    );
+    add_tx(&mut mempool, &add_tx_input!(tip: 10, tx_hash: 3, sender_address: "0x1"));
+}
+
+/// Creates an input of a transaction of the account at address 0x0, whose maximal fee is
+/// `10 * max_l1_gas_price`, and which reports the given balance.
+fn add_tx_input_with_balance(
+    tx_hash: u8,
+    tx_nonce: u8,
+    max_l1_gas_price: u128,
+    balance: u128,
+) -> MempoolInput {
+    let mut input = add_tx_input!(tip: 10, tx_hash: tx_hash, sender_address: "0x0",
+        tx_nonce: tx_nonce, account_nonce: 0_u8, max_l1_gas_price: max_l1_gas_price);
+    input.tx.resource_bounds.l1_gas.max_amount = 10;
+    input.account.state.balance = Fee(balance);
+    input
+}
+
+#[rstest]
+fn test_add_tx_exceeding_balance(mut mempool: Mempool) {
+    // Setup.
+    let input_nonce_0 = add_tx_input_with_balance(1, 0, 6, 100);
+    let input_nonce_1 = add_tx_input_with_balance(2, 1, 6, 100);
+    add_tx(&mut mempool, &input_nonce_0);
+
+    // Test and assert: the pending transactions together may be charged more than the balance.
+    assert_matches!(mempool.add_tx(input_nonce_1), Err(MempoolError::InsufficientBalance { .. }));
+    let expected_mempool_state = MempoolState::with_pool([input_nonce_0.tx]);
+    expected_mempool_state.assert_eq_pool_state(&mempool);
+}
+
+#[rstest]
+fn test_add_tx_balance_excludes_replaced_tx(mut mempool: Mempool) {
+    // Setup.
+    let input = add_tx_input_with_balance(1, 0, 6, 100);
+    let mut escalated_input = add_tx_input_with_balance(2, 0, 7, 100);
+    escalated_input.tx.tip = Tip(20);
+    add_tx(&mut mempool, &input);
+
+    // Test and assert: only the maximal fee of the replacing transaction is accounted for.
+    let output = mempool.add_tx(escalated_input.clone()).unwrap();
+    assert_eq!(output.replaced_tx_hash, Some(input.tx.tx_hash));
+    let expected_mempool_state = MempoolState::with_pool([escalated_input.tx]);
+    expected_mempool_state.assert_eq_pool_state(&mempool);
+}
+
+#[rstest]
+fn test_get_tx(mut mempool: Mempool) {
+    // Setup.
+    let input = add_tx_input!(tip: 1, tx_hash: 1);
+    add_tx(&mut mempool, &input);
+
+    // Test and assert.
+    assert_eq!(mempool.get_tx(input.tx.tx_hash), Ok(input.tx));
+    let missing_tx_hash = TransactionHash(felt!(2_u8));
+    assert_eq!(
+        mempool.get_tx(missing_tx_hash),
+        Err(MempoolError::TransactionNotFound { tx_hash: missing_tx_hash })
+    );
+}
+
+#[rstest]
+fn test_get_account_txs_includes_nonce_gaps(mut mempool: Mempool) {
+    // Setup.
+    let input_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let input_nonce_2 = add_tx_input!(tip: 1, tx_hash: 2, sender_address: "0x0", tx_nonce: 2_u8, account_nonce: 0_u8);
+    let input_other_account = add_tx_input!(tip: 1, tx_hash: 3, sender_address: "0x1");
+    for input in [&input_nonce_2, &input_nonce_0, &input_other_account] {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test and assert.
+    assert_eq!(
+        mempool.get_account_txs(contract_address!("0x0")),
+        [input_nonce_0.tx, input_nonce_2.tx]
+    );
+    assert_eq!(mempool.get_account_txs(contract_address!("0x2")), []);
+}
+
+#[rstest]
+fn test_stats(mut mempool: Mempool) {
+    // Setup.
+    let inputs = [
+        add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8),
+        add_tx_input!(tip: 1, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8, account_nonce: 0_u8),
+        // Waits for nonce 0.
+        add_tx_input!(tip: 1, tx_hash: 3, sender_address: "0x1", tx_nonce: 1_u8, account_nonce: 0_u8),
+        // Nonce 2 waits for nonce 1.
+        add_tx_input!(tip: 1, tx_hash: 4, sender_address: "0x2", tx_nonce: 0_u8, account_nonce: 0_u8),
+        add_tx_input!(tip: 1, tx_hash: 5, sender_address: "0x2", tx_nonce: 2_u8, account_nonce: 0_u8),
+    ];
+    for input in &inputs {
+        add_tx(&mut mempool, input);
+    }
+
+    // Test and assert.
+    let expected_stats =
+        MempoolStats { n_queued_txs: 2, n_pool_txs: 5, n_accounts_with_nonce_gaps: 2 };
+    assert_eq!(mempool.stats(), expected_stats);
+}
+
+#[rstest]
+fn test_remove_expired_txs() {
+    // Setup.
+    let transaction_ttl = Duration::from_secs(60);
+    let clock = Arc::new(FakeClock::new());
+    let mut mempool = Mempool {
+        clock: clock.clone(),
+        ..Mempool::with_config(MempoolConfig { transaction_ttl, ..MempoolConfig::default() })
+    };
+    let queued_input = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let nonce_gap_input = add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x1", tx_nonce: 1_u8, account_nonce: 0_u8);
+    let recent_input = add_tx_input!(tip: 10, tx_hash: 3, sender_address: "0x2");
+    for input in [&queued_input, &nonce_gap_input] {
+        add_tx(&mut mempool, input);
+    }
+    clock.advance(transaction_ttl / 2);
+    add_tx(&mut mempool, &recent_input);
+    clock.advance(transaction_ttl / 2 + Duration::from_secs(1));
+
+    // Test.
+    mempool.remove_expired_txs().unwrap();
+
+    // Assert: only the recently received transaction remains, and the expired ones are recorded.
+    let expected_queue_txs = [TransactionReference::new(&recent_input.tx)];
+    let expected_mempool_state = MempoolState::new([recent_input.tx.clone()], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+    for expired_input in [queued_input, nonce_gap_input] {
+        assert_eq!(mempool.get_drop_reason(expired_input.tx.tx_hash), Some(DropReason::Expired));
+    }
+    assert_eq!(mempool.get_drop_reason(recent_input.tx.tx_hash), None);
+}
+
 #[rstest]
 fn test_add_tx_with_identical_tip_succeeds(mut mempool: Mempool) {
     // Setup.
@@ -564,8 +1039,14 @@ fn test_commit_block_includes_all_txs() {
 
     // Test.
     let state_changes = HashMap::from([
-        (contract_address!("0x0"), AccountState { nonce: Nonce(felt!(3_u16)) }),
-        (contract_address!("0x1"), AccountState { nonce: Nonce(felt!(2_u16)) }),
+        (
+            contract_address!("0x0"),
+            AccountState { nonce: Nonce(felt!(3_u16)), ..Default::default() },
+        ),
+        (
+            contract_address!("0x1"),
+            AccountState { nonce: Nonce(felt!(2_u16)), ..Default::default() },
+        ),
     ]);
     assert!(mempool.commit_block(state_changes).is_ok());
 
@@ -585,8 +1066,14 @@ fn test_commit_block_rewinds_nonce() {
 
     // Test.
     let state_changes = HashMap::from([
-        (contract_address!("0x0"), AccountState { nonce: Nonce(felt!(3_u16)) }),
-        (contract_address!("0x1"), AccountState { nonce: Nonce(felt!(3_u16)) }),
+        (
+            contract_address!("0x0"),
+            AccountState { nonce: Nonce(felt!(3_u16)), ..Default::default() },
+        ),
+        (
+            contract_address!("0x1"),
+            AccountState { nonce: Nonce(felt!(3_u16)), ..Default::default() },
+        ),
     ]);
     assert!(mempool.commit_block(state_changes).is_ok());
 
@@ -610,10 +1097,19 @@ fn test_commit_block_from_different_leader() {
 
     // Test.
     let state_changes = HashMap::from([
-        (contract_address!("0x0"), AccountState { nonce: Nonce(felt!(5_u16)) }),
+        (
+            contract_address!("0x0"),
+            AccountState { nonce: Nonce(felt!(5_u16)), ..Default::default() },
+        ),
         // A hole, missing nonce 1.
-        (contract_address!("0x1"), AccountState { nonce: Nonce(felt!(0_u16)) }),
-        (contract_address!("0x2"), AccountState { nonce: Nonce(felt!(1_u16)) }),
+        (
+            contract_address!("0x1"),
+            AccountState { nonce: Nonce(felt!(0_u16)), ..Default::default() },
+        ),
+        (
+            contract_address!("0x2"),
+            AccountState { nonce: Nonce(felt!(1_u16)), ..Default::default() },
+        ),
     ]);
     assert!(mempool.commit_block(state_changes).is_ok());
 
@@ -665,8 +1161,14 @@ fn test_flow_partial_commit_block() {
 
     // Not included in block: `tx_address2_nonce2`, `tx_address1_nonce1`.
     let state_changes = HashMap::from([
-        (contract_address!("0x0"), AccountState { nonce: Nonce(felt!(3_u16)) }),
-        (contract_address!("0x1"), AccountState { nonce: Nonce(felt!(0_u16)) }),
+        (
+            contract_address!("0x0"),
+            AccountState { nonce: Nonce(felt!(3_u16)), ..Default::default() },
+        ),
+        (
+            contract_address!("0x1"),
+            AccountState { nonce: Nonce(felt!(0_u16)), ..Default::default() },
+        ),
     ]);
     assert!(mempool.commit_block(state_changes).is_ok());
 
@@ -675,3 +1177,225 @@ fn test_flow_partial_commit_block() {
     let expected_mempool_state = MempoolState::new(expected_pool_txs, []);
     expected_mempool_state.assert_eq_mempool_state(&mempool);
 }
+
+#[rstest]
+fn test_commit_block_records_included_txs() {
+    // Setup.
+    let tx_address0_nonce3 =
+        add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0", tx_nonce: 3_u8, account_nonce: 3_u8).tx;
+    let tx_address0_nonce4 =
+        add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x0", tx_nonce: 4_u8, account_nonce: 3_u8).tx;
+    let tx_address1_nonce0 =
+        add_tx_input!(tip: 20, tx_hash: 3, sender_address: "0x1", tx_nonce: 0_u8, account_nonce: 0_u8).tx;
+
+    let queue_txs = [&tx_address0_nonce3, &tx_address1_nonce0].map(TransactionReference::new);
+    let pool_txs =
+        [&tx_address0_nonce3, &tx_address0_nonce4, &tx_address1_nonce0].map(|tx| tx.clone());
+    let mut mempool: Mempool = MempoolState::new(pool_txs, queue_txs).into();
+
+    // Test.
+    let txs = mempool.get_txs(3).unwrap();
+    assert_eq!(
+        txs,
+        &[tx_address1_nonce0.clone(), tx_address0_nonce3.clone(), tx_address0_nonce4.clone()]
+    );
+    // Taken, but not yet committed.
+    assert!(!mempool.is_included(tx_address0_nonce3.tx_hash));
+
+    // Not included in block: `tx_address0_nonce4`, `tx_address1_nonce0`.
+    let state_changes = HashMap::from([(
+        contract_address!("0x0"),
+        AccountState { nonce: Nonce(felt!(3_u16)), ..Default::default() },
+    )]);
+    assert!(mempool.commit_block(state_changes).is_ok());
+
+    // Assert.
+    assert!(mempool.is_included(tx_address0_nonce3.tx_hash));
+    assert!(!mempool.is_included(tx_address0_nonce4.tx_hash));
+    assert!(!mempool.is_included(tx_address1_nonce0.tx_hash));
+}
+
+/// Creates a journal entry of the given input, received at the current time of the given clock.
+fn add_tx_journal_entry(input: MempoolInput, clock: &FakeClock) -> JournalEntry {
+    JournalEntry::AddTx { input, received_at: clock.system_now() }
+}
+
+#[rstest]
+fn test_new_restores_mempool_from_journal() {
+    // Setup.
+    let journal_dir = tempfile::tempdir().unwrap();
+    let journal_path = journal_dir.path().join("journal");
+    let clock = Arc::new(FakeClock::new());
+    let input_address_0_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let input_address_0_nonce_1 = add_tx_input!(tip: 1, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8, account_nonce: 0_u8);
+    let committed_input = add_tx_input!(tip: 1, tx_hash: 3, sender_address: "0x1", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let stale_input = add_tx_input!(tip: 1, tx_hash: 4, sender_address: "0x2", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let journal_entries = [
+        add_tx_journal_entry(input_address_0_nonce_0.clone(), &clock),
+        add_tx_journal_entry(committed_input, &clock),
+        add_tx_journal_entry(stale_input, &clock),
+        JournalEntry::CommitBlock(HashMap::from([(contract_address!("0x1"), Nonce(felt!(0_u8)))])),
+        add_tx_journal_entry(input_address_0_nonce_1.clone(), &clock),
+    ];
+    MempoolJournal::create(&journal_path, journal_entries).unwrap();
+    // The latest state shows that the transaction of 0x2 was already included in a block.
+    let latest_account_state_0 = AccountState { nonce: Nonce(felt!(0_u8)), balance: Fee(100) };
+    let account_state_reader = FakeAccountStateReader(HashMap::from([
+        (contract_address!("0x0"), latest_account_state_0),
+        (
+            contract_address!("0x2"),
+            AccountState { nonce: Nonce(felt!(1_u8)), ..Default::default() },
+        ),
+    ]));
+
+    // Test.
+    let config = MempoolConfig { journal_path: Some(journal_path.clone()), ..Default::default() };
+    let mempool = Mempool::new_with_clock(config, clock.clone(), &account_state_reader).unwrap();
+
+    // Assert: only the transactions that are still valid are restored, and the journal is
+    // compacted to them.
+    let expected_queue_txs = [TransactionReference::new(&input_address_0_nonce_0.tx)];
+    let expected_pool_txs =
+        [input_address_0_nonce_0.tx.clone(), input_address_0_nonce_1.tx.clone()];
+    let expected_mempool_state = MempoolState::new(expected_pool_txs, expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+    let expected_journal_entries =
+        [input_address_0_nonce_0, input_address_0_nonce_1].map(|mut input| {
+            input.account.state = latest_account_state_0;
+            add_tx_journal_entry(input, &clock)
+        });
+    assert_eq!(MempoolJournal::read(&journal_path).unwrap(), expected_journal_entries);
+}
+
+#[rstest]
+fn test_new_does_not_restore_dropped_txs() {
+    // Setup.
+    let journal_dir = tempfile::tempdir().unwrap();
+    let journal_path = journal_dir.path().join("journal");
+    let transaction_ttl = Duration::from_secs(60);
+    let config = MempoolConfig {
+        journal_path: Some(journal_path.clone()),
+        transaction_ttl,
+        max_txs: 2,
+        ..Default::default()
+    };
+    let account_state_reader = FakeAccountStateReader(HashMap::new());
+    let clock = Arc::new(FakeClock::new());
+    let mut mempool =
+        Mempool::new_with_clock(config.clone(), clock.clone(), &account_state_reader).unwrap();
+    let expired_input = add_tx_input!(tip: 10, tx_hash: 1, sender_address: "0x0");
+    let evicted_input = add_tx_input!(tip: 1, tx_hash: 2, sender_address: "0x1");
+    let old_input = add_tx_input!(tip: 10, tx_hash: 3, sender_address: "0x2");
+    let recent_input = add_tx_input!(tip: 10, tx_hash: 4, sender_address: "0x3");
+    for input in [&expired_input, &evicted_input] {
+        add_tx(&mut mempool, input);
+    }
+    clock.advance(transaction_ttl / 2);
+    add_tx(&mut mempool, &old_input);
+    clock.advance(transaction_ttl / 2 + Duration::from_secs(1));
+    mempool.remove_expired_txs().unwrap();
+    add_tx(&mut mempool, &recent_input);
+    clock.advance(transaction_ttl / 2);
+
+    // Test.
+    let mempool = Mempool::new_with_clock(config, clock, &account_state_reader).unwrap();
+
+    // Assert: the dropped transactions are not restored, and the restored transactions keep the
+    // time they were received, hence the older one expired while the mempool was down.
+    let expected_queue_txs = [TransactionReference::new(&recent_input.tx)];
+    let expected_mempool_state = MempoolState::new([recent_input.tx], expected_queue_txs);
+    expected_mempool_state.assert_eq_mempool_state(&mempool);
+}
+
+#[rstest]
+fn test_journal_records_added_txs_and_committed_nonces() {
+    // Setup.
+    let journal_dir = tempfile::tempdir().unwrap();
+    let journal_path = journal_dir.path().join("journal");
+    let config = MempoolConfig { journal_path: Some(journal_path.clone()), ..Default::default() };
+    let account_state_reader = FakeAccountStateReader(HashMap::new());
+    let clock = Arc::new(FakeClock::new());
+    let mut mempool =
+        Mempool::new_with_clock(config, clock.clone(), &account_state_reader).unwrap();
+    let input = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let rejected_input = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x1", tx_nonce: 0_u8, account_nonce: 0_u8);
+
+    // Test.
+    add_tx(&mut mempool, &input);
+    assert!(mempool.add_tx(rejected_input).is_err());
+    let state_changes = HashMap::from([(
+        contract_address!("0x2"),
+        AccountState { nonce: Nonce(felt!(3_u8)), ..Default::default() },
+    )]);
+    mempool.commit_block(state_changes).unwrap();
+
+    // Assert.
+    let expected_journal_entries = [
+        add_tx_journal_entry(input, &clock),
+        JournalEntry::CommitBlock(HashMap::from([(contract_address!("0x2"), Nonce(felt!(3_u8)))])),
+    ];
+    assert_eq!(MempoolJournal::read(&journal_path).unwrap(), expected_journal_entries);
+}
+
+#[rstest]
+fn test_journal_records_dropped_txs() {
+    // Setup.
+    let journal_dir = tempfile::tempdir().unwrap();
+    let journal_path = journal_dir.path().join("journal");
+    let config = MempoolConfig {
+        journal_path: Some(journal_path.clone()),
+        max_txs: 1,
+        ..Default::default()
+    };
+    let account_state_reader = FakeAccountStateReader(HashMap::new());
+    let clock = Arc::new(FakeClock::new());
+    let mut mempool =
+        Mempool::new_with_clock(config, clock.clone(), &account_state_reader).unwrap();
+    let evicted_input = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0");
+    let input = add_tx_input!(tip: 10, tx_hash: 2, sender_address: "0x1");
+
+    // Test.
+    add_tx(&mut mempool, &evicted_input);
+    add_tx(&mut mempool, &input);
+
+    // Assert.
+    let expected_journal_entries = [
+        add_tx_journal_entry(evicted_input.clone(), &clock),
+        JournalEntry::DropTx(evicted_input.tx.tx_hash),
+        add_tx_journal_entry(input, &clock),
+    ];
+    assert_eq!(MempoolJournal::read(&journal_path).unwrap(), expected_journal_entries);
+}
+
+#[rstest]
+fn test_commit_block_compacts_journal() {
+    // Setup.
+    let journal_dir = tempfile::tempdir().unwrap();
+    let journal_path = journal_dir.path().join("journal");
+    let config = MempoolConfig { journal_path: Some(journal_path.clone()), ..Default::default() };
+    let account_state_reader = FakeAccountStateReader(HashMap::new());
+    let clock = Arc::new(FakeClock::new());
+    let mut mempool =
+        Mempool::new_with_clock(config, clock.clone(), &account_state_reader).unwrap();
+    let input_nonce_0 = add_tx_input!(tip: 1, tx_hash: 1, sender_address: "0x0", tx_nonce: 0_u8, account_nonce: 0_u8);
+    let input_nonce_1 = add_tx_input!(tip: 1, tx_hash: 2, sender_address: "0x0", tx_nonce: 1_u8, account_nonce: 0_u8);
+    add_tx(&mut mempool, &input_nonce_0);
+    let received_at_nonce_1 = clock.system_now();
+    add_tx(&mut mempool, &input_nonce_1);
+    clock.advance(Duration::from_secs(1));
+
+    // Test.
+    let state_changes = HashMap::from([(
+        contract_address!("0x0"),
+        AccountState { nonce: Nonce(felt!(0_u8)), ..Default::default() },
+    )]);
+    mempool.commit_block(state_changes).unwrap();
+
+    // Assert: the journal holds only the remaining transaction, with the time it was received.
+    let mut expected_input = input_nonce_1;
+    expected_input.account.state = AccountState::default();
+    assert_eq!(
+        MempoolJournal::read(&journal_path).unwrap(),
+        [JournalEntry::AddTx { input: expected_input, received_at: received_at_nonce_1 }]
+    );
+}
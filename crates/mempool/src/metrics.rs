@@ -0,0 +1,6 @@
+/// The number of transactions evicted from the mempool, to make room for transactions with a
+/// higher priority.
+pub const MEMPOOL_EVICTED_TXS: &str = "mempool_evicted_txs";
+
+/// The number of transactions dropped from the mempool after their time-to-live elapsed.
+pub const MEMPOOL_EXPIRED_TXS: &str = "mempool_expired_txs";
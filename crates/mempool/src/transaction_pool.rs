@@ -1,7 +1,10 @@
+use std::cmp::Reverse;
 use std::collections::{hash_map, BTreeMap, HashMap};
+use std::ops::Bound;
+use std::time::Instant;
 
 use starknet_api::core::{ContractAddress, Nonce};
-use starknet_api::transaction::TransactionHash;
+use starknet_api::transaction::{Tip, TransactionHash};
 use starknet_mempool_types::errors::MempoolError;
 use starknet_mempool_types::mempool_types::{
     Account,
@@ -18,16 +21,34 @@ type HashToTransaction = HashMap<TransactionHash, ThinTransaction>;
 /// Invariant: both data structures are consistent regarding the existence of transactions:
 /// A transaction appears in one if and only if it appears in the other.
 /// No duplicate transactions appear in the pool.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Default)]
 pub struct TransactionPool {
     // Holds the complete transaction objects; it should be the sole entity that does so.
     tx_pool: HashToTransaction,
     // Transactions organized by account address, sorted by ascending nonce values.
     txs_by_account: AccountTransactionIndex,
+    // Transactions sorted by ascending priority, used for choosing transactions to evict.
+    txs_by_priority: PriorityIndex,
+    // Transactions sorted by the time they were received, used for removing expired transactions.
+    txs_by_arrival: ArrivalIndex,
+    // The size of each transaction in the pool, computed once on insertion.
+    tx_sizes_in_bytes: HashMap<TransactionHash, usize>,
+    // The total size of the transactions in the pool.
+    size_in_bytes: usize,
 }
 
+/// The priority and arrival indices and the sizes are derived from the transactions and the time
+/// they were inserted, hence they are not compared.
+impl PartialEq for TransactionPool {
+    fn eq(&self, other: &Self) -> bool {
+        self.tx_pool == other.tx_pool && self.txs_by_account == other.txs_by_account
+    }
+}
+
+impl Eq for TransactionPool {}
+
 impl TransactionPool {
-    pub fn insert(&mut self, tx: ThinTransaction) -> MempoolResult<()> {
+    pub fn insert(&mut self, tx: ThinTransaction, received_at: Instant) -> MempoolResult<()> {
         let tx_reference = TransactionReference::new(&tx);
         let tx_hash = tx_reference.tx_hash;
 
@@ -47,6 +68,12 @@ impl TransactionPool {
             )
         };
 
+        self.txs_by_priority.insert(tx_reference);
+        self.txs_by_arrival.insert(tx_reference, received_at);
+        let tx_size_in_bytes = tx_size_in_bytes(&self.tx_pool[&tx_hash]);
+        self.tx_sizes_in_bytes.insert(tx_hash, tx_size_in_bytes);
+        self.size_in_bytes += tx_size_in_bytes;
+
         Ok(())
     }
 
@@ -63,22 +90,95 @@ impl TransactionPool {
             )
         });
 
+        self.remove_from_secondary_indices(&tx);
+
         Ok(tx)
     }
 
     pub fn remove_up_to_nonce(&mut self, address: ContractAddress, nonce: Nonce) {
         let removed_txs = self.txs_by_account.remove_up_to_nonce(address, nonce);
 
-        for TransactionReference { tx_hash, .. } in removed_txs {
-            self.tx_pool.remove(&tx_hash).unwrap_or_else(|| {
+        for tx_reference in removed_txs {
+            let tx_hash = tx_reference.tx_hash;
+            let tx = self.tx_pool.remove(&tx_hash).unwrap_or_else(|| {
                 panic!(
                     "Transaction pool consistency error: transaction with hash {tx_hash} appears \
                      in account mapping, but does not appear in the main mapping"
                 );
             });
+            self.remove_from_secondary_indices(&tx);
         }
     }
 
+    /// Returns the transactions of the given account, sorted by ascending nonce.
+    pub fn account_txs(&self, address: ContractAddress) -> impl Iterator<Item = &ThinTransaction> {
+        self.txs_by_account.account_txs(address).map(|TransactionReference { tx_hash, .. }| {
+            self.tx_pool.get(tx_hash).unwrap_or_else(|| {
+                panic!(
+                    "Transaction pool consistency error: transaction with hash {tx_hash} appears \
+                     in account mapping, but does not appear in the main mapping"
+                )
+            })
+        })
+    }
+
+    /// Returns the addresses of the accounts with transactions in the pool.
+    pub fn accounts(&self) -> impl Iterator<Item = ContractAddress> + '_ {
+        self.txs_by_account.accounts()
+    }
+
+    pub fn n_txs(&self) -> usize {
+        self.tx_pool.len()
+    }
+
+    pub fn n_account_txs(&self, address: ContractAddress) -> usize {
+        self.txs_by_account.n_account_txs(address)
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes
+    }
+
+    /// Returns the size of the given transaction, as computed when it was inserted.
+    pub fn get_size_in_bytes(&self, tx_hash: TransactionHash) -> MempoolResult<usize> {
+        self.tx_sizes_in_bytes
+            .get(&tx_hash)
+            .copied()
+            .ok_or(MempoolError::TransactionNotFound { tx_hash })
+    }
+
+    /// Returns an iterator of the transactions in the pool, from the lowest priority to the
+    /// highest: ordered by tip, and for equal tips, newer transactions first.
+    pub fn iter_by_ascending_priority(&self) -> impl Iterator<Item = &TransactionReference> {
+        self.txs_by_priority.iter()
+    }
+
+    /// Returns an iterator of the transactions in the pool with the time they were received, from
+    /// the oldest to the newest.
+    pub fn iter_by_arrival(&self) -> impl Iterator<Item = (Instant, &ThinTransaction)> {
+        self.txs_by_arrival.iter().map(|(received_at, TransactionReference { tx_hash, .. })| {
+            let tx = self.tx_pool.get(tx_hash).unwrap_or_else(|| {
+                panic!(
+                    "Transaction pool consistency error: transaction with hash {tx_hash} appears \
+                     in the arrival index, but does not appear in the main mapping"
+                )
+            });
+            (received_at, tx)
+        })
+    }
+
+    /// Returns an iterator of the transactions received before the given time, from the oldest to
+    /// the newest.
+    pub fn iter_received_before(
+        &self,
+        time: Instant,
+    ) -> impl Iterator<Item = &TransactionReference> {
+        self.txs_by_arrival
+            .iter()
+            .take_while(move |&(received_at, _)| received_at < time)
+            .map(|(_, tx)| tx)
+    }
+
     pub fn get_by_tx_hash(&self, tx_hash: TransactionHash) -> MempoolResult<&ThinTransaction> {
         self.tx_pool.get(&tx_hash).ok_or(MempoolError::TransactionNotFound { tx_hash })
     }
@@ -91,22 +191,60 @@ impl TransactionPool {
         self.txs_by_account.get(address, nonce)
     }
 
+    /// Returns the transaction of the given account with the lowest nonce above the given one.
+    pub fn get_next_account_tx(
+        &self,
+        address: ContractAddress,
+        nonce: Nonce,
+    ) -> Option<&TransactionReference> {
+        self.txs_by_account.next(address, nonce)
+    }
+
     pub fn get_next_eligible_tx(
         &self,
         current_account_state: Account,
     ) -> MempoolResult<Option<&TransactionReference>> {
-        let Account { sender_address, state: AccountState { nonce } } = current_account_state;
+        let Account { sender_address, state: AccountState { nonce, .. } } = current_account_state;
         // TOOD(Ayelet): Change to StarknetApiError.
         let next_nonce = nonce.try_increment().map_err(|_| MempoolError::FeltOutOfRange)?;
         Ok(self.get_by_address_and_nonce(sender_address, next_nonce))
     }
 
+    fn remove_from_secondary_indices(&mut self, removed_tx: &ThinTransaction) {
+        let tx = TransactionReference::new(removed_tx);
+        let tx_hash = tx.tx_hash;
+        assert!(
+            self.txs_by_priority.remove(tx),
+            "Transaction pool consistency error: transaction with hash {tx_hash} appears in main \
+             mapping, but does not appear in the priority index"
+        );
+        assert!(
+            self.txs_by_arrival.remove(tx),
+            "Transaction pool consistency error: transaction with hash {tx_hash} appears in main \
+             mapping, but does not appear in the arrival index"
+        );
+        let tx_size_in_bytes = self.tx_sizes_in_bytes.remove(&tx_hash).unwrap_or_else(|| {
+            panic!(
+                "Transaction pool consistency error: transaction with hash {tx_hash} appears in \
+                 main mapping, but its size is not recorded"
+            )
+        });
+        self.size_in_bytes -= tx_size_in_bytes;
+    }
+
     #[cfg(test)]
     pub(crate) fn _tx_pool(&self) -> &HashToTransaction {
         &self.tx_pool
     }
 }
 
+/// Returns the number of bytes a transaction takes in the pool, i.e., the size of its
+/// serialization.
+// TODO: account for the size of the full transaction, once it is held by the mempool.
+pub fn tx_size_in_bytes(tx: &ThinTransaction) -> usize {
+    serde_json::to_vec(tx).expect("Failed serializing a transaction.").len()
+}
+
 #[derive(Debug, Default, Eq, PartialEq)]
 struct AccountTransactionIndex(HashMap<ContractAddress, BTreeMap<Nonce, TransactionReference>>);
 
@@ -133,6 +271,26 @@ impl AccountTransactionIndex {
         self.0.get(&address)?.get(&nonce)
     }
 
+    fn next(&self, address: ContractAddress, nonce: Nonce) -> Option<&TransactionReference> {
+        self.0
+            .get(&address)?
+            .range((Bound::Excluded(nonce), Bound::Unbounded))
+            .next()
+            .map(|(_, tx)| tx)
+    }
+
+    fn account_txs(&self, address: ContractAddress) -> impl Iterator<Item = &TransactionReference> {
+        self.0.get(&address).into_iter().flat_map(BTreeMap::values)
+    }
+
+    fn accounts(&self) -> impl Iterator<Item = ContractAddress> + '_ {
+        self.0.keys().copied()
+    }
+
+    fn n_account_txs(&self, address: ContractAddress) -> usize {
+        self.0.get(&address).map_or(0, BTreeMap::len)
+    }
+
     fn remove_up_to_nonce(
         &mut self,
         address: ContractAddress,
@@ -154,3 +312,58 @@ impl AccountTransactionIndex {
         txs_with_lower_nonce.into_values().collect()
     }
 }
+
+/// Orders transactions by ascending priority: by tip, and for equal tips, by descending age, as
+/// older transactions are prioritized.
+#[derive(Debug, Default)]
+struct PriorityIndex {
+    txs: BTreeMap<(Tip, Reverse<u64>), TransactionReference>,
+    // The insertion number of each transaction, serving as its age.
+    insertion_ids: HashMap<TransactionHash, u64>,
+    next_insertion_id: u64,
+}
+
+impl PriorityIndex {
+    fn insert(&mut self, tx: TransactionReference) {
+        let insertion_id = self.next_insertion_id;
+        self.next_insertion_id += 1;
+        self.insertion_ids.insert(tx.tx_hash, insertion_id);
+        self.txs.insert((tx.tip, Reverse(insertion_id)), tx);
+    }
+
+    fn remove(&mut self, tx: TransactionReference) -> bool {
+        let Some(insertion_id) = self.insertion_ids.remove(&tx.tx_hash) else {
+            return false;
+        };
+        self.txs.remove(&(tx.tip, Reverse(insertion_id))).is_some()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &TransactionReference> {
+        self.txs.values()
+    }
+}
+
+/// Orders transactions by the time they were received, from the oldest to the newest.
+#[derive(Debug, Default)]
+struct ArrivalIndex {
+    txs: BTreeMap<(Instant, TransactionHash), TransactionReference>,
+    received_at: HashMap<TransactionHash, Instant>,
+}
+
+impl ArrivalIndex {
+    fn insert(&mut self, tx: TransactionReference, received_at: Instant) {
+        self.received_at.insert(tx.tx_hash, received_at);
+        self.txs.insert((received_at, tx.tx_hash), tx);
+    }
+
+    fn remove(&mut self, tx: TransactionReference) -> bool {
+        let Some(received_at) = self.received_at.remove(&tx.tx_hash) else {
+            return false;
+        };
+        self.txs.remove(&(received_at, tx.tx_hash)).is_some()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (Instant, &TransactionReference)> {
+        self.txs.iter().map(|(&(received_at, _), tx)| (received_at, tx))
+    }
+}
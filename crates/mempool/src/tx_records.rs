@@ -0,0 +1,52 @@
+use std::collections::{HashMap, VecDeque};
+
+use starknet_api::transaction::TransactionHash;
+use starknet_mempool_types::mempool_types::DropReason;
+
+// The number of transactions remembered in each record; beyond it, the oldest records are
+// discarded.
+const MAX_TX_RECORDS: usize = 100_000;
+
+/// Records the transactions dropped from the mempool, along with the reason they were dropped.
+pub type DroppedTransactions = TransactionRecords<DropReason>;
+
+/// Records the transactions included in committed blocks.
+pub type IncludedTransactions = TransactionRecords<()>;
+
+/// Records a value for each of the latest transactions that left the mempool.
+#[derive(Debug)]
+pub struct TransactionRecords<T> {
+    records: HashMap<TransactionHash, T>,
+    // The recorded transactions, from the earliest recorded to the latest.
+    order: VecDeque<TransactionHash>,
+}
+
+impl<T> Default for TransactionRecords<T> {
+    fn default() -> Self {
+        TransactionRecords { records: HashMap::default(), order: VecDeque::default() }
+    }
+}
+
+impl<T: Copy> TransactionRecords<T> {
+    pub fn insert(&mut self, tx_hash: TransactionHash, record: T) {
+        if self.records.insert(tx_hash, record).is_none() {
+            self.order.push_back(tx_hash);
+        }
+
+        if self.order.len() > MAX_TX_RECORDS {
+            let oldest_tx_hash = self.order.pop_front().expect("Records should not be empty.");
+            self.records.remove(&oldest_tx_hash);
+        }
+    }
+
+    /// Forgets the given transaction, in case it is re-added to the mempool.
+    pub fn remove(&mut self, tx_hash: TransactionHash) {
+        if self.records.remove(&tx_hash).is_some() {
+            self.order.retain(|&recorded_tx_hash| recorded_tx_hash != tx_hash);
+        }
+    }
+
+    pub fn get(&self, tx_hash: TransactionHash) -> Option<T> {
+        self.records.get(&tx_hash).copied()
+    }
+}
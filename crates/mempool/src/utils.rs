@@ -0,0 +1,35 @@
+use std::fmt::Debug;
+use std::time::{Instant, SystemTime};
+
+/// A source of the current time, enabling control over time in tests.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Returns the current wall-clock time, used for times that outlive the process.
+    fn system_now(&self) -> SystemTime;
+
+    /// Converts a time of the clock to the corresponding wall-clock time, which, unlike the former,
+    /// remains meaningful across restarts.
+    fn to_system_time(&self, instant: Instant) -> SystemTime {
+        self.system_now() - self.now().saturating_duration_since(instant)
+    }
+
+    /// Converts a wall-clock time to the corresponding time of the clock, if it is representable.
+    fn to_instant(&self, system_time: SystemTime) -> Option<Instant> {
+        let age = self.system_now().duration_since(system_time).unwrap_or_default();
+        self.now().checked_sub(age)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InstantClock;
+
+impl Clock for InstantClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
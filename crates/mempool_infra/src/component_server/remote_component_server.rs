@@ -10,6 +10,7 @@ use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Request as HyperRequest, Response as HyperResponse, Server, StatusCode};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
+use tracing::error;
 
 use super::definitions::ComponentServerStarter;
 use crate::component_definitions::{
@@ -17,6 +18,7 @@ use crate::component_definitions::{
     ServerError,
     APPLICATION_OCTET_STREAM,
 };
+use crate::component_runner::ComponentStarter;
 
 pub struct RemoteComponentServer<Component, Request, Response>
 where
@@ -97,3 +99,69 @@ where
         Server::bind(&self.socket.clone()).serve(make_svc).await.unwrap();
     }
 }
+
+/// A remote server of a component that also runs a task of its own, i.e., its
+/// [`ComponentStarter::start`], alongside serving requests.
+pub struct RemoteActiveComponentServer<Component, Request, Response>
+where
+    Component: ComponentRequestHandler<Request, Response>
+        + ComponentStarter
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    Request: for<'a> Deserialize<'a> + Send + 'static,
+    Response: Serialize + 'static,
+{
+    component: Component,
+    server: RemoteComponentServer<Component, Request, Response>,
+}
+
+impl<Component, Request, Response> RemoteActiveComponentServer<Component, Request, Response>
+where
+    Component: ComponentRequestHandler<Request, Response>
+        + ComponentStarter
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    Request: for<'a> Deserialize<'a> + Send + 'static,
+    Response: Serialize + 'static,
+{
+    pub fn new(component: Component, ip_address: IpAddr, port: u16) -> Self {
+        Self {
+            component: component.clone(),
+            server: RemoteComponentServer::new(component, ip_address, port),
+        }
+    }
+}
+
+#[async_trait]
+impl<Component, Request, Response> ComponentServerStarter
+    for RemoteActiveComponentServer<Component, Request, Response>
+where
+    Component: ComponentRequestHandler<Request, Response>
+        + ComponentStarter
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    Request: for<'a> Deserialize<'a> + Send + Sync + 'static,
+    Response: Serialize + Send + Sync + 'static,
+{
+    async fn start(&mut self) {
+        let mut component = self.component.clone();
+        let component_future = async move { component.start().await };
+        let server_future = self.server.start();
+
+        tokio::select! {
+            _res = component_future => {
+                error!("Component stopped.");
+            }
+            _res = server_future => {
+                error!("Server stopped.");
+            }
+        };
+        error!("Server ended with unexpected Ok.");
+    }
+}
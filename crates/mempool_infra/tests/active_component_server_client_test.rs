@@ -1,9 +1,15 @@
 use std::future::pending;
+use std::net::{IpAddr, Ipv6Addr};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use starknet_mempool_infra::component_client::{ClientError, ClientResult, LocalComponentClient};
+use starknet_mempool_infra::component_client::{
+    ClientError,
+    ClientResult,
+    LocalComponentClient,
+    RemoteComponentClient,
+};
 use starknet_mempool_infra::component_definitions::{
     ComponentRequestAndResponseSender,
     ComponentRequestHandler,
@@ -13,11 +19,15 @@ use starknet_mempool_infra::component_server::{
     ComponentServerStarter,
     EmptyServer,
     LocalActiveComponentServer,
+    RemoteActiveComponentServer,
 };
 use tokio::sync::mpsc::{channel, Sender};
 use tokio::sync::{Barrier, Mutex};
 use tokio::task;
 
+const LOCAL_IP: IpAddr = IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+const C_PORT_REMOTE_SETUP: u16 = 10020;
+
 #[derive(Debug, Clone)]
 struct ComponentC {
     counter: Arc<Mutex<usize>>,
@@ -132,6 +142,23 @@ impl ComponentCClientTrait for LocalComponentClient<ComponentCRequest, Component
     }
 }
 
+#[async_trait]
+impl ComponentCClientTrait for RemoteComponentClient<ComponentCRequest, ComponentCResponse> {
+    async fn c_inc_counter(&self) -> ClientResult<()> {
+        match self.send(ComponentCRequest::CIncCounter).await? {
+            ComponentCResponse::CIncCounter => Ok(()),
+            _ => Err(ClientError::UnexpectedResponse("Unexpected Responce".to_string())),
+        }
+    }
+
+    async fn c_get_counter(&self) -> ClientResult<usize> {
+        match self.send(ComponentCRequest::CGetCounter).await? {
+            ComponentCResponse::CGetCounter(counter) => Ok(counter),
+            _ => Err(ClientError::UnexpectedResponse("Unexpected Responce".to_string())),
+        }
+    }
+}
+
 #[async_trait]
 impl ComponentRequestHandler<ComponentCRequest, ComponentCResponse> for ComponentC {
     async fn handle_request(&mut self, request: ComponentCRequest) -> ComponentCResponse {
@@ -187,3 +214,26 @@ async fn test_setup_c_d() {
     // Wait for the components to finish incrementing of the ComponentC::counter and verify it.
     wait_and_verify_response(tx_c.clone(), expected_counter_value, barrier).await;
 }
+
+#[tokio::test]
+async fn test_remote_active_server() {
+    let init_counter_value: usize = 0;
+    let max_iterations: usize = 1024;
+
+    let barrier = Arc::new(Barrier::new(2));
+    let component_c = ComponentC::new(init_counter_value, max_iterations, barrier.clone());
+    let mut component_c_server =
+        RemoteActiveComponentServer::new(component_c, LOCAL_IP, C_PORT_REMOTE_SETUP);
+
+    task::spawn(async move {
+        component_c_server.start().await;
+    });
+
+    // Wait for the component to finish incrementing its counter, and verify it through the server.
+    barrier.wait().await;
+    let c_client = RemoteComponentClient::<ComponentCRequest, ComponentCResponse>::new(
+        LOCAL_IP,
+        C_PORT_REMOTE_SETUP,
+    );
+    assert_eq!(c_client.c_get_counter().await.unwrap(), max_iterations);
+}
@@ -1,4 +1,6 @@
-use starknet_gateway::gateway::{create_gateway, Gateway};
+use std::thread;
+
+use starknet_gateway::gateway::{create_account_state_reader, create_gateway, Gateway};
 use starknet_mempool::mempool::Mempool;
 
 use crate::communication::MempoolNodeClients;
@@ -23,7 +25,29 @@ pub fn create_components(config: &MempoolNodeConfig, clients: &MempoolNodeClient
         None
     };
 
-    let mempool = if config.components.mempool.execute { Some(Mempool::empty()) } else { None };
+    let mempool = if config.components.mempool.execute {
+        Some(create_mempool(config))
+    } else {
+        None
+    };
 
     Components { gateway, mempool }
 }
+
+fn create_mempool(config: &MempoolNodeConfig) -> Mempool {
+    // Restoring the mempool from its journal reads the latest state by blocking requests, which
+    // must not be sent from within the async runtime.
+    thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let account_state_reader = create_account_state_reader(
+                    &config.gateway_config,
+                    config.rpc_state_reader_config.clone(),
+                );
+                Mempool::new(config.mempool_config.clone(), &account_state_reader)
+            })
+            .join()
+            .expect("Mempool creation should not panic.")
+    })
+    .expect("Failed to restore the mempool from its journal.")
+}
@@ -1,5 +1,6 @@
 use std::env;
 use std::fs::File;
+use std::time::Duration;
 
 use assert_json_diff::assert_json_eq;
 use assert_matches::assert_matches;
@@ -7,6 +8,7 @@ use colored::Colorize;
 use mempool_test_utils::get_absolute_path;
 use papyrus_config::dumping::SerializeConfig;
 use papyrus_config::validators::{ParsedValidationError, ParsedValidationErrors};
+use starknet_mempool::config::MempoolConfig;
 use validator::Validate;
 
 use crate::config::{
@@ -53,6 +55,25 @@ fn test_components_config_validation() {
     }
 }
 
+/// Test that the mempool config rejects a zero sweep interval, ttl and limits, which would
+/// otherwise panic or block the mempool at startup.
+#[test]
+fn test_mempool_config_validation() {
+    let invalid_configs = [
+        MempoolConfig { expired_txs_sweep_interval: Duration::ZERO, ..Default::default() },
+        MempoolConfig { transaction_ttl: Duration::ZERO, ..Default::default() },
+        MempoolConfig { max_txs: 0, ..Default::default() },
+        MempoolConfig { max_txs_per_account: 0, ..Default::default() },
+        MempoolConfig { max_total_size_in_bytes: 0, ..Default::default() },
+    ];
+    for config in invalid_configs {
+        let parsed_errors = ParsedValidationErrors::from(config.validate().unwrap_err());
+        assert_eq!(parsed_errors.0.len(), 1);
+    }
+
+    assert_matches!(MempoolConfig::default().validate(), Ok(()));
+}
+
 /// Test the validation of the struct MempoolNodeConfig and that the default config file is up to
 /// date. To update the default config file, run:
 /// cargo run --bin mempool_dump_config -q
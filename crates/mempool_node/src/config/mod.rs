@@ -11,6 +11,7 @@ use papyrus_config::loading::load_and_process_config;
 use papyrus_config::{ConfigError, ParamPath, ParamPrivacyInput, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_gateway::config::{GatewayConfig, RpcStateReaderConfig};
+use starknet_mempool::config::MempoolConfig;
 use validator::{Validate, ValidationError};
 
 use crate::version::VERSION_FULL;
@@ -80,6 +81,8 @@ pub struct MempoolNodeConfig {
     pub gateway_config: GatewayConfig,
     #[validate]
     pub rpc_state_reader_config: RpcStateReaderConfig,
+    #[validate]
+    pub mempool_config: MempoolConfig,
 }
 
 impl SerializeConfig for MempoolNodeConfig {
@@ -89,6 +92,7 @@ impl SerializeConfig for MempoolNodeConfig {
             append_sub_config_name(self.components.dump(), "components"),
             append_sub_config_name(self.gateway_config.dump(), "gateway_config"),
             append_sub_config_name(self.rpc_state_reader_config.dump(), "rpc_state_reader_config"),
+            append_sub_config_name(self.mempool_config.dump(), "mempool_config"),
         ];
 
         sub_configs.into_iter().flatten().collect()
@@ -10,10 +10,18 @@ use starknet_mempool_infra::component_client::{
     RemoteComponentClient,
 };
 use starknet_mempool_infra::component_definitions::ComponentRequestAndResponseSender;
+use starknet_api::core::ContractAddress;
+use starknet_api::transaction::TransactionHash;
 use thiserror::Error;
 
 use crate::errors::MempoolError;
-use crate::mempool_types::{MempoolInput, ThinTransaction};
+use crate::mempool_types::{
+    AddTransactionOutput,
+    DropReason,
+    MempoolInput,
+    MempoolStats,
+    ThinTransaction,
+};
 
 pub type MempoolClientImpl = LocalComponentClient<MempoolRequest, MempoolResponse>;
 pub type RemoteMempoolClientImpl = RemoteComponentClient<MempoolRequest, MempoolResponse>;
@@ -28,20 +36,56 @@ pub type SharedMempoolClient = Arc<dyn MempoolClient>;
 #[automock]
 #[async_trait]
 pub trait MempoolClient: Send + Sync {
-    async fn add_tx(&self, mempool_input: MempoolInput) -> MempoolClientResult<()>;
+    async fn add_tx(
+        &self,
+        mempool_input: MempoolInput,
+    ) -> MempoolClientResult<AddTransactionOutput>;
+    /// Adds the given transactions in a single request, returning the result of adding each one.
+    async fn add_txs(
+        &self,
+        mempool_inputs: Vec<MempoolInput>,
+    ) -> MempoolClientResult<Vec<MempoolResult<AddTransactionOutput>>>;
     async fn get_txs(&self, n_txs: usize) -> MempoolClientResult<Vec<ThinTransaction>>;
+    async fn get_tx(&self, tx_hash: TransactionHash) -> MempoolClientResult<ThinTransaction>;
+    /// Returns the pending transactions of the given account, including those waiting for a
+    /// missing nonce, sorted by ascending nonce.
+    async fn get_account_txs(
+        &self,
+        address: ContractAddress,
+    ) -> MempoolClientResult<Vec<ThinTransaction>>;
+    async fn get_stats(&self) -> MempoolClientResult<MempoolStats>;
+    /// Returns the reason the given transaction was dropped from the mempool, if it was.
+    async fn get_drop_reason(
+        &self,
+        tx_hash: TransactionHash,
+    ) -> MempoolClientResult<Option<DropReason>>;
+    /// Returns whether the given transaction was taken from the mempool and then included in a
+    /// committed block, as recorded by the mempool.
+    async fn is_tx_included(&self, tx_hash: TransactionHash) -> MempoolClientResult<bool>;
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum MempoolRequest {
     AddTransaction(MempoolInput),
+    AddTransactions(Vec<MempoolInput>),
     GetTransactions(usize),
+    GetTransaction(TransactionHash),
+    GetAccountTransactions(ContractAddress),
+    GetStats,
+    GetDropReason(TransactionHash),
+    IsTransactionIncluded(TransactionHash),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum MempoolResponse {
-    AddTransaction(MempoolResult<()>),
+    AddTransaction(MempoolResult<AddTransactionOutput>),
+    AddTransactions(MempoolResult<Vec<MempoolResult<AddTransactionOutput>>>),
     GetTransactions(MempoolResult<Vec<ThinTransaction>>),
+    GetTransaction(MempoolResult<ThinTransaction>),
+    GetAccountTransactions(MempoolResult<Vec<ThinTransaction>>),
+    GetStats(MempoolResult<MempoolStats>),
+    GetDropReason(MempoolResult<Option<DropReason>>),
+    IsTransactionIncluded(MempoolResult<bool>),
 }
 
 #[derive(Clone, Debug, Error)]
@@ -54,7 +98,10 @@ pub enum MempoolClientError {
 
 #[async_trait]
 impl MempoolClient for MempoolClientImpl {
-    async fn add_tx(&self, mempool_input: MempoolInput) -> MempoolClientResult<()> {
+    async fn add_tx(
+        &self,
+        mempool_input: MempoolInput,
+    ) -> MempoolClientResult<AddTransactionOutput> {
         let request = MempoolRequest::AddTransaction(mempool_input);
         let response = self.send(request).await;
         match response {
@@ -68,6 +115,23 @@ impl MempoolClient for MempoolClientImpl {
         }
     }
 
+    async fn add_txs(
+        &self,
+        mempool_inputs: Vec<MempoolInput>,
+    ) -> MempoolClientResult<Vec<MempoolResult<AddTransactionOutput>>> {
+        let request = MempoolRequest::AddTransactions(mempool_inputs);
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::AddTransactions(Ok(response)) => Ok(response),
+            MempoolResponse::AddTransactions(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
     async fn get_txs(&self, n_txs: usize) -> MempoolClientResult<Vec<ThinTransaction>> {
         let request = MempoolRequest::GetTransactions(n_txs);
         let response = self.send(request).await;
@@ -81,11 +145,90 @@ impl MempoolClient for MempoolClientImpl {
             )),
         }
     }
+
+    async fn get_tx(&self, tx_hash: TransactionHash) -> MempoolClientResult<ThinTransaction> {
+        let request = MempoolRequest::GetTransaction(tx_hash);
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::GetTransaction(Ok(response)) => Ok(response),
+            MempoolResponse::GetTransaction(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_account_txs(
+        &self,
+        address: ContractAddress,
+    ) -> MempoolClientResult<Vec<ThinTransaction>> {
+        let request = MempoolRequest::GetAccountTransactions(address);
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::GetAccountTransactions(Ok(response)) => Ok(response),
+            MempoolResponse::GetAccountTransactions(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_stats(&self) -> MempoolClientResult<MempoolStats> {
+        let request = MempoolRequest::GetStats;
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::GetStats(Ok(response)) => Ok(response),
+            MempoolResponse::GetStats(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_drop_reason(
+        &self,
+        tx_hash: TransactionHash,
+    ) -> MempoolClientResult<Option<DropReason>> {
+        let request = MempoolRequest::GetDropReason(tx_hash);
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::GetDropReason(Ok(response)) => Ok(response),
+            MempoolResponse::GetDropReason(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn is_tx_included(&self, tx_hash: TransactionHash) -> MempoolClientResult<bool> {
+        let request = MempoolRequest::IsTransactionIncluded(tx_hash);
+        let response = self.send(request).await;
+        match response {
+            MempoolResponse::IsTransactionIncluded(Ok(response)) => Ok(response),
+            MempoolResponse::IsTransactionIncluded(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
 }
 
 #[async_trait]
 impl MempoolClient for RemoteMempoolClientImpl {
-    async fn add_tx(&self, mempool_input: MempoolInput) -> MempoolClientResult<()> {
+    async fn add_tx(
+        &self,
+        mempool_input: MempoolInput,
+    ) -> MempoolClientResult<AddTransactionOutput> {
         let request = MempoolRequest::AddTransaction(mempool_input);
         let response = self.send(request).await?;
         match response {
@@ -99,6 +242,23 @@ impl MempoolClient for RemoteMempoolClientImpl {
         }
     }
 
+    async fn add_txs(
+        &self,
+        mempool_inputs: Vec<MempoolInput>,
+    ) -> MempoolClientResult<Vec<MempoolResult<AddTransactionOutput>>> {
+        let request = MempoolRequest::AddTransactions(mempool_inputs);
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::AddTransactions(Ok(response)) => Ok(response),
+            MempoolResponse::AddTransactions(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
     async fn get_txs(&self, n_txs: usize) -> MempoolClientResult<Vec<ThinTransaction>> {
         let request = MempoolRequest::GetTransactions(n_txs);
         let response = self.send(request).await?;
@@ -112,4 +272,80 @@ impl MempoolClient for RemoteMempoolClientImpl {
             )),
         }
     }
+
+    async fn get_tx(&self, tx_hash: TransactionHash) -> MempoolClientResult<ThinTransaction> {
+        let request = MempoolRequest::GetTransaction(tx_hash);
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::GetTransaction(Ok(response)) => Ok(response),
+            MempoolResponse::GetTransaction(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_account_txs(
+        &self,
+        address: ContractAddress,
+    ) -> MempoolClientResult<Vec<ThinTransaction>> {
+        let request = MempoolRequest::GetAccountTransactions(address);
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::GetAccountTransactions(Ok(response)) => Ok(response),
+            MempoolResponse::GetAccountTransactions(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_stats(&self) -> MempoolClientResult<MempoolStats> {
+        let request = MempoolRequest::GetStats;
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::GetStats(Ok(response)) => Ok(response),
+            MempoolResponse::GetStats(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn get_drop_reason(
+        &self,
+        tx_hash: TransactionHash,
+    ) -> MempoolClientResult<Option<DropReason>> {
+        let request = MempoolRequest::GetDropReason(tx_hash);
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::GetDropReason(Ok(response)) => Ok(response),
+            MempoolResponse::GetDropReason(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
+
+    async fn is_tx_included(&self, tx_hash: TransactionHash) -> MempoolClientResult<bool> {
+        let request = MempoolRequest::IsTransactionIncluded(tx_hash);
+        let response = self.send(request).await?;
+        match response {
+            MempoolResponse::IsTransactionIncluded(Ok(response)) => Ok(response),
+            MempoolResponse::IsTransactionIncluded(Err(response)) => {
+                Err(MempoolClientError::MempoolError(response))
+            }
+            unexpected_response => Err(MempoolClientError::ClientError(
+                ClientError::UnexpectedResponse(format!("{unexpected_response:?}")),
+            )),
+        }
+    }
 }
@@ -5,10 +5,35 @@ use thiserror::Error;
 
 #[derive(Clone, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MempoolError {
+    #[error("Failed to read the state of sender address: {address}: {error}")]
+    AccountStateReadError { address: ContractAddress, error: String },
+    #[error(
+        "Sender address: {address} reached the limit of transactions in the mempool; transaction \
+         with hash: {tx_hash} was rejected"
+    )]
+    AccountTransactionLimitExceeded { address: ContractAddress, tx_hash: TransactionHash },
     #[error("Duplicate transaction, sender address: {address}, nonce: {:?}", nonce)]
     DuplicateNonce { address: ContractAddress, nonce: Nonce },
     #[error("Duplicate transaction, with hash: {tx_hash}")]
     DuplicateTransaction { tx_hash: TransactionHash },
+    #[error(
+        "Sender address: {address} cannot afford the maximal fees of its pending transactions and \
+         transaction with hash: {tx_hash}"
+    )]
+    InsufficientBalance { address: ContractAddress, tx_hash: TransactionHash },
+    #[error("Mempool journal error: {0}")]
+    JournalError(String),
+    #[error(
+        "Transaction with hash: {tx_hash} does not pay enough to replace the transaction of \
+         sender address: {address}, nonce: {:?}",
+        nonce
+    )]
+    InsufficientFeeEscalation { address: ContractAddress, nonce: Nonce, tx_hash: TransactionHash },
+    #[error(
+        "Mempool is full, and transaction with hash: {tx_hash} has a lower priority than all \
+         pending transactions"
+    )]
+    MempoolFull { tx_hash: TransactionHash },
     #[error("Transaction with hash: {tx_hash} not found")]
     TransactionNotFound { tx_hash: TransactionHash },
     // TODO(Mohammad): Consider using `StarknetApiError` once it implements `PartialEq`.
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use starknet_api::core::{ContractAddress, Nonce};
-use starknet_api::transaction::{Tip, TransactionHash};
+use starknet_api::rpc_transaction::ResourceBoundsMapping;
+use starknet_api::transaction::{Fee, Tip, TransactionHash};
 
 use crate::errors::MempoolError;
 
@@ -10,12 +11,26 @@ pub struct ThinTransaction {
     pub tx_hash: TransactionHash,
     pub tip: Tip,
     pub nonce: Nonce,
+    pub resource_bounds: ResourceBoundsMapping,
+}
+
+impl ThinTransaction {
+    /// The maximal fee the transaction may be charged, according to its resource bounds.
+    pub fn max_fee(&self) -> Fee {
+        let ResourceBoundsMapping { l1_gas, l2_gas } = &self.resource_bounds;
+        let l1_gas_max_fee =
+            u128::from(l1_gas.max_amount).saturating_mul(l1_gas.max_price_per_unit);
+        let l2_gas_max_fee =
+            u128::from(l2_gas.max_amount).saturating_mul(l2_gas.max_price_per_unit);
+        Fee(l1_gas_max_fee.saturating_add(l2_gas_max_fee))
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct AccountState {
     pub nonce: Nonce,
-    // TODO: add balance field when needed.
+    // The account's balance of the fee token its transactions are paid with.
+    pub balance: Fee,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -31,4 +46,37 @@ pub struct MempoolInput {
     pub account: Account,
 }
 
+/// The outcome of successfully adding a transaction to the mempool.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddTransactionOutput {
+    // The hash of the transaction that was replaced by the added one (fee escalation), if any.
+    pub replaced_tx_hash: Option<TransactionHash>,
+}
+
+/// Aggregate statistics of the transactions held in the mempool.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MempoolStats {
+    // The number of transactions eligible for sequencing.
+    pub n_queued_txs: usize,
+    // The number of transactions held in the mempool, including the queued ones.
+    pub n_pool_txs: usize,
+    // The number of accounts with pending transactions waiting for a missing nonce.
+    pub n_accounts_with_nonce_gaps: usize,
+}
+
+/// The reason a transaction was dropped from the mempool, without being included in a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DropReason {
+    /// Evicted to make room for transactions with a higher priority.
+    Evicted,
+    /// Held in the mempool for longer than the configured time-to-live.
+    Expired,
+}
+
 pub type MempoolResult<T> = Result<T, MempoolError>;
+
+/// Provides the latest state of accounts, against which the mempool re-validates the transactions
+/// it restores after a restart.
+pub trait AccountStateReader {
+    fn get_account_state(&self, address: ContractAddress) -> MempoolResult<AccountState>;
+}
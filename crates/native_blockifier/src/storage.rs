@@ -51,6 +51,7 @@ impl PapyrusStorage {
                 growth_step: 2 << 30,     // 2GB
                 max_object_size: 1 << 30, // 1GB
             },
+            ..Default::default()
         };
         let (reader, writer) = papyrus_storage::open_storage(storage_config)?;
         log::debug!("Initialized Blockifier storage.");
@@ -1,3 +1,4 @@
+use std::fs;
 use std::net::{SocketAddr, TcpListener};
 use std::sync::Arc;
 
@@ -8,6 +9,7 @@ use axum::Router;
 use http_body::combinators::UnsyncBoxBody;
 use metrics::{absolute_counter, describe_counter, register_counter};
 use metrics_exporter_prometheus::PrometheusBuilder;
+use papyrus_consensus::evidence::EvidenceStore;
 use papyrus_storage::{table_names, test_utils};
 use pretty_assertions::assert_eq;
 use serde_json::{json, Value};
@@ -35,6 +37,7 @@ fn setup_app() -> Router {
         SECRET.to_string(),
         None,
         TEST_PEER_ID.to_string(),
+        None,
     )
 }
 
@@ -180,6 +183,7 @@ async fn with_metrics() {
         String::new(),
         Some(prometheus_handle),
         TEST_PEER_ID.to_string(),
+        None,
     );
 
     // Register a metric.
@@ -203,6 +207,60 @@ async fn with_metrics() {
     assert!(body_string.starts_with(&expected_prefix));
 }
 
+#[tokio::test]
+async fn without_consensus() {
+    let app = setup_app();
+    let response = request_app(app, "consensusEvidence").await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    let body_bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert!(body_bytes.is_empty());
+}
+
+#[tokio::test]
+async fn consensus_evidence() {
+    let ((storage_reader, _), _temp_dir) = test_utils::get_test_storage();
+    let evidence_dir = tempfile::tempdir().unwrap();
+    let evidence_path = evidence_dir.path().join("evidence.jsonl");
+    let evidence = json!({
+        "first_vote": {
+            "vote_type": "Prevote",
+            "height": 1,
+            "round": 0,
+            "block_hash": "0x1",
+            "voter": "0x2",
+            "signature": {"r": "0x3", "s": "0x4"},
+        },
+        "second_vote": {
+            "vote_type": "Prevote",
+            "height": 1,
+            "round": 0,
+            "block_hash": null,
+            "voter": "0x2",
+            "signature": {"r": "0x5", "s": "0x6"},
+        },
+    });
+    fs::write(&evidence_path, format!("{evidence}\n")).unwrap();
+    let app = app(
+        String::from("https://default_url"),
+        storage_reader,
+        TEST_VERSION,
+        serde_json::Value::default(),
+        serde_json::Value::default(),
+        String::new(),
+        None,
+        TEST_PEER_ID.to_string(),
+        Some(EvidenceStore::open(evidence_path).unwrap()),
+    );
+
+    let response = request_app(app, "consensusEvidence").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!([evidence]));
+}
+
 #[tokio::test]
 async fn run_server() {
     let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
@@ -18,6 +18,7 @@ use axum::routing::get;
 use axum::{Json, Router};
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
 use metrics_process::Collector;
+use papyrus_consensus::evidence::EvidenceStore;
 use papyrus_config::converters::{deserialize_optional_map, serialize_optional_map};
 use papyrus_config::dumping::{ser_generated_param, ser_param, SerializeConfig};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
@@ -120,6 +121,7 @@ pub struct MonitoringServer {
     version: &'static str,
     prometheus_handle: Option<PrometheusHandle>,
     own_peer_id: String,
+    evidence_store: Option<EvidenceStore>,
 }
 
 impl MonitoringServer {
@@ -130,6 +132,7 @@ impl MonitoringServer {
         storage_reader: StorageReader,
         version: &'static str,
         own_peer_id: String,
+        evidence_store: Option<EvidenceStore>,
     ) -> Result<Self, BuildError> {
         let prometheus_handle = if config.collect_metrics {
             let mut builder = PrometheusBuilder::new();
@@ -150,6 +153,7 @@ impl MonitoringServer {
             version,
             prometheus_handle,
             own_peer_id,
+            evidence_store,
         })
     }
 
@@ -179,6 +183,7 @@ impl MonitoringServer {
             self.config.present_full_config_secret.clone(),
             self.prometheus_handle.clone(),
             self.own_peer_id.clone(),
+            self.evidence_store.clone(),
         );
         debug!("Starting monitoring gateway.");
         axum::Server::bind(&server_address).serve(app.into_make_service()).await
@@ -195,6 +200,7 @@ fn app(
     present_full_config_secret: String,
     prometheus_handle: Option<PrometheusHandle>,
     own_peer_id: String,
+    evidence_store: Option<EvidenceStore>,
 ) -> Router {
     let is_ready_retry_config =
         RetryConfig { retry_base_millis: 50, retry_max_delay_millis: 1000, max_retries: 0 };
@@ -256,6 +262,10 @@ fn app(
             get(move || is_ready(starknet_client, starknet_feeder_client)),
         )
         .route(format!("/{MONITORING_PREFIX}/peer_id").as_str(), get(move || async { own_peer_id }))
+        .route(
+            format!("/{MONITORING_PREFIX}/consensusEvidence").as_str(),
+            get(move || consensus_evidence(evidence_store)),
+        )
 }
 
 async fn is_ready<TStarknetWriter: StarknetWriter, TStarknetReader: StarknetReader>(
@@ -320,6 +330,19 @@ async fn metrics(prometheus_handle: Option<PrometheusHandle>) -> Response {
     }
 }
 
+/// Returns the evidence of validators which equivocated in consensus.
+/// In case the node doesn’t run consensus returns an empty response with status code 405: method
+/// not allowed.
+#[instrument(level = "debug", ret)]
+async fn consensus_evidence(
+    evidence_store: Option<EvidenceStore>,
+) -> Result<Response, ServerError> {
+    match evidence_store {
+        Some(evidence_store) => Ok(Json(evidence_store.evidence()?).into_response()),
+        None => Ok(StatusCode::METHOD_NOT_ALLOWED.into_response()),
+    }
+}
+
 /// Returns the node version.
 #[instrument(level = "debug", ret)]
 async fn node_version(version: &'static str) -> String {
@@ -330,6 +353,8 @@ async fn node_version(version: &'static str) -> String {
 enum ServerError {
     #[error(transparent)]
     StorageError(#[from] StorageError),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
 }
 
 impl IntoResponse for ServerError {
@@ -337,6 +362,7 @@ impl IntoResponse for ServerError {
         let (status, error_message) = match self {
             // TODO(dan): consider using a generic error message instead.
             ServerError::StorageError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            ServerError::IoError(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
         (status, error_message).into_response()
     }
@@ -16,9 +16,17 @@ use papyrus_common::BlockHashAndNumber;
 use papyrus_config::presentation::get_config_presentation;
 use papyrus_config::validators::config_validate;
 use papyrus_config::ConfigError;
-use papyrus_consensus::config::ConsensusConfig;
+use papyrus_consensus::config::{ConsensusConfig, ValidatorSetSource};
+use papyrus_consensus::evidence::{EvidencePool, EvidenceStore};
 use papyrus_consensus::papyrus_consensus_context::PapyrusConsensusContext;
+use papyrus_consensus::signing::ConsensusSigner;
 use papyrus_consensus::types::ConsensusError;
+use papyrus_consensus::validator_set::{
+    StaticValidatorSetProvider,
+    StorageValidatorSetProvider,
+    ValidatorSetProvider,
+};
+use papyrus_consensus::wal::ConsensusWal;
 use papyrus_monitoring_gateway::MonitoringServer;
 use papyrus_network::gossipsub_impl::Topic;
 use papyrus_network::network_manager::{BroadcastSubscriberChannels, NetworkError};
@@ -33,15 +41,17 @@ use papyrus_p2p_sync::client::{
 };
 use papyrus_p2p_sync::server::{P2PSyncServer, P2PSyncServerChannels};
 use papyrus_p2p_sync::{Protocol, BUFFER_SIZE};
-use papyrus_protobuf::consensus::ConsensusMessage;
+use papyrus_protobuf::consensus::{ConsensusMessage, EquivocationEvidence};
 #[cfg(feature = "rpc")]
 use papyrus_rpc::run_server;
+use papyrus_storage::state::pruning::StatePruner;
 use papyrus_storage::{open_storage, update_storage_metrics, StorageReader, StorageWriter};
 use papyrus_sync::sources::base_layer::{BaseLayerSourceError, EthereumBaseLayerSource};
 use papyrus_sync::sources::central::{CentralError, CentralSource, CentralSourceConfig};
 use papyrus_sync::sources::pending::PendingSource;
 use papyrus_sync::{StateSync, StateSyncError, SyncConfig};
 use starknet_api::block::BlockHash;
+use starknet_api::core::ChainId;
 use starknet_api::felt;
 use starknet_client::reader::objects::pending_data::{PendingBlock, PendingBlockOrDeprecated};
 use starknet_client::reader::PendingData;
@@ -64,6 +74,11 @@ const GENESIS_HASH: &str = "0x0";
 // Duration between updates to the storage metrics (those in the collect_storage_metrics function).
 const STORAGE_METRICS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
 
+// Duration between rounds of state pruning, when there were no more blocks to prune.
+const STATE_PRUNING_INTERVAL: Duration = Duration::from_secs(60);
+// The maximal number of blocks whose state history is pruned in a single write transaction.
+const STATE_PRUNING_MAX_BLOCKS_PER_TXN: u64 = 100;
+
 #[cfg(feature = "rpc")]
 async fn create_rpc_server_future(
     config: &NodeConfig,
@@ -97,24 +112,44 @@ async fn create_rpc_server_future(
 
 fn run_consensus(
     config: ConsensusConfig,
+    chain_id: ChainId,
     storage_reader: StorageReader,
-    consensus_channels: BroadcastSubscriberChannels<ConsensusMessage>,
+    consensus_channels: ConsensusChannels,
+    evidence_store: EvidenceStore,
 ) -> anyhow::Result<JoinHandle<Result<(), ConsensusError>>> {
+    let (consensus_channels, evidence_channels) = consensus_channels;
     let validator_id = config.validator_id;
     info!("Running consensus as validator {validator_id}");
+    let signer = ConsensusSigner::new(config.private_key, chain_id);
+    let validator_set_provider: Box<dyn ValidatorSetProvider> = match config.validator_set.source {
+        ValidatorSetSource::File => {
+            Box::new(StaticValidatorSetProvider::from_file(&config.validator_set.file_path)?)
+        }
+        ValidatorSetSource::Storage => Box::new(StorageValidatorSetProvider::new(
+            storage_reader.clone(),
+            config.validator_set.staking_contract_address,
+        )),
+    };
     let context = PapyrusConsensusContext::new(
         storage_reader.clone(),
         consensus_channels.messages_to_broadcast_sender,
-        config.num_validators,
+        signer.clone(),
+        validator_set_provider,
     );
     let start_height = config.start_height;
+    let wal = ConsensusWal::open(config.wal_dir)?;
+    let evidence_pool = EvidencePool::new(evidence_store, evidence_channels)?;
 
     Ok(tokio::spawn(papyrus_consensus::run_consensus(
         context,
         start_height,
         validator_id,
+        signer,
         config.consensus_delay,
+        config.timeouts,
+        wal,
         consensus_channels.broadcasted_messages_receiver,
+        evidence_pool,
     )))
 }
 
@@ -127,6 +162,11 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
         tokio::spawn(pending())
     };
 
+    let state_pruner_handle = match storage_writer.state_pruner() {
+        Some(state_pruner) => spawn_state_pruner(state_pruner, STATE_PRUNING_INTERVAL),
+        None => tokio::spawn(pending()),
+    };
+
     // P2P network.
     let (
         network_future,
@@ -137,6 +177,13 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
     ) = run_network(config.network.clone(), config.consensus.clone())?;
     let network_handle = tokio::spawn(network_future);
 
+    // Evidence of equivocation, which is found by consensus and presented by the monitoring server.
+    let evidence_store = config
+        .consensus
+        .as_ref()
+        .map(|consensus_config| EvidenceStore::open(consensus_config.evidence_path.clone()))
+        .transpose()?;
+
     // Monitoring server.
     let monitoring_server = MonitoringServer::new(
         config.monitoring_gateway.clone(),
@@ -145,6 +192,7 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
         storage_reader.clone(),
         VERSION_FULL,
         local_peer_id,
+        evidence_store.clone(),
     )?;
     let monitoring_server_handle = monitoring_server.spawn_server().await;
 
@@ -216,8 +264,10 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
     let consensus_handle = if let Some(consensus_channels) = maybe_consensus_channels {
         run_consensus(
             config.consensus.expect("If consensus_channels is Some, consensus must be Some too."),
+            config.storage.db_config.chain_id.clone(),
             storage_reader.clone(),
             consensus_channels,
+            evidence_store.expect("If consensus_channels is Some, consensus must be Some too."),
         )?
     } else {
         tokio::spawn(pending())
@@ -228,6 +278,10 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
             error!("collecting storage metrics stopped.");
             res?
         }
+        res = state_pruner_handle => {
+            error!("State pruning stopped.");
+            res?
+        }
         res = server_handle_future => {
             error!("RPC server stopped.");
             res?
@@ -306,11 +360,17 @@ async fn run_threads(config: NodeConfig) -> anyhow::Result<()> {
     }
 }
 
+// The channels of the consensus messages and of the evidence of equivocation.
+type ConsensusChannels = (
+    BroadcastSubscriberChannels<ConsensusMessage>,
+    BroadcastSubscriberChannels<EquivocationEvidence>,
+);
+
 type NetworkRunReturn = (
     BoxFuture<'static, Result<(), NetworkError>>,
     Option<P2PSyncClientChannels>,
     Option<P2PSyncServerChannels>,
-    Option<BroadcastSubscriberChannels<ConsensusMessage>>,
+    Option<ConsensusChannels>,
     String,
 );
 
@@ -342,10 +402,14 @@ fn run_network(
         network_manager.register_sqmr_protocol_server(Protocol::Event.into(), BUFFER_SIZE);
 
     let consensus_channels = match consensus_config {
-        Some(consensus_config) => Some(
+        Some(consensus_config) => Some((
             network_manager
                 .register_broadcast_topic(Topic::new(consensus_config.topic), BUFFER_SIZE)?,
-        ),
+            network_manager.register_broadcast_topic(
+                Topic::new(consensus_config.evidence_topic),
+                BUFFER_SIZE,
+            )?,
+        )),
         None => None,
     };
     let p2p_sync_client_channels = P2PSyncClientChannels::new(
@@ -400,6 +464,32 @@ fn spawn_storage_metrics_collector(
     )
 }
 
+fn spawn_state_pruner(mut state_pruner: StatePruner, prune_interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(
+        async move {
+            loop {
+                let (returned_state_pruner, res) = tokio::task::spawn_blocking(move || {
+                    let res = state_pruner.prune(STATE_PRUNING_MAX_BLOCKS_PER_TXN);
+                    (state_pruner, res)
+                })
+                .await
+                .expect("State pruning task panicked.");
+                state_pruner = returned_state_pruner;
+                match res {
+                    // There might be more blocks to prune, continue without waiting.
+                    Ok(n_pruned_blocks) if n_pruned_blocks == STATE_PRUNING_MAX_BLOCKS_PER_TXN => {
+                        continue;
+                    }
+                    Ok(_) => {}
+                    Err(error) => warn!("Failed to prune the state: {error}"),
+                }
+                tokio::time::sleep(prune_interval).await;
+            }
+        }
+        .instrument(debug_span!("prune_state")),
+    )
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let config = NodeConfig::load_and_process(args().collect());
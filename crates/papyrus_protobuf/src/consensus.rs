@@ -1,42 +1,74 @@
+use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockHash;
 use starknet_api::core::ContractAddress;
+use starknet_api::crypto::utils::Signature;
 use starknet_api::transaction::Transaction;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Proposal {
+/// A part of a proposal, which is streamed as an init part, batches of transactions and a fin part.
+/// The parts are numbered in the order they are sent, so that they can be reassembled if they
+/// arrive out of order.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProposalPart {
     pub height: u64,
     pub round: u32,
     pub proposer: ContractAddress,
-    pub transactions: Vec<Transaction>,
-    pub block_hash: BlockHash,
+    pub sequence_number: u32,
+    pub content: ProposalContent,
+    pub signature: Signature,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ProposalContent {
+    /// The first part of the proposal. The valid round is set when the proposer re-proposes a
+    /// block which had a prevote quorum in an earlier round, to that round.
+    Init { valid_round: Option<u32> },
+    Transactions(Vec<Transaction>),
+    /// The last part of the proposal.
+    Fin { block_hash: BlockHash },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum VoteType {
     Prevote,
     Precommit,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Vote {
     pub vote_type: VoteType,
     pub height: u64,
     pub round: u32,
     pub block_hash: Option<BlockHash>,
     pub voter: ContractAddress,
+    pub signature: Signature,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ConsensusMessage {
-    Proposal(Proposal),
+    ProposalPart(ProposalPart),
     Vote(Vote),
 }
 
 impl ConsensusMessage {
     pub fn height(&self) -> u64 {
         match self {
-            ConsensusMessage::Proposal(proposal) => proposal.height,
+            ConsensusMessage::ProposalPart(part) => part.height,
             ConsensusMessage::Vote(vote) => vote.height,
         }
     }
+
+    /// The validator which signed the message.
+    pub fn sender(&self) -> ContractAddress {
+        match self {
+            ConsensusMessage::ProposalPart(part) => part.proposer,
+            ConsensusMessage::Vote(vote) => vote.voter,
+        }
+    }
+}
+
+/// Two conflicting votes signed by the same validator, of the same type, height and round.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EquivocationEvidence {
+    pub first_vote: Vote,
+    pub second_vote: Vote,
 }
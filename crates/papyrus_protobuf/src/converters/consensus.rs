@@ -2,22 +2,26 @@ use std::convert::{TryFrom, TryInto};
 
 use prost::Message;
 use starknet_api::block::BlockHash;
+use starknet_api::crypto::utils::Signature;
 use starknet_api::hash::StarkHash;
 use starknet_api::transaction::Transaction;
 
-use crate::consensus::{ConsensusMessage, Proposal, Vote, VoteType};
+use crate::consensus::{
+    ConsensusMessage,
+    EquivocationEvidence,
+    ProposalContent,
+    ProposalPart,
+    Vote,
+    VoteType,
+};
 use crate::converters::ProtobufConversionError;
 use crate::{auto_impl_into_and_try_from_vec_u8, protobuf};
 
-impl TryFrom<protobuf::Proposal> for Proposal {
+impl TryFrom<protobuf::ProposalPart> for ProposalPart {
     type Error = ProtobufConversionError;
 
-    fn try_from(value: protobuf::Proposal) -> Result<Self, Self::Error> {
-        let transactions = value
-            .transactions
-            .into_iter()
-            .map(|tx| tx.try_into())
-            .collect::<Result<Vec<Transaction>, ProtobufConversionError>>()?;
+    fn try_from(value: protobuf::ProposalPart) -> Result<Self, Self::Error> {
+        use protobuf::proposal_part::Content;
 
         let height = value.height;
         let round = value.round;
@@ -25,26 +29,63 @@ impl TryFrom<protobuf::Proposal> for Proposal {
             .proposer
             .ok_or(ProtobufConversionError::MissingField { field_description: "proposer" })?
             .try_into()?;
-        let block_hash: StarkHash = value
-            .block_hash
-            .ok_or(ProtobufConversionError::MissingField { field_description: "block_hash" })?
+        let sequence_number = value.sequence_number;
+        let content = match value
+            .content
+            .ok_or(ProtobufConversionError::MissingField { field_description: "content" })?
+        {
+            Content::Init(init) => ProposalContent::Init { valid_round: init.valid_round },
+            Content::Transactions(batch) => ProposalContent::Transactions(
+                batch
+                    .transactions
+                    .into_iter()
+                    .map(|tx| tx.try_into())
+                    .collect::<Result<Vec<Transaction>, ProtobufConversionError>>()?,
+            ),
+            Content::Fin(fin) => {
+                let block_hash: StarkHash = fin
+                    .block_hash
+                    .ok_or(ProtobufConversionError::MissingField {
+                        field_description: "block_hash",
+                    })?
+                    .try_into()?;
+                ProposalContent::Fin { block_hash: BlockHash(block_hash) }
+            }
+        };
+        let signature = value
+            .signature
+            .ok_or(ProtobufConversionError::MissingField { field_description: "signature" })?
             .try_into()?;
-        let block_hash = BlockHash(block_hash);
 
-        Ok(Proposal { height, round, proposer, transactions, block_hash })
+        Ok(ProposalPart { height, round, proposer, sequence_number, content, signature })
     }
 }
 
-impl From<Proposal> for protobuf::Proposal {
-    fn from(value: Proposal) -> Self {
-        let transactions = value.transactions.into_iter().map(Into::into).collect();
+impl From<ProposalPart> for protobuf::ProposalPart {
+    fn from(value: ProposalPart) -> Self {
+        use protobuf::proposal_part::Content;
+
+        let content = match value.content {
+            ProposalContent::Init { valid_round } => {
+                Content::Init(protobuf::ProposalInit { valid_round })
+            }
+            ProposalContent::Transactions(transactions) => {
+                Content::Transactions(protobuf::TransactionBatch {
+                    transactions: transactions.into_iter().map(Into::into).collect(),
+                })
+            }
+            ProposalContent::Fin { block_hash } => {
+                Content::Fin(protobuf::ProposalFin { block_hash: Some(block_hash.0.into()) })
+            }
+        };
 
-        protobuf::Proposal {
+        protobuf::ProposalPart {
             height: value.height,
             round: value.round,
             proposer: Some(value.proposer.into()),
-            transactions,
-            block_hash: Some(value.block_hash.0.into()),
+            sequence_number: value.sequence_number,
+            content: Some(content),
+            signature: Some(value.signature.into()),
         }
     }
 }
@@ -77,17 +118,20 @@ impl TryFrom<protobuf::Vote> for Vote {
 
         let height = value.height;
         let round = value.round;
-        let block_hash: StarkHash = value
+        let block_hash = value
             .block_hash
-            .ok_or(ProtobufConversionError::MissingField { field_description: "block_hash" })?
-            .try_into()?;
-        let block_hash = Some(BlockHash(block_hash));
+            .map(|block_hash| block_hash.try_into().map(BlockHash))
+            .transpose()?;
         let voter = value
             .voter
             .ok_or(ProtobufConversionError::MissingField { field_description: "voter" })?
             .try_into()?;
+        let signature = value
+            .signature
+            .ok_or(ProtobufConversionError::MissingField { field_description: "signature" })?
+            .try_into()?;
 
-        Ok(Vote { vote_type, height, round, block_hash, voter })
+        Ok(Vote { vote_type, height, round, block_hash, voter, signature })
     }
 }
 
@@ -104,10 +148,34 @@ impl From<Vote> for protobuf::Vote {
             round: value.round,
             block_hash: value.block_hash.map(|hash| hash.0.into()),
             voter: Some(value.voter.into()),
+            signature: Some(value.signature.into()),
         }
     }
 }
 
+impl TryFrom<protobuf::ConsensusSignature> for Signature {
+    type Error = ProtobufConversionError;
+
+    fn try_from(value: protobuf::ConsensusSignature) -> Result<Self, Self::Error> {
+        let r = value
+            .r
+            .ok_or(ProtobufConversionError::MissingField { field_description: "signature::r" })?
+            .try_into()?;
+        let s = value
+            .s
+            .ok_or(ProtobufConversionError::MissingField { field_description: "signature::s" })?
+            .try_into()?;
+
+        Ok(Signature { r, s })
+    }
+}
+
+impl From<Signature> for protobuf::ConsensusSignature {
+    fn from(value: Signature) -> Self {
+        protobuf::ConsensusSignature { r: Some(value.r.into()), s: Some(value.s.into()) }
+    }
+}
+
 impl TryFrom<protobuf::ConsensusMessage> for ConsensusMessage {
     type Error = ProtobufConversionError;
 
@@ -119,7 +187,7 @@ impl TryFrom<protobuf::ConsensusMessage> for ConsensusMessage {
         };
 
         match message {
-            Message::Proposal(proposal) => Ok(ConsensusMessage::Proposal(proposal.try_into()?)),
+            Message::ProposalPart(part) => Ok(ConsensusMessage::ProposalPart(part.try_into()?)),
             Message::Vote(vote) => Ok(ConsensusMessage::Vote(vote.try_into()?)),
         }
     }
@@ -128,8 +196,8 @@ impl TryFrom<protobuf::ConsensusMessage> for ConsensusMessage {
 impl From<ConsensusMessage> for protobuf::ConsensusMessage {
     fn from(value: ConsensusMessage) -> Self {
         match value {
-            ConsensusMessage::Proposal(proposal) => protobuf::ConsensusMessage {
-                message: Some(protobuf::consensus_message::Message::Proposal(proposal.into())),
+            ConsensusMessage::ProposalPart(part) => protobuf::ConsensusMessage {
+                message: Some(protobuf::consensus_message::Message::ProposalPart(part.into())),
             },
             ConsensusMessage::Vote(vote) => protobuf::ConsensusMessage {
                 message: Some(protobuf::consensus_message::Message::Vote(vote.into())),
@@ -139,3 +207,31 @@ impl From<ConsensusMessage> for protobuf::ConsensusMessage {
 }
 
 auto_impl_into_and_try_from_vec_u8!(ConsensusMessage, protobuf::ConsensusMessage);
+
+impl TryFrom<protobuf::EquivocationEvidence> for EquivocationEvidence {
+    type Error = ProtobufConversionError;
+
+    fn try_from(value: protobuf::EquivocationEvidence) -> Result<Self, Self::Error> {
+        let first_vote = value
+            .first_vote
+            .ok_or(ProtobufConversionError::MissingField { field_description: "first_vote" })?
+            .try_into()?;
+        let second_vote = value
+            .second_vote
+            .ok_or(ProtobufConversionError::MissingField { field_description: "second_vote" })?
+            .try_into()?;
+
+        Ok(EquivocationEvidence { first_vote, second_vote })
+    }
+}
+
+impl From<EquivocationEvidence> for protobuf::EquivocationEvidence {
+    fn from(value: EquivocationEvidence) -> Self {
+        protobuf::EquivocationEvidence {
+            first_vote: Some(value.first_vote.into()),
+            second_vote: Some(value.second_vote.into()),
+        }
+    }
+}
+
+auto_impl_into_and_try_from_vec_u8!(EquivocationEvidence, protobuf::EquivocationEvidence);
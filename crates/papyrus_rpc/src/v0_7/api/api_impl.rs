@@ -744,9 +744,12 @@ impl JsonRpcServer for JsonRpcServerImpl {
         // corresponding to the requested filter. If there are, we return a continuation token
         // pointing to the next relevant event. Otherwise, we return a continuation token None.
         let mut filtered_events = vec![];
+        // If the filter restricts the first key, only the events with a matching first key are
+        // iterated, using the storage index of the events by their first key.
+        let first_keys = filter.keys.first().filter(|keys| !keys.is_empty());
         if start_event_index.0.0 <= latest_block_number {
-            for ((from_address, event_index), content) in txn
-                .iter_events(filter.address, start_event_index, to_block_number)
+            for ((from_address, event_index), transaction_hash, content) in txn
+                .iter_events(filter.address, first_keys, start_event_index, to_block_number)
                 .map_err(internal_server_error)?
             {
                 let block_number = (event_index.0).0;
@@ -774,10 +777,6 @@ impl JsonRpcServer for JsonRpcServerImpl {
                     let header: BlockHeader = get_block_header_by_number(&txn, block_number)
                         .map_err(internal_server_error)?
                         .into();
-                    let transaction_hash = txn
-                        .get_transaction_hash_by_idx(&event_index.0)
-                        .map_err(internal_server_error)?
-                        .ok_or_else(|| internal_server_error("Unknown internal error."))?;
                     let emitted_event = Event {
                         block_hash: Some(header.block_hash),
                         block_number: Some(block_number),
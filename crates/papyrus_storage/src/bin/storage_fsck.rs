@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use papyrus_storage::integrity::{check_integrity, truncate_storage, IntegrityReport};
+use papyrus_storage::{open_storage, open_storage_read_only, StorageConfig, StorageResult};
+use starknet_api::core::ChainId;
+
+/// This executable checks the integrity of the storage offline, prints a report of the issues it
+/// found as JSON, and optionally truncates the storage back to its last consistent block.
+fn main() {
+    let matches = get_command().get_matches();
+    match run(&matches) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(2),
+        Err(e) => {
+            println!("Failed with error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Returns whether the storage is consistent at the end of the run.
+fn run(matches: &ArgMatches) -> StorageResult<bool> {
+    let mut storage_config = get_storage_config(matches);
+    // The check opens the storage read-only, so it leaves the storage as it is.
+    let reader = open_storage_read_only(
+        storage_config.db_config.clone(),
+        storage_config.mmap_file_config.clone(),
+    )?;
+    let report = check_integrity(&reader)?;
+    print_report(&report);
+    let Some(first_inconsistent_block) = report.first_inconsistent_block() else {
+        // Issues which aren't related to a block, e.g. a truncated file, can't be fixed by
+        // truncating the storage.
+        return Ok(report.is_consistent());
+    };
+    if !matches.get_flag("truncate") {
+        eprintln!(
+            "The storage is inconsistent from block {first_inconsistent_block}. Run again with \
+             --truncate to truncate the storage back to that block."
+        );
+        return Ok(false);
+    }
+
+    eprintln!("Truncating the storage back to block {first_inconsistent_block}.");
+    // Open the storage in its own scope, so opening it for writing doesn't change the scope.
+    storage_config.scope = reader.get_scope();
+    drop(reader);
+    let (reader, mut writer) = open_storage(storage_config.clone())?;
+    truncate_storage(&mut writer, first_inconsistent_block)?;
+    // Reopen the storage so the files are remapped according to the rewound offsets.
+    drop((reader, writer));
+    let reader = open_storage_read_only(storage_config.db_config, storage_config.mmap_file_config)?;
+    let report = check_integrity(&reader)?;
+    print_report(&report);
+    Ok(report.is_consistent())
+}
+
+fn print_report(report: &IntegrityReport) {
+    println!("{}", serde_json::to_string_pretty(report).expect("Failed serializing the report"));
+}
+
+fn get_storage_config(matches: &ArgMatches) -> StorageConfig {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = PathBuf::from(get_string(matches, "path_prefix"));
+    storage_config.db_config.chain_id = ChainId::Other(get_string(matches, "chain_id"));
+    storage_config
+}
+
+fn get_string(matches: &ArgMatches, name: &str) -> String {
+    matches.get_one::<String>(name).unwrap_or_else(|| panic!("Failed parsing {name}")).to_string()
+}
+
+fn get_command() -> Command {
+    Command::new("Storage fsck")
+        .about("Checks the integrity of the storage.")
+        .arg(
+            Arg::new("path_prefix")
+                .short('p')
+                .long("path_prefix")
+                .default_value("./data")
+                .help("The path prefix of the storage."),
+        )
+        .arg(
+            Arg::new("chain_id")
+                .short('c')
+                .long("chain_id")
+                .required(true)
+                .help("The chain id of the storage, e.g. SN_MAIN or SN_SEPOLIA."),
+        )
+        .arg(
+            Arg::new("truncate")
+                .long("truncate")
+                .action(ArgAction::SetTrue)
+                .help("Truncate the storage back to its first inconsistent block."),
+        )
+}
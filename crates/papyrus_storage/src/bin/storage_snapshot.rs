@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgMatches, Command};
+use papyrus_storage::snapshot::{export_snapshot, import_snapshot, TrustedBlock};
+use papyrus_storage::{open_storage, StorageConfig, StorageResult};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ChainId, GlobalRoot};
+use starknet_types_core::felt::Felt;
+
+/// This executable exports the storage into a portable snapshot and imports such snapshots into
+/// a storage.
+fn main() {
+    let matches = get_command().get_matches();
+    let result = match matches.subcommand() {
+        Some(("export", sub_matches)) => export(sub_matches),
+        Some(("import", sub_matches)) => import(sub_matches),
+        _ => unreachable!("A subcommand is required."),
+    };
+    if let Err(e) = result {
+        println!("Failed with error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn export(matches: &ArgMatches) -> StorageResult<()> {
+    let storage_config = get_storage_config(matches);
+    let chain_id = storage_config.db_config.chain_id.clone();
+    let last_block = BlockNumber(parse_u64(matches, "block_number"));
+    let blocks_per_chunk = parse_u64(matches, "blocks_per_chunk");
+    let archive_dir = PathBuf::from(get_string(matches, "archive_dir"));
+    let (reader, _writer) = open_storage(storage_config)?;
+    let manifest = export_snapshot(&reader, &chain_id, last_block, &archive_dir, blocks_per_chunk)?;
+    println!(
+        "Exported the blocks up to {} into {} chunks at {}.",
+        manifest.last_block,
+        manifest.chunks.len(),
+        archive_dir.display()
+    );
+    Ok(())
+}
+
+fn import(matches: &ArgMatches) -> StorageResult<()> {
+    let storage_config = get_storage_config(matches);
+    let chain_id = storage_config.db_config.chain_id.clone();
+    let archive_dir = PathBuf::from(get_string(matches, "archive_dir"));
+    let trusted_last_block = TrustedBlock {
+        block_number: BlockNumber(parse_u64(matches, "block_number")),
+        block_hash: BlockHash(parse_felt(matches, "block_hash")),
+        state_root: GlobalRoot(parse_felt(matches, "state_root")),
+    };
+    let (_reader, mut writer) = open_storage(storage_config)?;
+    let last_block = import_snapshot(&mut writer, &chain_id, &archive_dir, &trusted_last_block)?;
+    println!("Imported the blocks up to {} from {}.", last_block, archive_dir.display());
+    Ok(())
+}
+
+fn get_storage_config(matches: &ArgMatches) -> StorageConfig {
+    let mut storage_config = StorageConfig::default();
+    storage_config.db_config.path_prefix = PathBuf::from(get_string(matches, "path_prefix"));
+    storage_config.db_config.chain_id = ChainId::Other(get_string(matches, "chain_id"));
+    storage_config
+}
+
+fn get_string(matches: &ArgMatches, name: &str) -> String {
+    matches.get_one::<String>(name).unwrap_or_else(|| panic!("Failed parsing {name}")).to_string()
+}
+
+fn parse_u64(matches: &ArgMatches, name: &str) -> u64 {
+    get_string(matches, name).parse::<u64>().unwrap_or_else(|_| panic!("Failed parsing {name}"))
+}
+
+fn parse_felt(matches: &ArgMatches, name: &str) -> Felt {
+    Felt::from_hex(&get_string(matches, name)).unwrap_or_else(|_| panic!("Failed parsing {name}"))
+}
+
+fn storage_args() -> [Arg; 3] {
+    [
+        Arg::new("path_prefix")
+            .short('p')
+            .long("path_prefix")
+            .default_value("./data")
+            .help("The path prefix of the storage."),
+        Arg::new("chain_id")
+            .short('c')
+            .long("chain_id")
+            .required(true)
+            .help("The chain id of the storage, e.g. SN_MAIN or SN_SEPOLIA."),
+        Arg::new("archive_dir")
+            .short('a')
+            .long("archive_dir")
+            .required(true)
+            .help("The directory of the snapshot."),
+    ]
+}
+
+fn get_command() -> Command {
+    Command::new("Storage snapshot")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Exports the blocks up to the given block into a snapshot.")
+                .args(storage_args())
+                .arg(
+                    Arg::new("block_number")
+                        .short('b')
+                        .long("block_number")
+                        .required(true)
+                        .help("The last block to export."),
+                )
+                .arg(
+                    Arg::new("blocks_per_chunk")
+                        .long("blocks_per_chunk")
+                        .default_value("1000")
+                        .help("The number of blocks in each chunk file of the snapshot."),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about(
+                    "Imports a snapshot into an empty storage, or resumes an interrupted import. \
+                     The last block of the snapshot should be the given one, as known from a \
+                     trusted source. The content of the blocks is not verified against their \
+                     hashes, so the snapshot must come from a trusted source as well.",
+                )
+                .args(storage_args())
+                .arg(
+                    Arg::new("block_number")
+                        .short('b')
+                        .long("block_number")
+                        .required(true)
+                        .help("The number of the last block of the snapshot."),
+                )
+                .arg(
+                    Arg::new("block_hash")
+                        .long("block_hash")
+                        .required(true)
+                        .help("The hex encoded hash of the last block of the snapshot."),
+                )
+                .arg(
+                    Arg::new("state_root")
+                        .long("state_root")
+                        .required(true)
+                        .help("The hex encoded state root after the last block of the snapshot."),
+                ),
+        )
+}
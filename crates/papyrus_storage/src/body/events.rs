@@ -5,6 +5,8 @@
 //!
 //! # Example
 //! ```
+//! use std::collections::HashSet;
+//!
 //! use papyrus_storage::open_storage;
 //! use papyrus_storage::body::TransactionIndex;
 //! use papyrus_storage::body::events::{EventIndex, EventsReader};
@@ -14,6 +16,7 @@
 //! use starknet_api::core::ContractAddress;
 //! use starknet_api::transaction::TransactionOffsetInBlock;
 //! use starknet_api::transaction::EventIndexInTransactionOutput;
+//! use starknet_api::transaction::EventKey;
 //!
 //! # let dir_handle = tempfile::tempdir().unwrap();
 //! # let dir = dir_handle.path().to_path_buf();
@@ -34,13 +37,19 @@
 //!     EventIndexInTransactionOutput(0),
 //! );
 //! let txn = reader.begin_ro_txn()?; // The transaction must live longer than the iterator.
-//! let events_iterator = txn.iter_events(None, event_index, BlockNumber(0))?;
-//! for ((contract_address, event_index), event_content) in events_iterator {
+//! let events_iterator = txn.iter_events(None, None, event_index, BlockNumber(0))?;
+//! for ((contract_address, event_index), transaction_hash, event_content) in events_iterator {
 //!    // Do something with the event.
 //! }
 //! // iterate events from a specific contract.
-//! let contract_events_iterator = txn.iter_events(Some(ContractAddress::default()), event_index, BlockNumber(0))?;
-//! for ((contract_address, event_index), event_content) in contract_events_iterator {
+//! let contract_events_iterator = txn.iter_events(Some(ContractAddress::default()), None, event_index, BlockNumber(0))?;
+//! for ((contract_address, event_index), transaction_hash, event_content) in contract_events_iterator {
+//!    // Do something with the event.
+//! }
+//! // iterate events whose first key is one of the given keys.
+//! let first_keys = HashSet::from([EventKey::default()]);
+//! let key_events_iterator = txn.iter_events(None, Some(&first_keys), event_index, BlockNumber(0))?;
+//! for ((contract_address, event_index), transaction_hash, event_content) in key_events_iterator {
 //!    // Do something with the event.
 //! }
 //! # Ok::<(), papyrus_storage::StorageError>(())
@@ -48,7 +57,7 @@
 #[path = "events_test.rs"]
 mod events_test;
 
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
@@ -57,14 +66,16 @@ use starknet_api::transaction::{
     Event,
     EventContent,
     EventIndexInTransactionOutput,
+    EventKey,
+    TransactionHash,
     TransactionOutput,
 };
 
 use super::TransactionMetadataTable;
-use crate::body::{EventsTableKey, TransactionIndex};
+use crate::body::{EventFirstKeysTableKey, EventsTableKey, TransactionIndex};
 use crate::db::serialization::{NoVersionValueWrapper, VersionZeroWrapper};
 use crate::db::table_types::{CommonPrefix, DbCursor, DbCursorTrait, NoValue, SimpleTable, Table};
-use crate::db::{DbTransaction, RO};
+use crate::db::{DbTransaction, TransactionKind, RO};
 use crate::{FileHandlers, StorageResult, StorageTxn, TransactionMetadata};
 
 /// An identifier of an event.
@@ -72,14 +83,21 @@ use crate::{FileHandlers, StorageResult, StorageTxn, TransactionMetadata};
 #[cfg_attr(any(test, feature = "testing"), derive(Hash))]
 pub struct EventIndex(pub TransactionIndex, pub EventIndexInTransactionOutput);
 
+/// An event returned by the events iterators: the address that emitted it together with its index,
+/// the hash of the transaction that emitted it and its content.
+pub type EventIterItem = ((ContractAddress, EventIndex), TransactionHash, EventContent);
+
 /// An interface for reading events.
-pub trait EventsReader<'txn, 'env> {
-    /// Returns an iterator over events, which is a wrapper of two iterators.
-    /// If the address is none it iterates the events by the order of the event index,
+pub trait EventsReader<'txn, 'env, Mode: TransactionKind> {
+    /// Returns an iterator over events, which is a wrapper of three iterators.
+    /// If the first keys are given, it iterates only the events whose first key is one of them
+    /// (and that were emitted by the address, if it is given) by the order of the event index.
+    /// Else, if the address is none it iterates the events by the order of the event index,
     /// else, it iterated the events by the order of the contract addresses.
     ///
     /// # Arguments
     /// * address - contract address to iterate over events was emitted by it.
+    /// * first_keys - keys to iterate over events whose first key is one of them.
     /// * event_index - event index to start iterate from it.
     /// * to_block_number - block number to stop iterate at it.
     ///
@@ -88,19 +106,29 @@ pub trait EventsReader<'txn, 'env> {
     fn iter_events(
         &'env self,
         address: Option<ContractAddress>,
+        first_keys: Option<&HashSet<EventKey>>,
         event_index: EventIndex,
         to_block_number: BlockNumber,
-    ) -> StorageResult<EventIter<'txn, 'env>>;
+    ) -> StorageResult<EventIter<'txn, 'env, Mode>>;
 }
 
-// TODO: support all read transactions (including RW).
-impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
+impl<'txn, 'env, Mode: TransactionKind> EventsReader<'txn, 'env, Mode> for StorageTxn<'env, Mode> {
     fn iter_events(
         &'env self,
         optional_address: Option<ContractAddress>,
+        first_keys: Option<&HashSet<EventKey>>,
         event_index: EventIndex,
         to_block_number: BlockNumber,
-    ) -> StorageResult<EventIter<'txn, 'env>> {
+    ) -> StorageResult<EventIter<'txn, 'env, Mode>> {
+        if let Some(first_keys) = first_keys {
+            return Ok(EventIter::ByFirstKeys(self.iter_events_by_first_keys(
+                optional_address,
+                first_keys,
+                event_index,
+                to_block_number,
+            )?));
+        }
+
         if let Some(address) = optional_address {
             return Ok(EventIter::ByContractAddress(
                 self.iter_events_by_contract_address((address, event_index))?,
@@ -111,27 +139,27 @@ impl<'txn, 'env> EventsReader<'txn, 'env> for StorageTxn<'env, RO> {
     }
 }
 
-// TODO(dvir): add transaction hash to the return value. In the RPC when returning events this is
-// with the transaction hash. We can do it efficiently here because we anyway read the relevant
-// entry in the transaction_metadata table..
 #[allow(missing_docs)]
-/// A wrapper of two iterators [`EventIterByContractAddress`] and [`EventIterByEventIndex`].
-pub enum EventIter<'txn, 'env> {
-    ByContractAddress(EventIterByContractAddress<'env, 'txn>),
-    ByEventIndex(EventIterByEventIndex<'txn>),
+/// A wrapper of three iterators [`EventIterByContractAddress`], [`EventIterByEventIndex`] and
+/// [`EventIterByFirstKeys`].
+pub enum EventIter<'txn, 'env, Mode: TransactionKind = RO> {
+    ByContractAddress(EventIterByContractAddress<'env, 'txn, Mode>),
+    ByEventIndex(EventIterByEventIndex<'txn, Mode>),
+    ByFirstKeys(EventIterByFirstKeys<'env, 'txn, Mode>),
 }
 
-/// This iterator is a wrapper of two iterators [`EventIterByContractAddress`]
-/// and [`EventIterByEventIndex`].
+/// This iterator is a wrapper of three iterators [`EventIterByContractAddress`],
+/// [`EventIterByEventIndex`] and [`EventIterByFirstKeys`].
 /// With this wrapper we can execute the same code, regardless the
 /// type of iteration used.
-impl Iterator for EventIter<'_, '_> {
-    type Item = ((ContractAddress, EventIndex), EventContent);
+impl<Mode: TransactionKind> Iterator for EventIter<'_, '_, Mode> {
+    type Item = EventIterItem;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
             EventIter::ByContractAddress(it) => it.next(),
             EventIter::ByEventIndex(it) => it.next(),
+            EventIter::ByFirstKeys(it) => it.next(),
         }
         .unwrap_or(None)
     }
@@ -139,25 +167,25 @@ impl Iterator for EventIter<'_, '_> {
 
 /// This iterator goes over the events in the order of the events table key.
 /// That is, the events iterated first by the contract address and then by the event index.
-pub struct EventIterByContractAddress<'env, 'txn> {
-    txn: &'txn DbTransaction<'env, RO>,
-    file_handles: &'txn FileHandlers<RO>,
+pub struct EventIterByContractAddress<'env, 'txn, Mode: TransactionKind = RO> {
+    txn: &'txn DbTransaction<'env, Mode>,
+    file_handles: &'txn FileHandlers<Mode>,
     // This value is the next entry in the events table to search for relevant events. If it is
     // None there are no more events.
     next_entry_in_event_table: Option<EventsTableKey>,
     // Queue of events to return from the iterator. When this queue is empty, we need to fetch more
     // events.
-    events_queue: VecDeque<((ContractAddress, EventIndex), EventContent)>,
-    cursor: EventsTableCursor<'txn>,
+    events_queue: VecDeque<EventIterItem>,
+    cursor: EventsTableCursor<'txn, Mode>,
     transaction_metadata_table: TransactionMetadataTable<'env>,
 }
 
-impl<'env, 'txn> EventIterByContractAddress<'env, 'txn> {
+impl<'env, 'txn, Mode: TransactionKind> EventIterByContractAddress<'env, 'txn, Mode> {
     /// Returns the next event. If there are no more events, returns None.
     ///
     /// # Errors
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
-    fn next(&mut self) -> StorageResult<Option<((ContractAddress, EventIndex), EventContent)>> {
+    fn next(&mut self) -> StorageResult<Option<EventIterItem>> {
         // Here we make sure that the events_queue is not empty. If it does we fill it with new
         // relevant events.
         if self.events_queue.is_empty() {
@@ -172,8 +200,13 @@ impl<'env, 'txn> EventIterByContractAddress<'env, 'txn> {
                 .file_handles
                 .get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
             // TODO(dvir): don't clone the events here.
-            self.events_queue =
-                get_events_from_tx(tx_output.events().into(), tx_index, contract_address, 0);
+            self.events_queue = get_events_from_tx(
+                tx_output.events().into(),
+                tx_index,
+                tx_metadata.tx_hash,
+                contract_address,
+                0,
+            );
             self.next_entry_in_event_table = self.cursor.next()?.map(|(key, _)| key);
         }
 
@@ -185,32 +218,33 @@ impl<'env, 'txn> EventIterByContractAddress<'env, 'txn> {
 /// That is, the events are iterated by the order they are emitted.
 /// First by the block number, then by the transaction offset in the block,
 /// and finally, by the event index in the transaction output.
-pub struct EventIterByEventIndex<'txn> {
-    file_handlers: &'txn FileHandlers<RO>,
-    tx_current: Option<(TransactionIndex, TransactionOutput)>,
-    tx_cursor: TransactionMetadataTableCursor<'txn>,
+pub struct EventIterByEventIndex<'txn, Mode: TransactionKind = RO> {
+    file_handlers: &'txn FileHandlers<Mode>,
+    tx_current: Option<(TransactionIndex, TransactionHash, TransactionOutput)>,
+    tx_cursor: TransactionMetadataTableCursor<'txn, Mode>,
     event_index_in_tx_current: EventIndexInTransactionOutput,
     to_block_number: BlockNumber,
 }
 
-impl EventIterByEventIndex<'_> {
+impl<Mode: TransactionKind> EventIterByEventIndex<'_, Mode> {
     /// Returns the next event. If there are no more events, returns None.
     ///
     /// # Errors
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
-    fn next(&mut self) -> StorageResult<Option<((ContractAddress, EventIndex), EventContent)>> {
-        let Some((tx_index, tx_output)) = &self.tx_current else { return Ok(None) };
+    fn next(&mut self) -> StorageResult<Option<EventIterItem>> {
+        let Some((tx_index, tx_hash, tx_output)) = &self.tx_current else { return Ok(None) };
         let Some(Event { from_address, content }) =
             tx_output.events().get(self.event_index_in_tx_current.0)
         else {
             return Ok(None);
         };
         let key = (*from_address, EventIndex(*tx_index, self.event_index_in_tx_current));
+        let tx_hash = *tx_hash;
         // TODO(dvir): don't clone here the event content.
         let content = content.clone();
         self.event_index_in_tx_current.0 += 1;
         self.find_next_event_by_event_index()?;
-        Ok(Some((key, content.clone())))
+        Ok(Some((key, tx_hash, content)))
     }
 
     /// Finds the event that corresponds to the first event index greater than or equals to the
@@ -221,7 +255,7 @@ impl EventIterByEventIndex<'_> {
     /// # Errors
     /// Returns [`StorageError`](crate::StorageError) if there was an error.
     fn find_next_event_by_event_index(&mut self) -> StorageResult<()> {
-        while let Some((tx_index, tx_output)) = &self.tx_current {
+        while let Some((tx_index, _, tx_output)) = &self.tx_current {
             if tx_index.0 > self.to_block_number {
                 self.tx_current = None;
                 break;
@@ -239,6 +273,7 @@ impl EventIterByEventIndex<'_> {
             };
             self.tx_current = Some((
                 tx_index,
+                tx_metadata.tx_hash,
                 self.file_handlers
                     .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
             ));
@@ -249,7 +284,98 @@ impl EventIterByEventIndex<'_> {
     }
 }
 
-impl<'txn, 'env> StorageTxn<'env, RO>
+/// This iterator goes over the events whose first key is one of the given keys, by the order of
+/// the event index. Only the transactions that emitted such events are read, using the event first
+/// keys table.
+pub struct EventIterByFirstKeys<'env, 'txn, Mode: TransactionKind = RO> {
+    txn: &'txn DbTransaction<'env, Mode>,
+    file_handles: &'txn FileHandlers<Mode>,
+    address: Option<ContractAddress>,
+    first_keys: HashSet<EventKey>,
+    start_event_index: EventIndex,
+    to_block_number: BlockNumber,
+    // For each of the first keys, the next entry in the event first keys table and a cursor
+    // pointing at it. The entries are ordered by the transaction index, so the first entry is the
+    // next transaction to read.
+    next_entries_in_table:
+        BTreeMap<(TransactionIndex, EventKey), EventFirstKeysTableCursor<'txn, Mode>>,
+    // Queue of events to return from the iterator. When this queue is empty, we need to fetch more
+    // events.
+    events_queue: VecDeque<EventIterItem>,
+    transaction_metadata_table: TransactionMetadataTable<'env>,
+}
+
+impl<'env, 'txn, Mode: TransactionKind> EventIterByFirstKeys<'env, 'txn, Mode> {
+    /// Returns the next event. If there are no more events, returns None.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn next(&mut self) -> StorageResult<Option<EventIterItem>> {
+        while self.events_queue.is_empty() {
+            let Some(((tx_index, _), _)) = self.next_entries_in_table.first_key_value() else {
+                return Ok(None);
+            };
+            let tx_index = *tx_index;
+            if tx_index.0 > self.to_block_number {
+                self.next_entries_in_table.clear();
+                return Ok(None);
+            }
+            self.advance_entries_of_transaction(tx_index)?;
+            self.events_queue = self.get_relevant_events_from_tx(tx_index)?;
+        }
+
+        Ok(self.events_queue.pop_front())
+    }
+
+    // Moves the cursors of all the first keys that point at the given transaction to the next
+    // transaction with the same first key.
+    fn advance_entries_of_transaction(&mut self, tx_index: TransactionIndex) -> StorageResult<()> {
+        while let Some(entry) = self.next_entries_in_table.first_entry() {
+            if entry.key().0 != tx_index {
+                break;
+            }
+            let ((_, first_key), mut cursor) = entry.remove_entry();
+            if let Some((next_first_key, next_tx_index)) = cursor.next()?.map(|(key, _)| key) {
+                if next_first_key == first_key {
+                    self.next_entries_in_table.insert((next_tx_index, first_key), cursor);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the events of the transaction whose first key is one of the first keys and that were
+    // emitted by the address, if it is given.
+    fn get_relevant_events_from_tx(
+        &self,
+        tx_index: TransactionIndex,
+    ) -> StorageResult<VecDeque<EventIterItem>> {
+        let tx_metadata =
+            self.transaction_metadata_table.get(self.txn, &tx_index)?.unwrap_or_else(|| {
+                panic!("Transaction metadata not found for transaction index: {tx_index:?}")
+            });
+        let tx_output =
+            self.file_handles.get_transaction_output_unchecked(tx_metadata.tx_output_location)?;
+        let start_index =
+            if tx_index == self.start_event_index.0 { self.start_event_index.1.0 } else { 0 };
+
+        let mut events = VecDeque::new();
+        for (i, event) in tx_output.events().iter().enumerate().skip(start_index) {
+            if self.address.is_some_and(|address| address != event.from_address) {
+                continue;
+            }
+            if !event.content.keys.first().is_some_and(|key| self.first_keys.contains(key)) {
+                continue;
+            }
+            let key = (event.from_address, EventIndex(tx_index, EventIndexInTransactionOutput(i)));
+            // TODO(dvir): don't clone the event content here.
+            events.push_back((key, tx_metadata.tx_hash, event.content.clone()));
+        }
+        Ok(events)
+    }
+}
+
+impl<'txn, 'env, Mode: TransactionKind> StorageTxn<'env, Mode>
 where
     'env: 'txn,
 {
@@ -263,7 +389,7 @@ where
     fn iter_events_by_contract_address(
         &'env self,
         key: (ContractAddress, EventIndex),
-    ) -> StorageResult<EventIterByContractAddress<'env, 'txn>> {
+    ) -> StorageResult<EventIterByContractAddress<'env, 'txn, Mode>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
         let events_table = self.open_table(&self.tables.events)?;
         let mut cursor = events_table.cursor(&self.txn)?;
@@ -285,6 +411,7 @@ where
             get_events_from_tx(
                 tx_output.events().into(),
                 tx_index,
+                tx_metadata.tx_hash,
                 contract_address,
                 start_event_index,
             )
@@ -316,7 +443,7 @@ where
         &'env self,
         event_index: EventIndex,
         to_block_number: BlockNumber,
-    ) -> StorageResult<EventIterByEventIndex<'txn>> {
+    ) -> StorageResult<EventIterByEventIndex<'txn, Mode>> {
         let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
         let mut tx_cursor = transaction_metadata_table.cursor(&self.txn)?;
         let first_txn_location = tx_cursor.lower_bound(&event_index.0)?;
@@ -324,6 +451,7 @@ where
             None => None,
             Some((tx_index, tx_metadata)) => Some((
                 tx_index,
+                tx_metadata.tx_hash,
                 self.file_handlers
                     .get_transaction_output_unchecked(tx_metadata.tx_output_location)?,
             )),
@@ -339,27 +467,77 @@ where
         it.find_next_event_by_event_index()?;
         Ok(it)
     }
+
+    /// Returns an events iterator that iterates by event index over the events whose first key is
+    /// one of the given keys, from the given event index.
+    ///
+    /// # Arguments
+    /// * address - if given, only events emitted by this contract address are returned.
+    /// * first_keys - keys to iterate over events whose first key is one of them.
+    /// * event_index - event index to start from the first event with an index greater or equals
+    ///   to.
+    /// * to_block_number - block number to stop iterate at it.
+    ///
+    /// # Errors
+    /// Returns [`StorageError`](crate::StorageError) if there was an error.
+    fn iter_events_by_first_keys(
+        &'env self,
+        address: Option<ContractAddress>,
+        first_keys: &HashSet<EventKey>,
+        event_index: EventIndex,
+        to_block_number: BlockNumber,
+    ) -> StorageResult<EventIterByFirstKeys<'env, 'txn, Mode>> {
+        let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
+        let event_first_keys_table = self.open_table(&self.tables.event_first_keys)?;
+        let mut next_entries_in_table = BTreeMap::new();
+        for first_key in first_keys {
+            let mut cursor = event_first_keys_table.cursor(&self.txn)?;
+            if let Some((got_first_key, tx_index)) =
+                cursor.lower_bound(&(first_key.clone(), event_index.0))?.map(|(key, _)| key)
+            {
+                if got_first_key == *first_key {
+                    next_entries_in_table.insert((tx_index, got_first_key), cursor);
+                }
+            }
+        }
+
+        Ok(EventIterByFirstKeys {
+            txn: &self.txn,
+            file_handles: &self.file_handlers,
+            address,
+            first_keys: first_keys.clone(),
+            start_event_index: event_index,
+            to_block_number,
+            next_entries_in_table,
+            events_queue: VecDeque::new(),
+            transaction_metadata_table,
+        })
+    }
 }
 
 fn get_events_from_tx(
     events_list: Vec<Event>,
     tx_index: TransactionIndex,
+    tx_hash: TransactionHash,
     contract_address: ContractAddress,
     start_index: usize,
-) -> VecDeque<((ContractAddress, EventIndex), EventContent)> {
+) -> VecDeque<EventIterItem> {
     let mut events = VecDeque::new();
     for (i, event) in events_list.into_iter().enumerate().skip(start_index) {
         if event.from_address == contract_address {
             let key = (contract_address, EventIndex(tx_index, EventIndexInTransactionOutput(i)));
-            events.push_back((key, event.content));
+            events.push_back((key, tx_hash, event.content));
         }
     }
     events
 }
 
 /// A cursor of the events table.
-type EventsTableCursor<'txn> =
-    DbCursor<'txn, RO, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+type EventsTableCursor<'txn, Mode> =
+    DbCursor<'txn, Mode, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+/// A cursor of the event first keys table.
+type EventFirstKeysTableCursor<'txn, Mode> =
+    DbCursor<'txn, Mode, EventFirstKeysTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
 /// A cursor of the transaction outputs table.
-type TransactionMetadataTableCursor<'txn> =
-    DbCursor<'txn, RO, TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>;
+type TransactionMetadataTableCursor<'txn, Mode> =
+    DbCursor<'txn, Mode, TransactionIndex, VersionZeroWrapper<TransactionMetadata>, SimpleTable>;
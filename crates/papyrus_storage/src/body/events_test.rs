@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::vec;
 
 use assert_matches::assert_matches;
@@ -9,6 +10,8 @@ use starknet_api::transaction::{
     EventContent,
     EventData,
     EventIndexInTransactionOutput,
+    EventKey,
+    TransactionHash,
     TransactionOffsetInBlock,
 };
 
@@ -47,10 +50,11 @@ fn iter_events_by_key() {
                 TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
                 EventIndexInTransactionOutput(event_i),
             );
+            let tx_hash = block.body.transaction_hashes[tx_i];
             if event.from_address == ca1 {
-                events_ca1.push(((event.from_address, event_index), event.content.clone()))
+                events_ca1.push(((event.from_address, event_index), tx_hash, event.content.clone()))
             } else {
-                events_ca2.push(((event.from_address, event_index), event.content.clone()))
+                events_ca2.push(((event.from_address, event_index), tx_hash, event.content.clone()))
             }
         }
     }
@@ -64,7 +68,7 @@ fn iter_events_by_key() {
         TransactionIndex(block_number, TransactionOffsetInBlock(0)),
         EventIndexInTransactionOutput(0),
     );
-    let event_iter = txn.iter_events(Some(ca1), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca1), None, event_index, block_number).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), all_events);
 
     // Start from not existing event index.
@@ -72,7 +76,7 @@ fn iter_events_by_key() {
         TransactionIndex(block_number, TransactionOffsetInBlock(5)),
         EventIndexInTransactionOutput(0),
     );
-    let event_iter = txn.iter_events(Some(ca2), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca2), None, event_index, block_number).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), vec![]);
 
     // TODO(dvir): add non random test that checks the iterator when there are no more relevant
@@ -86,7 +90,7 @@ fn iter_events_by_key() {
     } else {
         events_ca1.iter().cloned().chain(events_ca2.iter().cloned()).collect::<Vec<_>>()
     };
-    let event_iter = txn.iter_events(Some(ca1), event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(Some(ca1), None, event_index, block_number).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), expected_events);
 }
 
@@ -116,7 +120,8 @@ fn iter_events_by_index() {
                 TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
                 EventIndexInTransactionOutput(event_i),
             );
-            emitted_events.push(((event.from_address, event_index), event.content.clone()))
+            let tx_hash = block.body.transaction_hashes[tx_i];
+            emitted_events.push(((event.from_address, event_index), tx_hash, event.content.clone()))
         }
     }
 
@@ -125,10 +130,101 @@ fn iter_events_by_index() {
         EventIndexInTransactionOutput(2),
     );
     let txn = storage_reader.begin_ro_txn().unwrap();
-    let event_iter = txn.iter_events(None, event_index, block_number).unwrap();
+    let event_iter = txn.iter_events(None, None, event_index, block_number).unwrap();
     assert_eq!(event_iter.into_iter().collect::<Vec<_>>(), emitted_events);
 }
 
+#[test]
+fn iter_events_by_first_keys() {
+    let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let ca1 = 1u32.into();
+    let ca2 = 2u32.into();
+    let k1 = EventKey(1u32.into());
+    let k2 = EventKey(2u32.into());
+    let k3 = EventKey(3u32.into());
+    let keys = vec![vec![k1.clone(), k2.clone(), k3.clone()], vec![k1.clone(), k2.clone()]];
+    let block = get_test_block(10, Some(3), Some(vec![ca1, ca2]), Some(keys));
+    let block_number = block.header.block_number;
+    storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let txn = storage_reader.begin_ro_txn().unwrap();
+    let start_event_indices = [
+        EventIndex(
+            TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+            EventIndexInTransactionOutput(0),
+        ),
+        EventIndex(
+            TransactionIndex(block_number, TransactionOffsetInBlock(3)),
+            EventIndexInTransactionOutput(1),
+        ),
+    ];
+    let first_keys_options = [
+        HashSet::from([k1.clone()]),
+        HashSet::from([k2.clone(), k3.clone()]),
+        HashSet::from([k1, k2, k3]),
+        HashSet::from([EventKey(4u32.into())]),
+    ];
+    for event_index in start_event_indices {
+        for address in [None, Some(ca1)] {
+            for first_keys in &first_keys_options {
+                // The result must be identical to scanning all the events and filtering them.
+                let expected_events = txn
+                    .iter_events(None, None, event_index, block_number)
+                    .unwrap()
+                    .filter(|((from_address, _), _, content)| {
+                        (address.is_none() || address == Some(*from_address))
+                            && first_keys.contains(&content.keys[0])
+                    })
+                    .collect::<Vec<_>>();
+                let event_iter =
+                    txn.iter_events(address, Some(first_keys), event_index, block_number).unwrap();
+                assert_eq!(event_iter.collect::<Vec<_>>(), expected_events);
+            }
+        }
+    }
+}
+
+#[test]
+fn iter_events_in_rw_txn() {
+    let ((_storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
+    let block = get_test_block(2, Some(5), None, None);
+    let block_number = block.header.block_number;
+    let txn = storage_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap();
+
+    // The events are readable before the transaction is committed.
+    let mut emitted_events = vec![];
+    for (tx_i, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        for (event_i, event) in tx_output.events().iter().enumerate() {
+            let event_index = EventIndex(
+                TransactionIndex(block_number, TransactionOffsetInBlock(tx_i)),
+                EventIndexInTransactionOutput(event_i),
+            );
+            let tx_hash = block.body.transaction_hashes[tx_i];
+            emitted_events.push(((event.from_address, event_index), tx_hash, event.content.clone()))
+        }
+    }
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let event_iter = txn.iter_events(None, None, event_index, block_number).unwrap();
+    assert_eq!(event_iter.collect::<Vec<_>>(), emitted_events);
+}
+
 #[test]
 fn revert_events() {
     let ((storage_reader, mut storage_writer), _temp_dir) = get_test_storage();
@@ -154,7 +250,7 @@ fn revert_events() {
         storage_reader
             .begin_ro_txn()
             .unwrap()
-            .iter_events(None, event_index, block_number)
+            .iter_events(None, None, event_index, block_number)
             .unwrap()
             .last()
             .is_some()
@@ -163,6 +259,7 @@ fn revert_events() {
     // Test events raw table.
     let txn = storage_reader.begin_ro_txn().unwrap();
     let events_table = txn.txn.open_table(&txn.tables.events).unwrap();
+    let event_first_keys_table = txn.txn.open_table(&txn.tables.event_first_keys).unwrap();
     for (tx_idx, tx_output) in block.body.transaction_outputs.iter().enumerate() {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(tx_idx));
         for event in tx_output.events().iter() {
@@ -170,6 +267,11 @@ fn revert_events() {
                 events_table.get(&txn.txn, &(event.from_address, transaction_index)),
                 Ok(Some(_))
             );
+            assert_matches!(
+                event_first_keys_table
+                    .get(&txn.txn, &(event.content.keys[0].clone(), transaction_index)),
+                Ok(Some(_))
+            );
         }
     }
 
@@ -188,7 +290,7 @@ fn revert_events() {
         storage_reader
             .begin_ro_txn()
             .unwrap()
-            .iter_events(None, event_index, block_number)
+            .iter_events(None, None, event_index, block_number)
             .unwrap()
             .last()
             .is_none()
@@ -196,6 +298,7 @@ fn revert_events() {
 
     let txn = storage_reader.begin_ro_txn().unwrap();
     let events_table = txn.txn.open_table(&txn.tables.events).unwrap();
+    let event_first_keys_table = txn.txn.open_table(&txn.tables.event_first_keys).unwrap();
     for (tx_idx, tx_output) in block.body.transaction_outputs.iter().enumerate() {
         let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(tx_idx));
         for event in tx_output.events().iter() {
@@ -203,6 +306,11 @@ fn revert_events() {
                 events_table.get(&txn.txn, &(event.from_address, transaction_index)),
                 Ok(None)
             );
+            assert_matches!(
+                event_first_keys_table
+                    .get(&txn.txn, &(event.content.keys[0].clone(), transaction_index)),
+                Ok(None)
+            );
         }
     }
 }
@@ -210,6 +318,7 @@ fn revert_events() {
 #[test]
 fn get_events_from_tx_test() {
     let tx_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0));
+    let tx_hash = TransactionHash(1u32.into());
     let ca1 = 1u32.into();
     let ca2 = 2u32.into();
 
@@ -227,29 +336,50 @@ fn get_events_from_tx_test() {
     };
 
     let events = vec![e1.clone(), e2.clone(), e3.clone()];
-    let e1_output =
-        ((ca1, EventIndex(tx_index, EventIndexInTransactionOutput(0))), e1.content.clone());
-    let e2_output =
-        ((ca2, EventIndex(tx_index, EventIndexInTransactionOutput(1))), e2.content.clone());
-    let e3_output =
-        ((ca1, EventIndex(tx_index, EventIndexInTransactionOutput(2))), e3.content.clone());
+    let e1_output = (
+        (ca1, EventIndex(tx_index, EventIndexInTransactionOutput(0))),
+        tx_hash,
+        e1.content.clone(),
+    );
+    let e2_output = (
+        (ca2, EventIndex(tx_index, EventIndexInTransactionOutput(1))),
+        tx_hash,
+        e2.content.clone(),
+    );
+    let e3_output = (
+        (ca1, EventIndex(tx_index, EventIndexInTransactionOutput(2))),
+        tx_hash,
+        e3.content.clone(),
+    );
 
     // All events.
     assert_eq!(
-        get_events_from_tx(events.clone(), tx_index, ca1, 0),
+        get_events_from_tx(events.clone(), tx_index, tx_hash, ca1, 0),
         vec![e1_output.clone(), e3_output.clone()]
     );
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 0), vec![e2_output.clone()]);
+    assert_eq!(
+        get_events_from_tx(events.clone(), tx_index, tx_hash, ca2, 0),
+        vec![e2_output.clone()]
+    );
 
     // All events of starting from the second event.
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca1, 1), vec![e3_output.clone()]);
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 1), vec![e2_output.clone()]);
+    assert_eq!(
+        get_events_from_tx(events.clone(), tx_index, tx_hash, ca1, 1),
+        vec![e3_output.clone()]
+    );
+    assert_eq!(
+        get_events_from_tx(events.clone(), tx_index, tx_hash, ca2, 1),
+        vec![e2_output.clone()]
+    );
 
     // All events of starting from the third event.
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca1, 2), vec![e3_output.clone()]);
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 2), vec![]);
+    assert_eq!(
+        get_events_from_tx(events.clone(), tx_index, tx_hash, ca1, 2),
+        vec![e3_output.clone()]
+    );
+    assert_eq!(get_events_from_tx(events.clone(), tx_index, tx_hash, ca2, 2), vec![]);
 
     // All events of starting from the not existing index.
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca1, 3), vec![]);
-    assert_eq!(get_events_from_tx(events.clone(), tx_index, ca2, 3), vec![]);
+    assert_eq!(get_events_from_tx(events.clone(), tx_index, tx_hash, ca1, 3), vec![]);
+    assert_eq!(get_events_from_tx(events.clone(), tx_index, tx_hash, ca2, 3), vec![]);
 }
@@ -50,6 +50,7 @@ use serde::{Deserialize, Serialize};
 use starknet_api::block::{BlockBody, BlockNumber};
 use starknet_api::core::ContractAddress;
 use starknet_api::transaction::{
+    EventKey,
     Transaction,
     TransactionHash,
     TransactionOffsetInBlock,
@@ -66,9 +67,11 @@ use crate::{
     MarkersTable,
     OffsetKind,
     StorageError,
+    StorageReader,
     StorageResult,
     StorageScope,
     StorageTxn,
+    StorageWriter,
     TransactionMetadata,
 };
 
@@ -81,6 +84,9 @@ type TransactionHashToIdxTable<'env> =
 type EventsTableKey = (ContractAddress, TransactionIndex);
 type EventsTable<'env> =
     TableHandle<'env, EventsTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
+type EventFirstKeysTableKey = (EventKey, TransactionIndex);
+type EventFirstKeysTable<'env> =
+    TableHandle<'env, EventFirstKeysTableKey, NoVersionValueWrapper<NoValue>, CommonPrefix>;
 
 /// The index of a transaction in a block.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, PartialOrd, Ord)]
@@ -347,8 +353,9 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
         let markers_table = self.open_table(&self.tables.markers)?;
         update_marker(&self.txn, &markers_table, block_number)?;
 
-        if self.scope != StorageScope::StateOnly {
+        if self.scope == StorageScope::FullArchive {
             let events_table = self.open_table(&self.tables.events)?;
+            let event_first_keys_table = self.open_table(&self.tables.event_first_keys)?;
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let transaction_metadata_table = self.open_table(&self.tables.transaction_metadata)?;
@@ -362,6 +369,7 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
                 &transaction_hash_to_idx_table,
                 &transaction_metadata_table,
                 &events_table,
+                &event_first_keys_table,
                 block_number,
             )?;
         }
@@ -390,7 +398,7 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
         }
 
         let reverted_block_body = 'reverted_block_body: {
-            if self.scope == StorageScope::StateOnly {
+            if self.scope != StorageScope::FullArchive {
                 break 'reverted_block_body None;
             }
 
@@ -398,6 +406,7 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
             let transaction_hash_to_idx_table =
                 self.open_table(&self.tables.transaction_hash_to_idx)?;
             let events_table = self.open_table(&self.tables.events)?;
+            let event_first_keys_table = self.open_table(&self.tables.event_first_keys)?;
 
             let transactions = self
                 .get_block_transactions(block_number)?
@@ -417,6 +426,9 @@ impl<'env> BodyStorageWriter for StorageTxn<'env, RW> {
 
                 for event in tx_output.events().iter() {
                     events_table.delete(&self.txn, &(event.from_address, tx_index))?;
+                    if let Some(first_key) = event.content.keys.first() {
+                        event_first_keys_table.delete(&self.txn, &(first_key.clone(), tx_index))?;
+                    }
                 }
                 transaction_hash_to_idx_table.delete(&self.txn, tx_hash)?;
                 transaction_metadata_table.delete(&self.txn, &tx_index)?;
@@ -441,6 +453,7 @@ fn write_transactions<'env>(
     transaction_hash_to_idx_table: &'env TransactionHashToIdxTable<'env>,
     transaction_metadata_table: &'env TransactionMetadataTable<'env>,
     events_table: &'env EventsTable<'env>,
+    event_first_keys_table: &'env EventFirstKeysTable<'env>,
     block_number: BlockNumber,
 ) -> StorageResult<()> {
     for (index, ((tx, tx_output), tx_hash)) in block_body
@@ -454,7 +467,7 @@ fn write_transactions<'env>(
         let transaction_index = TransactionIndex(block_number, tx_offset_in_block);
         let tx_location = file_handlers.append_transaction(tx);
         let tx_output_location = file_handlers.append_transaction_output(tx_output);
-        write_events(tx_output, txn, events_table, transaction_index)?;
+        write_events(tx_output, txn, events_table, event_first_keys_table, transaction_index)?;
         transaction_hash_to_idx_table.insert(txn, tx_hash, &transaction_index)?;
         transaction_metadata_table.append(
             txn,
@@ -481,12 +494,17 @@ fn write_events<'env>(
     tx_output: &TransactionOutput,
     txn: &DbTransaction<'env, RW>,
     events_table: &'env EventsTable<'env>,
+    event_first_keys_table: &'env EventFirstKeysTable<'env>,
     transaction_index: TransactionIndex,
 ) -> StorageResult<()> {
     let mut contract_addresses_set = HashSet::new();
+    let mut first_keys_set = HashSet::new();
 
     for event in tx_output.events().iter() {
         contract_addresses_set.insert(event.from_address);
+        if let Some(first_key) = event.content.keys.first() {
+            first_keys_set.insert(first_key.clone());
+        }
     }
 
     for contract_address in contract_addresses_set {
@@ -495,6 +513,57 @@ fn write_events<'env>(
         // is a table.
         events_table.append_greater_sub_key(txn, &key, &NoValue)?;
     }
+    for first_key in first_keys_set {
+        event_first_keys_table.append_greater_sub_key(
+            txn,
+            &(first_key, transaction_index),
+            &NoValue,
+        )?;
+    }
+    Ok(())
+}
+
+// The number of blocks whose events are indexed by their first keys in each transaction of the
+// migration.
+const FILL_EVENT_FIRST_KEYS_BATCH_SIZE: u64 = 1000;
+
+// Indexes the events of the stored blocks by their first keys. Blocks stored by blocks version 2.0
+// were written without this index. Each batch of blocks is committed on its own, and writing an
+// entry twice is harmless, so an interrupted migration is run again from the start.
+pub(crate) fn fill_event_first_keys(
+    reader: &StorageReader,
+    writer: &mut StorageWriter,
+) -> StorageResult<()> {
+    let body_marker = reader.begin_ro_txn()?.get_body_marker()?;
+    debug!("Indexing the events of {body_marker} blocks by their first keys.");
+    let mut batch_start = 0;
+    while batch_start < body_marker.0 {
+        let batch_end = (batch_start + FILL_EVENT_FIRST_KEYS_BATCH_SIZE).min(body_marker.0);
+        let txn = writer.begin_rw_txn()?;
+        {
+            let event_first_keys_table = txn.open_table(&txn.tables.event_first_keys)?;
+            for block_number in (batch_start..batch_end).map(BlockNumber) {
+                let Some(tx_outputs) = txn.get_block_transaction_outputs(block_number)? else {
+                    continue;
+                };
+                for (offset, tx_output) in tx_outputs.iter().enumerate() {
+                    let transaction_index =
+                        TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+                    for event in tx_output.events() {
+                        if let Some(first_key) = event.content.keys.first() {
+                            event_first_keys_table.upsert(
+                                &txn.txn,
+                                &(first_key.clone(), transaction_index),
+                                &NoValue,
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        txn.commit()?;
+        batch_start = batch_end;
+    }
     Ok(())
 }
 
@@ -29,7 +29,7 @@ use std::path::PathBuf;
 use std::result;
 use std::sync::Arc;
 
-use libmdbx::{DatabaseFlags, Geometry, PageSize, WriteMap};
+use libmdbx::{DatabaseFlags, Geometry, Mode, PageSize, WriteMap};
 use papyrus_config::dumping::{ser_param, SerializeConfig};
 use papyrus_config::validators::{validate_ascii, validate_path_exists};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
@@ -42,7 +42,7 @@ use self::table_types::{DbCursor, DbCursorTrait};
 use crate::db::table_types::TableType;
 
 // Maximum number of Sub-Databases.
-const MAX_DBS: usize = 18;
+const MAX_DBS: usize = 19;
 
 // Note that NO_TLS mode is used by default.
 type EnvironmentKind = WriteMap;
@@ -219,6 +219,28 @@ pub(crate) fn open_env(config: &DbConfig) -> DbResult<(DbReader, DbWriter)> {
     Ok((DbReader { env: env.clone() }, DbWriter { env }))
 }
 
+/// Opens an existing MDBX environment in read-only mode and returns a reader to it. The geometry of
+/// the environment is taken from the database file, which is never resized.
+pub(crate) fn open_env_read_only(config: &DbConfig) -> DbResult<DbReader> {
+    let db_file_path = config.path().join("mdbx.dat");
+    if !db_file_path.exists() {
+        return Err(DbError::FileDoesNotExist(db_file_path));
+    }
+    const MAX_READERS: u32 = 1 << 13; // 8K readers
+    let env = Arc::new(
+        Environment::new()
+            .set_max_tables(MAX_DBS)
+            .set_max_readers(MAX_READERS)
+            .set_flags(DatabaseFlags {
+                mode: Mode::ReadOnly,
+                no_rdahead: true,
+                ..Default::default()
+            })
+            .open(&config.path())?,
+    );
+    Ok(DbReader { env })
+}
+
 // Size in bytes.
 const MDBX_MIN_PAGESIZE: usize = 256;
 const MDBX_MAX_PAGESIZE: usize = 65536; // 64KB
@@ -248,6 +270,20 @@ impl DbReader {
     pub(crate) fn begin_ro_txn(&self) -> DbResult<DbReadTransaction<'_>> {
         Ok(DbReadTransaction { txn: self.env.begin_ro_txn()? })
     }
+
+    // Returns the identifier of an existing table, without creating it.
+    pub(crate) fn existing_table<K: Key + Debug, V: ValueSerde + Debug, T: TableType>(
+        &self,
+        name: &'static str,
+    ) -> DbResult<TableIdentifier<K, V, T>> {
+        self.env.begin_ro_txn()?.open_table(Some(name))?;
+        Ok(TableIdentifier {
+            name,
+            _key_type: PhantomData {},
+            _value_type: PhantomData {},
+            _table_type: PhantomData {},
+        })
+    }
 }
 
 type DbReadTransaction<'env> = DbTransaction<'env, RO>;
@@ -256,6 +292,13 @@ impl DbWriter {
     pub(crate) fn begin_rw_txn(&mut self) -> DbResult<DbWriteTransaction<'_>> {
         Ok(DbWriteTransaction { txn: self.env.begin_rw_txn()? })
     }
+
+    // Returns another writer to the same environment. The environment allows a single write
+    // transaction at any given moment, so beginning a write transaction in one of the writers
+    // waits for the write transaction of the other to end.
+    pub(crate) fn duplicate(&self) -> DbWriter {
+        DbWriter { env: self.env.clone() }
+    }
 }
 
 type DbWriteTransaction<'env> = DbTransaction<'env, RW>;
@@ -84,6 +84,30 @@ pub(crate) struct StorageBlockHeader {
     pub n_events: usize,
 }
 
+impl StorageBlockHeader {
+    pub(crate) fn into_block_header(self, starknet_version: StarknetVersion) -> BlockHeader {
+        BlockHeader {
+            block_hash: self.block_hash,
+            parent_hash: self.parent_hash,
+            block_number: self.block_number,
+            l1_gas_price: self.l1_gas_price,
+            l1_data_gas_price: self.l1_data_gas_price,
+            state_root: self.state_root,
+            sequencer: self.sequencer,
+            timestamp: self.timestamp,
+            l1_da_mode: self.l1_da_mode,
+            state_diff_commitment: self.state_diff_commitment,
+            transaction_commitment: self.transaction_commitment,
+            event_commitment: self.event_commitment,
+            receipt_commitment: self.receipt_commitment,
+            state_diff_length: self.state_diff_length,
+            n_transactions: self.n_transactions,
+            n_events: self.n_events,
+            starknet_version,
+        }
+    }
+}
+
 type BlockHashToNumberTable<'env> =
     TableHandle<'env, BlockHash, NoVersionValueWrapper<BlockNumber>, SimpleTable>;
 
@@ -163,25 +187,7 @@ impl<'env, Mode: TransactionKind> HeaderStorageReader for StorageTxn<'env, Mode>
         let Some(starknet_version) = self.get_starknet_version(block_number)? else {
             return Ok(None);
         };
-        Ok(Some(BlockHeader {
-            block_hash: block_header.block_hash,
-            parent_hash: block_header.parent_hash,
-            block_number: block_header.block_number,
-            l1_gas_price: block_header.l1_gas_price,
-            l1_data_gas_price: block_header.l1_data_gas_price,
-            state_root: block_header.state_root,
-            sequencer: block_header.sequencer,
-            timestamp: block_header.timestamp,
-            l1_da_mode: block_header.l1_da_mode,
-            state_diff_commitment: block_header.state_diff_commitment,
-            transaction_commitment: block_header.transaction_commitment,
-            event_commitment: block_header.event_commitment,
-            receipt_commitment: block_header.receipt_commitment,
-            state_diff_length: block_header.state_diff_length,
-            n_transactions: block_header.n_transactions,
-            n_events: block_header.n_events,
-            starknet_version,
-        }))
+        Ok(Some(block_header.into_block_header(starknet_version)))
     }
 
     fn get_block_number_by_hash(
@@ -0,0 +1,866 @@
+//! Offline integrity checks of the storage.
+//!
+//! A crash in the middle of a commit or a truncated mmap file may leave the storage in a state
+//! that is only noticed much later, when reading the broken data fails. [`check_integrity`] walks
+//! the tables of the storage and returns an [`IntegrityReport`] of the problems it found. It checks
+//! that:
+//! - The markers are consistent with each other, and no table has entries beyond its marker.
+//! - Every [`LocationInFile`] points inside the written data of its mmap file and deserializes.
+//! - The `transaction_hash_to_idx` and `transaction_metadata` tables agree, and the event indices
+//!   point to existing transactions.
+//! - The stored block hashes chain correctly.
+//!
+//! [`truncate_storage`] removes all the data of the blocks from a given block on, typically the
+//! [first inconsistent block](IntegrityReport::first_inconsistent_block) of a report. It doesn't
+//! read the mmap files, so it works even if they are corrupted.
+
+#[cfg(test)]
+#[path = "integrity_test.rs"]
+mod integrity_test;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ClassHash, ContractAddress};
+use starknet_api::state::StateNumber;
+use starknet_api::transaction::{EventKey, TransactionHash, TransactionOffsetInBlock};
+
+use crate::body::TransactionIndex;
+use crate::db::serialization::{ValueSerde, VersionZeroWrapper};
+use crate::db::table_types::{DbCursor, DbCursorTrait, SimpleTable, Table};
+use crate::db::{DbTransaction, TableIdentifier, TransactionKind, RO, RW};
+use crate::header::HeaderStorageReader;
+use crate::mmap_file::{FileHandler, LocationInFile, Reader};
+use crate::{
+    MarkerKind,
+    OffsetKind,
+    StorageError,
+    StorageReader,
+    StorageResult,
+    StorageTxn,
+    StorageWriter,
+};
+
+/// The problems found in the storage by [`check_integrity`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// The markers of the storage, by their names.
+    pub markers: BTreeMap<String, BlockNumber>,
+    /// The problems found in the storage.
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Returns whether no problems were found.
+    pub fn is_consistent(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the first block that a problem was found in. Truncating the storage to this block
+    /// removes all the problems that are related to blocks.
+    pub fn first_inconsistent_block(&self) -> Option<BlockNumber> {
+        self.issues.iter().filter_map(|issue| issue.block_number).min()
+    }
+}
+
+/// A single problem found in the storage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    /// The kind of the problem.
+    pub kind: IssueKind,
+    /// The table or mmap file that the problem was found in.
+    pub source: String,
+    /// The first block affected by the problem, if it's known.
+    pub block_number: Option<BlockNumber>,
+    /// A description of the problem.
+    pub details: String,
+}
+
+impl IntegrityIssue {
+    fn new(
+        kind: IssueKind,
+        source: &str,
+        block_number: Option<BlockNumber>,
+        details: String,
+    ) -> Self {
+        Self { kind, source: source.to_owned(), block_number, details }
+    }
+}
+
+/// The kinds of problems that [`check_integrity`] finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A marker is above a marker that it should not exceed.
+    MarkerInconsistency,
+    /// An entry of a block below the marker of the table is missing.
+    MissingEntry,
+    /// An entry of a block that is not below the marker of the table exists.
+    EntryAboveMarker,
+    /// Two tables that index the same data disagree.
+    IndexMismatch,
+    /// The parent hash of a block is not the hash of the previous block.
+    BrokenHashChain,
+    /// A location points outside of the written data of its mmap file.
+    LocationOutOfBounds,
+    /// A value in an mmap file can't be deserialized.
+    CorruptedValue,
+    /// An mmap file is shorter than the data that was written to it.
+    TruncatedFile,
+}
+
+/// Walks all the tables of the storage and reports the problems it finds.
+pub fn check_integrity(reader: &StorageReader) -> StorageResult<IntegrityReport> {
+    let txn = reader.begin_ro_txn()?;
+    let mut issues = Vec::new();
+    check_files(&txn, &mut issues);
+    let markers = check_markers(&txn, &mut issues)?;
+    check_headers(&txn, markers[&MarkerKind::Header], &mut issues)?;
+    check_transactions(&txn, markers[&MarkerKind::Body], &mut issues)?;
+    check_state(&txn, markers[&MarkerKind::State], &mut issues)?;
+    check_classes(
+        &txn,
+        markers[&MarkerKind::Class],
+        markers[&MarkerKind::CompiledClass],
+        &mut issues,
+    )?;
+    Ok(IntegrityReport {
+        markers: markers
+            .into_iter()
+            .map(|(marker_kind, marker)| (marker_name(marker_kind).to_owned(), marker))
+            .collect(),
+        issues,
+    })
+}
+
+/// Removes all the data of the blocks from `block_number` on and lowers the markers accordingly.
+/// The tables are scanned for the data to remove, so the mmap files are not read. Fails if the
+/// state of `block_number` was already pruned.
+pub fn truncate_storage(
+    writer: &mut StorageWriter,
+    block_number: BlockNumber,
+) -> StorageResult<()> {
+    let txn = writer.begin_rw_txn()?;
+    let markers_table = txn.txn.open_table(&txn.tables.markers)?;
+    let pruned_state_marker =
+        markers_table.get(&txn.txn, &MarkerKind::PrunedState)?.unwrap_or_default();
+    if block_number < pruned_state_marker {
+        return Err(StorageError::StatePruned {
+            state_number: StateNumber::right_before_block(block_number),
+            pruned_state_marker,
+        });
+    }
+    let is_truncated = |entry_block_number: &BlockNumber| *entry_block_number >= block_number;
+    // The end of the data that is still referenced, for each mmap file.
+    let mut file_ends = BTreeMap::<OffsetKind, usize>::new();
+    let mut keep_location = |offset_kind: OffsetKind, location: &LocationInFile| {
+        let file_end = file_ends.entry(offset_kind).or_default();
+        *file_end = (*file_end).max(location.next_offset());
+    };
+
+    // Headers.
+    let headers_table = txn.txn.open_table(&txn.tables.headers)?;
+    delete_entries(&txn.txn, &headers_table, &BlockNumber(0), |key, _| is_truncated(key))?;
+    let block_hash_to_number_table = txn.txn.open_table(&txn.tables.block_hash_to_number)?;
+    delete_entries(&txn.txn, &block_hash_to_number_table, &BlockHash::default(), |_, value| {
+        is_truncated(value)
+    })?;
+    let block_signatures_table = txn.txn.open_table(&txn.tables.block_signatures)?;
+    delete_entries(&txn.txn, &block_signatures_table, &BlockNumber(0), |key, _| is_truncated(key))?;
+    let starknet_version_table = txn.txn.open_table(&txn.tables.starknet_version)?;
+    delete_entries(&txn.txn, &starknet_version_table, &BlockNumber(0), |key, _| is_truncated(key))?;
+
+    // Bodies.
+    let first_transaction_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0));
+    let transaction_metadata_table = txn.txn.open_table(&txn.tables.transaction_metadata)?;
+    delete_entries(
+        &txn.txn,
+        &transaction_metadata_table,
+        &first_transaction_index,
+        |tx_index, tx_metadata| {
+            if is_truncated(&tx_index.0) {
+                return true;
+            }
+            keep_location(OffsetKind::Transaction, &tx_metadata.tx_location);
+            keep_location(OffsetKind::TransactionOutput, &tx_metadata.tx_output_location);
+            false
+        },
+    )?;
+    let transaction_hash_to_idx_table = txn.txn.open_table(&txn.tables.transaction_hash_to_idx)?;
+    delete_entries(
+        &txn.txn,
+        &transaction_hash_to_idx_table,
+        &TransactionHash::default(),
+        |_, tx_index| is_truncated(&tx_index.0),
+    )?;
+    let events_table = txn.txn.open_table(&txn.tables.events)?;
+    delete_entries(
+        &txn.txn,
+        &events_table,
+        &(ContractAddress::default(), first_transaction_index),
+        |(_, tx_index), _| is_truncated(&tx_index.0),
+    )?;
+    let event_first_keys_table = txn.txn.open_table(&txn.tables.event_first_keys)?;
+    delete_entries(
+        &txn.txn,
+        &event_first_keys_table,
+        &(EventKey::default(), first_transaction_index),
+        |(_, tx_index), _| is_truncated(&tx_index.0),
+    )?;
+
+    // State.
+    let state_diffs_table = txn.txn.open_table(&txn.tables.state_diffs)?;
+    delete_entries(&txn.txn, &state_diffs_table, &BlockNumber(0), |key, location| {
+        if is_truncated(key) {
+            return true;
+        }
+        keep_location(OffsetKind::ThinStateDiff, location);
+        false
+    })?;
+    let deployed_contracts_table = txn.txn.open_table(&txn.tables.deployed_contracts)?;
+    delete_entries(
+        &txn.txn,
+        &deployed_contracts_table,
+        &(ContractAddress::default(), BlockNumber(0)),
+        |(_, entry_block_number), _| is_truncated(entry_block_number),
+    )?;
+    let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
+    delete_entries(
+        &txn.txn,
+        &nonces_table,
+        &(ContractAddress::default(), BlockNumber(0)),
+        |(_, entry_block_number), _| is_truncated(entry_block_number),
+    )?;
+    let contract_storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
+    delete_entries(
+        &txn.txn,
+        &contract_storage_table,
+        &((ContractAddress::default(), Default::default()), BlockNumber(0)),
+        |(_, entry_block_number), _| is_truncated(entry_block_number),
+    )?;
+
+    // Classes. The classes and casms are matched to their blocks by the declared_classes_block
+    // table, which is written with the state diff.
+    let declared_classes_block_table = txn.txn.open_table(&txn.tables.declared_classes_block)?;
+    let mut truncated_classes = HashSet::new();
+    delete_entries(
+        &txn.txn,
+        &declared_classes_block_table,
+        &ClassHash::default(),
+        |class_hash, entry_block_number| {
+            if is_truncated(entry_block_number) {
+                truncated_classes.insert(*class_hash);
+                return true;
+            }
+            false
+        },
+    )?;
+    let declared_classes_table = txn.txn.open_table(&txn.tables.declared_classes)?;
+    delete_entries(
+        &txn.txn,
+        &declared_classes_table,
+        &ClassHash::default(),
+        |class_hash, location| {
+            if truncated_classes.contains(class_hash) {
+                return true;
+            }
+            keep_location(OffsetKind::ContractClass, location);
+            false
+        },
+    )?;
+    let casms_table = txn.txn.open_table(&txn.tables.casms)?;
+    delete_entries(&txn.txn, &casms_table, &ClassHash::default(), |class_hash, location| {
+        if truncated_classes.contains(class_hash) {
+            return true;
+        }
+        keep_location(OffsetKind::Casm, location);
+        false
+    })?;
+    let deprecated_declared_classes_table =
+        txn.txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    delete_entries(
+        &txn.txn,
+        &deprecated_declared_classes_table,
+        &ClassHash::default(),
+        |_, indexed_class| {
+            if is_truncated(&indexed_class.block_number) {
+                return true;
+            }
+            keep_location(OffsetKind::DeprecatedContractClass, &indexed_class.location_in_file);
+            false
+        },
+    )?;
+
+    for marker_kind in [
+        MarkerKind::Header,
+        MarkerKind::Body,
+        MarkerKind::State,
+        MarkerKind::Class,
+        MarkerKind::CompiledClass,
+        MarkerKind::BaseLayerBlock,
+    ] {
+        if markers_table.get(&txn.txn, &marker_kind)?.unwrap_or_default() > block_number {
+            markers_table.upsert(&txn.txn, &marker_kind, &block_number)?;
+        }
+    }
+    // Rewind the mmap files to the end of the data that is still referenced, so a file that lost
+    // its tail is consistent again.
+    let file_offsets_table = txn.txn.open_table(&txn.tables.file_offsets)?;
+    for offset_kind in [
+        OffsetKind::ThinStateDiff,
+        OffsetKind::ContractClass,
+        OffsetKind::Casm,
+        OffsetKind::DeprecatedContractClass,
+        OffsetKind::TransactionOutput,
+        OffsetKind::Transaction,
+    ] {
+        let file_end = file_ends.get(&offset_kind).copied().unwrap_or_default();
+        if file_offsets_table.get(&txn.txn, &offset_kind)?.unwrap_or_default() > file_end {
+            file_offsets_table.upsert(&txn.txn, &offset_kind, &file_end)?;
+        }
+    }
+    txn.commit()
+}
+
+// Pairs of markers such that the first should not be above the second.
+const MARKER_BOUNDS: [(MarkerKind, MarkerKind); 6] = [
+    (MarkerKind::CompiledClass, MarkerKind::Class),
+    (MarkerKind::Class, MarkerKind::State),
+    (MarkerKind::State, MarkerKind::Header),
+    (MarkerKind::Body, MarkerKind::Header),
+    (MarkerKind::BaseLayerBlock, MarkerKind::Header),
+    (MarkerKind::PrunedState, MarkerKind::State),
+];
+
+fn marker_name(marker_kind: MarkerKind) -> &'static str {
+    match marker_kind {
+        MarkerKind::Header => "header",
+        MarkerKind::Body => "body",
+        MarkerKind::Event => "event",
+        MarkerKind::State => "state",
+        MarkerKind::Class => "class",
+        MarkerKind::CompiledClass => "compiled_class",
+        MarkerKind::BaseLayerBlock => "base_layer_block",
+        MarkerKind::PrunedState => "pruned_state",
+    }
+}
+
+fn check_files(txn: &StorageTxn<'_, RO>, issues: &mut Vec<IntegrityIssue>) {
+    let files_stats: BTreeMap<_, _> = txn.file_handlers.stats().into_iter().collect();
+    for (file_name, stats) in files_stats {
+        if stats.is_truncated() {
+            issues.push(IntegrityIssue::new(
+                IssueKind::TruncatedFile,
+                &file_name,
+                None,
+                format!("The file is shorter than the data that was written to it: {stats:?}."),
+            ));
+        }
+    }
+}
+
+fn check_markers(
+    txn: &StorageTxn<'_, RO>,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<BTreeMap<MarkerKind, BlockNumber>> {
+    let markers_table = txn.txn.open_table(&txn.tables.markers)?;
+    let mut markers = BTreeMap::new();
+    for (marker_kind, bound_kind) in MARKER_BOUNDS {
+        for kind in [marker_kind, bound_kind] {
+            markers.insert(kind, markers_table.get(&txn.txn, &kind)?.unwrap_or_default());
+        }
+        let (marker, bound) = (markers[&marker_kind], markers[&bound_kind]);
+        if marker > bound {
+            issues.push(IntegrityIssue::new(
+                IssueKind::MarkerInconsistency,
+                "markers",
+                Some(bound),
+                format!(
+                    "The {} marker {marker} is above the {} marker {bound}.",
+                    marker_name(marker_kind),
+                    marker_name(bound_kind)
+                ),
+            ));
+        }
+    }
+    Ok(markers)
+}
+
+fn check_headers(
+    txn: &StorageTxn<'_, RO>,
+    header_marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    let headers_table = txn.txn.open_table(&txn.tables.headers)?;
+    let block_hash_to_number_table = txn.txn.open_table(&txn.tables.block_hash_to_number)?;
+    let mut parent_hash = None;
+    for block_number in BlockNumber(0).iter_up_to(header_marker) {
+        let Some(header) = headers_table.get(&txn.txn, &block_number)? else {
+            issues.push(IntegrityIssue::new(
+                IssueKind::MissingEntry,
+                txn.tables.headers.name,
+                Some(block_number),
+                "The header of a block below the header marker is missing.".to_owned(),
+            ));
+            parent_hash = None;
+            continue;
+        };
+        if header.block_number != block_number {
+            issues.push(IntegrityIssue::new(
+                IssueKind::IndexMismatch,
+                txn.tables.headers.name,
+                Some(block_number),
+                format!("The header is of block {}.", header.block_number),
+            ));
+        }
+        if block_hash_to_number_table.get(&txn.txn, &header.block_hash)? != Some(block_number) {
+            issues.push(IntegrityIssue::new(
+                IssueKind::IndexMismatch,
+                txn.tables.block_hash_to_number.name,
+                Some(block_number),
+                format!("The block hash {} is not mapped to its block.", header.block_hash),
+            ));
+        }
+        if let Some(parent_hash) = parent_hash.filter(|hash| *hash != header.parent_hash) {
+            issues.push(IntegrityIssue::new(
+                IssueKind::BrokenHashChain,
+                txn.tables.headers.name,
+                Some(block_number),
+                format!(
+                    "The parent hash {} is not the hash {parent_hash} of the previous block.",
+                    header.parent_hash
+                ),
+            ));
+        }
+        if txn.get_starknet_version(block_number)?.is_none() {
+            issues.push(IntegrityIssue::new(
+                IssueKind::MissingEntry,
+                txn.tables.starknet_version.name,
+                Some(block_number),
+                "The Starknet version of a block below the header marker is missing.".to_owned(),
+            ));
+        }
+        parent_hash = Some(header.block_hash);
+    }
+    check_no_entries_from(txn, &txn.tables.headers, header_marker, issues)?;
+    check_no_entries_from(txn, &txn.tables.block_signatures, header_marker, issues)?;
+    check_no_entries_from(txn, &txn.tables.starknet_version, header_marker, issues)?;
+    for_each_entry(
+        &txn.txn,
+        &block_hash_to_number_table,
+        &BlockHash::default(),
+        |block_hash, block_number| {
+            if block_number >= header_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.block_hash_to_number.name,
+                    block_number,
+                    header_marker,
+                    &block_hash,
+                ));
+            }
+            Ok(())
+        },
+    )
+}
+
+fn check_transactions(
+    txn: &StorageTxn<'_, RO>,
+    body_marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    let transaction_metadata_table = txn.txn.open_table(&txn.tables.transaction_metadata)?;
+    let transaction_hash_to_idx_table = txn.txn.open_table(&txn.tables.transaction_hash_to_idx)?;
+    let first_transaction_index = TransactionIndex(BlockNumber(0), TransactionOffsetInBlock(0));
+    for_each_entry(
+        &txn.txn,
+        &transaction_metadata_table,
+        &first_transaction_index,
+        |tx_index, tx_metadata| {
+            let block_number = tx_index.0;
+            if block_number >= body_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.transaction_metadata.name,
+                    block_number,
+                    body_marker,
+                    &tx_index,
+                ));
+            }
+            check_location(
+                &txn.file_handlers.transaction,
+                "transaction",
+                tx_metadata.tx_location,
+                Some(block_number),
+                issues,
+            );
+            check_location(
+                &txn.file_handlers.transaction_output,
+                "transaction_output",
+                tx_metadata.tx_output_location,
+                Some(block_number),
+                issues,
+            );
+            if transaction_hash_to_idx_table.get(&txn.txn, &tx_metadata.tx_hash)? != Some(tx_index)
+            {
+                issues.push(IntegrityIssue::new(
+                    IssueKind::IndexMismatch,
+                    txn.tables.transaction_hash_to_idx.name,
+                    Some(block_number),
+                    format!(
+                        "The hash {} of transaction {tx_index:?} is not mapped to it.",
+                        tx_metadata.tx_hash
+                    ),
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    for_each_entry(
+        &txn.txn,
+        &transaction_hash_to_idx_table,
+        &TransactionHash::default(),
+        |tx_hash, tx_index| {
+            let stored_tx_hash = transaction_metadata_table
+                .get(&txn.txn, &tx_index)?
+                .map(|tx_metadata| tx_metadata.tx_hash);
+            if stored_tx_hash != Some(tx_hash) {
+                issues.push(IntegrityIssue::new(
+                    IssueKind::IndexMismatch,
+                    txn.tables.transaction_metadata.name,
+                    Some(tx_index.0),
+                    format!(
+                        "The hash {tx_hash} is mapped to transaction {tx_index:?}, whose hash is \
+                         {stored_tx_hash:?}."
+                    ),
+                ));
+            }
+            Ok(())
+        },
+    )?;
+
+    let events_table = txn.txn.open_table(&txn.tables.events)?;
+    for_each_entry(
+        &txn.txn,
+        &events_table,
+        &(ContractAddress::default(), first_transaction_index),
+        |(address, tx_index), _| {
+            if transaction_metadata_table.get(&txn.txn, &tx_index)?.is_none() {
+                issues.push(missing_indexed_transaction(
+                    txn.tables.events.name,
+                    tx_index,
+                    &address,
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    let event_first_keys_table = txn.txn.open_table(&txn.tables.event_first_keys)?;
+    for_each_entry(
+        &txn.txn,
+        &event_first_keys_table,
+        &(EventKey::default(), first_transaction_index),
+        |(event_key, tx_index), _| {
+            if transaction_metadata_table.get(&txn.txn, &tx_index)?.is_none() {
+                issues.push(missing_indexed_transaction(
+                    txn.tables.event_first_keys.name,
+                    tx_index,
+                    &event_key,
+                ));
+            }
+            Ok(())
+        },
+    )
+}
+
+fn check_state(
+    txn: &StorageTxn<'_, RO>,
+    state_marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    let state_diffs_table = txn.txn.open_table(&txn.tables.state_diffs)?;
+    for block_number in BlockNumber(0).iter_up_to(state_marker) {
+        match state_diffs_table.get(&txn.txn, &block_number)? {
+            Some(location) => check_location(
+                &txn.file_handlers.thin_state_diff,
+                "thin_state_diff",
+                location,
+                Some(block_number),
+                issues,
+            ),
+            None => issues.push(IntegrityIssue::new(
+                IssueKind::MissingEntry,
+                txn.tables.state_diffs.name,
+                Some(block_number),
+                "The state diff of a block below the state marker is missing.".to_owned(),
+            )),
+        }
+    }
+    check_no_entries_from(txn, &txn.tables.state_diffs, state_marker, issues)?;
+
+    let deployed_contracts_table = txn.txn.open_table(&txn.tables.deployed_contracts)?;
+    for_each_entry(
+        &txn.txn,
+        &deployed_contracts_table,
+        &(ContractAddress::default(), BlockNumber(0)),
+        |(address, block_number), _| {
+            if block_number >= state_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.deployed_contracts.name,
+                    block_number,
+                    state_marker,
+                    &address,
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
+    for_each_entry(
+        &txn.txn,
+        &nonces_table,
+        &(ContractAddress::default(), BlockNumber(0)),
+        |(address, block_number), _| {
+            if block_number >= state_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.nonces.name,
+                    block_number,
+                    state_marker,
+                    &address,
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    let contract_storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
+    for_each_entry(
+        &txn.txn,
+        &contract_storage_table,
+        &((ContractAddress::default(), Default::default()), BlockNumber(0)),
+        |(storage_key, block_number), _| {
+            if block_number >= state_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.contract_storage.name,
+                    block_number,
+                    state_marker,
+                    &storage_key,
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    let declared_classes_block_table = txn.txn.open_table(&txn.tables.declared_classes_block)?;
+    for_each_entry(
+        &txn.txn,
+        &declared_classes_block_table,
+        &ClassHash::default(),
+        |class_hash, block_number| {
+            if block_number >= state_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.declared_classes_block.name,
+                    block_number,
+                    state_marker,
+                    &class_hash,
+                ));
+            }
+            Ok(())
+        },
+    )
+}
+
+fn check_classes(
+    txn: &StorageTxn<'_, RO>,
+    class_marker: BlockNumber,
+    compiled_class_marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    check_class_locations(
+        txn,
+        &txn.tables.declared_classes,
+        &txn.file_handlers.contract_class,
+        "contract_class",
+        class_marker,
+        issues,
+    )?;
+    check_class_locations(
+        txn,
+        &txn.tables.casms,
+        &txn.file_handlers.casm,
+        "casm",
+        compiled_class_marker,
+        issues,
+    )?;
+
+    let deprecated_declared_classes_table =
+        txn.txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    for_each_entry(
+        &txn.txn,
+        &deprecated_declared_classes_table,
+        &ClassHash::default(),
+        |class_hash, indexed_class| {
+            if indexed_class.block_number >= class_marker {
+                issues.push(entry_above_marker(
+                    txn.tables.deprecated_declared_classes.name,
+                    indexed_class.block_number,
+                    class_marker,
+                    &class_hash,
+                ));
+            }
+            check_location(
+                &txn.file_handlers.deprecated_contract_class,
+                "deprecated_contract_class",
+                indexed_class.location_in_file,
+                Some(indexed_class.block_number),
+                issues,
+            );
+            Ok(())
+        },
+    )
+}
+
+// Checks a table of class locations, whose classes are matched to their blocks by the
+// declared_classes_block table.
+fn check_class_locations<V: ValueSerde>(
+    txn: &StorageTxn<'_, RO>,
+    table_id: &TableIdentifier<ClassHash, VersionZeroWrapper<LocationInFile>, SimpleTable>,
+    file_handler: &FileHandler<V, RO>,
+    file_name: &str,
+    marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    let table = txn.txn.open_table(table_id)?;
+    let declared_classes_block_table = txn.txn.open_table(&txn.tables.declared_classes_block)?;
+    for_each_entry(&txn.txn, &table, &ClassHash::default(), |class_hash, location| {
+        let block_number = declared_classes_block_table.get(&txn.txn, &class_hash)?;
+        match block_number {
+            Some(block_number) if block_number >= marker => {
+                issues.push(entry_above_marker(table_id.name, block_number, marker, &class_hash));
+            }
+            Some(_) => {}
+            None => issues.push(IntegrityIssue::new(
+                IssueKind::MissingEntry,
+                txn.tables.declared_classes_block.name,
+                None,
+                format!("The block of the stored class {class_hash} is missing."),
+            )),
+        }
+        check_location(file_handler, file_name, location, block_number, issues);
+        Ok(())
+    })
+}
+
+// Reports a problem if the location is outside of the written data of the file, or if the value
+// in it can't be deserialized. The location is checked before reading it, since reading outside
+// of the file is undefined behavior.
+fn check_location<V: ValueSerde>(
+    file_handler: &FileHandler<V, RO>,
+    file_name: &str,
+    location: LocationInFile,
+    block_number: Option<BlockNumber>,
+    issues: &mut Vec<IntegrityIssue>,
+) {
+    let stats = file_handler.stats();
+    if !stats.contains(location) {
+        issues.push(IntegrityIssue::new(
+            IssueKind::LocationOutOfBounds,
+            file_name,
+            block_number,
+            format!("The location {location:?} is outside of the written data {stats:?}."),
+        ));
+        return;
+    }
+    if !matches!(file_handler.get(location), Ok(Some(_))) {
+        issues.push(IntegrityIssue::new(
+            IssueKind::CorruptedValue,
+            file_name,
+            block_number,
+            format!("The value at {location:?} can't be deserialized."),
+        ));
+    }
+}
+
+// Reports a problem if the table, which is keyed by block numbers, has entries from the marker on.
+fn check_no_entries_from<V: ValueSerde + Debug>(
+    txn: &StorageTxn<'_, RO>,
+    table_id: &TableIdentifier<BlockNumber, V, SimpleTable>,
+    marker: BlockNumber,
+    issues: &mut Vec<IntegrityIssue>,
+) -> StorageResult<()> {
+    let table = txn.txn.open_table(table_id)?;
+    let mut cursor = table.cursor(&txn.txn)?;
+    if let Some((block_number, _)) = cursor.lower_bound(&marker)? {
+        issues.push(entry_above_marker(table_id.name, block_number, marker, &block_number));
+    }
+    Ok(())
+}
+
+fn entry_above_marker(
+    table_name: &str,
+    block_number: BlockNumber,
+    marker: BlockNumber,
+    key: &impl Debug,
+) -> IntegrityIssue {
+    IntegrityIssue::new(
+        IssueKind::EntryAboveMarker,
+        table_name,
+        Some(block_number),
+        format!("The entry of {key:?} is of block {block_number}, which is not below {marker}."),
+    )
+}
+
+fn missing_indexed_transaction(
+    table_name: &str,
+    tx_index: TransactionIndex,
+    key: &impl Debug,
+) -> IntegrityIssue {
+    IntegrityIssue::new(
+        IssueKind::IndexMismatch,
+        table_name,
+        Some(tx_index.0),
+        format!("The entry of {key:?} points to the missing transaction {tx_index:?}."),
+    )
+}
+
+// Calls `f` with every entry of the table from `first_key` on, in the order of the keys.
+fn for_each_entry<'env, Mode: TransactionKind, T: Table<'env>>(
+    txn: &'env DbTransaction<'env, Mode>,
+    table: &'env T,
+    first_key: &T::Key,
+    mut f: impl FnMut(T::Key, <T::Value as ValueSerde>::Value) -> StorageResult<()>,
+) -> StorageResult<()>
+where
+    DbCursor<'env, Mode, T::Key, T::Value, T::TableVariant>:
+        DbCursorTrait<Key = T::Key, Value = T::Value>,
+{
+    let mut cursor = table.cursor(txn)?;
+    let mut entry = cursor.lower_bound(first_key)?;
+    while let Some((key, value)) = entry {
+        f(key, value)?;
+        entry = cursor.next()?;
+    }
+    Ok(())
+}
+
+// Deletes the entries of the table that `should_delete` returns true for.
+fn delete_entries<'env, T: Table<'env>>(
+    txn: &'env DbTransaction<'env, RW>,
+    table: &'env T,
+    first_key: &T::Key,
+    mut should_delete: impl FnMut(&T::Key, &<T::Value as ValueSerde>::Value) -> bool,
+) -> StorageResult<()>
+where
+    DbCursor<'env, RW, T::Key, T::Value, T::TableVariant>:
+        DbCursorTrait<Key = T::Key, Value = T::Value>,
+{
+    let mut deleted_keys = Vec::new();
+    for_each_entry(txn, table, first_key, |key, value| {
+        if should_delete(&key, &value) {
+            deleted_keys.push(key);
+        }
+        Ok(())
+    })?;
+    for key in deleted_keys {
+        table.delete(txn, &key)?;
+    }
+    Ok(())
+}
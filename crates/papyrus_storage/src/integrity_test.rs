@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::IndexMap;
+use papyrus_test_utils::get_test_body;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber};
+use starknet_api::core::{ClassHash, CompiledClassHash, ContractAddress, Nonce};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::hash::StarkHash;
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use starknet_types_core::felt::Felt;
+
+use crate::body::{BodyStorageReader, BodyStorageWriter, TransactionIndex};
+use crate::class::ClassStorageWriter;
+use crate::compiled_class::CasmStorageWriter;
+use crate::db::table_types::Table;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::integrity::{
+    check_integrity,
+    truncate_storage,
+    IntegrityIssue,
+    IntegrityReport,
+    IssueKind,
+};
+use crate::state::StateStorageWriter;
+use crate::test_utils::get_test_config;
+use crate::{
+    open_storage,
+    open_storage_read_only,
+    MarkerKind,
+    OffsetKind,
+    StorageError,
+    StorageScope,
+    StorageWriter,
+};
+
+const N_BLOCKS: u64 = 4;
+const TXS_PER_BLOCK: usize = 2;
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(Felt::from(block_number + 1000))
+}
+
+// Appends N_BLOCKS blocks with all of their data. Each block declares a class and a deprecated
+// class, and deploys a contract.
+fn append_blocks(writer: &mut StorageWriter) {
+    let body = get_test_body(N_BLOCKS as usize * TXS_PER_BLOCK, Some(1), None, None);
+    for block_number in 0..N_BLOCKS {
+        let header = BlockHeader {
+            block_hash: block_hash(block_number),
+            parent_hash: block_number.checked_sub(1).map(block_hash).unwrap_or_default(),
+            block_number: BlockNumber(block_number),
+            ..Default::default()
+        };
+        let txs =
+            block_number as usize * TXS_PER_BLOCK..(block_number as usize + 1) * TXS_PER_BLOCK;
+        let block_body = BlockBody {
+            transactions: body.transactions[txs.clone()].to_vec(),
+            transaction_outputs: body.transaction_outputs[txs.clone()].to_vec(),
+            transaction_hashes: body.transaction_hashes[txs].to_vec(),
+        };
+        let class_hash = ClassHash(Felt::from(block_number * 10 + 1));
+        let deprecated_class_hash = ClassHash(Felt::from(block_number * 10 + 2));
+        let address = ContractAddress::from(block_number * 10 + 3);
+        let state_diff = ThinStateDiff {
+            deployed_contracts: IndexMap::from([(address, deprecated_class_hash)]),
+            storage_diffs: IndexMap::from([(
+                address,
+                IndexMap::from([(Default::default(), Felt::from(block_number))]),
+            )]),
+            declared_classes: IndexMap::from([(
+                class_hash,
+                CompiledClassHash(Felt::from(block_number)),
+            )]),
+            deprecated_declared_classes: vec![deprecated_class_hash],
+            nonces: IndexMap::from([(address, Nonce(StarkHash::ONE))]),
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .append_body(BlockNumber(block_number), block_body)
+            .unwrap()
+            .append_state_diff(BlockNumber(block_number), state_diff)
+            .unwrap()
+            .append_classes(
+                BlockNumber(block_number),
+                &[(class_hash, &ContractClass::default())],
+                &[(deprecated_class_hash, &DeprecatedContractClass::default())],
+            )
+            .unwrap()
+            .append_casm(&class_hash, &CasmContractClass::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+}
+
+#[test]
+fn consistent_storage() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    assert!(check_integrity(&reader).unwrap().is_consistent());
+
+    append_blocks(&mut writer);
+    let report = check_integrity(&reader).unwrap();
+    assert_eq!(report.issues, vec![]);
+    assert_eq!(report.first_inconsistent_block(), None);
+    for marker_name in ["header", "body", "state", "class", "compiled_class"] {
+        assert_eq!(report.markers[marker_name], BlockNumber(N_BLOCKS));
+    }
+}
+
+#[test]
+fn broken_hash_chain() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    for block_number in 0..3 {
+        let parent_hash = match block_number {
+            0 => BlockHash::default(),
+            // The parent hash of block 2 should be the hash of block 1.
+            2 => block_hash(0),
+            _ => block_hash(block_number - 1),
+        };
+        let header = BlockHeader {
+            block_hash: block_hash(block_number),
+            parent_hash,
+            block_number: BlockNumber(block_number),
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+
+    let report = check_integrity(&reader).unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, IssueKind::BrokenHashChain);
+    assert_eq!(report.issues[0].source, "headers");
+    assert_eq!(report.first_inconsistent_block(), Some(BlockNumber(2)));
+}
+
+#[test]
+fn transaction_index_mismatch() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_blocks(&mut writer);
+    let tx_hashes =
+        reader.begin_ro_txn().unwrap().get_block_transaction_hashes(BlockNumber(1)).unwrap();
+    let txn = writer.begin_rw_txn().unwrap();
+    let transaction_hash_to_idx_table =
+        txn.open_table(&txn.tables.transaction_hash_to_idx).unwrap();
+    transaction_hash_to_idx_table.delete(&txn.txn, &tx_hashes.unwrap()[1]).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_integrity(&reader).unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, IssueKind::IndexMismatch);
+    assert_eq!(report.issues[0].source, "transaction_hash_to_idx");
+    assert_eq!(report.first_inconsistent_block(), Some(BlockNumber(1)));
+}
+
+#[test]
+fn marker_inconsistency() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config).unwrap();
+    append_blocks(&mut writer);
+    let txn = writer.begin_rw_txn().unwrap();
+    let markers_table = txn.open_table(&txn.tables.markers).unwrap();
+    markers_table.upsert(&txn.txn, &MarkerKind::Header, &BlockNumber(2)).unwrap();
+    txn.commit().unwrap();
+
+    let report = check_integrity(&reader).unwrap();
+    let kinds: Vec<_> = report.issues.iter().map(|issue| issue.kind).collect();
+    assert!(kinds.contains(&IssueKind::MarkerInconsistency));
+    assert!(kinds.contains(&IssueKind::EntryAboveMarker));
+    assert_eq!(report.first_inconsistent_block(), Some(BlockNumber(2)));
+
+    truncate_storage(&mut writer, BlockNumber(2)).unwrap();
+    assert_eq!(check_integrity(&reader).unwrap().issues, vec![]);
+}
+
+#[test]
+fn lost_file_data_is_truncated() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_blocks(&mut writer);
+    // Simulate a crash that lost the data of the transaction file after the first transaction of
+    // block 2.
+    let txn = reader.begin_ro_txn().unwrap();
+    let last_kept_tx_location = txn
+        .open_table(&txn.tables.transaction_metadata)
+        .unwrap()
+        .get(&txn.txn, &TransactionIndex(BlockNumber(2), Default::default()))
+        .unwrap()
+        .unwrap()
+        .tx_location;
+    drop(txn);
+    let txn = writer.begin_rw_txn().unwrap();
+    let file_offsets_table = txn.open_table(&txn.tables.file_offsets).unwrap();
+    file_offsets_table
+        .upsert(&txn.txn, &OffsetKind::Transaction, &last_kept_tx_location.next_offset())
+        .unwrap();
+    txn.commit().unwrap();
+    drop((reader, writer));
+
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    let report = check_integrity(&reader).unwrap();
+    assert!(!report.issues.is_empty());
+    assert!(
+        report
+            .issues
+            .iter()
+            .all(|issue| issue.kind == IssueKind::LocationOutOfBounds
+                && issue.source == "transaction")
+    );
+    assert_eq!(report.first_inconsistent_block(), Some(BlockNumber(2)));
+
+    truncate_storage(&mut writer, BlockNumber(2)).unwrap();
+    drop((reader, writer));
+    let (reader, _writer) = open_storage(config).unwrap();
+    let report = check_integrity(&reader).unwrap();
+    assert_eq!(report.issues, vec![]);
+    assert_eq!(report.markers["header"], BlockNumber(2));
+    assert_eq!(report.markers["compiled_class"], BlockNumber(2));
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_block_header(BlockNumber(2)).unwrap(), None);
+    assert_eq!(txn.get_block_header(BlockNumber(1)).unwrap().unwrap().block_hash, block_hash(1));
+}
+
+#[test]
+fn truncated_file_without_block_is_inconsistent() {
+    let report = IntegrityReport {
+        markers: BTreeMap::new(),
+        issues: vec![IntegrityIssue::new(
+            IssueKind::TruncatedFile,
+            "transaction",
+            None,
+            "The file is shorter than the data that was written to it.".to_owned(),
+        )],
+    };
+    // No block can be truncated to fix the issue, yet the storage is inconsistent.
+    assert_eq!(report.first_inconsistent_block(), None);
+    assert!(!report.is_consistent());
+}
+
+#[test]
+fn truncate_pruned_state_fails() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    config.state_history_blocks = 1;
+    let (_reader, mut writer) = open_storage(config).unwrap();
+    append_blocks(&mut writer);
+    writer.state_pruner().unwrap().prune(u64::MAX).unwrap();
+
+    assert_matches!(
+        truncate_storage(&mut writer, BlockNumber(0)),
+        Err(StorageError::StatePruned { pruned_state_marker, .. })
+            if pruned_state_marker == BlockNumber(N_BLOCKS - 1)
+    );
+}
+
+#[test]
+fn read_only_check_leaves_storage_unchanged() {
+    let (config, _temp_dir) = get_test_config(None);
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    append_blocks(&mut writer);
+    drop((reader, writer));
+    // The sizes of the database and the mmap files, leaving out the lock file of the database.
+    let file_sizes = || {
+        std::fs::read_dir(config.db_config.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "dat"))
+            .map(|path| (path.clone(), path.metadata().unwrap().len()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let file_sizes_before = file_sizes();
+
+    let reader =
+        open_storage_read_only(config.db_config.clone(), config.mmap_file_config.clone()).unwrap();
+    assert_eq!(reader.get_scope(), StorageScope::FullArchive);
+    assert_eq!(check_integrity(&reader).unwrap().issues, vec![]);
+    drop(reader);
+    assert_eq!(file_sizes(), file_sizes_before);
+}
+
+#[test]
+fn read_only_scope_is_taken_from_the_storage() {
+    let (config, _temp_dir) = get_test_config(Some(StorageScope::StateOnly));
+    drop(open_storage(config.clone()).unwrap());
+
+    let reader = open_storage_read_only(config.db_config, config.mmap_file_config).unwrap();
+    assert_eq!(reader.get_scope(), StorageScope::StateOnly);
+}
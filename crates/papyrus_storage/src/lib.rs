@@ -56,9 +56,9 @@
 //! higher major version indicates that a re-sync is necessary, while a higher minor version
 //! indicates a change that is migratable.
 //!
-//! When a storage is opened with [`StorageScope::StateOnly`], only the state version must match.
-//! For storage opened with [`StorageScope::FullArchive`], both versions must match the crate's
-//! versions.
+//! When a storage is opened with [`StorageScope::StateOnly`] or [`StorageScope::Pruned`], only the
+//! state version must match. For storage opened with [`StorageScope::FullArchive`], both versions
+//! must match the crate's versions.
 //!
 //! Incompatibility occurs when the code and the database have differing major versions. However,
 //! if the code has the same major version but a higher minor version compared to the database, it
@@ -87,8 +87,10 @@ pub mod utils;
 pub mod compression_utils;
 pub mod db;
 pub mod header;
+pub mod integrity;
 pub mod mmap_file;
 mod serialization;
+pub mod snapshot;
 pub mod state;
 mod version;
 
@@ -111,6 +113,7 @@ use db::serialization::{Key, NoVersionValueWrapper, ValueSerde, VersionZeroWrapp
 use db::table_types::{CommonPrefix, NoValue, Table, TableType};
 use mmap_file::{
     open_file,
+    open_file_read_only,
     FileHandler,
     LocationInFile,
     MMapFileError,
@@ -122,20 +125,22 @@ use papyrus_config::dumping::{append_sub_config_name, ser_param, SerializeConfig
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializedParam};
 use papyrus_proc_macros::latency_histogram;
 use serde::{Deserialize, Serialize};
+use snapshot::SnapshotError;
 use starknet_api::block::{BlockHash, BlockNumber, BlockSignature, StarknetVersion};
 use starknet_api::core::{ClassHash, ContractAddress, Nonce};
 use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
 use starknet_api::state::{ContractClass, StateNumber, StorageKey, ThinStateDiff};
-use starknet_api::transaction::{Transaction, TransactionHash, TransactionOutput};
+use starknet_api::transaction::{EventKey, Transaction, TransactionHash, TransactionOutput};
 use starknet_types_core::felt::Felt;
 use tracing::{debug, warn};
 use validator::Validate;
 use version::{StorageVersionError, Version};
 
-use crate::body::TransactionIndex;
+use crate::body::{fill_event_first_keys, TransactionIndex};
 use crate::db::table_types::SimpleTable;
 use crate::db::{
     open_env,
+    open_env_read_only,
     DbConfig,
     DbError,
     DbReader,
@@ -150,6 +155,8 @@ use crate::db::{
 use crate::header::StorageBlockHeader;
 use crate::mmap_file::MMapFileStats;
 use crate::state::data::IndexedDeprecatedContractClass;
+use crate::state::pruning::StatePruner;
+use crate::state::StateStorageReader;
 pub use crate::utils::update_storage_metrics;
 use crate::version::{VersionStorageReader, VersionStorageWriter};
 
@@ -157,7 +164,7 @@ use crate::version::{VersionStorageReader, VersionStorageWriter};
 /// The current version of the storage state code.
 pub const STORAGE_VERSION_STATE: Version = Version { major: 1, minor: 1 };
 /// The current version of the storage blocks code.
-pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 2, minor: 0 };
+pub const STORAGE_VERSION_BLOCKS: Version = Version { major: 2, minor: 1 };
 
 /// Opens a storage and returns a [`StorageReader`] and a [`StorageWriter`].
 pub fn open_storage(
@@ -174,6 +181,7 @@ pub fn open_storage(
         deprecated_declared_classes: db_writer
             .create_simple_table("deprecated_declared_classes")?,
         deployed_contracts: db_writer.create_simple_table("deployed_contracts")?,
+        event_first_keys: db_writer.create_common_prefix_table("event_first_keys")?,
         events: db_writer.create_common_prefix_table("events")?,
         headers: db_writer.create_simple_table("headers")?,
         markers: db_writer.create_simple_table("markers")?,
@@ -200,13 +208,78 @@ pub fn open_storage(
         scope: storage_config.scope,
         file_readers,
     };
-    let writer = StorageWriter { db_writer, tables, scope: storage_config.scope, file_writers };
+    let writer = StorageWriter {
+        db_writer,
+        tables,
+        scope: storage_config.scope,
+        file_writers,
+        state_history_blocks: storage_config.state_history_blocks,
+    };
 
     let writer = set_version_if_needed(reader.clone(), writer)?;
     verify_storage_version(reader.clone())?;
     Ok((reader, writer))
 }
 
+/// Opens an existing storage for reading only and returns a [`StorageReader`] to it. Unlike
+/// [`open_storage`], nothing is written to the storage: the tables, the storage version and the
+/// mmap files are left as they are, and the scope is taken from the stored version.
+pub fn open_storage_read_only(
+    db_config: DbConfig,
+    mmap_file_config: MmapFileConfig,
+) -> StorageResult<StorageReader> {
+    let db_reader = open_env_read_only(&db_config)?;
+    let tables = Arc::new(Tables {
+        block_hash_to_number: db_reader.existing_table("block_hash_to_number")?,
+        block_signatures: db_reader.existing_table("block_signatures")?,
+        casms: db_reader.existing_table("casms")?,
+        contract_storage: db_reader.existing_table("contract_storage")?,
+        declared_classes: db_reader.existing_table("declared_classes")?,
+        declared_classes_block: db_reader.existing_table("declared_classes_block")?,
+        deprecated_declared_classes: db_reader.existing_table("deprecated_declared_classes")?,
+        deployed_contracts: db_reader.existing_table("deployed_contracts")?,
+        event_first_keys: db_reader.existing_table("event_first_keys")?,
+        events: db_reader.existing_table("events")?,
+        headers: db_reader.existing_table("headers")?,
+        markers: db_reader.existing_table("markers")?,
+        nonces: db_reader.existing_table("nonces")?,
+        file_offsets: db_reader.existing_table("file_offsets")?,
+        state_diffs: db_reader.existing_table("state_diffs")?,
+        transaction_hash_to_idx: db_reader.existing_table("transaction_hash_to_idx")?,
+        transaction_metadata: db_reader.existing_table("transaction_metadata")?,
+
+        // Version tables
+        starknet_version: db_reader.existing_table("starknet_version")?,
+        storage_version: db_reader.existing_table("storage_version")?,
+    });
+    let file_readers = open_storage_files_read_only(
+        &db_config,
+        mmap_file_config,
+        db_reader.clone(),
+        &tables.file_offsets,
+    )?;
+    let mut reader =
+        StorageReader { db_reader, tables, scope: StorageScope::default(), file_readers };
+
+    reader.scope = match get_storage_version(reader.clone())? {
+        None => {
+            return Err(StorageError::StorageVersionInconsistency(
+                StorageVersionError::MissingStorageVersion,
+            ));
+        }
+        Some(StorageVersion::FullArchive(_)) => StorageScope::FullArchive,
+        Some(StorageVersion::StateOnly(_)) => {
+            if reader.begin_ro_txn()?.get_pruned_state_marker()? > BlockNumber(0) {
+                StorageScope::Pruned
+            } else {
+                StorageScope::StateOnly
+            }
+        }
+    };
+    verify_storage_version(reader.clone())?;
+    Ok(reader)
+}
+
 // In case storage version does not exist, set it to the crate version.
 // Expected to happen once - when the node is launched for the first time.
 // If the storage scope has changed, update accordingly.
@@ -214,7 +287,7 @@ fn set_version_if_needed(
     reader: StorageReader,
     mut writer: StorageWriter,
 ) -> StorageResult<StorageWriter> {
-    let Some(existing_storage_version) = get_storage_version(reader)? else {
+    let Some(existing_storage_version) = get_storage_version(reader.clone())? else {
         // Initialize the storage version.
         writer.begin_rw_txn()?.set_state_version(&STORAGE_VERSION_STATE)?.commit()?;
         // If in full-archive mode, also set the block version.
@@ -233,11 +306,11 @@ fn set_version_if_needed(
         StorageVersion::FullArchive(FullArchiveVersion { state_version: _, blocks_version: _ }) => {
             // TODO(yael): consider optimizing by deleting the block's data if the scope has changed
             // to StateOnly
-            if writer.scope == StorageScope::StateOnly {
+            if writer.scope != StorageScope::FullArchive {
                 // Deletion of the block's version is required here. It ensures that the node knows
                 // that the storage operates in StateOnly mode and prevents the operator from
                 // running it in FullArchive mode again.
-                debug!("Changing the storage scope from FullArchive to StateOnly.");
+                debug!("Changing the storage scope from FullArchive to {:?}.", writer.scope);
                 writer.begin_rw_txn()?.delete_blocks_version()?.commit()?;
             }
         }
@@ -250,6 +323,27 @@ fn set_version_if_needed(
             }
         }
     }
+    // The history of a pruned state cannot be restored, so the storage cannot leave the pruned
+    // mode.
+    if writer.scope != StorageScope::Pruned
+        && reader.begin_ro_txn()?.get_pruned_state_marker()? > BlockNumber(0)
+    {
+        return Err(StorageError::StorageVersionInconsistency(
+            StorageVersionError::InconsistentStorageScope,
+        ));
+    }
+    // Blocks version 2.1 added the index of the events by their first keys, which the blocks
+    // stored by lower versions lack.
+    if let StorageVersion::FullArchive(FullArchiveVersion { blocks_version, .. }) =
+        &existing_storage_version
+    {
+        if writer.scope == StorageScope::FullArchive
+            && blocks_version.major == STORAGE_VERSION_BLOCKS.major
+            && blocks_version.minor == 0
+        {
+            fill_event_first_keys(&reader, &mut writer)?;
+        }
+    }
     // Update the version if it's lower than the crate version.
     let mut wtxn = writer.begin_rw_txn()?;
     match existing_storage_version {
@@ -397,6 +491,10 @@ pub enum StorageScope {
     /// Stores the data describing the current state. In this mode the transaction, events and
     /// state-diffs are not stored.
     StateOnly,
+    /// Stores the same data as [`StorageScope::StateOnly`], but keeps the full history of the
+    /// state only for the last [`StorageConfig::state_history_blocks`] blocks. The history of older
+    /// blocks is compacted by a [`StatePruner`], so only their latest values are kept.
+    Pruned,
 }
 
 /// A struct for starting RO transactions ([`StorageTxn`]) to the storage.
@@ -448,6 +546,7 @@ pub struct StorageWriter {
     file_writers: FileHandlers<RW>,
     tables: Arc<Tables>,
     scope: StorageScope,
+    state_history_blocks: u64,
 }
 
 impl StorageWriter {
@@ -461,6 +560,27 @@ impl StorageWriter {
             scope: self.scope,
         })
     }
+
+    /// Returns a [`StatePruner`] of the storage, or `None` if the storage scope is not
+    /// [`StorageScope::Pruned`].
+    ///
+    /// The pruner writes to the storage in its own transactions, which wait for the transactions of
+    /// this writer to end (and vice versa). It is meant to be run by a background task.
+    pub fn state_pruner(&self) -> Option<StatePruner> {
+        if self.scope != StorageScope::Pruned {
+            return None;
+        }
+        Some(StatePruner::new(
+            StorageWriter {
+                db_writer: self.db_writer.duplicate(),
+                file_writers: self.file_writers.clone(),
+                tables: self.tables.clone(),
+                scope: self.scope,
+                state_history_blocks: self.state_history_blocks,
+            },
+            self.state_history_blocks,
+        ))
+    }
 }
 
 /// A struct for interacting with the storage.
@@ -486,8 +606,9 @@ impl<'env, Mode: TransactionKind> StorageTxn<'env, Mode> {
         &self,
         table_id: &TableIdentifier<K, V, T>,
     ) -> StorageResult<TableHandle<'_, K, V, T>> {
-        if self.scope == StorageScope::StateOnly {
+        if self.scope != StorageScope::FullArchive {
             let unused_tables = [
+                self.tables.event_first_keys.name,
                 self.tables.events.name,
                 self.tables.transaction_hash_to_idx.name,
                 self.tables.transaction_metadata.name,
@@ -521,6 +642,8 @@ struct_field_names! {
         deprecated_declared_classes: TableIdentifier<ClassHash, VersionZeroWrapper<IndexedDeprecatedContractClass>, SimpleTable>,
         // TODO(dvir): consider use here also the CommonPrefix table type.
         deployed_contracts: TableIdentifier<(ContractAddress, BlockNumber), VersionZeroWrapper<ClassHash>, SimpleTable>,
+        // An index of the transactions by the first keys of their events, for filtering events by key.
+        event_first_keys: TableIdentifier<(EventKey, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
         events: TableIdentifier<(ContractAddress, TransactionIndex), NoVersionValueWrapper<NoValue>, CommonPrefix>,
         headers: TableIdentifier<BlockNumber, VersionZeroWrapper<StorageBlockHeader>, SimpleTable>,
         markers: TableIdentifier<MarkerKind, VersionZeroWrapper<BlockNumber>, SimpleTable>,
@@ -603,6 +726,13 @@ pub enum StorageError {
          {block_number}."
     )]
     BlockSignatureForNonExistingBlock { block_number: BlockNumber, block_signature: BlockSignature },
+    #[error(
+        "The state at {state_number:?} was pruned. Only the states from block \
+         {pruned_state_marker} on are kept."
+    )]
+    StatePruned { state_number: StateNumber, pruned_state_marker: BlockNumber },
+    #[error(transparent)]
+    SnapshotError(#[from] SnapshotError),
 }
 
 /// A type alias that maps to std::result::Result<T, StorageError>.
@@ -610,23 +740,47 @@ pub type StorageResult<V> = std::result::Result<V, StorageError>;
 
 /// A struct for the configuration of the storage.
 #[allow(missing_docs)]
-#[derive(Serialize, Debug, Default, Deserialize, Clone, PartialEq, Validate)]
+#[derive(Serialize, Debug, Deserialize, Clone, PartialEq, Validate)]
 pub struct StorageConfig {
     #[validate]
     pub db_config: DbConfig,
     #[validate]
     pub mmap_file_config: MmapFileConfig,
     pub scope: StorageScope,
+    /// The number of most recent blocks whose full state history is kept, under the
+    /// [`StorageScope::Pruned`] scope.
+    #[validate(range(min = 1))]
+    pub state_history_blocks: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            db_config: DbConfig::default(),
+            mmap_file_config: MmapFileConfig::default(),
+            scope: StorageScope::default(),
+            state_history_blocks: 1000,
+        }
+    }
 }
 
 impl SerializeConfig for StorageConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        let mut dumped_config = BTreeMap::from_iter([ser_param(
-            "scope",
-            &self.scope,
-            "The categories of data saved in storage.",
-            ParamPrivacyInput::Public,
-        )]);
+        let mut dumped_config = BTreeMap::from_iter([
+            ser_param(
+                "scope",
+                &self.scope,
+                "The categories of data saved in storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "state_history_blocks",
+                &self.state_history_blocks,
+                "The number of most recent blocks whose full state history is kept when the \
+                 storage scope is Pruned. The history of older blocks is compacted.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
         dumped_config
             .extend(append_sub_config_name(self.mmap_file_config.dump(), "mmap_file_config"));
         dumped_config.extend(append_sub_config_name(self.db_config.dump(), "db_config"));
@@ -646,9 +800,13 @@ pub struct DbStats {
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
 // A marker is the first block number for which the corresponding data doesn't exist yet.
 // Invariants:
+// The PrunedState marker is different: it is the first block number whose state history is fully
+// kept, and the states before it can't be read.
+// Invariants:
 // - CompiledClass <= Class <= State <= Header
 // - Body <= Header
 // - BaseLayerBlock <= Header
+// - PrunedState <= State
 // Event is currently unsupported.
 pub(crate) enum MarkerKind {
     Header,
@@ -658,6 +816,7 @@ pub(crate) enum MarkerKind {
     Class,
     CompiledClass,
     BaseLayerBlock,
+    PrunedState,
 }
 
 pub(crate) type MarkersTable<'env> =
@@ -862,6 +1021,53 @@ fn open_storage_files(
     ))
 }
 
+fn open_storage_files_read_only(
+    db_config: &DbConfig,
+    mmap_file_config: MmapFileConfig,
+    db_reader: DbReader,
+    file_offsets_table: &TableIdentifier<OffsetKind, NoVersionValueWrapper<usize>, SimpleTable>,
+) -> StorageResult<FileHandlers<RO>> {
+    let db_transaction = db_reader.begin_ro_txn()?;
+    let table = db_transaction.open_table(file_offsets_table)?;
+    let offset = |offset_kind: OffsetKind| -> StorageResult<usize> {
+        Ok(table.get(&db_transaction, &offset_kind)?.unwrap_or_default())
+    };
+    let path = |file_name: &str| db_config.path().join(file_name);
+
+    Ok(FileHandlers {
+        thin_state_diff: open_file_read_only(
+            mmap_file_config.clone(),
+            path("thin_state_diff.dat"),
+            offset(OffsetKind::ThinStateDiff)?,
+        )?,
+        contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            path("contract_class.dat"),
+            offset(OffsetKind::ContractClass)?,
+        )?,
+        casm: open_file_read_only(
+            mmap_file_config.clone(),
+            path("casm.dat"),
+            offset(OffsetKind::Casm)?,
+        )?,
+        deprecated_contract_class: open_file_read_only(
+            mmap_file_config.clone(),
+            path("deprecated_contract_class.dat"),
+            offset(OffsetKind::DeprecatedContractClass)?,
+        )?,
+        transaction_output: open_file_read_only(
+            mmap_file_config.clone(),
+            path("transaction_output.dat"),
+            offset(OffsetKind::TransactionOutput)?,
+        )?,
+        transaction: open_file_read_only(
+            mmap_file_config,
+            path("transaction.dat"),
+            offset(OffsetKind::Transaction)?,
+        )?,
+    })
+}
+
 /// Represents a kind of mmap file.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord)]
 pub enum OffsetKind {
@@ -198,6 +198,35 @@ pub(crate) fn open_file<V: ValueSerde>(
     Ok((write_file_handler, read_file_handler))
 }
 
+/// Open an existing memory mapped file for reading only. Unlike [`open_file`], the file is neither
+/// created nor grown, and it is mapped privately, so nothing is ever written back to it.
+#[instrument(level = "debug", err)]
+pub(crate) fn open_file_read_only<V: ValueSerde>(
+    config: MmapFileConfig,
+    path: PathBuf,
+    offset: usize,
+) -> MmapFileResult<FileHandler<V, RO>> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let size = file.metadata()?.len();
+    let mmap = unsafe { MmapOptions::new().len(config.max_size).map_copy(&file)? };
+    let mmap_ptr = mmap.as_ptr();
+    let mmap_file = MMapFile {
+        config,
+        file,
+        mmap,
+        size: size.try_into().expect("size should fit in usize"),
+        offset,
+        should_flush: false,
+        _value_type: PhantomData {},
+    };
+
+    Ok(FileHandler {
+        memory_ptr: mmap_ptr,
+        mmap_file: Arc::new(Mutex::new(mmap_file)),
+        _mode: PhantomData,
+    })
+}
+
 /// A wrapper around `MMapFile` that provides both write and read interfaces.
 #[derive(Clone, Debug)]
 pub(crate) struct FileHandler<V: ValueSerde, Mode: TransactionKind> {
@@ -278,6 +307,18 @@ pub struct MMapFileStats {
     offset: usize,
 }
 
+impl MMapFileStats {
+    /// Returns whether the location is inside the written data that the file holds.
+    pub(crate) fn contains(&self, location: LocationInFile) -> bool {
+        location.next_offset() <= self.offset.min(self.size)
+    }
+
+    /// Returns whether the file is shorter than the data that was written to it.
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.size < self.offset
+    }
+}
+
 impl<V: ValueSerde, Mode: TransactionKind> FileHandler<V, Mode> {
     pub fn stats(&self) -> MMapFileStats {
         let mmap_file = self.mmap_file.lock().expect("Lock should not be poisoned");
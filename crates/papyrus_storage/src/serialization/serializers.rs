@@ -316,6 +316,7 @@ auto_storage_serde! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        PrunedState = 7,
     }
     pub struct MessageToL1 {
         pub to_address: EthAddress,
@@ -475,6 +476,7 @@ auto_storage_serde! {
     (ContractAddress, Nonce);
     (ContractAddress, StorageKey);
     (ContractAddress, TransactionIndex);
+    (EventKey, TransactionIndex);
     ((ContractAddress, StorageKey), BlockNumber);
     (usize, Vec<Hint>);
     (usize, Vec<String>);
@@ -0,0 +1,497 @@
+//! Export and import of the storage in a portable snapshot format.
+//!
+//! A snapshot holds the blocks from the genesis up to a chosen block: their headers, signatures,
+//! bodies, state diffs, classes and compiled classes. It is a directory with a
+//! [`SnapshotManifest`] file and a list of chunk files. Each chunk holds a consecutive range of
+//! blocks as zstd-compressed JSON lines, one line per block, and its SHA-256 checksum is kept in
+//! the manifest.
+//!
+//! Importing a snapshot requires its last block as known from a trusted source, e.g. a feeder
+//! gateway or a block explorer, and verifies that the chunks match the checksums of the manifest
+//! and that the headers lead to that block through their parent hashes. The block hashes and the
+//! commitments of the bodies, events and state diffs are not recomputed, so a snapshot whose
+//! blocks, and then checksums, were altered is imported as is. Snapshots must come from a trusted
+//! source.
+//!
+//! The snapshot doesn't depend on the layout of the database, so it can be imported into a
+//! storage of any storage version that supports its format version. Importing commits each block
+//! in its own transaction, so an interrupted import resumes from the first block that wasn't
+//! imported.
+
+#[cfg(test)]
+#[path = "snapshot_test.rs"]
+mod snapshot_test;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use starknet_api::block::{BlockBody, BlockHash, BlockNumber, BlockSignature, StarknetVersion};
+use starknet_api::core::{ChainId, ClassHash, GlobalRoot};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use tracing::{debug, info};
+
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::class::{ClassStorageReader, ClassStorageWriter};
+use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::db::table_types::Table;
+use crate::db::RO;
+use crate::header::{HeaderStorageReader, HeaderStorageWriter, StorageBlockHeader};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::{StorageError, StorageReader, StorageResult, StorageScope, StorageTxn, StorageWriter};
+
+/// The version of the snapshot format. Snapshots of other versions can't be imported.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 0;
+
+/// The name of the manifest file in the snapshot directory.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Errors that may occur when exporting or importing a snapshot.
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug)]
+pub enum SnapshotError {
+    #[error("Unsupported snapshot format version {version} (expected {SNAPSHOT_FORMAT_VERSION}).")]
+    UnsupportedFormatVersion { version: u32 },
+    #[error(
+        "The snapshot is of chain {snapshot_chain_id}, but the storage is of chain {chain_id}."
+    )]
+    ChainIdMismatch { snapshot_chain_id: ChainId, chain_id: ChainId },
+    #[error(
+        "Snapshots can only be exported from a storage with the FullArchive scope, found {0:?}."
+    )]
+    UnsupportedScope(StorageScope),
+    #[error("Block {block_number} is not fully stored. The storage is complete up to {marker}.")]
+    BlockNotAvailable { block_number: BlockNumber, marker: BlockNumber },
+    #[error("The number of blocks in a chunk should be positive.")]
+    EmptyChunk,
+    #[error("The checksum of the snapshot chunk {file_name} doesn't match the manifest.")]
+    ChecksumMismatch { file_name: String },
+    #[error("Expected block {expected} in the snapshot, found {found:?}.")]
+    UnexpectedBlock { expected: BlockNumber, found: Option<BlockNumber> },
+    #[error("Block hash mismatch at block {block_number} (expected {expected}, found {found}).")]
+    BlockHashMismatch { block_number: BlockNumber, expected: BlockHash, found: BlockHash },
+    #[error(
+        "State root mismatch at block {block_number} (expected {expected:?}, found {found:?})."
+    )]
+    StateRootMismatch { block_number: BlockNumber, expected: GlobalRoot, found: GlobalRoot },
+    #[error("The last block of the snapshot {found:?} is not the trusted one {expected:?}.")]
+    UntrustedLastBlock { expected: TrustedBlock, found: TrustedBlock },
+    #[error(
+        "Can't import into a storage whose markers are not aligned with the header marker \
+         {header_marker} (found {marker_name} marker {marker})."
+    )]
+    UnalignedMarkers { header_marker: BlockNumber, marker_name: &'static str, marker: BlockNumber },
+}
+
+/// The description of a snapshot, kept in its [`MANIFEST_FILE_NAME`] file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// The version of the snapshot format.
+    pub format_version: u32,
+    /// The chain the snapshot belongs to.
+    pub chain_id: ChainId,
+    /// The last block in the snapshot. The snapshot holds all the blocks up to it.
+    pub last_block: BlockNumber,
+    /// The hash of the last block, used to verify the imported storage.
+    pub last_block_hash: BlockHash,
+    /// The state root after the last block, used to verify the imported storage.
+    pub last_state_root: GlobalRoot,
+    /// The chunks of the snapshot, ordered by their blocks.
+    pub chunks: Vec<SnapshotChunk>,
+}
+
+/// A block as known from a trusted source, against which an imported snapshot is verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedBlock {
+    /// The number of the block.
+    pub block_number: BlockNumber,
+    /// The hash of the block.
+    pub block_hash: BlockHash,
+    /// The state root after the block.
+    pub state_root: GlobalRoot,
+}
+
+/// A file holding a consecutive range of blocks of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotChunk {
+    /// The name of the chunk file, relative to the snapshot directory.
+    pub file_name: String,
+    /// The first block in the chunk.
+    pub first_block: BlockNumber,
+    /// The number of blocks in the chunk.
+    pub n_blocks: u64,
+    /// The hex encoded SHA-256 checksum of the chunk file.
+    pub sha256: String,
+}
+
+// The data of a single block in a snapshot chunk. The header is kept in its storage
+// representation since the serialization of `BlockHeader` skips some of its fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotBlock {
+    header: StorageBlockHeader,
+    starknet_version: StarknetVersion,
+    signature: Option<BlockSignature>,
+    body: BlockBody,
+    state_diff: ThinStateDiff,
+    classes: Vec<(ClassHash, ContractClass)>,
+    // The deprecated classes that were first declared in this block, including the ones that were
+    // implicitly declared by deploying them.
+    deprecated_classes: Vec<(ClassHash, DeprecatedContractClass)>,
+    // The compiled classes of `classes`, in the order of the declared classes of the state diff.
+    casms: Vec<(ClassHash, CasmContractClass)>,
+}
+
+/// Exports the blocks up to and including `last_block` into a snapshot in `archive_dir`, with
+/// `blocks_per_chunk` blocks in each chunk. All the data is read in a single transaction, so the
+/// snapshot is consistent even if the storage is written to in the meantime.
+pub fn export_snapshot(
+    reader: &StorageReader,
+    chain_id: &ChainId,
+    last_block: BlockNumber,
+    archive_dir: &Path,
+    blocks_per_chunk: u64,
+) -> StorageResult<SnapshotManifest> {
+    if reader.scope != StorageScope::FullArchive {
+        return Err(SnapshotError::UnsupportedScope(reader.scope).into());
+    }
+    if blocks_per_chunk == 0 {
+        return Err(SnapshotError::EmptyChunk.into());
+    }
+    let txn = reader.begin_ro_txn()?;
+    let marker = [
+        txn.get_header_marker()?,
+        txn.get_body_marker()?,
+        txn.get_state_marker()?,
+        txn.get_class_marker()?,
+        txn.get_compiled_class_marker()?,
+    ]
+    .into_iter()
+    .min()
+    .expect("The markers list should not be empty.");
+    if last_block >= marker {
+        return Err(SnapshotError::BlockNotAvailable { block_number: last_block, marker }.into());
+    }
+
+    std::fs::create_dir_all(archive_dir)?;
+    let end_block = last_block.unchecked_next();
+    let mut chunks = Vec::new();
+    let mut last_header = None;
+    let mut first_block = BlockNumber(0);
+    while first_block < end_block {
+        let chunk_end = BlockNumber(first_block.0.saturating_add(blocks_per_chunk)).min(end_block);
+        let file_name = format!("chunk_{:06}.jsonl.zst", chunks.len());
+        debug!("Exporting blocks [{first_block}, {chunk_end}) into {file_name}.");
+        let file_path = archive_dir.join(&file_name);
+        let mut encoder =
+            zstd::stream::write::Encoder::new(File::create(&file_path)?, CHUNK_COMPRESSION_LEVEL)?;
+        for block_number in first_block.iter_up_to(chunk_end) {
+            let block = read_block(&txn, block_number)?;
+            serde_json::to_writer(&mut encoder, &block)?;
+            encoder.write_all(b"\n")?;
+            last_header = Some(block.header);
+        }
+        encoder.finish()?.sync_all()?;
+        chunks.push(SnapshotChunk {
+            file_name,
+            first_block,
+            n_blocks: chunk_end.0 - first_block.0,
+            sha256: file_sha256(&file_path)?,
+        });
+        first_block = chunk_end;
+    }
+
+    let last_header = last_header.expect("The snapshot should contain at least one block.");
+    let manifest = SnapshotManifest {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        chain_id: chain_id.clone(),
+        last_block,
+        last_block_hash: last_header.block_hash,
+        last_state_root: last_header.state_root,
+        chunks,
+    };
+    // The manifest is written last, so a snapshot with a manifest is complete.
+    let mut manifest_writer = BufWriter::new(File::create(archive_dir.join(MANIFEST_FILE_NAME))?);
+    serde_json::to_writer_pretty(&mut manifest_writer, &manifest)?;
+    manifest_writer.flush()?;
+    info!("Exported the blocks up to {last_block} into {archive_dir:?}.");
+    Ok(manifest)
+}
+
+/// Imports the snapshot in `archive_dir` into the storage and returns the last imported block.
+///
+/// The storage should either be empty or contain a prefix of the snapshot's blocks, e.g. from an
+/// interrupted import, in which case the import resumes after the stored blocks. The last block of
+/// the snapshot should be `trusted_last_block`. The checksum of every chunk is verified before its
+/// blocks are imported, and the block hashes and the final state root are verified against the
+/// stored blocks and the trusted last block. The content of the blocks is not verified against
+/// their hashes, so the snapshot must come from a trusted source.
+pub fn import_snapshot(
+    writer: &mut StorageWriter,
+    chain_id: &ChainId,
+    archive_dir: &Path,
+    trusted_last_block: &TrustedBlock,
+) -> StorageResult<BlockNumber> {
+    let manifest: SnapshotManifest =
+        serde_json::from_reader(BufReader::new(File::open(archive_dir.join(MANIFEST_FILE_NAME))?))?;
+    if manifest.format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(
+            SnapshotError::UnsupportedFormatVersion { version: manifest.format_version }.into()
+        );
+    }
+    if manifest.chain_id != *chain_id {
+        return Err(SnapshotError::ChainIdMismatch {
+            snapshot_chain_id: manifest.chain_id,
+            chain_id: chain_id.clone(),
+        }
+        .into());
+    }
+    // The manifest is only as trustworthy as the snapshot, so the blocks are verified to lead to
+    // the trusted last block rather than to the one of the manifest.
+    let last_block = TrustedBlock {
+        block_number: manifest.last_block,
+        block_hash: manifest.last_block_hash,
+        state_root: manifest.last_state_root,
+    };
+    if last_block != *trusted_last_block {
+        return Err(SnapshotError::UntrustedLastBlock {
+            expected: *trusted_last_block,
+            found: last_block,
+        }
+        .into());
+    }
+
+    let txn = writer.begin_rw_txn()?;
+    let next_block = txn.get_header_marker()?;
+    for (marker_name, marker) in [
+        ("body", txn.get_body_marker()?),
+        ("state", txn.get_state_marker()?),
+        ("class", txn.get_class_marker()?),
+        ("compiled class", txn.get_compiled_class_marker()?),
+    ] {
+        if marker != next_block {
+            return Err(SnapshotError::UnalignedMarkers {
+                header_marker: next_block,
+                marker_name,
+                marker,
+            }
+            .into());
+        }
+    }
+    // The hash of the last stored block, which the next imported block should point to.
+    let mut parent_hash = match next_block.prev() {
+        Some(block_number) => txn.get_block_header(block_number)?.map(|header| header.block_hash),
+        None => None,
+    };
+    drop(txn);
+    if next_block > manifest.last_block {
+        info!("The snapshot in {archive_dir:?} was already imported.");
+        return verify_last_block(writer, trusted_last_block);
+    }
+    info!("Importing the blocks [{next_block}, {}] from {archive_dir:?}.", manifest.last_block);
+
+    let mut expected_block = BlockNumber(0);
+    for chunk in &manifest.chunks {
+        if chunk.first_block != expected_block {
+            return Err(SnapshotError::UnexpectedBlock {
+                expected: expected_block,
+                found: Some(chunk.first_block),
+            }
+            .into());
+        }
+        let chunk_end = BlockNumber(chunk.first_block.0 + chunk.n_blocks);
+        // Chunks whose blocks, including the parent of the next block, are all stored are skipped.
+        if chunk_end < next_block {
+            expected_block = chunk_end;
+            continue;
+        }
+
+        let file_path = archive_dir.join(&chunk.file_name);
+        if file_sha256(&file_path)? != chunk.sha256 {
+            return Err(
+                SnapshotError::ChecksumMismatch { file_name: chunk.file_name.clone() }.into()
+            );
+        }
+        debug!(
+            "Importing the blocks [{}, {chunk_end}) from {}.",
+            chunk.first_block, chunk.file_name
+        );
+        let mut lines =
+            BufReader::new(zstd::stream::read::Decoder::new(File::open(&file_path)?)?).lines();
+        for block_number in chunk.first_block.iter_up_to(chunk_end) {
+            let Some(line) = lines.next().transpose()? else {
+                return Err(
+                    SnapshotError::UnexpectedBlock { expected: block_number, found: None }.into()
+                );
+            };
+            if block_number.unchecked_next() < next_block {
+                continue;
+            }
+            let block: SnapshotBlock = serde_json::from_str(&line)?;
+            if block.header.block_number != block_number {
+                return Err(SnapshotError::UnexpectedBlock {
+                    expected: block_number,
+                    found: Some(block.header.block_number),
+                }
+                .into());
+            }
+            if block_number < next_block {
+                // The last stored block, which should match the snapshot.
+                verify_block_hash(block_number, block.header.block_hash, parent_hash)?;
+                continue;
+            }
+            if let (Some(parent_hash), Some(parent_number)) = (parent_hash, block_number.prev()) {
+                verify_block_hash(parent_number, parent_hash, Some(block.header.parent_hash))?;
+            }
+            parent_hash = Some(block.header.block_hash);
+            write_block(writer, block)?;
+        }
+        if let Some(line) = lines.next().transpose()? {
+            let block: SnapshotBlock = serde_json::from_str(&line)?;
+            return Err(SnapshotError::UnexpectedBlock {
+                expected: chunk_end,
+                found: Some(block.header.block_number),
+            }
+            .into());
+        }
+        expected_block = chunk_end;
+    }
+    if expected_block != manifest.last_block.unchecked_next() {
+        return Err(SnapshotError::UnexpectedBlock { expected: expected_block, found: None }.into());
+    }
+    info!("Imported the blocks up to {} from {archive_dir:?}.", manifest.last_block);
+    verify_last_block(writer, trusted_last_block)
+}
+
+const CHUNK_COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+fn read_block(txn: &StorageTxn<'_, RO>, block_number: BlockNumber) -> StorageResult<SnapshotBlock> {
+    // The block is below all the markers, so all of its data is stored.
+    let missing_data = |data: &str| StorageError::DBInconsistency {
+        msg: format!("Missing {data} of block {block_number} below the markers."),
+    };
+    let header = txn
+        .open_table(&txn.tables.headers)?
+        .get(&txn.txn, &block_number)?
+        .ok_or_else(|| missing_data("header"))?;
+    let starknet_version =
+        txn.get_starknet_version(block_number)?.ok_or_else(|| missing_data("Starknet version"))?;
+    let body = BlockBody {
+        transactions: txn
+            .get_block_transactions(block_number)?
+            .ok_or_else(|| missing_data("transactions"))?,
+        transaction_outputs: txn
+            .get_block_transaction_outputs(block_number)?
+            .ok_or_else(|| missing_data("transaction outputs"))?,
+        transaction_hashes: txn
+            .get_block_transaction_hashes(block_number)?
+            .ok_or_else(|| missing_data("transaction hashes"))?,
+    };
+    let state_diff = txn.get_state_diff(block_number)?.ok_or_else(|| missing_data("state diff"))?;
+
+    let mut classes = Vec::with_capacity(state_diff.declared_classes.len());
+    let mut casms = Vec::with_capacity(state_diff.declared_classes.len());
+    for class_hash in state_diff.declared_classes.keys() {
+        classes
+            .push((*class_hash, txn.get_class(class_hash)?.ok_or_else(|| missing_data("class"))?));
+        casms.push((*class_hash, txn.get_casm(class_hash)?.ok_or_else(|| missing_data("casm"))?));
+    }
+
+    let deprecated_declared_classes_table =
+        txn.open_table(&txn.tables.deprecated_declared_classes)?;
+    let mut deprecated_classes: Vec<(ClassHash, DeprecatedContractClass)> = Vec::new();
+    for class_hash in state_diff
+        .deprecated_declared_classes
+        .iter()
+        .chain(state_diff.deployed_contracts.values())
+        .chain(state_diff.replaced_classes.values())
+    {
+        let first_declared_in_block = deprecated_declared_classes_table
+            .get(&txn.txn, class_hash)?
+            .is_some_and(|indexed_class| indexed_class.block_number == block_number);
+        if !first_declared_in_block || deprecated_classes.iter().any(|(hash, _)| hash == class_hash)
+        {
+            continue;
+        }
+        let deprecated_class = txn
+            .get_deprecated_class(class_hash)?
+            .ok_or_else(|| missing_data("deprecated class"))?;
+        deprecated_classes.push((*class_hash, deprecated_class));
+    }
+
+    Ok(SnapshotBlock {
+        header,
+        starknet_version,
+        signature: txn.get_block_signature(block_number)?,
+        body,
+        state_diff,
+        classes,
+        deprecated_classes,
+        casms,
+    })
+}
+
+fn write_block(writer: &mut StorageWriter, block: SnapshotBlock) -> StorageResult<()> {
+    let block_number = block.header.block_number;
+    let classes: Vec<_> = block.classes.iter().map(|(hash, class)| (*hash, class)).collect();
+    let deprecated_classes: Vec<_> =
+        block.deprecated_classes.iter().map(|(hash, class)| (*hash, class)).collect();
+    let mut txn = writer
+        .begin_rw_txn()?
+        .append_header(block_number, &block.header.into_block_header(block.starknet_version))?;
+    if let Some(signature) = &block.signature {
+        txn = txn.append_block_signature(block_number, signature)?;
+    }
+    txn = txn
+        .append_body(block_number, block.body)?
+        .append_state_diff(block_number, block.state_diff)?
+        .append_classes(block_number, &classes, &deprecated_classes)?;
+    // The casms are appended after the state diff, which determines the compiled class marker.
+    for (class_hash, casm) in &block.casms {
+        txn = txn.append_casm(class_hash, casm)?;
+    }
+    txn.commit()
+}
+
+// Verifies that `found`, if known, is the hash of the given block, which should be `expected`.
+fn verify_block_hash(
+    block_number: BlockNumber,
+    expected: BlockHash,
+    found: Option<BlockHash>,
+) -> StorageResult<()> {
+    match found {
+        Some(found) if found != expected => {
+            Err(SnapshotError::BlockHashMismatch { block_number, expected, found }.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn verify_last_block(
+    writer: &mut StorageWriter,
+    trusted_last_block: &TrustedBlock,
+) -> StorageResult<BlockNumber> {
+    let block_number = trusted_last_block.block_number;
+    let header = writer.begin_rw_txn()?.get_block_header(block_number)?.ok_or_else(|| {
+        StorageError::DBInconsistency { msg: format!("Missing header of block {block_number}.") }
+    })?;
+    verify_block_hash(block_number, trusted_last_block.block_hash, Some(header.block_hash))?;
+    if header.state_root != trusted_last_block.state_root {
+        return Err(SnapshotError::StateRootMismatch {
+            block_number,
+            expected: trusted_last_block.state_root,
+            found: header.state_root,
+        }
+        .into());
+    }
+    Ok(block_number)
+}
+
+fn file_sha256(path: &Path) -> StorageResult<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
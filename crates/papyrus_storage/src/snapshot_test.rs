@@ -0,0 +1,434 @@
+use std::fs;
+use std::path::Path;
+
+use assert_matches::assert_matches;
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
+use indexmap::IndexMap;
+use papyrus_test_utils::get_test_body;
+use pretty_assertions::assert_eq;
+use starknet_api::block::{BlockBody, BlockHash, BlockHeader, BlockNumber, BlockSignature};
+use starknet_api::core::{ChainId, ClassHash, CompiledClassHash, ContractAddress, GlobalRoot};
+use starknet_api::deprecated_contract_class::ContractClass as DeprecatedContractClass;
+use starknet_api::state::{ContractClass, ThinStateDiff};
+use starknet_types_core::felt::Felt;
+use tempfile::{tempdir, TempDir};
+
+use crate::body::{BodyStorageReader, BodyStorageWriter};
+use crate::class::{ClassStorageReader, ClassStorageWriter};
+use crate::compiled_class::{CasmStorageReader, CasmStorageWriter};
+use crate::header::{HeaderStorageReader, HeaderStorageWriter};
+use crate::snapshot::{
+    export_snapshot,
+    import_snapshot,
+    SnapshotError,
+    SnapshotManifest,
+    TrustedBlock,
+    MANIFEST_FILE_NAME,
+};
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{open_storage, StorageError, StorageReader, StorageScope, StorageWriter};
+
+const N_BLOCKS: u64 = 5;
+const TXS_PER_BLOCK: usize = 2;
+
+fn chain_id() -> ChainId {
+    ChainId::Other("SN_TEST".to_owned())
+}
+
+fn block_hash(block_number: u64) -> BlockHash {
+    BlockHash(Felt::from(block_number + 1000))
+}
+
+fn state_root(block_number: u64) -> GlobalRoot {
+    GlobalRoot(Felt::from(block_number))
+}
+
+// The block of the source storage, as known from a trusted source.
+fn trusted_block(block_number: u64) -> TrustedBlock {
+    TrustedBlock {
+        block_number: BlockNumber(block_number),
+        block_hash: block_hash(block_number),
+        state_root: state_root(block_number),
+    }
+}
+
+fn class_hash(block_number: u64) -> ClassHash {
+    ClassHash(Felt::from(block_number * 10 + 1))
+}
+
+fn deprecated_class_hash(block_number: u64) -> ClassHash {
+    ClassHash(Felt::from(block_number * 10 + 2))
+}
+
+// Returns a storage with N_BLOCKS blocks. Each block declares a class and a deprecated class, and
+// deploys a contract of the deprecated class of the genesis block.
+fn get_source_storage() -> ((StorageReader, StorageWriter), TempDir) {
+    let ((reader, mut writer), temp_dir) = get_test_storage();
+    let body = get_test_body(N_BLOCKS as usize * TXS_PER_BLOCK, Some(1), None, None);
+    for block_number in 0..N_BLOCKS {
+        let header = BlockHeader {
+            block_hash: block_hash(block_number),
+            parent_hash: block_number.checked_sub(1).map(block_hash).unwrap_or_default(),
+            block_number: BlockNumber(block_number),
+            state_root: state_root(block_number),
+            n_transactions: TXS_PER_BLOCK,
+            ..Default::default()
+        };
+        let txs =
+            block_number as usize * TXS_PER_BLOCK..(block_number as usize + 1) * TXS_PER_BLOCK;
+        let block_body = BlockBody {
+            transactions: body.transactions[txs.clone()].to_vec(),
+            transaction_outputs: body.transaction_outputs[txs.clone()].to_vec(),
+            transaction_hashes: body.transaction_hashes[txs].to_vec(),
+        };
+        let state_diff = ThinStateDiff {
+            deployed_contracts: IndexMap::from([(
+                ContractAddress::from(block_number * 10 + 3),
+                deprecated_class_hash(0),
+            )]),
+            declared_classes: IndexMap::from([(
+                class_hash(block_number),
+                CompiledClassHash(Felt::from(block_number)),
+            )]),
+            deprecated_declared_classes: vec![deprecated_class_hash(block_number)],
+            ..Default::default()
+        };
+        let class = ContractClass::default();
+        let deprecated_class = DeprecatedContractClass::default();
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_header(BlockNumber(block_number), &header)
+            .unwrap()
+            .append_block_signature(BlockNumber(block_number), &BlockSignature::default())
+            .unwrap()
+            .append_body(BlockNumber(block_number), block_body)
+            .unwrap()
+            .append_state_diff(BlockNumber(block_number), state_diff)
+            .unwrap()
+            .append_classes(
+                BlockNumber(block_number),
+                &[(class_hash(block_number), &class)],
+                &[(deprecated_class_hash(block_number), &deprecated_class)],
+            )
+            .unwrap()
+            .append_casm(&class_hash(block_number), &CasmContractClass::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    ((reader, writer), temp_dir)
+}
+
+// Asserts that the first `n_blocks` blocks of the storages are the same, and that the target
+// storage has no other blocks.
+fn assert_same_blocks(source: &StorageReader, target: &StorageReader, n_blocks: u64) {
+    let source_txn = source.begin_ro_txn().unwrap();
+    let target_txn = target.begin_ro_txn().unwrap();
+    for block_number in (0..n_blocks).map(BlockNumber) {
+        assert_eq!(
+            target_txn.get_block_header(block_number).unwrap(),
+            source_txn.get_block_header(block_number).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_block_signature(block_number).unwrap(),
+            source_txn.get_block_signature(block_number).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_block_transactions(block_number).unwrap(),
+            source_txn.get_block_transactions(block_number).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_block_transaction_outputs(block_number).unwrap(),
+            source_txn.get_block_transaction_outputs(block_number).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_block_transaction_hashes(block_number).unwrap(),
+            source_txn.get_block_transaction_hashes(block_number).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_state_diff(block_number).unwrap(),
+            source_txn.get_state_diff(block_number).unwrap()
+        );
+        let class_hash = class_hash(block_number.0);
+        assert_eq!(
+            target_txn.get_class(&class_hash).unwrap(),
+            source_txn.get_class(&class_hash).unwrap()
+        );
+        assert_eq!(
+            target_txn.get_casm(&class_hash).unwrap(),
+            source_txn.get_casm(&class_hash).unwrap()
+        );
+        let deprecated_class_hash = deprecated_class_hash(block_number.0);
+        assert_eq!(
+            target_txn.get_deprecated_class(&deprecated_class_hash).unwrap(),
+            source_txn.get_deprecated_class(&deprecated_class_hash).unwrap()
+        );
+    }
+    assert_eq!(target_txn.get_header_marker().unwrap(), BlockNumber(n_blocks));
+    assert_eq!(target_txn.get_body_marker().unwrap(), BlockNumber(n_blocks));
+    assert_eq!(target_txn.get_state_marker().unwrap(), BlockNumber(n_blocks));
+    assert_eq!(target_txn.get_class_marker().unwrap(), BlockNumber(n_blocks));
+    assert_eq!(target_txn.get_compiled_class_marker().unwrap(), BlockNumber(n_blocks));
+}
+
+fn read_manifest(archive_dir: &Path) -> SnapshotManifest {
+    serde_json::from_slice(&fs::read(archive_dir.join(MANIFEST_FILE_NAME)).unwrap()).unwrap()
+}
+
+#[test]
+fn export_and_import() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    let last_block = BlockNumber(N_BLOCKS - 1);
+
+    let manifest =
+        export_snapshot(&source_reader, &chain_id(), last_block, archive_dir.path(), 2).unwrap();
+    assert_eq!(read_manifest(archive_dir.path()), manifest);
+    assert_eq!(manifest.last_block, last_block);
+    assert_eq!(manifest.last_block_hash, block_hash(last_block.0));
+    assert_eq!(manifest.last_state_root, state_root(last_block.0));
+    let chunk_sizes: Vec<_> = manifest.chunks.iter().map(|chunk| chunk.n_blocks).collect();
+    assert_eq!(chunk_sizes, vec![2, 2, 1]);
+
+    let ((target_reader, mut target_writer), _target_dir) = get_test_storage();
+    assert_eq!(
+        import_snapshot(
+            &mut target_writer,
+            &chain_id(),
+            archive_dir.path(),
+            &trusted_block(last_block.0)
+        )
+        .unwrap(),
+        last_block
+    );
+    assert_same_blocks(&source_reader, &target_reader, N_BLOCKS);
+
+    // Importing the same snapshot again has nothing left to do.
+    assert_eq!(
+        import_snapshot(
+            &mut target_writer,
+            &chain_id(),
+            archive_dir.path(),
+            &trusted_block(last_block.0)
+        )
+        .unwrap(),
+        last_block
+    );
+    assert_same_blocks(&source_reader, &target_reader, N_BLOCKS);
+}
+
+#[test]
+fn import_resumes_from_stored_blocks() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let partial_archive_dir = tempdir().unwrap();
+    export_snapshot(&source_reader, &chain_id(), BlockNumber(2), partial_archive_dir.path(), 2)
+        .unwrap();
+    let archive_dir = tempdir().unwrap();
+    export_snapshot(&source_reader, &chain_id(), BlockNumber(N_BLOCKS - 1), archive_dir.path(), 2)
+        .unwrap();
+
+    let ((target_reader, mut target_writer), _target_dir) = get_test_storage();
+    import_snapshot(&mut target_writer, &chain_id(), partial_archive_dir.path(), &trusted_block(2))
+        .unwrap();
+    assert_same_blocks(&source_reader, &target_reader, 3);
+
+    // The stored blocks are in the middle of a chunk of the full snapshot.
+    import_snapshot(
+        &mut target_writer,
+        &chain_id(),
+        archive_dir.path(),
+        &trusted_block(N_BLOCKS - 1),
+    )
+    .unwrap();
+    assert_same_blocks(&source_reader, &target_reader, N_BLOCKS);
+}
+
+#[test]
+fn import_verifies_checksums() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    let manifest = export_snapshot(
+        &source_reader,
+        &chain_id(),
+        BlockNumber(N_BLOCKS - 1),
+        archive_dir.path(),
+        2,
+    )
+    .unwrap();
+    let corrupted_chunk = &manifest.chunks[1];
+    let chunk_path = archive_dir.path().join(&corrupted_chunk.file_name);
+    let mut chunk = fs::read(&chunk_path).unwrap();
+    *chunk.last_mut().unwrap() ^= 1;
+    fs::write(&chunk_path, chunk).unwrap();
+
+    let ((target_reader, mut target_writer), _target_dir) = get_test_storage();
+    let err = import_snapshot(
+        &mut target_writer,
+        &chain_id(),
+        archive_dir.path(),
+        &trusted_block(N_BLOCKS - 1),
+    )
+    .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::ChecksumMismatch { file_name })
+            if file_name == corrupted_chunk.file_name
+    );
+    // The blocks of the chunks before the corrupted one were imported.
+    assert_same_blocks(&source_reader, &target_reader, corrupted_chunk.first_block.0);
+}
+
+#[test]
+fn import_rejects_tampered_body() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    let manifest = export_snapshot(
+        &source_reader,
+        &chain_id(),
+        BlockNumber(N_BLOCKS - 1),
+        archive_dir.path(),
+        2,
+    )
+    .unwrap();
+    // Swap the transaction hashes of the first block of the chunk.
+    let tampered_chunk = &manifest.chunks[1];
+    let chunk_path = archive_dir.path().join(&tampered_chunk.file_name);
+    let content = zstd::decode_all(fs::read(&chunk_path).unwrap().as_slice()).unwrap();
+    let mut blocks: Vec<serde_json::Value> = content
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    let transaction_hashes = blocks[0]["body"]["transaction_hashes"].as_array_mut().unwrap();
+    assert_ne!(transaction_hashes[0], transaction_hashes[1]);
+    transaction_hashes.swap(0, 1);
+    let mut tampered_content = Vec::new();
+    for block in &blocks {
+        serde_json::to_writer(&mut tampered_content, block).unwrap();
+        tampered_content.push(b'\n');
+    }
+    fs::write(&chunk_path, zstd::encode_all(tampered_content.as_slice(), 0).unwrap()).unwrap();
+
+    // The body doesn't match the checksum of the chunk in the manifest.
+    let ((target_reader, mut target_writer), _target_dir) = get_test_storage();
+    let err = import_snapshot(
+        &mut target_writer,
+        &chain_id(),
+        archive_dir.path(),
+        &trusted_block(N_BLOCKS - 1),
+    )
+    .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::ChecksumMismatch { file_name })
+            if file_name == tampered_chunk.file_name
+    );
+    assert_same_blocks(&source_reader, &target_reader, tampered_chunk.first_block.0);
+}
+
+#[test]
+fn import_verifies_stored_blocks() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    export_snapshot(&source_reader, &chain_id(), BlockNumber(N_BLOCKS - 1), archive_dir.path(), 2)
+        .unwrap();
+
+    let ((_target_reader, mut target_writer), _target_dir) = get_test_storage();
+    let other_header = BlockHeader { block_hash: BlockHash(Felt::ONE), ..Default::default() };
+    target_writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(BlockNumber(0), &other_header)
+        .unwrap()
+        .append_body(BlockNumber(0), BlockBody::default())
+        .unwrap()
+        .append_state_diff(BlockNumber(0), ThinStateDiff::default())
+        .unwrap()
+        .append_classes(BlockNumber(0), &[], &[])
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let err = import_snapshot(
+        &mut target_writer,
+        &chain_id(),
+        archive_dir.path(),
+        &trusted_block(N_BLOCKS - 1),
+    )
+    .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::BlockHashMismatch {
+            block_number: BlockNumber(0),
+            expected,
+            found,
+        }) if expected == block_hash(0) && found == other_header.block_hash
+    );
+}
+
+#[test]
+fn import_requires_trusted_last_block() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    export_snapshot(&source_reader, &chain_id(), BlockNumber(N_BLOCKS - 1), archive_dir.path(), 2)
+        .unwrap();
+
+    // E.g. a snapshot whose blocks, and so their hashes, were tampered with.
+    let ((target_reader, mut target_writer), _target_dir) = get_test_storage();
+    let trusted_last_block =
+        TrustedBlock { block_hash: BlockHash(Felt::ONE), ..trusted_block(N_BLOCKS - 1) };
+    let err =
+        import_snapshot(&mut target_writer, &chain_id(), archive_dir.path(), &trusted_last_block)
+            .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::UntrustedLastBlock { expected, found })
+            if expected == trusted_last_block && found == trusted_block(N_BLOCKS - 1)
+    );
+    // Nothing was imported.
+    assert_same_blocks(&source_reader, &target_reader, 0);
+}
+
+#[test]
+fn import_rejects_other_chain() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    export_snapshot(&source_reader, &chain_id(), BlockNumber(0), archive_dir.path(), 1).unwrap();
+
+    let ((_target_reader, mut target_writer), _target_dir) = get_test_storage();
+    let other_chain_id = ChainId::Other("SN_OTHER".to_owned());
+    let err =
+        import_snapshot(&mut target_writer, &other_chain_id, archive_dir.path(), &trusted_block(0))
+            .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::ChainIdMismatch { snapshot_chain_id, chain_id })
+            if snapshot_chain_id == self::chain_id() && chain_id == other_chain_id
+    );
+}
+
+#[test]
+fn export_requires_stored_blocks_and_full_archive() {
+    let ((source_reader, _source_writer), _source_dir) = get_source_storage();
+    let archive_dir = tempdir().unwrap();
+    let err =
+        export_snapshot(&source_reader, &chain_id(), BlockNumber(N_BLOCKS), archive_dir.path(), 1)
+            .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::BlockNotAvailable { block_number, marker })
+            if block_number == BlockNumber(N_BLOCKS) && marker == BlockNumber(N_BLOCKS)
+    );
+
+    let (config, _temp_dir) = get_test_config(Some(StorageScope::StateOnly));
+    let (state_only_reader, _state_only_writer) = open_storage(config).unwrap();
+    let err =
+        export_snapshot(&state_only_reader, &chain_id(), BlockNumber(0), archive_dir.path(), 1)
+            .unwrap_err();
+    assert_matches!(
+        err,
+        StorageError::SnapshotError(SnapshotError::UnsupportedScope(StorageScope::StateOnly))
+    );
+}
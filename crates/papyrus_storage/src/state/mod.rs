@@ -51,6 +51,7 @@
 
 #[doc(hidden)]
 pub mod data;
+pub mod pruning;
 #[cfg(test)]
 mod state_test;
 
@@ -127,6 +128,9 @@ pub(crate) type NoncesTable<'env> =
 pub trait StateStorageReader<Mode: TransactionKind> {
     /// The state marker is the first block number that doesn't exist yet.
     fn get_state_marker(&self) -> StorageResult<BlockNumber>;
+    /// The pruned state marker is the first block number whose state history is fully kept. The
+    /// states before it were pruned and can't be read.
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber>;
     /// Returns the state diff at a given block number.
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>>;
     /// Returns a state reader.
@@ -165,6 +169,12 @@ impl<'env, Mode: TransactionKind> StateStorageReader<Mode> for StorageTxn<'env,
         let markers_table = self.open_table(&self.tables.markers)?;
         Ok(markers_table.get(&self.txn, &MarkerKind::State)?.unwrap_or_default())
     }
+
+    fn get_pruned_state_marker(&self) -> StorageResult<BlockNumber> {
+        let markers_table = self.open_table(&self.tables.markers)?;
+        Ok(markers_table.get(&self.txn, &MarkerKind::PrunedState)?.unwrap_or_default())
+    }
+
     fn get_state_diff(&self, block_number: BlockNumber) -> StorageResult<Option<ThinStateDiff>> {
         let state_diffs_table = self.open_table(&self.tables.state_diffs)?;
         let state_diff_location = state_diffs_table.get(&self.txn, &block_number)?;
@@ -193,6 +203,7 @@ pub struct StateReader<'env, Mode: TransactionKind> {
     nonces_table: NoncesTable<'env>,
     storage_table: ContractStorageTable<'env>,
     markers_table: MarkersTable<'env>,
+    pruned_state_marker: BlockNumber,
     file_handlers: &'env FileHandlers<Mode>,
 }
 
@@ -216,6 +227,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         let nonces_table = txn.txn.open_table(&txn.tables.nonces)?;
         let storage_table = txn.txn.open_table(&txn.tables.contract_storage)?;
         let markers_table = txn.txn.open_table(&txn.tables.markers)?;
+        let pruned_state_marker =
+            markers_table.get(&txn.txn, &MarkerKind::PrunedState)?.unwrap_or_default();
         Ok(StateReader {
             txn: &txn.txn,
             declared_classes_table,
@@ -225,10 +238,22 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
             nonces_table,
             storage_table,
             markers_table,
+            pruned_state_marker,
             file_handlers: &txn.file_handlers,
         })
     }
 
+    // Returns an error if the history of the given state was pruned.
+    fn verify_state_not_pruned(&self, state_number: StateNumber) -> StorageResult<()> {
+        if state_number.0 < self.pruned_state_marker {
+            return Err(StorageError::StatePruned {
+                state_number,
+                pruned_state_marker: self.pruned_state_marker,
+            });
+        }
+        Ok(())
+    }
+
     /// Returns the class hash at a given state number.
     /// If class hash is not found, returns `None`.
     ///
@@ -238,6 +263,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StatePruned if the state at the given state number was pruned.
     pub fn get_class_hash_at(
         &self,
         state_number: StateNumber,
@@ -246,6 +273,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
         // TODO(dvir): create an attribute instead of this.
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetClassHashAt(state_number, *address));
+        self.verify_state_not_pruned(state_number)?;
 
         let first_irrelevant_block: BlockNumber = state_number.block_after();
         let db_key = (*address, first_irrelevant_block);
@@ -269,6 +297,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StatePruned if the state at the given state number was pruned.
     pub fn get_nonce_at(
         &self,
         state_number: StateNumber,
@@ -276,6 +306,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ) -> StorageResult<Option<Nonce>> {
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetNonceAt(state_number, *address));
+        self.verify_state_not_pruned(state_number)?;
 
         // State diff updates are indexed by the block_number at which they occurred.
         let first_irrelevant_block: BlockNumber = state_number.block_after();
@@ -309,6 +340,8 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ///
     /// # Errors
     /// Returns [`StorageError`] if there was an error searching the table.
+    ///
+    /// Returns [`StorageError`]::StatePruned if the state at the given state number was pruned.
     pub fn get_storage_at(
         &self,
         state_number: StateNumber,
@@ -317,6 +350,7 @@ impl<'env, Mode: TransactionKind> StateReader<'env, Mode> {
     ) -> StorageResult<Felt> {
         #[cfg(feature = "document_calls")]
         add_query(StorageQuery::GetStorageAt(state_number, *address, *key));
+        self.verify_state_not_pruned(state_number)?;
 
         // The updates to the storage key are indexed by the block_number at which they occurred.
         let first_irrelevant_block: BlockNumber = state_number.block_after();
@@ -516,6 +550,16 @@ impl<'env> StateStorageWriter for StorageTxn<'env, RW> {
             return Ok((self, None));
         };
 
+        // Reverting the block exposes the state before it, which is unavailable if it was pruned.
+        let pruned_state_marker =
+            markers_table.get(&self.txn, &MarkerKind::PrunedState)?.unwrap_or_default();
+        if block_number < pruned_state_marker {
+            return Err(StorageError::StatePruned {
+                state_number: StateNumber::right_before_block(block_number),
+                pruned_state_marker,
+            });
+        }
+
         let thin_state_diff = self
             .get_state_diff(block_number)?
             .unwrap_or_else(|| panic!("Missing state diff for block {block_number}."));
@@ -0,0 +1,152 @@
+//! Compaction of old state history for storages with the [`StorageScope::Pruned`] scope.
+//!
+//! The state tables keep an entry for every block in which a value changed. Pruning removes the
+//! entries that were superseded by a later change in a block older than the last
+//! [`StorageConfig::state_history_blocks`] blocks. The state at any block number from the pruned
+//! state marker on stays intact, while the states before it can no longer be read.
+//!
+//! [`StorageScope::Pruned`]: crate::StorageScope::Pruned
+//! [`StorageConfig::state_history_blocks`]: crate::StorageConfig::state_history_blocks
+
+#[cfg(test)]
+#[path = "pruning_test.rs"]
+mod pruning_test;
+
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_api::state::{StorageKey, ThinStateDiff};
+use tracing::debug;
+
+use crate::db::table_types::{DbCursorTrait, Table};
+use crate::db::{DbTransaction, RW};
+use crate::state::{ContractStorageTable, DeployedContractsTable, NoncesTable, StateStorageReader};
+use crate::{MarkerKind, StorageResult, StorageWriter};
+
+/// Compacts the state history of the storage. Returned by [`StorageWriter::state_pruner`].
+pub struct StatePruner {
+    writer: StorageWriter,
+    state_history_blocks: u64,
+}
+
+impl StatePruner {
+    pub(crate) fn new(writer: StorageWriter, state_history_blocks: u64) -> Self {
+        Self { writer, state_history_blocks }
+    }
+
+    /// Compacts the history of at most `max_blocks` blocks that are older than the last
+    /// `state_history_blocks` blocks, in a single transaction. Returns the number of compacted
+    /// blocks.
+    pub fn prune(&mut self, max_blocks: u64) -> StorageResult<u64> {
+        let txn = self.writer.begin_rw_txn()?;
+        let pruned_state_marker = txn.get_pruned_state_marker()?;
+        let target_marker = BlockNumber(
+            txn.get_state_marker()?
+                .0
+                .saturating_sub(self.state_history_blocks)
+                .min(pruned_state_marker.0.saturating_add(max_blocks)),
+        );
+        if target_marker <= pruned_state_marker {
+            return Ok(0);
+        }
+        debug!("Pruning the state history of blocks [{pruned_state_marker}, {target_marker}).");
+
+        let markers_table = txn.open_table(&txn.tables.markers)?;
+        let deployed_contracts_table = txn.open_table(&txn.tables.deployed_contracts)?;
+        let nonces_table = txn.open_table(&txn.tables.nonces)?;
+        let storage_table = txn.open_table(&txn.tables.contract_storage)?;
+
+        for block_number in pruned_state_marker.iter_up_to(target_marker) {
+            // Blocks below the state marker always have a state diff.
+            let Some(thin_state_diff) = txn.get_state_diff(block_number)? else {
+                continue;
+            };
+            compact_deployed_contracts(
+                &txn.txn,
+                block_number,
+                &thin_state_diff,
+                &deployed_contracts_table,
+            )?;
+            compact_nonces(&txn.txn, block_number, &thin_state_diff, &nonces_table)?;
+            compact_storage_diffs(&txn.txn, block_number, &thin_state_diff, &storage_table)?;
+        }
+
+        markers_table.upsert(&txn.txn, &MarkerKind::PrunedState, &target_marker)?;
+        txn.commit()?;
+        Ok(target_marker.0 - pruned_state_marker.0)
+    }
+}
+
+// Each of the following functions deletes, for every key changed at `block_number`, the previous
+// change of that key, which is no longer needed once the states before `block_number` are pruned.
+
+fn compact_deployed_contracts<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    deployed_contracts_table: &'env DeployedContractsTable<'env>,
+) -> StorageResult<()> {
+    for address in
+        thin_state_diff.deployed_contracts.keys().chain(thin_state_diff.replaced_classes.keys())
+    {
+        let mut cursor = deployed_contracts_table.cursor(txn)?;
+        if cursor.lower_bound(&(*address, block_number))?.map(|(key, _)| key)
+            != Some((*address, block_number))
+        {
+            continue;
+        }
+        if let Some(((prev_address, prev_block_number), _)) = cursor.prev()? {
+            if prev_address == *address {
+                deployed_contracts_table.delete(txn, &(*address, prev_block_number))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compact_nonces<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    nonces_table: &'env NoncesTable<'env>,
+) -> StorageResult<()> {
+    // Deploying a contract also writes its nonce.
+    for address in thin_state_diff.nonces.keys().chain(thin_state_diff.deployed_contracts.keys()) {
+        let mut cursor = nonces_table.cursor(txn)?;
+        if cursor.lower_bound(&(*address, block_number))?.map(|(key, _)| key)
+            != Some((*address, block_number))
+        {
+            continue;
+        }
+        if let Some(((prev_address, prev_block_number), _)) = cursor.prev()? {
+            if prev_address == *address {
+                nonces_table.delete(txn, &(*address, prev_block_number))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compact_storage_diffs<'env>(
+    txn: &'env DbTransaction<'env, RW>,
+    block_number: BlockNumber,
+    thin_state_diff: &ThinStateDiff,
+    storage_table: &'env ContractStorageTable<'env>,
+) -> StorageResult<()> {
+    for (address, storage_entries) in &thin_state_diff.storage_diffs {
+        for key in storage_entries.keys() {
+            let storage_key: (ContractAddress, StorageKey) = (*address, *key);
+            let mut cursor = storage_table.cursor(txn)?;
+            if cursor.lower_bound(&(storage_key, block_number))?.map(|(key, _)| key)
+                != Some((storage_key, block_number))
+            {
+                continue;
+            }
+            if let Some(((prev_storage_key, prev_block_number), _)) = cursor.prev()? {
+                if prev_storage_key == storage_key {
+                    storage_table.delete(txn, &(storage_key, prev_block_number))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use assert_matches::assert_matches;
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::hash::StarkHash;
+use starknet_api::state::{StateNumber, StorageKey, ThinStateDiff};
+use starknet_api::{felt, patricia_key};
+use starknet_types_core::felt::Felt;
+use tempfile::TempDir;
+
+use crate::db::table_types::Table;
+use crate::state::{StateStorageReader, StateStorageWriter};
+use crate::test_utils::{get_test_config, get_test_storage};
+use crate::{
+    open_storage,
+    StorageConfig,
+    StorageError,
+    StorageReader,
+    StorageScope,
+    StorageVersionError,
+    StorageWriter,
+};
+
+const STATE_HISTORY_BLOCKS: u64 = 2;
+const N_BLOCKS: u64 = 5;
+
+fn contract_address() -> ContractAddress {
+    ContractAddress(patricia_key!("0x11"))
+}
+
+fn storage_key() -> StorageKey {
+    StorageKey(patricia_key!("0x22"))
+}
+
+// The class hash, nonce and storage value of the contract after the given block.
+fn class_hash_at(block_number: u64) -> ClassHash {
+    ClassHash(Felt::from(block_number / 2 * 2 + 1))
+}
+
+fn nonce_at(block_number: u64) -> Nonce {
+    Nonce(StarkHash::from(block_number + 1))
+}
+
+fn storage_value_at(block_number: u64) -> Felt {
+    Felt::from(block_number + 100)
+}
+
+// Returns a pruned storage with N_BLOCKS blocks, each of them changing the state of the same
+// contract. The class of the contract is replaced every second block.
+fn get_pruned_test_storage() -> ((StorageReader, StorageWriter), TempDir) {
+    let (mut config, temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    config.state_history_blocks = STATE_HISTORY_BLOCKS;
+    let (reader, mut writer) = open_storage(config).unwrap();
+
+    for block_number in 0..N_BLOCKS {
+        let class_change = IndexMap::from([(contract_address(), class_hash_at(block_number))]);
+        let (deployed_contracts, replaced_classes) = match block_number {
+            0 => (class_change, IndexMap::new()),
+            _ if block_number % 2 == 0 => (IndexMap::new(), class_change),
+            _ => (IndexMap::new(), IndexMap::new()),
+        };
+        let state_diff = ThinStateDiff {
+            deployed_contracts,
+            storage_diffs: IndexMap::from([(
+                contract_address(),
+                IndexMap::from([(storage_key(), storage_value_at(block_number))]),
+            )]),
+            nonces: IndexMap::from([(contract_address(), nonce_at(block_number))]),
+            replaced_classes,
+            ..Default::default()
+        };
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(block_number), state_diff)
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    ((reader, writer), temp_dir)
+}
+
+#[test]
+fn state_pruner_exists_only_for_pruned_scope() {
+    let ((_reader, writer), _temp_dir) = get_test_storage();
+    assert!(writer.state_pruner().is_none());
+
+    let (config, _temp_dir) = get_test_config(Some(StorageScope::StateOnly));
+    let (_reader, writer) = open_storage(config).unwrap();
+    assert!(writer.state_pruner().is_none());
+
+    let (config, _temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    let (_reader, writer) = open_storage(config).unwrap();
+    assert!(writer.state_pruner().is_some());
+}
+
+#[test]
+fn prune_keeps_recent_states() {
+    let ((reader, writer), _temp_dir) = get_pruned_test_storage();
+    let mut state_pruner = writer.state_pruner().unwrap();
+
+    let expected_pruned_state_marker = BlockNumber(N_BLOCKS - STATE_HISTORY_BLOCKS);
+    assert_eq!(state_pruner.prune(u64::MAX).unwrap(), expected_pruned_state_marker.0);
+    assert_eq!(state_pruner.prune(u64::MAX).unwrap(), 0);
+
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(txn.get_pruned_state_marker().unwrap(), expected_pruned_state_marker);
+    let state_reader = txn.get_state_reader().unwrap();
+    for block_number in expected_pruned_state_marker.0..N_BLOCKS {
+        let state_number = StateNumber::unchecked_right_after_block(BlockNumber(block_number));
+        assert_eq!(
+            state_reader.get_class_hash_at(state_number, &contract_address()).unwrap(),
+            Some(class_hash_at(block_number))
+        );
+        assert_eq!(
+            state_reader.get_nonce_at(state_number, &contract_address()).unwrap(),
+            Some(nonce_at(block_number))
+        );
+        assert_eq!(
+            state_reader.get_storage_at(state_number, &contract_address(), &storage_key()).unwrap(),
+            storage_value_at(block_number)
+        );
+    }
+
+    // The changes that are superseded before the pruned state marker were deleted.
+    let storage_table = txn.open_table(&txn.tables.contract_storage).unwrap();
+    for block_number in 0..expected_pruned_state_marker.0 - 1 {
+        let key = ((contract_address(), storage_key()), BlockNumber(block_number));
+        assert_eq!(storage_table.get(&txn.txn, &key).unwrap(), None);
+    }
+}
+
+#[test]
+fn prune_at_most_max_blocks() {
+    let ((reader, writer), _temp_dir) = get_pruned_test_storage();
+    let mut state_pruner = writer.state_pruner().unwrap();
+
+    assert_eq!(state_pruner.prune(1).unwrap(), 1);
+    assert_eq!(reader.begin_ro_txn().unwrap().get_pruned_state_marker().unwrap(), BlockNumber(1));
+    assert_eq!(state_pruner.prune(N_BLOCKS).unwrap(), N_BLOCKS - STATE_HISTORY_BLOCKS - 1);
+}
+
+#[test]
+fn read_pruned_state_fails() {
+    let ((reader, writer), _temp_dir) = get_pruned_test_storage();
+    writer.state_pruner().unwrap().prune(u64::MAX).unwrap();
+    let pruned_state_marker = BlockNumber(N_BLOCKS - STATE_HISTORY_BLOCKS);
+    let state_number = StateNumber::right_before_block(pruned_state_marker.prev().unwrap());
+
+    let txn = reader.begin_ro_txn().unwrap();
+    let state_reader = txn.get_state_reader().unwrap();
+    assert_matches!(
+        state_reader.get_class_hash_at(state_number, &contract_address()),
+        Err(StorageError::StatePruned {
+            state_number: got_state_number,
+            pruned_state_marker: got_pruned_state_marker,
+        }) if got_state_number == state_number && got_pruned_state_marker == pruned_state_marker
+    );
+    assert_matches!(
+        state_reader.get_nonce_at(state_number, &contract_address()),
+        Err(StorageError::StatePruned { .. })
+    );
+    assert_matches!(
+        state_reader.get_storage_at(state_number, &contract_address(), &storage_key()),
+        Err(StorageError::StatePruned { .. })
+    );
+}
+
+#[test]
+fn revert_pruned_block_fails() {
+    let ((_reader, mut writer), _temp_dir) = get_pruned_test_storage();
+    writer.state_pruner().unwrap().prune(u64::MAX).unwrap();
+    let pruned_state_marker = BlockNumber(N_BLOCKS - STATE_HISTORY_BLOCKS);
+
+    // Reverting the blocks from the pruned state marker on exposes states that were kept.
+    for block_number in (pruned_state_marker.0..N_BLOCKS).rev() {
+        let (txn, reverted) =
+            writer.begin_rw_txn().unwrap().revert_state_diff(BlockNumber(block_number)).unwrap();
+        assert!(reverted.is_some());
+        txn.commit().unwrap();
+    }
+
+    let block_number = pruned_state_marker.prev().unwrap();
+    let Err(err) = writer.begin_rw_txn().unwrap().revert_state_diff(block_number) else {
+        panic!("Reverting a block before the pruned state marker should fail.");
+    };
+    assert_matches!(
+        err,
+        StorageError::StatePruned { state_number, .. }
+            if state_number == StateNumber::right_before_block(block_number)
+    );
+}
+
+#[test]
+fn pruned_storage_cannot_change_scope() {
+    let (mut config, _temp_dir) = get_test_config(Some(StorageScope::Pruned));
+    config.state_history_blocks = 1;
+    let (reader, mut writer) = open_storage(config.clone()).unwrap();
+    for block_number in 0..2 {
+        writer
+            .begin_rw_txn()
+            .unwrap()
+            .append_state_diff(BlockNumber(block_number), ThinStateDiff::default())
+            .unwrap()
+            .commit()
+            .unwrap();
+    }
+    writer.state_pruner().unwrap().prune(u64::MAX).unwrap();
+    drop((reader, writer));
+
+    for scope in [StorageScope::StateOnly, StorageScope::FullArchive] {
+        let Err(err) = open_storage(StorageConfig { scope, ..config.clone() }) else {
+            panic!("Opening a pruned storage with scope {scope:?} should fail.");
+        };
+        assert_matches!(
+            err,
+            StorageError::StorageVersionInconsistency(
+                StorageVersionError::InconsistentStorageScope
+            )
+        );
+    }
+    assert!(open_storage(config).is_ok());
+}
@@ -60,6 +60,7 @@ auto_impl_get_test_instance! {
         Class = 4,
         CompiledClass = 5,
         BaseLayerBlock = 6,
+        PrunedState = 7,
     }
     pub enum OffsetKind {
         ThinStateDiff = 0,
@@ -30,6 +30,9 @@ pub enum StorageVersionError {
     )]
     InconsistentStorageScope,
 
+    #[error("The storage has no version, it was never initialized.")]
+    MissingStorageVersion,
+
     #[error(
         "Trying to set a DB minor version {crate_version:} which is not higher that the existing \
          one {storage_version}."
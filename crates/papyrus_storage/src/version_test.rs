@@ -1,8 +1,19 @@
+use std::collections::HashSet;
+
 use assert_matches::assert_matches;
+use papyrus_test_utils::get_test_block;
 use pretty_assertions::assert_eq;
 use rand::Rng;
+use starknet_api::transaction::{
+    EventIndexInTransactionOutput,
+    EventKey,
+    TransactionOffsetInBlock,
+};
 
+use crate::body::events::{EventIndex, EventsReader};
+use crate::body::{BodyStorageWriter, TransactionIndex};
 use crate::db::table_types::Table;
+use crate::header::HeaderStorageWriter;
 use crate::test_utils::{
     get_test_storage,
     get_test_storage_by_scope,
@@ -115,6 +126,72 @@ fn version_migration() {
     assert_eq!(version_blocks.unwrap(), STORAGE_VERSION_BLOCKS);
 }
 
+#[test]
+fn version_migration_indexes_events_by_first_keys() {
+    let ((reader, mut writer), config, _temp_dir) =
+        get_test_storage_with_config_by_scope(StorageScope::FullArchive);
+    let keys = vec![vec![EventKey(1u32.into())], vec![EventKey(2u32.into())]];
+    let block = get_test_block(4, Some(3), None, Some(keys));
+    let block_number = block.header.block_number;
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_header(block_number, &block.header)
+        .unwrap()
+        .append_body(block_number, block.body.clone())
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    // Remove the index of the events by their first keys, as in a storage of blocks version 2.0.
+    let wtxn = writer.begin_rw_txn().unwrap();
+    let event_first_keys_table = wtxn.open_table(&wtxn.tables.event_first_keys).unwrap();
+    let mut first_keys = HashSet::new();
+    for (offset, tx_output) in block.body.transaction_outputs.iter().enumerate() {
+        let transaction_index = TransactionIndex(block_number, TransactionOffsetInBlock(offset));
+        for event in tx_output.events() {
+            let first_key = event.content.keys[0].clone();
+            event_first_keys_table
+                .delete(&wtxn.txn, &(first_key.clone(), transaction_index))
+                .unwrap();
+            first_keys.insert(first_key);
+        }
+    }
+    drop(event_first_keys_table);
+    wtxn.commit().unwrap();
+    change_storage_version(
+        &mut writer,
+        VERSION_BLOCKS_KEY,
+        &Version { major: STORAGE_VERSION_BLOCKS.major, minor: 0 },
+    );
+    let event_index = EventIndex(
+        TransactionIndex(block_number, TransactionOffsetInBlock(0)),
+        EventIndexInTransactionOutput(0),
+    );
+    let txn = reader.begin_ro_txn().unwrap();
+    assert_eq!(
+        txn.iter_events(None, Some(&first_keys), event_index, block_number).unwrap().count(),
+        0
+    );
+    drop(txn);
+    drop(reader);
+    drop(writer);
+
+    // Reopening the storage fills the index, and the events are found by their first keys again.
+    let (reader, _) = open_storage(config).unwrap();
+    let txn = reader.begin_ro_txn().unwrap();
+    let all_events =
+        txn.iter_events(None, None, event_index, block_number).unwrap().collect::<Vec<_>>();
+    assert!(!all_events.is_empty());
+    assert_eq!(
+        txn.iter_events(None, Some(&first_keys), event_index, block_number)
+            .unwrap()
+            .collect::<Vec<_>>(),
+        all_events
+    );
+    assert_eq!(txn.get_blocks_version().unwrap().unwrap(), STORAGE_VERSION_BLOCKS);
+}
+
 #[test]
 fn open_storage_full_archive_different_state_major_versions() {
     let ((reader, mut writer), config, _temp_dir) =
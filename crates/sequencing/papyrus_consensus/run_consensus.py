@@ -7,6 +7,7 @@ import tempfile
 import socket
 from contextlib import closing
 import fcntl
+import json
 
 # The SECRET_KEY is used for building the BOOT_NODE_PEER_ID, so they are coupled and must be used together.
 SECRET_KEY = "0xabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcd"
@@ -14,6 +15,26 @@ BOOT_NODE_PEER_ID = "12D3KooWDFYi71juk6dYWo3UDvqs5gAzGDc124LSvcR5d187Tdvi"
 
 MONITORING_PERIOD = 10
 
+# The private key of validator i is i + 1, and PUBLIC_KEYS[i] is its public key.
+PUBLIC_KEYS = [
+    "0x1ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca",
+    "0x759ca09377679ecd535a81e83039658bf40959283187c654c5416f439403cf5",
+    "0x411494b501a98abd8262b0da1351e17899a0c4ef23dd2f96fec5ba847310b20",
+    "0xa7da05a4d664859ccd6e567b935cdfbfe3018c7771cb980892ef38878ae9bc",
+    "0x788435d61046d3eec54d77d25bd194525f4fa26ebe6575536bc6f656656b74c",
+    "0x1efc3d7c9649900fcbd03f578a8248d095bc4b6a13b3c25f9886ef971ff96fa",
+    "0x743829e0a179f8afe223fc8112dfc8d024ab6b235fd42283c4f5970259ce7b7",
+    "0x6eeee2b0c71d681692559735e08a2c3ba04e7347c0c18d4d49b83bb89771591",
+    "0x216b4f076ff47e03a05032d1c6ee17933d8de8b2b4c43eb5ad5a7e1b25d3849",
+    "0x320ceae3120e56f6006f7d626760f12fc276a3c7683e9b0b87c097d7be8dbde",
+    "0x408f052dfe0289ab18a69ffdcb38a303fb0766979dca58262a9fabe4a0c7632",
+    "0x66276b22edf076517b8fa9287280242555afda9ed00e78eedc9f99be8542aa3",
+    "0x55b1d8ab7fa62903691a92eecbead7205f512fc27ce1ec2db7120643585b23d",
+    "0x3ed4a45432b30fb5f765be330e5d5766d54e78c24f50a804f998cb6b043bb4c",
+    "0x64b098ab256881bb3916f719b8c1e362b9c681446d454773d513e647f0b148d",
+    "0xb582a82e6c8ad99e38fcbd2a4da97b37d0cdb7d776edb84a661d79ec4824ac",
+]
+
 
 class Node:
     def __init__(self, validator_id, monitoring_gateway_server_port, cmd):
@@ -118,7 +139,19 @@ def run_simulation(nodes, duration, stagnation_timeout):
             node.stop()
 
 
-def build_node(base_layer_node_url, data_dir, logs_dir, num_validators, i):
+# Writes a validator set of the given size, with equal voting power, and returns its path.
+def write_validator_set(logs_dir, num_validators):
+    validators = [
+        {"id": hex(i), "voting_power": 1, "public_key": PUBLIC_KEYS[i]}
+        for i in range(num_validators)
+    ]
+    path = os.path.join(logs_dir, "validator_sets.json")
+    with open(path, "w") as f:
+        json.dump([{"start_height": 0, "validators": validators}], f, indent=2)
+    return path
+
+
+def build_node(base_layer_node_url, data_dir, logs_dir, validator_set_path, i):
     is_bootstrap = i == 1
     tcp_port = BOOTNODE_TCP_PORT if is_bootstrap else find_free_port()
     monitoring_gateway_server_port = find_free_port()
@@ -130,7 +163,10 @@ def build_node(base_layer_node_url, data_dir, logs_dir, num_validators, i):
         f"--base_layer.node_url {base_layer_node_url} "
         f"--storage.db_config.path_prefix {data_dir} "
         f"--consensus.#is_none false --consensus.validator_id 0x{i} "
-        f"--consensus.num_validators {num_validators} "
+        f"--consensus.private_key {hex(i + 1)} "
+        f"--consensus.validator_set.file_path {validator_set_path} "
+        f"--consensus.wal_dir {os.path.join(data_dir, 'consensus_wal')} "
+        f"--consensus.evidence_path {os.path.join(data_dir, 'consensus_evidence.jsonl')} "
         f"--network.tcp_port {tcp_port} "
         f"--rpc.server_address 127.0.0.1:{find_free_port()} "
         f"--monitoring_gateway.server_address 127.0.0.1:{monitoring_gateway_server_port} "
@@ -158,6 +194,8 @@ def build_node(base_layer_node_url, data_dir, logs_dir, num_validators, i):
 
 
 def build_all_nodes(base_layer_node_url, data_dir, logs_dir, num_validators):
+    validator_set_path = write_validator_set(logs_dir, num_validators)
+
     # Validators are started in a specific order to ensure proper network formation:
     # 1. The bootnode (validator 1) is started first for network peering.
     # 2. Validators 2+ are started next to join the network through the bootnode.
@@ -165,18 +203,21 @@ def build_all_nodes(base_layer_node_url, data_dir, logs_dir, num_validators):
 
     nodes = []
 
-    nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, num_validators, 1))  # Bootstrap
+    nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, validator_set_path, 1))  # Bootstrap
 
     for i in range(2, num_validators):
-        nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, num_validators, i))
+        nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, validator_set_path, i))
 
-    nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, num_validators, 0))  # Proposer
+    nodes.append(build_node(base_layer_node_url, data_dir, logs_dir, validator_set_path, 0))  # Proposer
 
     return nodes
 
 
 def main(base_layer_node_url, num_validators, db_dir, stagnation_threshold, duration):
     assert num_validators >= 2, "At least 2 validators are required for the simulation."
+    assert num_validators <= len(
+        PUBLIC_KEYS
+    ), f"At most {len(PUBLIC_KEYS)} validators are supported by the simulation."
 
     logs_dir = tempfile.mkdtemp()
 
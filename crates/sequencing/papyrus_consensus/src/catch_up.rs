@@ -0,0 +1,63 @@
+//! Detection of a node which fell behind the other validators, e.g. after being disconnected. Such
+//! a node cannot take part in the consensus of its height, since the other validators already
+//! decided it, so instead it catches up on the decided blocks through the sync.
+
+#[cfg(test)]
+#[path = "catch_up_test.rs"]
+mod catch_up_test;
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use papyrus_protobuf::consensus::ConsensusMessage;
+use starknet_api::block::BlockNumber;
+
+use crate::types::{ValidatorId, VotingPower};
+use crate::validator_set::ValidatorSet;
+
+/// The highest height at which each validator sent a message.
+#[derive(Debug, Default)]
+pub(crate) struct PeerHeights {
+    heights: HashMap<ValidatorId, BlockNumber>,
+}
+
+impl PeerHeights {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the height of a message, whose signature the caller verified.
+    pub(crate) fn observe(&mut self, message: &ConsensusMessage) {
+        let height = BlockNumber(message.height());
+        let highest = self.heights.entry(message.sender()).or_insert(height);
+        *highest = (*highest).max(height);
+    }
+
+    /// The height to catch up to, if validators with a quorum of the voting power already sent
+    /// messages at heights above `height`. Every height below the highest height reached by such a
+    /// quorum was decided, since it includes honest validators which moved past it.
+    pub(crate) fn catch_up_height(
+        &self,
+        validators: &ValidatorSet,
+        height: BlockNumber,
+    ) -> Option<BlockNumber> {
+        let mut ahead: Vec<(BlockNumber, VotingPower)> = validators
+            .iter()
+            .filter_map(|validator| {
+                let peer_height = *self.heights.get(&validator.id)?;
+                (peer_height > height).then_some((peer_height, validator.voting_power))
+            })
+            .collect();
+        ahead.sort_unstable_by_key(|(peer_height, _)| Reverse(*peer_height));
+
+        let quorum = validators.quorum_size();
+        let mut voting_power = 0;
+        for (peer_height, validator_voting_power) in ahead {
+            voting_power += validator_voting_power;
+            if voting_power >= quorum {
+                return Some(peer_height);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,60 @@
+use papyrus_protobuf::consensus::{ConsensusMessage, Vote, VoteType};
+use starknet_api::block::BlockNumber;
+use starknet_api::crypto::utils::Signature;
+
+use super::PeerHeights;
+use crate::test_utils::test_validator_set;
+use crate::types::ValidatorId;
+use crate::validator_set::ValidatorSet;
+
+fn prevote(height: u64, voter: u64) -> ConsensusMessage {
+    ConsensusMessage::Vote(Vote {
+        vote_type: VoteType::Prevote,
+        height,
+        round: 0,
+        block_hash: None,
+        voter: voter.into(),
+        signature: Signature::default(),
+    })
+}
+
+// Any three of the validators have a quorum.
+fn validators() -> ValidatorSet {
+    test_validator_set([1_u64, 2, 3, 4].map(|id| (ValidatorId::from(id), 1)))
+}
+
+#[test]
+fn no_catch_up_without_a_quorum_ahead() {
+    let mut peer_heights = PeerHeights::new();
+    peer_heights.observe(&prevote(5, 1));
+    peer_heights.observe(&prevote(4, 2));
+    // Messages of the current height or of past heights don't show that the node is behind.
+    peer_heights.observe(&prevote(3, 3));
+    peer_heights.observe(&prevote(2, 4));
+
+    assert_eq!(peer_heights.catch_up_height(&validators(), BlockNumber(3)), None);
+}
+
+#[test]
+fn catch_up_to_the_height_reached_by_a_quorum() {
+    let mut peer_heights = PeerHeights::new();
+    peer_heights.observe(&prevote(9, 1));
+    peer_heights.observe(&prevote(8, 2));
+    peer_heights.observe(&prevote(7, 3));
+    peer_heights.observe(&prevote(4, 4));
+    // Only the highest height of each validator counts.
+    peer_heights.observe(&prevote(6, 2));
+
+    // Validators 1, 2 and 3 all moved past the heights below 7.
+    assert_eq!(peer_heights.catch_up_height(&validators(), BlockNumber(3)), Some(BlockNumber(7)));
+}
+
+#[test]
+fn unknown_validators_are_not_counted() {
+    let mut peer_heights = PeerHeights::new();
+    peer_heights.observe(&prevote(9, 1));
+    peer_heights.observe(&prevote(9, 2));
+    peer_heights.observe(&prevote(9, 5));
+
+    assert_eq!(peer_heights.catch_up_height(&validators(), BlockNumber(3)), None);
+}
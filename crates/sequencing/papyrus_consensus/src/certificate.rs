@@ -0,0 +1,81 @@
+//! Commit certificates, i.e. the precommits of a quorum of the validators of a height for a block,
+//! which prove that the block was decided.
+//!
+//! The decided blocks are written to storage by the sync, rather than by consensus. So consensus
+//! keeps the certificates of the heights it decided, or caught up on, until the sync writes their
+//! blocks, and checks the written blocks against them.
+
+#[cfg(test)]
+#[path = "certificate_test.rs"]
+mod certificate_test;
+
+use std::collections::{HashMap, HashSet};
+
+use papyrus_protobuf::consensus::{ConsensusMessage, Vote, VoteType};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::ChainId;
+
+use crate::signing::verify_message;
+use crate::types::{Round, ValidatorId, VotingPower};
+use crate::validator_set::ValidatorSet;
+
+/// The precommits of a quorum of the validators of a height for a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitCertificate {
+    pub height: BlockNumber,
+    pub block_hash: BlockHash,
+    pub precommits: Vec<Vote>,
+}
+
+#[derive(Default)]
+struct RoundPrecommits<'a> {
+    voters: HashSet<ValidatorId>,
+    voting_power: VotingPower,
+    precommits: Vec<&'a Vote>,
+}
+
+impl CommitCertificate {
+    /// Finds a certificate among the given votes, whose signatures are verified against the given
+    /// validators of the height. The votes of other heights, and the other votes, are ignored.
+    pub fn find<'a>(
+        height: BlockNumber,
+        votes: impl IntoIterator<Item = &'a Vote>,
+        validators: &ValidatorSet,
+        chain_id: &ChainId,
+    ) -> Option<Self> {
+        let quorum = validators.quorum_size();
+        // A quorum must precommit the same block in the same round.
+        let mut rounds: HashMap<(Round, BlockHash), RoundPrecommits<'a>> = HashMap::new();
+        for vote in votes {
+            if vote.vote_type != VoteType::Precommit || vote.height != height.0 {
+                continue;
+            }
+            let Some(block_hash) = vote.block_hash else {
+                continue;
+            };
+            let Some(validator) = validators.get(&vote.voter) else {
+                continue;
+            };
+            let round = rounds.entry((vote.round, block_hash)).or_default();
+            if round.voters.contains(&vote.voter)
+                || !verify_message(
+                    &ConsensusMessage::Vote(vote.clone()),
+                    &validator.public_key,
+                    chain_id,
+                )
+            {
+                continue;
+            }
+            round.voters.insert(vote.voter);
+            // The total voting power of a validator set doesn't overflow.
+            round.voting_power += validator.voting_power;
+            round.precommits.push(vote);
+            if round.voting_power >= quorum {
+                let precommits = round.precommits.iter().map(|vote| (*vote).clone()).collect();
+                return Some(Self { height, block_hash, precommits });
+            }
+        }
+        None
+    }
+}
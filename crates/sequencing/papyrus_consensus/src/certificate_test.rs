@@ -0,0 +1,75 @@
+use papyrus_protobuf::consensus::{Vote, VoteType};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::crypto::utils::Signature;
+use starknet_types_core::felt::Felt;
+
+use super::CommitCertificate;
+use crate::test_utils::{test_signer, test_validator_set, TEST_CHAIN_ID};
+use crate::types::ValidatorId;
+use crate::validator_set::ValidatorSet;
+
+const HEIGHT: BlockNumber = BlockNumber(1);
+
+fn precommit(voter: u32, round: u32, block_hash: Option<BlockHash>) -> Vote {
+    let voter: ValidatorId = voter.into();
+    test_signer(voter).sign_vote(Vote {
+        vote_type: VoteType::Precommit,
+        height: HEIGHT.0,
+        round,
+        block_hash,
+        voter,
+        signature: Signature::default(),
+    })
+}
+
+fn validators() -> ValidatorSet {
+    test_validator_set((1..=4_u32).map(|id| (id.into(), 1)))
+}
+
+#[test]
+fn quorum_of_precommits_is_a_certificate() {
+    let block_hash = Some(BlockHash(Felt::ONE));
+    let votes = vec![
+        precommit(1, 0, block_hash),
+        // Repeated votes are counted once.
+        precommit(1, 0, block_hash),
+        precommit(2, 0, None),
+        precommit(3, 0, block_hash),
+        precommit(4, 0, block_hash),
+    ];
+
+    let certificate = CommitCertificate::find(HEIGHT, &votes, &validators(), &TEST_CHAIN_ID);
+    assert_eq!(
+        certificate,
+        Some(CommitCertificate {
+            height: HEIGHT,
+            block_hash: BlockHash(Felt::ONE),
+            precommits: vec![votes[0].clone(), votes[3].clone(), votes[4].clone()],
+        })
+    );
+}
+
+#[test]
+fn precommits_without_a_quorum_are_not_a_certificate() {
+    let block_hash = Some(BlockHash(Felt::ONE));
+    let validators = validators();
+    let find = |votes: &[Vote]| CommitCertificate::find(HEIGHT, votes, &validators, &TEST_CHAIN_ID);
+
+    // The precommits of different rounds don't add up.
+    assert_eq!(
+        find(&[
+            precommit(1, 0, block_hash),
+            precommit(2, 0, block_hash),
+            precommit(3, 1, block_hash)
+        ]),
+        None
+    );
+    // Neither do precommits of another height, or with an invalid signature.
+    let other_height = Vote { height: HEIGHT.0 + 1, ..precommit(3, 0, block_hash) };
+    let forged = Vote { voter: 3_u32.into(), ..precommit(4, 0, block_hash) };
+    assert_eq!(
+        find(&[precommit(1, 0, block_hash), precommit(2, 0, block_hash), other_height]),
+        None
+    );
+    assert_eq!(find(&[precommit(1, 0, block_hash), precommit(2, 0, block_hash), forged]), None);
+}
@@ -1,44 +1,73 @@
 //! This module contains the configuration for consensus, including the `ConsensusConfig` struct
 //! and its implementation of the `SerializeConfig` trait. The configuration includes parameters
-//! such as the validator ID, the network topic of the consensus, and the starting block height.
+//! such as the validator ID and signing key, the network topics of the consensus and of the
+//! equivocation evidence, the starting block height, the source of the validator sets, where the
+//! write-ahead log and the evidence are kept, and the timeouts of the consensus steps.
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use papyrus_config::converters::deserialize_seconds_to_duration;
-use papyrus_config::dumping::{ser_param, ser_required_param, SerializeConfig};
+use papyrus_config::converters::{
+    deserialize_milliseconds_to_duration,
+    deserialize_seconds_to_duration,
+};
+use papyrus_config::dumping::{
+    append_sub_config_name,
+    ser_param,
+    ser_required_param,
+    SerializeConfig,
+};
 use papyrus_config::{ParamPath, ParamPrivacyInput, SerializationType, SerializedParam};
 use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_types_core::felt::Felt;
 
-use super::types::ValidatorId;
+use super::types::{Round, ValidatorId};
 
 /// Configuration for consensus.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ConsensusConfig {
     /// The validator ID of the node.
     pub validator_id: ValidatorId,
+    /// The Stark private key with which the node signs its consensus messages.
+    pub private_key: Felt,
     /// The network topic of the consensus.
     pub topic: String,
+    /// The network topic on which evidence of equivocation is gossiped.
+    pub evidence_topic: String,
     /// The height to start the consensus from.
     pub start_height: BlockNumber,
-    /// The number of validators in the consensus.
-    // Used for testing in an early milestones.
-    pub num_validators: u64,
+    /// Where the validators of each height, and their voting power, are read from.
+    pub validator_set: ValidatorSetConfig,
     /// The delay (seconds) before starting consensus to give time for network peering.
     #[serde(deserialize_with = "deserialize_seconds_to_duration")]
     pub consensus_delay: Duration,
+    /// The directory of the write-ahead log, from which the consensus state is restored on
+    /// restart.
+    pub wal_dir: PathBuf,
+    /// The file in which evidence of equivocation is kept.
+    pub evidence_path: PathBuf,
+    /// The timeouts of the consensus steps.
+    pub timeouts: TimeoutsConfig,
 }
 
 impl SerializeConfig for ConsensusConfig {
     fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
-        BTreeMap::from_iter([
+        let mut config = BTreeMap::from_iter([
             ser_required_param(
                 "validator_id",
                 SerializationType::String,
                 "The validator id of the node.",
                 ParamPrivacyInput::Public,
             ),
+            ser_required_param(
+                "private_key",
+                SerializationType::String,
+                "The Stark private key with which the node signs its consensus messages.",
+                ParamPrivacyInput::Private,
+            ),
             ser_param(
                 "topic",
                 &self.topic,
@@ -46,15 +75,15 @@ impl SerializeConfig for ConsensusConfig {
                 ParamPrivacyInput::Public,
             ),
             ser_param(
-                "start_height",
-                &self.start_height,
-                "The height to start the consensus from.",
+                "evidence_topic",
+                &self.evidence_topic,
+                "The topic on which evidence of equivocation is gossiped.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
-                "num_validators",
-                &self.num_validators,
-                "The number of validators in the consensus.",
+                "start_height",
+                &self.start_height,
+                "The height to start the consensus from.",
                 ParamPrivacyInput::Public,
             ),
             ser_param(
@@ -63,7 +92,23 @@ impl SerializeConfig for ConsensusConfig {
                 "Delay (seconds) before starting consensus to give time for network peering.",
                 ParamPrivacyInput::Public,
             ),
-        ])
+            ser_param(
+                "wal_dir",
+                &self.wal_dir,
+                "The directory of the write-ahead log, from which the consensus state is restored \
+                 on restart.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "evidence_path",
+                &self.evidence_path,
+                "The file in which evidence of equivocation is kept.",
+                ParamPrivacyInput::Public,
+            ),
+        ]);
+        config.extend(append_sub_config_name(self.validator_set.dump(), "validator_set"));
+        config.extend(append_sub_config_name(self.timeouts.dump(), "timeouts"));
+        config
     }
 }
 
@@ -71,10 +116,151 @@ impl Default for ConsensusConfig {
     fn default() -> Self {
         Self {
             validator_id: ValidatorId::default(),
+            private_key: Felt::default(),
             topic: "consensus".to_string(),
+            evidence_topic: "consensus_evidence".to_string(),
             start_height: BlockNumber::default(),
-            num_validators: 4,
+            validator_set: ValidatorSetConfig::default(),
             consensus_delay: Duration::from_secs(5),
+            wal_dir: PathBuf::from("./data/consensus_wal"),
+            evidence_path: PathBuf::from("./data/consensus_evidence.jsonl"),
+            timeouts: TimeoutsConfig::default(),
+        }
+    }
+}
+
+/// The source of the validator sets.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum ValidatorSetSource {
+    /// A file listing the validator sets and the heights from which they apply.
+    #[default]
+    File,
+    /// The storage of the staking contract, as synced by the node.
+    Storage,
+}
+
+/// Configuration of the validator sets of the consensus.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ValidatorSetConfig {
+    /// Where the validator sets are read from.
+    pub source: ValidatorSetSource,
+    /// The file of the validator sets, used when the source is `File`.
+    pub file_path: PathBuf,
+    /// The address of the staking contract, used when the source is `Storage`.
+    pub staking_contract_address: ContractAddress,
+}
+
+impl SerializeConfig for ValidatorSetConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "source",
+                &self.source,
+                "Where the validator sets are read from, either File or Storage.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "file_path",
+                &self.file_path,
+                "The file of the validator sets, used when the source is File.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "staking_contract_address",
+                &self.staking_contract_address,
+                "The address of the staking contract, used when the source is Storage.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
+
+impl Default for ValidatorSetConfig {
+    fn default() -> Self {
+        Self {
+            source: ValidatorSetSource::default(),
+            file_path: PathBuf::from("config/papyrus/consensus_validator_sets.json"),
+            staking_contract_address: ContractAddress::default(),
+        }
+    }
+}
+
+/// The timeouts of the consensus steps, after which a validator that has not heard from enough of
+/// its peers moves on (see the timeouts in Algorithm 1 of the Tendermint
+/// [paper](https://arxiv.org/pdf/1807.04938)).
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct TimeoutsConfig {
+    /// How long to wait for the proposal of a round before prevoting nil.
+    pub proposal: TimeoutConfig,
+    /// How long to wait for a prevote quorum on a value before precommitting nil.
+    pub prevote: TimeoutConfig,
+    /// How long to wait for a precommit quorum on a value before moving to the next round.
+    pub precommit: TimeoutConfig,
+}
+
+impl SerializeConfig for TimeoutsConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        let mut config = BTreeMap::new();
+        config.extend(append_sub_config_name(self.proposal.dump(), "proposal"));
+        config.extend(append_sub_config_name(self.prevote.dump(), "prevote"));
+        config.extend(append_sub_config_name(self.precommit.dump(), "precommit"));
+        config
+    }
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        Self {
+            proposal: TimeoutConfig {
+                base: Duration::from_millis(3000),
+                delta: Duration::from_millis(500),
+            },
+            prevote: TimeoutConfig {
+                base: Duration::from_millis(1000),
+                delta: Duration::from_millis(500),
+            },
+            precommit: TimeoutConfig {
+                base: Duration::from_millis(1000),
+                delta: Duration::from_millis(500),
+            },
         }
     }
 }
+
+/// A timeout which grows linearly with the round, so that the network eventually waits long
+/// enough for the messages of a round to arrive.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct TimeoutConfig {
+    /// The timeout (milliseconds) in the first round.
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub base: Duration,
+    /// The increase (milliseconds) of the timeout in each round.
+    #[serde(deserialize_with = "deserialize_milliseconds_to_duration")]
+    pub delta: Duration,
+}
+
+impl TimeoutConfig {
+    /// The timeout in the given round.
+    pub fn at_round(&self, round: Round) -> Duration {
+        self.base + self.delta * round
+    }
+}
+
+impl SerializeConfig for TimeoutConfig {
+    fn dump(&self) -> BTreeMap<ParamPath, SerializedParam> {
+        BTreeMap::from_iter([
+            ser_param(
+                "base",
+                &(self.base.as_millis() as u64),
+                "The timeout (milliseconds) in the first round.",
+                ParamPrivacyInput::Public,
+            ),
+            ser_param(
+                "delta",
+                &(self.delta.as_millis() as u64),
+                "The increase (milliseconds) of the timeout in each round.",
+                ParamPrivacyInput::Public,
+            ),
+        ])
+    }
+}
@@ -0,0 +1,209 @@
+//! Evidence of equivocation, i.e. of a validator which signed two conflicting votes, so that it can
+//! be slashed.
+//!
+//! A node which receives conflicting votes keeps them as evidence and gossips the evidence to its
+//! peers, which verify it before keeping it as well. The kept evidence can be read through
+//! [`EvidenceStore`], e.g. by the monitoring gateway.
+
+#[cfg(test)]
+#[path = "evidence_test.rs"]
+mod evidence_test;
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use papyrus_network::network_manager::{
+    BroadcastSubscriberChannels,
+    BroadcastSubscriberReceiver,
+    BroadcastSubscriberSender,
+};
+use papyrus_protobuf::consensus::{ConsensusMessage, EquivocationEvidence, VoteType};
+use starknet_api::core::ChainId;
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, warn};
+
+use crate::signing::verify_message;
+use crate::types::{ConsensusError, ValidatorId};
+use crate::validator_set::ValidatorSet;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum InvalidEvidence {
+    #[error("The votes are not of the same type, height, round and voter.")]
+    DifferentVoteSlots,
+    #[error("The votes are for the same block.")]
+    SameBlock,
+    #[error("{0:?} is not a validator at the height of the votes.")]
+    NotValidator(ValidatorId),
+    #[error("A vote is not signed by its voter.")]
+    InvalidSignature,
+}
+
+/// Verifies that the evidence proves an equivocation of one of the given validators, which are the
+/// validators at the height of the votes, on the given chain.
+pub fn verify_evidence(
+    evidence: &EquivocationEvidence,
+    validators: &ValidatorSet,
+    chain_id: &ChainId,
+) -> Result<(), InvalidEvidence> {
+    let EquivocationEvidence { first_vote, second_vote } = evidence;
+    if first_vote.vote_type != second_vote.vote_type
+        || first_vote.height != second_vote.height
+        || first_vote.round != second_vote.round
+        || first_vote.voter != second_vote.voter
+    {
+        return Err(InvalidEvidence::DifferentVoteSlots);
+    }
+    if first_vote.block_hash == second_vote.block_hash {
+        return Err(InvalidEvidence::SameBlock);
+    }
+    let validator =
+        validators.get(&first_vote.voter).ok_or(InvalidEvidence::NotValidator(first_vote.voter))?;
+    for vote in [first_vote, second_vote] {
+        if !verify_message(&ConsensusMessage::Vote(vote.clone()), &validator.public_key, chain_id) {
+            return Err(InvalidEvidence::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+/// The evidence kept by the node, in a file of JSON lines.
+#[derive(Debug, Clone)]
+pub struct EvidenceStore {
+    path: PathBuf,
+}
+
+impl EvidenceStore {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(Self { path })
+    }
+
+    /// All the kept evidence, in the order it was found.
+    pub fn evidence(&self) -> io::Result<Vec<EquivocationEvidence>> {
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        // The last line may still be being written.
+        content
+            .split_inclusive(|byte| *byte == b'\n')
+            .filter(|line| line.ends_with(b"\n"))
+            .map(|line| Ok(serde_json::from_slice(line)?))
+            .collect()
+    }
+
+    /// Keeps the evidence. Whether the equivocation it proves is already known is checked by the
+    /// [`EvidencePool`], which indexes the kept evidence.
+    pub fn append(&self, evidence: &EquivocationEvidence) -> io::Result<()> {
+        let mut line = serde_json::to_vec(evidence)?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+}
+
+// The vote on which a validator equivocated, by its voter, height, round and type.
+type VoteSlot = (ValidatorId, u64, u32, VoteType);
+
+fn vote_slot(evidence: &EquivocationEvidence) -> VoteSlot {
+    let vote = &evidence.first_vote;
+    (vote.voter, vote.height, vote.round, vote.vote_type.clone())
+}
+
+/// Keeps the evidence found by this node, or received from its peers, and gossips the former.
+pub struct EvidencePool {
+    // The vote slots of the kept evidence. A validator which equivocates once is slashed once, so
+    // a single piece of evidence for each vote it equivocated on suffices.
+    known_slots: HashSet<VoteSlot>,
+    // The evidence is written to the store by a separate task, so consensus doesn't wait for it.
+    store_sender: mpsc::UnboundedSender<EquivocationEvidence>,
+    store_writer: JoinHandle<()>,
+    broadcast_sender: BroadcastSubscriberSender<EquivocationEvidence>,
+    pub(crate) received: BroadcastSubscriberReceiver<EquivocationEvidence>,
+}
+
+impl EvidencePool {
+    pub fn new(
+        store: EvidenceStore,
+        channels: BroadcastSubscriberChannels<EquivocationEvidence>,
+    ) -> io::Result<Self> {
+        let known_slots = store.evidence()?.iter().map(vote_slot).collect();
+        let (store_sender, store_receiver) = mpsc::unbounded();
+        Ok(Self {
+            known_slots,
+            store_sender,
+            store_writer: tokio::spawn(write_evidence(store, store_receiver)),
+            broadcast_sender: channels.messages_to_broadcast_sender,
+            received: channels.broadcasted_messages_receiver,
+        })
+    }
+
+    /// Keeps and gossips evidence found by this node, which it verified while handling the votes.
+    pub(crate) async fn add_own(
+        &mut self,
+        evidence: EquivocationEvidence,
+    ) -> Result<(), ConsensusError> {
+        if self.add(&evidence)? {
+            self.broadcast_sender.send(evidence).await?;
+        }
+        Ok(())
+    }
+
+    /// Keeps evidence received from a peer. Verifying it is up to the caller, which knows the
+    /// validators of the height.
+    pub(crate) fn add_received(
+        &mut self,
+        evidence: EquivocationEvidence,
+    ) -> Result<(), ConsensusError> {
+        self.add(&evidence)?;
+        Ok(())
+    }
+
+    // Returns whether the evidence is new. Fails if the evidence can no longer be written to the
+    // store.
+    fn add(&mut self, evidence: &EquivocationEvidence) -> Result<bool, ConsensusError> {
+        if !self.known_slots.insert(vote_slot(evidence)) {
+            return Ok(false);
+        }
+        warn!("Validator {:?} equivocated: {:?}", evidence.first_vote.voter, evidence);
+        self.store_sender.unbounded_send(evidence.clone()).map_err(|err| err.into_send_error())?;
+        Ok(true)
+    }
+
+    /// Stops keeping evidence, and waits until all the kept evidence is written to the store.
+    pub async fn close(self) -> Result<(), JoinError> {
+        drop(self.store_sender);
+        self.store_writer.await
+    }
+}
+
+// Writes the evidence to the store, in the order it was kept, until the pool is dropped or a write
+// fails. After a failure, the pool fails to keep further evidence.
+async fn write_evidence(
+    store: EvidenceStore,
+    mut evidence_receiver: mpsc::UnboundedReceiver<EquivocationEvidence>,
+) {
+    while let Some(evidence) = evidence_receiver.next().await {
+        let store = store.clone();
+        let result = tokio::task::spawn_blocking(move || store.append(&evidence)).await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                error!("Failed to write the consensus evidence: {err}");
+                return;
+            }
+            Err(err) => {
+                error!("The task writing the consensus evidence failed: {err}");
+                return;
+            }
+        }
+    }
+}
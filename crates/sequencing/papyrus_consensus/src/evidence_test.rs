@@ -0,0 +1,115 @@
+use futures::StreamExt;
+use papyrus_network::network_manager::mock_register_broadcast_subscriber;
+use papyrus_protobuf::consensus::{EquivocationEvidence, Vote, VoteType};
+use starknet_api::block::BlockHash;
+use starknet_api::crypto::utils::Signature;
+use starknet_types_core::felt::Felt;
+
+use super::{verify_evidence, EvidencePool, EvidenceStore, InvalidEvidence};
+use crate::test_utils::{test_signer, test_validator_set, TEST_CHAIN_ID};
+use crate::types::ValidatorId;
+
+const VOTER: u64 = 1;
+
+fn vote(voter: ValidatorId, round: u32, block_hash: Option<BlockHash>) -> Vote {
+    test_signer(voter).sign_vote(Vote {
+        vote_type: VoteType::Prevote,
+        height: 0,
+        round,
+        block_hash,
+        voter,
+        signature: Signature::default(),
+    })
+}
+
+fn evidence(round: u32) -> EquivocationEvidence {
+    EquivocationEvidence {
+        first_vote: vote(VOTER.into(), round, Some(BlockHash(Felt::ONE))),
+        second_vote: vote(VOTER.into(), round, None),
+    }
+}
+
+fn test_store() -> (EvidenceStore, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    (EvidenceStore::open(dir.path().join("evidence.jsonl")).unwrap(), dir)
+}
+
+#[test]
+fn verify_conflicting_votes() {
+    let validators = test_validator_set([(VOTER.into(), 1), (2_u64.into(), 1)]);
+    assert_eq!(verify_evidence(&evidence(0), &validators, &TEST_CHAIN_ID), Ok(()));
+
+    let EquivocationEvidence { first_vote, second_vote } = evidence(0);
+    let same_block =
+        EquivocationEvidence { first_vote: first_vote.clone(), second_vote: first_vote.clone() };
+    assert_eq!(
+        verify_evidence(&same_block, &validators, &TEST_CHAIN_ID),
+        Err(InvalidEvidence::SameBlock)
+    );
+
+    let different_rounds = EquivocationEvidence {
+        first_vote: first_vote.clone(),
+        second_vote: vote(VOTER.into(), 1, None),
+    };
+    assert_eq!(
+        verify_evidence(&different_rounds, &validators, &TEST_CHAIN_ID),
+        Err(InvalidEvidence::DifferentVoteSlots)
+    );
+
+    // A validator cannot frame another by signing votes in its name.
+    let forged = EquivocationEvidence {
+        first_vote: first_vote.clone(),
+        second_vote: test_signer(2_u64.into()).sign_vote(second_vote),
+    };
+    assert_eq!(
+        verify_evidence(&forged, &validators, &TEST_CHAIN_ID),
+        Err(InvalidEvidence::InvalidSignature)
+    );
+
+    let validators = test_validator_set([(2_u64.into(), 1)]);
+    assert_eq!(
+        verify_evidence(&evidence(0), &validators, &TEST_CHAIN_ID),
+        Err(InvalidEvidence::NotValidator(VOTER.into()))
+    );
+}
+
+#[tokio::test]
+async fn pool_keeps_each_equivocation_once() {
+    let (store, _dir) = test_store();
+    assert_eq!(store.evidence().unwrap(), Vec::new());
+    let test_channels = mock_register_broadcast_subscriber().unwrap();
+    let mut pool = EvidencePool::new(store.clone(), test_channels.subscriber_channels).unwrap();
+
+    pool.add_received(evidence(0)).unwrap();
+    pool.add_received(evidence(1)).unwrap();
+    // The same equivocation, with the votes in the opposite order.
+    let EquivocationEvidence { first_vote, second_vote } = evidence(0);
+    pool.add_received(EquivocationEvidence { first_vote: second_vote, second_vote: first_vote })
+        .unwrap();
+    pool.close().await.unwrap();
+    assert_eq!(store.evidence().unwrap(), vec![evidence(0), evidence(1)]);
+
+    // The evidence kept before a restart is known to the new pool.
+    let test_channels = mock_register_broadcast_subscriber().unwrap();
+    let mut pool = EvidencePool::new(store.clone(), test_channels.subscriber_channels).unwrap();
+    pool.add_received(evidence(0)).unwrap();
+    pool.close().await.unwrap();
+    assert_eq!(store.evidence().unwrap(), vec![evidence(0), evidence(1)]);
+}
+
+#[tokio::test]
+async fn own_evidence_is_gossiped_once() {
+    let (store, _dir) = test_store();
+    let test_channels = mock_register_broadcast_subscriber().unwrap();
+    let mut pool = EvidencePool::new(store.clone(), test_channels.subscriber_channels).unwrap();
+
+    pool.add_own(evidence(0)).await.unwrap();
+    pool.add_own(evidence(0)).await.unwrap();
+    pool.add_received(evidence(1)).unwrap();
+    pool.close().await.unwrap();
+
+    let gossiped: Vec<_> =
+        test_channels.mock_network.messages_to_broadcast_receiver.collect().await;
+    assert_eq!(gossiped, vec![evidence(0)]);
+    assert_eq!(store.evidence().unwrap(), vec![evidence(0), evidence(1)]);
+}
@@ -3,27 +3,39 @@
 // TODO(Matan): fix #[allow(missing_docs)].
 //! A consensus implementation for a [`Starknet`](https://www.starknet.io/) node.
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
-use futures::channel::{mpsc, oneshot};
+use catch_up::PeerHeights;
+use certificate::CommitCertificate;
+use config::TimeoutsConfig;
+use evidence::{verify_evidence, EvidencePool};
+use futures::channel::oneshot;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
 use papyrus_common::metrics as papyrus_metrics;
 use papyrus_network::network_manager::BroadcastSubscriberReceiver;
-use papyrus_protobuf::consensus::{ConsensusMessage, Proposal};
-use single_height_consensus::SingleHeightConsensus;
-use starknet_api::block::{BlockHash, BlockNumber};
-use tracing::{debug, info, instrument};
-use types::{
-    ConsensusBlock,
-    ConsensusContext,
-    ConsensusError,
-    Decision,
-    ProposalInit,
-    ValidatorId,
-};
+use papyrus_protobuf::consensus::{ConsensusMessage, EquivocationEvidence};
+use signing::{verify_message, ConsensusSigner};
+use single_height_consensus::{ShcReturn, ShcTask, SingleHeightConsensus};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ChainId;
+use starknet_api::transaction::Transaction;
+use tracing::{debug, info, instrument, warn};
+use types::{ConsensusBlock, ConsensusContext, ConsensusError, Decision, ValidatorId};
+use wal::ConsensusWal;
 
+pub(crate) mod catch_up;
+#[allow(missing_docs)]
+pub mod certificate;
 pub mod config;
 #[allow(missing_docs)]
+pub mod evidence;
+#[allow(missing_docs)]
 pub mod papyrus_consensus_context;
+pub(crate) mod proposal_stream;
+#[allow(missing_docs)]
+pub mod signing;
 #[allow(missing_docs)]
 pub mod single_height_consensus;
 #[allow(missing_docs)]
@@ -32,114 +44,409 @@ pub mod state_machine;
 pub(crate) mod test_utils;
 #[allow(missing_docs)]
 pub mod types;
+#[allow(missing_docs)]
+pub mod validator_set;
+#[allow(missing_docs)]
+pub mod wal;
 
-use futures::StreamExt;
+// Sending on it lowers the reputation of the peer which sent the message.
+pub(crate) type ReportSender = oneshot::Sender<()>;
+
+// How a height ended.
+enum RunHeightRes<BlockT: ConsensusBlock> {
+    Decision(Decision<BlockT>),
+    // The other validators already decided the height, and moved on to the given height.
+    Sync(BlockNumber),
+}
 
-#[instrument(skip(context, validator_id, network_receiver, cached_messages), level = "info")]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    skip(
+        context,
+        validator_id,
+        signer,
+        timeouts,
+        wal,
+        network_receiver,
+        evidence_pool,
+        peer_heights,
+        cached_messages
+    ),
+    level = "info"
+)]
 #[allow(missing_docs)]
 async fn run_height<BlockT: ConsensusBlock, ContextT: ConsensusContext<Block = BlockT>>(
     context: &mut ContextT,
     height: BlockNumber,
     validator_id: ValidatorId,
+    signer: ConsensusSigner,
+    timeouts: TimeoutsConfig,
+    wal: ConsensusWal,
     network_receiver: &mut BroadcastSubscriberReceiver<ConsensusMessage>,
-    cached_messages: &mut Vec<ConsensusMessage>,
-) -> Result<Decision<BlockT>, ConsensusError>
+    evidence_pool: &mut EvidencePool,
+    peer_heights: &mut PeerHeights,
+    cached_messages: &mut Vec<(ConsensusMessage, ReportSender)>,
+) -> Result<RunHeightRes<BlockT>, ConsensusError>
 where
-    ProposalWrapper:
-        Into<(ProposalInit, mpsc::Receiver<BlockT::ProposalChunk>, oneshot::Receiver<BlockHash>)>,
+    TransactionBatchWrapper: Into<Vec<BlockT::ProposalChunk>>,
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
 {
-    let validators = context.validators(height).await;
-    let mut shc = SingleHeightConsensus::new(height, validator_id, validators);
+    let validators = context.validators(height).await?;
+    // The messages of the peers are verified against the chain on which this node signs.
+    let chain_id = signer.chain_id().clone();
+    let mut shc =
+        SingleHeightConsensus::new(height, validator_id, signer, validators, timeouts, wal);
+    let mut shc_tasks = FuturesUnordered::new();
 
-    if let Some(decision) = shc.start(context).await? {
-        return Ok(decision);
+    match shc.start(context).await? {
+        ShcReturn::Decision(decision) => return Ok(RunHeightRes::Decision(decision)),
+        ShcReturn::Tasks(tasks) => shc_tasks.extend(tasks.into_iter().map(ShcTask::run)),
     }
 
     let mut current_height_messages = Vec::new();
-    for msg in std::mem::take(cached_messages) {
+    for (msg, report_sender) in std::mem::take(cached_messages) {
         match height.0.cmp(&msg.height()) {
-            std::cmp::Ordering::Less => cached_messages.push(msg),
-            std::cmp::Ordering::Equal => current_height_messages.push(msg),
+            std::cmp::Ordering::Less => cached_messages.push((msg, report_sender)),
+            std::cmp::Ordering::Equal => current_height_messages.push((msg, report_sender)),
             std::cmp::Ordering::Greater => {}
         }
     }
 
     loop {
-        let message = if let Some(msg) = current_height_messages.pop() {
-            msg
+        // The messages of future heights are cached, and show whether the node fell behind.
+        if let Some(sync_height) = peer_heights.catch_up_height(shc.validators(), height) {
+            return Ok(RunHeightRes::Sync(sync_height));
+        }
+
+        let shc_return = if let Some((message, report_sender)) = current_height_messages.pop() {
+            handle_message(
+                &mut shc,
+                context,
+                height,
+                &chain_id,
+                message,
+                report_sender,
+                evidence_pool,
+                peer_heights,
+                cached_messages,
+            )
+            .await?
         } else {
-            // TODO(matan): Handle parsing failures and utilize ReportCallback.
-            network_receiver
-                .next()
-                .await
-                .expect("Network receiver closed unexpectedly")
-                .0
-                .expect("Failed to parse consensus message")
+            tokio::select! {
+                message = network_receiver.next() => {
+                    let (message, report_sender) =
+                        message.expect("Network receiver closed unexpectedly");
+                    match message {
+                        Ok(message) => {
+                            handle_message(
+                                &mut shc,
+                                context,
+                                height,
+                                &chain_id,
+                                message,
+                                report_sender,
+                                evidence_pool,
+                                peer_heights,
+                                cached_messages,
+                            )
+                            .await?
+                        }
+                        Err(err) => {
+                            warn!("Failed to parse consensus message: {:?}", err);
+                            report_peer(report_sender);
+                            ShcReturn::Tasks(Vec::new())
+                        }
+                    }
+                }
+                evidence = evidence_pool.received.next() => {
+                    let (evidence, report_sender) =
+                        evidence.expect("Evidence receiver closed unexpectedly");
+                    match evidence {
+                        Ok(evidence) => {
+                            handle_evidence(
+                                &shc,
+                                context,
+                                height,
+                                &chain_id,
+                                evidence,
+                                report_sender,
+                                evidence_pool,
+                            )
+                            .await?;
+                        }
+                        Err(err) => {
+                            warn!("Failed to parse equivocation evidence: {:?}", err);
+                            report_peer(report_sender);
+                        }
+                    }
+                    ShcReturn::Tasks(Vec::new())
+                }
+                Some(proposal) = shc.next_validated_proposal() => {
+                    shc.handle_validated_proposal(context, proposal).await?
+                }
+                Some(task) = shc_tasks.next() => shc.handle_task(context, task).await?,
+            }
         };
 
-        if message.height() != height.0 {
-            debug!("Received a message for a different height. {:?}", message);
-            if message.height() > height.0 {
-                cached_messages.push(message);
-            }
-            continue;
+        match shc_return {
+            ShcReturn::Decision(decision) => return Ok(RunHeightRes::Decision(decision)),
+            ShcReturn::Tasks(tasks) => shc_tasks.extend(tasks.into_iter().map(ShcTask::run)),
         }
+    }
+}
 
-        let maybe_decision = match message {
-            ConsensusMessage::Proposal(proposal) => {
-                // Special case due to fake streaming.
-                let (proposal_init, content_receiver, fin_receiver) =
-                    ProposalWrapper(proposal).into();
-                shc.handle_proposal(context, proposal_init, content_receiver, fin_receiver).await?
+#[allow(clippy::too_many_arguments)]
+async fn handle_message<BlockT: ConsensusBlock, ContextT: ConsensusContext<Block = BlockT>>(
+    shc: &mut SingleHeightConsensus<BlockT>,
+    context: &mut ContextT,
+    height: BlockNumber,
+    chain_id: &ChainId,
+    message: ConsensusMessage,
+    report_sender: ReportSender,
+    evidence_pool: &mut EvidencePool,
+    peer_heights: &mut PeerHeights,
+    cached_messages: &mut Vec<(ConsensusMessage, ReportSender)>,
+) -> Result<ShcReturn<BlockT>, ConsensusError>
+where
+    TransactionBatchWrapper: Into<Vec<BlockT::ProposalChunk>>,
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
+{
+    if message.height() != height.0 {
+        debug!("Received a message for a different height. {:?}", message);
+        if message.height() > height.0 {
+            // The message is handled, and so fully verified, only once its height is reached. Until
+            // then, it only counts towards detecting that the node fell behind if it is signed by
+            // the sender's key at this height, so that it cannot be forged.
+            if let Some(validator) = shc.validators().get(&message.sender()) {
+                if verify_message(&message, &validator.public_key, chain_id) {
+                    peer_heights.observe(&message);
+                }
             }
-            _ => shc.handle_message(context, message).await?,
-        };
+            cached_messages.push((message, report_sender));
+        }
+        return Ok(ShcReturn::Tasks(Vec::new()));
+    }
+
+    // Messages are verified only once their height is reached, since the validators, and so their
+    // keys, may change between heights.
+    let is_signature_valid = match shc.validators().get(&message.sender()) {
+        Some(validator) => verify_message(&message, &validator.public_key, chain_id),
+        None => false,
+    };
+    if !is_signature_valid {
+        warn!("Rejecting a consensus message with an invalid signature: {:?}", message);
+        report_peer(report_sender);
+        return Ok(ShcReturn::Tasks(Vec::new()));
+    }
 
-        if let Some(decision) = maybe_decision {
-            return Ok(decision);
+    let result = match message {
+        ConsensusMessage::ProposalPart(part) => {
+            shc.handle_proposal_part(context, part, Some(report_sender)).await
+        }
+        _ => shc.handle_message(context, message).await,
+    };
+    match result {
+        // The conflicting vote is ignored, and consensus carries on with the first one.
+        Err(ConsensusError::Equivocation(evidence)) => {
+            evidence_pool.add_own(*evidence).await?;
+            Ok(ShcReturn::Tasks(Vec::new()))
+        }
+        result => result,
+    }
+}
+
+async fn handle_evidence<BlockT: ConsensusBlock, ContextT: ConsensusContext<Block = BlockT>>(
+    shc: &SingleHeightConsensus<BlockT>,
+    context: &mut ContextT,
+    height: BlockNumber,
+    chain_id: &ChainId,
+    evidence: EquivocationEvidence,
+    report_sender: ReportSender,
+    evidence_pool: &mut EvidencePool,
+) -> Result<(), ConsensusError>
+where
+    TransactionBatchWrapper: Into<Vec<BlockT::ProposalChunk>>,
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
+{
+    let evidence_height = BlockNumber(evidence.first_vote.height);
+    // The validators of future heights may not be known yet.
+    if evidence_height > height {
+        debug!("Ignoring evidence of a future height. {:?}", evidence);
+        return Ok(());
+    }
+    let validators = if evidence_height == height {
+        shc.validators().clone()
+    } else {
+        match context.validators(evidence_height).await {
+            Ok(validators) => validators,
+            // Evidence which cannot be verified is not kept.
+            Err(err) => {
+                warn!(
+                    "Dropping equivocation evidence which cannot be verified: {err}. {:?}",
+                    evidence
+                );
+                report_peer(report_sender);
+                return Ok(());
+            }
+        }
+    };
+    match verify_evidence(&evidence, &validators, chain_id) {
+        Ok(()) => evidence_pool.add_received(evidence)?,
+        Err(err) => {
+            warn!("Rejecting invalid equivocation evidence: {err}. {:?}", evidence);
+            report_peer(report_sender);
         }
     }
+    Ok(())
+}
+
+pub(crate) fn report_peer(report_sender: ReportSender) {
+    // The network may have already dropped the receiver, in which case there is nothing to do.
+    let _ = report_sender.send(());
 }
 
 // TODO(dvir): add test for this.
-#[instrument(skip(context, start_height, network_receiver), level = "info")]
+#[allow(clippy::too_many_arguments)]
+#[instrument(
+    skip(context, start_height, signer, timeouts, wal, network_receiver, evidence_pool),
+    level = "info"
+)]
 #[allow(missing_docs)]
 pub async fn run_consensus<BlockT: ConsensusBlock, ContextT: ConsensusContext<Block = BlockT>>(
     mut context: ContextT,
     start_height: BlockNumber,
     validator_id: ValidatorId,
+    signer: ConsensusSigner,
     consensus_delay: Duration,
+    timeouts: TimeoutsConfig,
+    wal: ConsensusWal,
     mut network_receiver: BroadcastSubscriberReceiver<ConsensusMessage>,
+    mut evidence_pool: EvidencePool,
 ) -> Result<(), ConsensusError>
 where
-    ProposalWrapper:
-        Into<(ProposalInit, mpsc::Receiver<BlockT::ProposalChunk>, oneshot::Receiver<BlockHash>)>,
+    TransactionBatchWrapper: Into<Vec<BlockT::ProposalChunk>>,
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
 {
     // Add a short delay to allow peers to connect and avoid "InsufficientPeers" error
     tokio::time::sleep(consensus_delay).await;
     let mut current_height = start_height;
+    let mut peer_heights = PeerHeights::new();
     let mut future_messages = Vec::new();
+    let chain_id = signer.chain_id().clone();
+    // The certificates of the decided heights whose blocks the sync did not write yet.
+    let mut certificates: BTreeMap<BlockNumber, CommitCertificate> = wal
+        .certificates()
+        .map_err(|err| ConsensusError::WriteAheadLog(err.to_string()))?
+        .into_iter()
+        .map(|certificate| (certificate.height, certificate))
+        .collect();
     loop {
-        let decision = run_height(
+        let decision = match run_height(
             &mut context,
             current_height,
             validator_id,
+            signer.clone(),
+            timeouts,
+            wal.clone(),
             &mut network_receiver,
+            &mut evidence_pool,
+            &mut peer_heights,
             &mut future_messages,
         )
-        .await?;
+        .await?
+        {
+            RunHeightRes::Decision(decision) => decision,
+            RunHeightRes::Sync(sync_height) => {
+                info!(
+                    "Consensus fell behind at height {current_height}, while the other validators \
+                     reached height {sync_height}. Catching up through the sync."
+                );
+                context.sync_to(sync_height).await?;
+                // The heights below `sync_height` were decided without this node, so they are not
+                // run, and their cached messages are dropped when the next height starts. Before
+                // that, the precommits among them certify the blocks of these heights.
+                let last_synced_height =
+                    sync_height.prev().expect("Consensus syncs only to heights above its own.");
+                for height in current_height.iter_up_to(sync_height) {
+                    let validators = context.validators(height).await?;
+                    let votes = future_messages.iter().filter_map(|(message, _)| match message {
+                        ConsensusMessage::Vote(vote) => Some(vote),
+                        _ => None,
+                    });
+                    if let Some(certificate) =
+                        CommitCertificate::find(height, votes, &validators, &chain_id)
+                    {
+                        certificates.insert(height, certificate);
+                    }
+                }
+                // The hash of a block covers its parent, so the last synced block is the one whose
+                // check matters most.
+                if !certificates.contains_key(&last_synced_height) {
+                    warn!(
+                        "No precommit quorum was received for height {last_synced_height}, so its \
+                         synced block is not checked against the decision of the validators."
+                    );
+                }
+                // All the blocks below `sync_height` are synced, so all the certificates are
+                // checked.
+                check_synced_blocks(&context, &wal, &mut certificates).await?;
+                wal.prune(last_synced_height)
+                    .map_err(|err| ConsensusError::WriteAheadLog(err.to_string()))?;
+                current_height = sync_height;
+                continue;
+            }
+        };
 
         info!(
             "Finished consensus for height: {current_height}. Agreed on block with id: {:x}",
             decision.block.id().0
         );
         debug!("Decision: {:?}", decision);
+        let certificate = CommitCertificate {
+            height: current_height,
+            block_hash: decision.block.id(),
+            precommits: decision.precommits,
+        };
+        certificates.insert(current_height, certificate);
+        check_synced_blocks(&context, &wal, &mut certificates).await?;
+        wal.prune(current_height).map_err(|err| ConsensusError::WriteAheadLog(err.to_string()))?;
         metrics::gauge!(papyrus_metrics::PAPYRUS_CONSENSUS_HEIGHT, current_height.0 as f64);
         current_height = current_height.unchecked_next();
     }
 }
 
-// `Proposal` is defined in the protobuf crate so we can't implement `Into` for it because of the
-// orphan rule. This wrapper enables us to implement `Into` for the inner `Proposal`.
+// Checks the blocks which the sync already wrote against the certificates of their heights, and
+// keeps the certificates of the other heights, until their blocks are written.
+async fn check_synced_blocks<ContextT: ConsensusContext>(
+    context: &ContextT,
+    wal: &ConsensusWal,
+    certificates: &mut BTreeMap<BlockNumber, CommitCertificate>,
+) -> Result<(), ConsensusError> {
+    let mut checked_heights = Vec::new();
+    for (height, certificate) in certificates.iter() {
+        // The sync writes the blocks in order, so the higher blocks are not written either.
+        let Some(block_hash) = context.synced_block_hash(*height).await? else {
+            break;
+        };
+        if block_hash != certificate.block_hash {
+            return Err(ConsensusError::SyncedBlockMismatch(
+                *height,
+                certificate.block_hash,
+                block_hash,
+            ));
+        }
+        checked_heights.push(*height);
+    }
+    for height in checked_heights {
+        certificates.remove(&height);
+    }
+    let certificates: Vec<_> = certificates.values().cloned().collect();
+    wal.set_certificates(&certificates)
+        .map_err(|err| ConsensusError::WriteAheadLog(err.to_string()))
+}
+
+// `Transaction` is defined in another crate so we can't implement `Into` for the transactions of a
+// proposal because of the orphan rule. This wrapper enables us to implement `Into` for the inner
+// transactions, converting them into the proposal chunks of a block, and back.
 #[allow(missing_docs)]
-pub struct ProposalWrapper(Proposal);
+pub struct TransactionBatchWrapper(Vec<Transaction>);
@@ -10,17 +10,26 @@ use futures::channel::{mpsc, oneshot};
 use futures::sink::SinkExt;
 use futures::StreamExt;
 use papyrus_network::network_manager::BroadcastSubscriberSender;
-use papyrus_protobuf::consensus::{ConsensusMessage, Proposal};
+use papyrus_protobuf::consensus::{ConsensusMessage, ProposalContent, ProposalPart};
 use papyrus_storage::body::BodyStorageReader;
 use papyrus_storage::header::HeaderStorageReader;
 use papyrus_storage::{StorageError, StorageReader};
 use starknet_api::block::{BlockHash, BlockNumber};
-use starknet_api::core::ContractAddress;
+use starknet_api::crypto::utils::Signature;
 use starknet_api::transaction::Transaction;
-use tracing::debug;
-
-use crate::types::{ConsensusBlock, ConsensusContext, ConsensusError, ProposalInit, ValidatorId};
-use crate::ProposalWrapper;
+use tracing::{debug, warn};
+
+use crate::signing::ConsensusSigner;
+use crate::types::{
+    ConsensusBlock,
+    ConsensusContext,
+    ConsensusError,
+    ProposalInit,
+    Round,
+    ValidatorId,
+};
+use crate::validator_set::{ValidatorSet, ValidatorSetError, ValidatorSetProvider};
+use crate::TransactionBatchWrapper;
 
 // TODO: add debug messages and span to the tasks.
 
@@ -46,7 +55,8 @@ impl ConsensusBlock for PapyrusConsensusBlock {
 pub struct PapyrusConsensusContext {
     storage_reader: StorageReader,
     broadcast_sender: BroadcastSubscriberSender<ConsensusMessage>,
-    validators: Vec<ValidatorId>,
+    signer: ConsensusSigner,
+    validator_set_provider: Box<dyn ValidatorSetProvider>,
 }
 
 impl PapyrusConsensusContext {
@@ -55,17 +65,15 @@ impl PapyrusConsensusContext {
     pub fn new(
         storage_reader: StorageReader,
         broadcast_sender: BroadcastSubscriberSender<ConsensusMessage>,
-        num_validators: u64,
+        signer: ConsensusSigner,
+        validator_set_provider: Box<dyn ValidatorSetProvider>,
     ) -> Self {
-        Self {
-            storage_reader,
-            broadcast_sender,
-            validators: (0..num_validators).map(ContractAddress::from).collect(),
-        }
+        Self { storage_reader, broadcast_sender, signer, validator_set_provider }
     }
 }
 
 const CHANNEL_SIZE: usize = 5000;
+const TRANSACTION_BATCH_SIZE: usize = 100;
 
 #[async_trait]
 impl ConsensusContext for PapyrusConsensusContext {
@@ -133,18 +141,22 @@ impl ConsensusContext for PapyrusConsensusContext {
                     panic!("Block in {height} was not found in storage despite waiting for it")
                 });
 
+            // A proposal which doesn't match the block in storage is invalid, and the block is
+            // never sent.
             for tx in transactions.iter() {
-                let received_tx = content
-                    .next()
-                    .await
-                    .unwrap_or_else(|| panic!("Not received transaction equals to {tx:?}"));
+                let Some(received_tx) = content.next().await else {
+                    warn!("Not received transaction equals to {tx:?}");
+                    return;
+                };
                 if tx != &received_tx {
-                    panic!("Transactions are not equal. In storage: {tx:?}, : {received_tx:?}");
+                    warn!("Transactions are not equal. In storage: {tx:?}, : {received_tx:?}");
+                    return;
                 }
             }
 
             if content.next().await.is_some() {
-                panic!("Received more transactions than expected");
+                warn!("Received more transactions than expected");
+                return;
             }
 
             let block_hash = txn
@@ -162,12 +174,26 @@ impl ConsensusContext for PapyrusConsensusContext {
         fin_receiver
     }
 
-    async fn validators(&self, _height: BlockNumber) -> Vec<ValidatorId> {
-        self.validators.clone()
+    async fn validators(&self, height: BlockNumber) -> Result<ValidatorSet, ConsensusError> {
+        loop {
+            match self.validator_set_provider.validator_set(height) {
+                Ok(validator_set) => return Ok(validator_set),
+                Err(ValidatorSetError::StateNotSynced(_)) => {
+                    debug!("Waiting for the state before {height:?} to get its validators");
+                    tokio::time::sleep(SLEEP_BETWEEN_CHECK_FOR_BLOCK).await;
+                }
+                Err(err) => return Err(ConsensusError::Validators(height, err.to_string())),
+            }
+        }
     }
 
-    fn proposer(&self, _validators: &[ValidatorId], _height: BlockNumber) -> ValidatorId {
-        *self.validators.first().expect("validators should have at least 2 validators")
+    fn proposer(
+        &self,
+        validators: &ValidatorSet,
+        height: BlockNumber,
+        round: Round,
+    ) -> ValidatorId {
+        validators.proposer(height, round)
     }
 
     async fn broadcast(&mut self, message: ConsensusMessage) -> Result<(), ConsensusError> {
@@ -179,41 +205,89 @@ impl ConsensusContext for PapyrusConsensusContext {
     async fn propose(
         &self,
         init: ProposalInit,
-        mut content_receiver: mpsc::Receiver<Transaction>,
+        content_receiver: mpsc::Receiver<Transaction>,
         fin_receiver: oneshot::Receiver<BlockHash>,
     ) -> Result<(), ConsensusError> {
         let mut broadcast_sender = self.broadcast_sender.clone();
+        let signer = self.signer.clone();
 
         tokio::spawn(async move {
-            let mut transactions = Vec::new();
-            while let Some(tx) = content_receiver.next().await {
-                transactions.push(tx);
+            let part = |sequence_number, content| {
+                let part = ProposalPart {
+                    height: init.height.0,
+                    round: init.round,
+                    proposer: init.proposer,
+                    sequence_number,
+                    content,
+                    signature: Signature::default(),
+                };
+                ConsensusMessage::ProposalPart(
+                    signer.sign_proposal_part(part).expect("Failed to sign proposal part"),
+                )
+            };
+            debug!(
+                "Streaming proposal: height={:?} round={:?} id={:?} valid_round={:?}",
+                init.height, init.round, init.proposer, init.valid_round
+            );
+
+            let mut sequence_number = 0;
+            broadcast_sender
+                .send(part(
+                    sequence_number,
+                    ProposalContent::Init { valid_round: init.valid_round },
+                ))
+                .await
+                .expect("Failed to send proposal init");
+            // Each batch holds the transactions which are already built, so that they are sent
+            // without waiting for the rest of the block.
+            let mut batches = content_receiver.ready_chunks(TRANSACTION_BATCH_SIZE);
+            while let Some(transactions) = batches.next().await {
+                sequence_number += 1;
+                broadcast_sender
+                    .send(part(sequence_number, ProposalContent::Transactions(transactions)))
+                    .await
+                    .expect("Failed to send proposal transactions");
             }
 
             let block_hash =
                 fin_receiver.await.expect("Failed to get block hash from fin receiver");
-            let proposal = Proposal {
-                height: init.height.0,
-                round: 0, // TODO(Asmaa): add round to the proposal.
-                proposer: init.proposer,
-                transactions,
-                block_hash,
-            };
+            sequence_number += 1;
+            broadcast_sender
+                .send(part(sequence_number, ProposalContent::Fin { block_hash }))
+                .await
+                .expect("Failed to send proposal fin");
             debug!(
-                "Sending proposal: height={:?} id={:?} num_txs={} block_hash={:?}",
-                proposal.height,
-                proposal.proposer,
-                proposal.transactions.len(),
-                proposal.block_hash
+                "Sent proposal: height={:?} round={:?} num_parts={} block_hash={:?}",
+                init.height,
+                init.round,
+                sequence_number + 1,
+                block_hash
             );
+        });
+        Ok(())
+    }
 
-            broadcast_sender
-                .send(ConsensusMessage::Proposal(proposal))
+    async fn sync_to(&mut self, height: BlockNumber) -> Result<(), ConsensusError> {
+        // The blocks decided by the other validators are written to storage by the sync.
+        if let Some(last_decided_height) = height.prev() {
+            wait_for_block(&self.storage_reader, last_decided_height)
                 .await
-                .expect("Failed to send proposal");
-        });
+                .map_err(|err| ConsensusError::SyncedBlock(last_decided_height, err.to_string()))?;
+        }
         Ok(())
     }
+
+    async fn synced_block_hash(
+        &self,
+        height: BlockNumber,
+    ) -> Result<Option<BlockHash>, ConsensusError> {
+        let header = self
+            .storage_reader
+            .begin_ro_txn()
+            .and_then(|txn| txn.get_block_header(height))
+            .map_err(|err| ConsensusError::SyncedBlock(height, err.to_string()))?;
+        Ok(header.map(|header| header.block_hash))
+    }
 }
 
 const SLEEP_BETWEEN_CHECK_FOR_BLOCK: Duration = Duration::from_secs(10);
@@ -229,22 +303,14 @@ async fn wait_for_block(
     Ok(())
 }
 
-impl From<ProposalWrapper>
-    for (ProposalInit, mpsc::Receiver<Transaction>, oneshot::Receiver<BlockHash>)
-{
-    fn from(val: ProposalWrapper) -> Self {
-        let transactions: Vec<Transaction> = val.0.transactions.into_iter().collect();
-        let proposal_init =
-            ProposalInit { height: BlockNumber(val.0.height), proposer: val.0.proposer };
-        let (mut content_sender, content_receiver) = mpsc::channel(transactions.len());
-        for tx in transactions {
-            content_sender.try_send(tx).expect("Send should succeed");
-        }
-        content_sender.close_channel();
-
-        let (fin_sender, fin_receiver) = oneshot::channel();
-        fin_sender.send(val.0.block_hash).expect("Send should succeed");
+impl From<TransactionBatchWrapper> for Vec<Transaction> {
+    fn from(val: TransactionBatchWrapper) -> Self {
+        val.0
+    }
+}
 
-        (proposal_init, content_receiver, fin_receiver)
+impl From<Vec<Transaction>> for TransactionBatchWrapper {
+    fn from(transactions: Vec<Transaction>) -> Self {
+        TransactionBatchWrapper(transactions)
     }
 }
@@ -1,22 +1,28 @@
+use std::collections::BTreeMap;
+
 use futures::channel::{mpsc, oneshot};
 use futures::StreamExt;
 use papyrus_network::network_manager::{mock_register_broadcast_subscriber, BroadcastNetworkMock};
-use papyrus_protobuf::consensus::{ConsensusMessage, Proposal};
+use papyrus_protobuf::consensus::{ConsensusMessage, ProposalContent};
 use papyrus_storage::body::BodyStorageWriter;
 use papyrus_storage::header::HeaderStorageWriter;
 use papyrus_storage::test_utils::get_test_storage;
 use papyrus_test_utils::get_test_block;
-use starknet_api::block::Block;
+use starknet_api::block::{Block, BlockNumber};
 use starknet_api::core::ContractAddress;
 use starknet_api::transaction::Transaction;
 
 use crate::papyrus_consensus_context::PapyrusConsensusContext;
+use crate::signing::verify_message;
+use crate::test_utils::{test_signer, test_validator_set, TEST_CHAIN_ID};
 use crate::types::{ConsensusBlock, ConsensusContext, ProposalInit};
+use crate::validator_set::{StaticValidatorSetProvider, ValidatorSet};
 
 // TODO(dvir): consider adding tests for times, i.e, the calls are returned immediately and nothing
 // happen until it should (for example, not creating a block before we have it in storage).
 
 const TEST_CHANNEL_SIZE: usize = 10;
+const NUM_VALIDATORS: u64 = 4;
 
 #[tokio::test]
 async fn build_proposal() {
@@ -84,18 +90,82 @@ async fn propose() {
     let (fin_sender, fin_receiver) = oneshot::channel();
     fin_sender.send(block.header.block_hash).unwrap();
 
-    let proposal_init = ProposalInit { height: block_number, proposer: ContractAddress::default() };
+    let proposal_init = ProposalInit {
+        height: block_number,
+        round: 0,
+        proposer: ContractAddress::default(),
+        valid_round: None,
+    };
     papyrus_context.propose(proposal_init.clone(), content_receiver, fin_receiver).await.unwrap();
 
-    let expected_message = ConsensusMessage::Proposal(Proposal {
-        height: proposal_init.height.0,
-        round: 0,
-        proposer: proposal_init.proposer,
-        transactions: block.body.transactions,
-        block_hash: block.header.block_hash,
-    });
+    // The proposal is streamed as signed parts, in order.
+    let signer = test_signer(proposal_init.proposer);
+    let mut parts = Vec::new();
+    loop {
+        let message = mock_network.messages_to_broadcast_receiver.next().await.unwrap();
+        assert!(verify_message(&message, &signer.public_key(), &TEST_CHAIN_ID));
+        let ConsensusMessage::ProposalPart(part) = message else {
+            panic!("Expected a proposal part, got {message:?}");
+        };
+        assert_eq!(part.height, proposal_init.height.0);
+        assert_eq!(part.sequence_number, u32::try_from(parts.len()).unwrap());
+        let is_fin = matches!(part.content, ProposalContent::Fin { .. });
+        parts.push(part.content);
+        if is_fin {
+            break;
+        }
+    }
+
+    let Some(ProposalContent::Init { valid_round: None }) = parts.first() else {
+        panic!("Expected an init part, got {:?}", parts.first());
+    };
+    let Some(ProposalContent::Fin { block_hash }) = parts.last() else {
+        unreachable!();
+    };
+    assert_eq!(*block_hash, block.header.block_hash);
+    let transactions: Vec<Transaction> = parts[1..parts.len() - 1]
+        .iter()
+        .flat_map(|content| match content {
+            ProposalContent::Transactions(transactions) => transactions.clone(),
+            _ => panic!("Expected a transaction batch, got {content:?}"),
+        })
+        .collect();
+    assert_eq!(transactions, block.body.transactions);
+}
+
+#[tokio::test]
+async fn validators() {
+    let (block, papyrus_context, _mock_network) = test_setup();
+
+    let validators = papyrus_context.validators(block.header.block_number).await.unwrap();
+    assert_eq!(validators, test_validators());
+    // With equal voting power, the validators propose in turns.
+    for height in 0..NUM_VALIDATORS {
+        assert_eq!(
+            papyrus_context.proposer(&validators, BlockNumber(height), 0),
+            ContractAddress::from(height)
+        );
+    }
+}
+
+#[tokio::test]
+async fn sync_to_returns_once_the_blocks_below_the_height_are_synced() {
+    let (block, mut papyrus_context, _mock_network) = test_setup();
+
+    // The test block is the last block in storage.
+    papyrus_context.sync_to(block.header.block_number.unchecked_next()).await.unwrap();
+}
+
+#[tokio::test]
+async fn synced_block_hash_is_read_from_storage() {
+    let (block, papyrus_context, _mock_network) = test_setup();
+    let block_number = block.header.block_number;
 
-    assert_eq!(mock_network.messages_to_broadcast_receiver.next().await.unwrap(), expected_message);
+    assert_eq!(
+        papyrus_context.synced_block_hash(block_number).await,
+        Ok(Some(block.header.block_hash))
+    );
+    assert_eq!(papyrus_context.synced_block_hash(block_number.unchecked_next()).await, Ok(None));
 }
 
 fn test_setup() -> (Block, PapyrusConsensusContext, BroadcastNetworkMock<ConsensusMessage>) {
@@ -116,7 +186,15 @@ fn test_setup() -> (Block, PapyrusConsensusContext, BroadcastNetworkMock<Consens
     let papyrus_context = PapyrusConsensusContext::new(
         storage_reader.clone(),
         test_channels.subscriber_channels.messages_to_broadcast_sender,
-        4,
+        test_signer(ContractAddress::default()),
+        Box::new(StaticValidatorSetProvider::new(BTreeMap::from([(
+            BlockNumber(0),
+            test_validators(),
+        )]))),
     );
     (block, papyrus_context, test_channels.mock_network)
 }
+
+fn test_validators() -> ValidatorSet {
+    test_validator_set((0..NUM_VALIDATORS).map(|validator| (ContractAddress::from(validator), 1)))
+}
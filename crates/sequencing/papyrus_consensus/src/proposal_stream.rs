@@ -0,0 +1,154 @@
+//! Reassembly of the proposals streamed by the proposers. The parts of a proposal may arrive out of
+//! order, so each part is held until the parts preceding it arrive, and only then passed on to the
+//! validation of the proposal.
+
+#[cfg(test)]
+#[path = "proposal_stream_test.rs"]
+mod proposal_stream_test;
+
+use std::collections::{BTreeMap, HashMap};
+
+use futures::channel::{mpsc, oneshot};
+use futures::StreamExt;
+use papyrus_protobuf::consensus::{ProposalContent, ProposalPart};
+use starknet_api::block::{BlockHash, BlockNumber};
+use tracing::{debug, warn};
+
+use crate::types::{ProposalInit, Round, ValidatorId};
+use crate::TransactionBatchWrapper;
+
+// The content is passed on as it arrives, so this only needs to cover a validation which falls
+// behind the stream. Beyond it, the content is buffered by the task which passes it on, so that
+// handling the parts never waits for the validation.
+const CONTENT_CHANNEL_SIZE: usize = 1000;
+// The parts which arrive this far ahead of the next part of their stream are dropped.
+const MAX_PENDING_PARTS: u32 = 1000;
+// A proposer may stream a proposal of each round it leads, but a proposer with this many streams
+// which were not completed doesn't get to open another.
+const MAX_OPEN_STREAMS_PER_PROPOSER: usize = 4;
+
+/// A proposal whose init part arrived. Its content and fin are passed on through the channels as
+/// they arrive.
+pub(crate) type StartedProposal<ChunkT> =
+    (ProposalInit, mpsc::Receiver<ChunkT>, oneshot::Receiver<BlockHash>);
+
+/// The streams of the proposals of a single height, by the round and proposer of the proposal.
+pub(crate) struct ProposalStreams<ChunkT> {
+    streams: HashMap<(Round, ValidatorId), ProposalStream<ChunkT>>,
+}
+
+struct ProposalStream<ChunkT> {
+    next_sequence_number: u32,
+    // The parts which arrived before some of the parts preceding them.
+    pending_parts: BTreeMap<u32, ProposalPart>,
+    // Dropped once the fin part is passed on, which closes the content channel.
+    content_sender: Option<mpsc::UnboundedSender<ChunkT>>,
+    fin_sender: Option<oneshot::Sender<BlockHash>>,
+    // Handed out once the init part is passed on.
+    receivers: Option<(mpsc::Receiver<ChunkT>, oneshot::Receiver<BlockHash>)>,
+}
+
+impl<ChunkT: Send + 'static> ProposalStream<ChunkT> {
+    fn new() -> Self {
+        let (content_sender, buffered_content) = mpsc::unbounded();
+        let (forwarded_content, content_receiver) = mpsc::channel(CONTENT_CHANNEL_SIZE);
+        // Ends once the stream is done, or once the validation drops the content receiver, e.g. if
+        // it failed, in which case the rest of the content is not needed.
+        tokio::spawn(buffered_content.map(Ok).forward(forwarded_content));
+        let (fin_sender, fin_receiver) = oneshot::channel();
+        Self {
+            next_sequence_number: 0,
+            pending_parts: BTreeMap::new(),
+            content_sender: Some(content_sender),
+            fin_sender: Some(fin_sender),
+            receivers: Some((content_receiver, fin_receiver)),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.content_sender.is_some()
+    }
+}
+
+impl<ChunkT: Send + 'static> ProposalStreams<ChunkT> {
+    pub(crate) fn new() -> Self {
+        Self { streams: HashMap::new() }
+    }
+
+    /// Passes on the part, and the pending parts following it, once the parts preceding it
+    /// arrived. Returns the proposal if its init part was passed on.
+    pub(crate) fn handle_part(&mut self, part: ProposalPart) -> Option<StartedProposal<ChunkT>>
+    where
+        TransactionBatchWrapper: Into<Vec<ChunkT>>,
+    {
+        let key = (part.round, part.proposer);
+        if !self.streams.contains_key(&key) {
+            let open_streams = self
+                .streams
+                .iter()
+                .filter(|((_, proposer), stream)| *proposer == part.proposer && stream.is_open())
+                .count();
+            if open_streams >= MAX_OPEN_STREAMS_PER_PROPOSER {
+                debug!("Ignoring a part of a proposer with too many open streams. {:?}", part);
+                return None;
+            }
+        }
+        let stream = self.streams.entry(key).or_insert_with(ProposalStream::new);
+        if part.sequence_number < stream.next_sequence_number
+            || stream.pending_parts.contains_key(&part.sequence_number)
+        {
+            debug!("Ignoring a repeated proposal part. {:?}", part);
+            return None;
+        }
+        if part.sequence_number - stream.next_sequence_number >= MAX_PENDING_PARTS {
+            debug!("Ignoring a proposal part too far ahead of its stream. {:?}", part);
+            return None;
+        }
+        stream.pending_parts.insert(part.sequence_number, part);
+
+        let mut started = None;
+        while let Some(part) = stream.pending_parts.remove(&stream.next_sequence_number) {
+            stream.next_sequence_number += 1;
+            let is_init = part.sequence_number == 0;
+            let is_open = stream.is_open();
+            match part.content {
+                ProposalContent::Init { valid_round } if is_init => {
+                    let (content_receiver, fin_receiver) =
+                        stream.receivers.take().expect("A stream has a single init part.");
+                    let init = ProposalInit {
+                        height: BlockNumber(part.height),
+                        round: part.round,
+                        proposer: part.proposer,
+                        valid_round,
+                    };
+                    started = Some((init, content_receiver, fin_receiver));
+                }
+                ProposalContent::Transactions(transactions) if !is_init && is_open => {
+                    let content_sender =
+                        stream.content_sender.as_ref().expect("The stream is still open.");
+                    for chunk in TransactionBatchWrapper(transactions).into() {
+                        // The validation may have already ended, in which case the rest of the
+                        // content is not needed.
+                        if content_sender.unbounded_send(chunk).is_err() {
+                            break;
+                        }
+                    }
+                }
+                ProposalContent::Fin { block_hash } if !is_init && is_open => {
+                    stream.content_sender = None;
+                    let fin_sender = stream.fin_sender.take().expect("The stream is still open.");
+                    // As above, the validation may have already ended.
+                    let _ = fin_sender.send(block_hash);
+                }
+                content => {
+                    warn!(
+                        "Ignoring proposal part {} of round {} by {:?}, which doesn't fit its \
+                         place in the stream: {:?}",
+                        part.sequence_number, part.round, part.proposer, content
+                    );
+                }
+            }
+        }
+        started
+    }
+}
@@ -0,0 +1,120 @@
+use futures::StreamExt;
+use papyrus_protobuf::consensus::{ProposalContent, ProposalPart};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::crypto::utils::Signature;
+use starknet_types_core::felt::Felt;
+
+use super::{ProposalStreams, MAX_OPEN_STREAMS_PER_PROPOSER, MAX_PENDING_PARTS};
+use crate::test_utils::test_transaction;
+use crate::types::{ProposalInit, ValidatorId};
+
+const PROPOSER: u32 = 1;
+
+fn part(sequence_number: u32, content: ProposalContent) -> ProposalPart {
+    ProposalPart {
+        height: 0,
+        round: 0,
+        proposer: PROPOSER.into(),
+        sequence_number,
+        content,
+        signature: Signature::default(),
+    }
+}
+
+fn transactions(chunks: &[u32]) -> ProposalContent {
+    ProposalContent::Transactions(chunks.iter().copied().map(test_transaction).collect())
+}
+
+fn fin() -> ProposalContent {
+    ProposalContent::Fin { block_hash: BlockHash(Felt::ONE) }
+}
+
+#[tokio::test]
+async fn parts_are_passed_on_as_they_arrive() {
+    let mut streams = ProposalStreams::<u32>::new();
+
+    let (init, mut content_receiver, fin_receiver) =
+        streams.handle_part(part(0, ProposalContent::Init { valid_round: None })).unwrap();
+    let proposer: ValidatorId = PROPOSER.into();
+    assert_eq!(
+        init,
+        ProposalInit { height: BlockNumber(0), round: 0, proposer, valid_round: None }
+    );
+
+    // The validation can start before the rest of the proposal arrives.
+    assert!(streams.handle_part(part(1, transactions(&[1, 2]))).is_none());
+    assert_eq!(content_receiver.next().await, Some(1));
+    assert_eq!(content_receiver.next().await, Some(2));
+
+    assert!(streams.handle_part(part(2, fin())).is_none());
+    assert_eq!(content_receiver.next().await, None);
+    assert_eq!(fin_receiver.await, Ok(BlockHash(Felt::ONE)));
+}
+
+#[tokio::test]
+async fn out_of_order_parts_are_reassembled() {
+    let mut streams = ProposalStreams::<u32>::new();
+
+    for part in [part(3, fin()), part(2, transactions(&[3])), part(1, transactions(&[1, 2]))] {
+        assert!(streams.handle_part(part).is_none());
+    }
+    let (_, content_receiver, fin_receiver) =
+        streams.handle_part(part(0, ProposalContent::Init { valid_round: None })).unwrap();
+
+    assert_eq!(content_receiver.collect::<Vec<_>>().await, vec![1, 2, 3]);
+    assert_eq!(fin_receiver.await, Ok(BlockHash(Felt::ONE)));
+}
+
+#[tokio::test]
+async fn repeated_and_misplaced_parts_are_ignored() {
+    let mut streams = ProposalStreams::<u32>::new();
+
+    let (_, content_receiver, fin_receiver) =
+        streams.handle_part(part(0, ProposalContent::Init { valid_round: None })).unwrap();
+    assert!(streams.handle_part(part(0, ProposalContent::Init { valid_round: None })).is_none());
+    assert!(streams.handle_part(part(1, ProposalContent::Init { valid_round: None })).is_none());
+    assert!(streams.handle_part(part(2, transactions(&[1]))).is_none());
+    assert!(streams.handle_part(part(2, transactions(&[2]))).is_none());
+    assert!(streams.handle_part(part(3, fin())).is_none());
+    assert!(streams.handle_part(part(4, transactions(&[3]))).is_none());
+
+    assert_eq!(content_receiver.collect::<Vec<_>>().await, vec![1]);
+    assert_eq!(fin_receiver.await, Ok(BlockHash(Felt::ONE)));
+}
+
+#[tokio::test]
+async fn parts_far_ahead_of_their_stream_are_dropped() {
+    let mut streams = ProposalStreams::<u32>::new();
+
+    let (_, content_receiver, fin_receiver) =
+        streams.handle_part(part(0, ProposalContent::Init { valid_round: None })).unwrap();
+    assert!(streams.handle_part(part(MAX_PENDING_PARTS + 1, fin())).is_none());
+    for sequence_number in 1..=MAX_PENDING_PARTS {
+        let content = transactions(&[sequence_number]);
+        assert!(streams.handle_part(part(sequence_number, content)).is_none());
+    }
+    // The fin was dropped, so its place in the stream is still free.
+    assert!(streams.handle_part(part(MAX_PENDING_PARTS + 1, transactions(&[0]))).is_none());
+    assert!(streams.handle_part(part(MAX_PENDING_PARTS + 2, fin())).is_none());
+
+    let expected_content: Vec<_> = (1..=MAX_PENDING_PARTS).chain([0]).collect();
+    assert_eq!(content_receiver.collect::<Vec<_>>().await, expected_content);
+    assert_eq!(fin_receiver.await, Ok(BlockHash(Felt::ONE)));
+}
+
+#[tokio::test]
+async fn proposer_open_streams_are_limited() {
+    let mut streams = ProposalStreams::<u32>::new();
+    let init =
+        |round| ProposalPart { round, ..part(0, ProposalContent::Init { valid_round: None }) };
+
+    let max_open_streams = u32::try_from(MAX_OPEN_STREAMS_PER_PROPOSER).unwrap();
+    for round in 0..max_open_streams {
+        assert!(streams.handle_part(init(round)).is_some());
+    }
+    assert!(streams.handle_part(init(max_open_streams)).is_none());
+
+    // Once a stream is completed, the proposer can open another.
+    assert!(streams.handle_part(part(1, fin())).is_none());
+    assert!(streams.handle_part(init(max_open_streams)).is_some());
+}
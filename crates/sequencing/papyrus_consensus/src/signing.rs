@@ -0,0 +1,187 @@
+//! Signing and verification of consensus messages. Each validator signs its messages with its
+//! Stark key, so that peers cannot impersonate validators, and so that the precommits of a
+//! decision form a commit certificate which anyone holding the validators' public keys can verify.
+//!
+//! The signed hashes include the chain id, so that a message signed on one chain, e.g. a testnet,
+//! cannot be replayed on another chain which shares validators with it.
+
+#[cfg(test)]
+#[path = "signing_test.rs"]
+mod signing_test;
+
+use std::fmt::Debug;
+
+use papyrus_common::transaction_hash::get_transaction_hash;
+use papyrus_common::TransactionOptions;
+use papyrus_protobuf::consensus::{
+    ConsensusMessage,
+    ProposalContent,
+    ProposalPart,
+    Vote,
+    VoteType,
+};
+use starknet_api::core::ChainId;
+use starknet_api::crypto::utils::{verify_message_hash_signature, PublicKey, Signature};
+use starknet_api::transaction::Transaction;
+use starknet_api::StarknetApiError;
+use starknet_crypto::FieldElement;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+
+// Prefixes of the signed hashes, which separate the domains of proposals and votes.
+const PROPOSAL_PREFIX: &[u8] = b"PROPOSAL";
+const VOTE_PREFIX: &[u8] = b"VOTE";
+
+/// Signs consensus messages of the given chain with the private key of this node's validator.
+#[derive(Clone)]
+pub struct ConsensusSigner {
+    private_key: FieldElement,
+    public_key: PublicKey,
+    chain_id: ChainId,
+}
+
+impl ConsensusSigner {
+    pub fn new(private_key: Felt, chain_id: ChainId) -> Self {
+        assert_ne!(private_key, Felt::ZERO, "The private key of a validator must be non-zero.");
+        let private_key = to_field_element(&private_key);
+        let public_key = PublicKey(to_felt(&starknet_crypto::get_public_key(&private_key)));
+        Self { private_key, public_key, chain_id }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// The chain whose messages are signed, against which the messages of peers are verified.
+    pub fn chain_id(&self) -> &ChainId {
+        &self.chain_id
+    }
+
+    /// Fails if the hash of one of the part's transactions cannot be calculated.
+    pub fn sign_proposal_part(&self, part: ProposalPart) -> Result<ProposalPart, StarknetApiError> {
+        let signature = self.sign(&proposal_part_hash(&part, &self.chain_id)?);
+        Ok(ProposalPart { signature, ..part })
+    }
+
+    pub fn sign_vote(&self, vote: Vote) -> Vote {
+        let signature = self.sign(&vote_hash(&vote, &self.chain_id));
+        Vote { signature, ..vote }
+    }
+
+    fn sign(&self, message_hash: &Felt) -> Signature {
+        let message_hash = to_field_element(message_hash);
+        let k = starknet_crypto::rfc6979_generate_k(&message_hash, &self.private_key, None);
+        let signature = starknet_crypto::sign(&self.private_key, &message_hash, &k)
+            .expect("Signing with an RFC 6979 nonce should succeed.");
+        Signature { r: to_felt(&signature.r), s: to_felt(&signature.s) }
+    }
+}
+
+// Avoid leaking the private key to the logs.
+impl Debug for ConsensusSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsensusSigner")
+            .field("public_key", &self.public_key)
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+/// The hash signed by the proposer, over the part's position in the proposal and its content.
+pub fn proposal_part_hash(
+    part: &ProposalPart,
+    chain_id: &ChainId,
+) -> Result<Felt, StarknetApiError> {
+    let content = match &part.content {
+        ProposalContent::Init { valid_round: Some(valid_round) } => {
+            [Felt::ZERO, Felt::ONE, (*valid_round).into()]
+        }
+        ProposalContent::Init { valid_round: None } => [Felt::ZERO, Felt::ZERO, Felt::ZERO],
+        ProposalContent::Transactions(transactions) => {
+            [Felt::ONE, transactions_hash(transactions, chain_id)?, Felt::ZERO]
+        }
+        ProposalContent::Fin { block_hash } => [Felt::TWO, block_hash.0, Felt::ZERO],
+    };
+    Ok(Poseidon::hash_array(&[
+        Felt::from_bytes_be_slice(PROPOSAL_PREFIX),
+        chain_id_as_felt(chain_id),
+        part.height.into(),
+        part.round.into(),
+        *part.proposer.0.key(),
+        part.sequence_number.into(),
+        content[0],
+        content[1],
+        content[2],
+    ]))
+}
+
+// The transactions are bound to the block hash only once the proposal is validated, so they are
+// signed as well, to keep others from injecting transactions into the proposal. Their canonical
+// hashes are signed, rather than their encoding, so that the signature does not depend on how the
+// transactions are serialized, and survives their conversion to and from protobuf.
+fn transactions_hash(
+    transactions: &[Transaction],
+    chain_id: &ChainId,
+) -> Result<Felt, StarknetApiError> {
+    let transaction_hashes = transactions
+        .iter()
+        .map(|transaction| {
+            get_transaction_hash(transaction, chain_id, &TransactionOptions::default())
+                .map(|transaction_hash| transaction_hash.0)
+        })
+        .collect::<Result<Vec<Felt>, StarknetApiError>>()?;
+    Ok(Poseidon::hash_array(&transaction_hashes))
+}
+
+/// The hash signed by the voter. A nil vote is hashed differently than a vote for any block.
+pub fn vote_hash(vote: &Vote, chain_id: &ChainId) -> Felt {
+    let vote_type = match vote.vote_type {
+        VoteType::Prevote => Felt::ZERO,
+        VoteType::Precommit => Felt::ONE,
+    };
+    let block_hash = match vote.block_hash {
+        Some(block_hash) => [Felt::ONE, block_hash.0],
+        None => [Felt::ZERO, Felt::ZERO],
+    };
+    Poseidon::hash_array(&[
+        Felt::from_bytes_be_slice(VOTE_PREFIX),
+        chain_id_as_felt(chain_id),
+        vote_type,
+        vote.height.into(),
+        vote.round.into(),
+        block_hash[0],
+        block_hash[1],
+        *vote.voter.0.key(),
+    ])
+}
+
+/// Returns whether the message of the given chain was signed by the owner of the given public key.
+pub fn verify_message(
+    message: &ConsensusMessage,
+    public_key: &PublicKey,
+    chain_id: &ChainId,
+) -> bool {
+    let (message_hash, signature) = match message {
+        // A proposal part whose transactions cannot be hashed cannot have been signed.
+        ConsensusMessage::ProposalPart(part) => match proposal_part_hash(part, chain_id) {
+            Ok(message_hash) => (message_hash, &part.signature),
+            Err(_) => return false,
+        },
+        ConsensusMessage::Vote(vote) => (vote_hash(vote, chain_id), &vote.signature),
+    };
+    // Malformed signatures and keys are simply invalid.
+    verify_message_hash_signature(&message_hash, signature, public_key).unwrap_or(false)
+}
+
+// The chain id as a short string, as in the hashes of transactions.
+fn chain_id_as_felt(chain_id: &ChainId) -> Felt {
+    Felt::from_bytes_be_slice(chain_id.to_string().as_bytes())
+}
+
+fn to_field_element(felt: &Felt) -> FieldElement {
+    FieldElement::from_bytes_be(&felt.to_bytes_be()).expect("A felt should be a field element.")
+}
+
+fn to_felt(field_element: &FieldElement) -> Felt {
+    Felt::from_bytes_be(&field_element.to_bytes_be())
+}
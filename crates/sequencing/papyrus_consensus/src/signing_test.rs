@@ -0,0 +1,164 @@
+use papyrus_protobuf::consensus::{
+    ConsensusMessage,
+    ProposalContent,
+    ProposalPart,
+    Vote,
+    VoteType,
+};
+use starknet_api::block::BlockHash;
+use starknet_api::core::ChainId;
+use starknet_api::crypto::utils::Signature;
+use starknet_types_core::felt::Felt;
+use test_case::test_case;
+
+use super::{verify_message, ConsensusSigner};
+use crate::test_utils::{test_transaction, TEST_CHAIN_ID};
+use crate::types::ValidatorId;
+
+fn vote(vote_type: VoteType, block_hash: Option<BlockHash>, voter: ValidatorId) -> Vote {
+    Vote { vote_type, height: 1, round: 2, block_hash, voter, signature: Signature::default() }
+}
+
+#[test_case(Some(BlockHash(Felt::ONE)); "block")]
+#[test_case(None; "nil")]
+fn signed_vote_is_verified(block_hash: Option<BlockHash>) {
+    let signer = ConsensusSigner::new(Felt::TWO, TEST_CHAIN_ID);
+    let message =
+        ConsensusMessage::Vote(signer.sign_vote(vote(VoteType::Prevote, block_hash, 1_u32.into())));
+
+    assert!(verify_message(&message, &signer.public_key(), &TEST_CHAIN_ID));
+    let other_signer = ConsensusSigner::new(Felt::THREE, TEST_CHAIN_ID);
+    assert!(!verify_message(&message, &other_signer.public_key(), &TEST_CHAIN_ID));
+}
+
+#[test]
+fn message_of_another_chain_is_rejected() {
+    let signer = ConsensusSigner::new(Felt::TWO, TEST_CHAIN_ID);
+    let vote = signer.sign_vote(vote(VoteType::Prevote, Some(BlockHash(Felt::ONE)), 1_u32.into()));
+    let part = signer
+        .sign_proposal_part(ProposalPart {
+            height: 1,
+            round: 2,
+            proposer: 1_u32.into(),
+            sequence_number: 1,
+            content: ProposalContent::Transactions(vec![test_transaction(1)]),
+            signature: Signature::default(),
+        })
+        .unwrap();
+
+    for message in [ConsensusMessage::Vote(vote), ConsensusMessage::ProposalPart(part)] {
+        assert!(verify_message(&message, &signer.public_key(), &TEST_CHAIN_ID));
+        assert!(!verify_message(&message, &signer.public_key(), &ChainId::Mainnet));
+    }
+}
+
+#[test]
+fn tampered_vote_is_rejected() {
+    let signer = ConsensusSigner::new(Felt::TWO, TEST_CHAIN_ID);
+    let signed =
+        signer.sign_vote(vote(VoteType::Prevote, Some(BlockHash(Felt::ONE)), 1_u32.into()));
+
+    let tampered_votes = [
+        Vote { vote_type: VoteType::Precommit, ..signed.clone() },
+        Vote { block_hash: Some(BlockHash(Felt::TWO)), ..signed.clone() },
+        Vote { block_hash: None, ..signed.clone() },
+        Vote { round: signed.round + 1, ..signed.clone() },
+        Vote { voter: 2_u32.into(), ..signed.clone() },
+    ];
+    for tampered in tampered_votes {
+        assert!(!verify_message(
+            &ConsensusMessage::Vote(tampered),
+            &signer.public_key(),
+            &TEST_CHAIN_ID
+        ));
+    }
+}
+
+#[test]
+fn signed_proposal_parts_are_verified() {
+    let signer = ConsensusSigner::new(Felt::TWO, TEST_CHAIN_ID);
+    let part = |sequence_number, content| {
+        signer
+            .sign_proposal_part(ProposalPart {
+                height: 1,
+                round: 2,
+                proposer: 1_u32.into(),
+                sequence_number,
+                content,
+                signature: Signature::default(),
+            })
+            .unwrap()
+    };
+    let parts = [
+        (
+            part(0, ProposalContent::Init { valid_round: Some(1) }),
+            ProposalContent::Init { valid_round: None },
+        ),
+        (
+            part(1, ProposalContent::Transactions(vec![test_transaction(1)])),
+            ProposalContent::Transactions(vec![test_transaction(2)]),
+        ),
+        (
+            part(2, ProposalContent::Fin { block_hash: BlockHash(Felt::ONE) }),
+            ProposalContent::Fin { block_hash: BlockHash(Felt::TWO) },
+        ),
+    ];
+
+    for (signed, tampered_content) in parts {
+        assert!(verify_message(
+            &ConsensusMessage::ProposalPart(signed.clone()),
+            &signer.public_key(),
+            &TEST_CHAIN_ID
+        ));
+        let tampered_parts = [
+            ProposalPart { content: tampered_content, ..signed.clone() },
+            ProposalPart { sequence_number: signed.sequence_number + 1, ..signed.clone() },
+            ProposalPart { round: signed.round + 1, ..signed.clone() },
+        ];
+        for tampered in tampered_parts {
+            assert!(!verify_message(
+                &ConsensusMessage::ProposalPart(tampered),
+                &signer.public_key(),
+                &TEST_CHAIN_ID
+            ));
+        }
+    }
+}
+
+// Peers verify the messages as decoded from the network, so the signature must survive the
+// protobuf encoding.
+#[test]
+fn signature_is_verified_after_protobuf_round_trip() {
+    let signer = ConsensusSigner::new(Felt::TWO, TEST_CHAIN_ID);
+    let part = |sequence_number, content| {
+        ConsensusMessage::ProposalPart(
+            signer
+                .sign_proposal_part(ProposalPart {
+                    height: 1,
+                    round: 2,
+                    proposer: 1_u32.into(),
+                    sequence_number,
+                    content,
+                    signature: Signature::default(),
+                })
+                .unwrap(),
+        )
+    };
+    let messages = [
+        part(0, ProposalContent::Init { valid_round: Some(1) }),
+        part(1, ProposalContent::Transactions(vec![test_transaction(1), test_transaction(2)])),
+        part(2, ProposalContent::Fin { block_hash: BlockHash(Felt::ONE) }),
+        ConsensusMessage::Vote(signer.sign_vote(vote(
+            VoteType::Precommit,
+            Some(BlockHash(Felt::ONE)),
+            1_u32.into(),
+        ))),
+    ];
+
+    for message in messages {
+        let bytes: Vec<u8> = message.clone().into();
+        let decoded = ConsensusMessage::try_from(bytes).unwrap();
+        assert_eq!(decoded, message);
+        assert!(verify_message(&decoded, &signer.public_key(), &TEST_CHAIN_ID));
+    }
+}
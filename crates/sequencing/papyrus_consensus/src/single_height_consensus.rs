@@ -3,12 +3,27 @@
 mod single_height_consensus_test;
 
 use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
 use futures::channel::{mpsc, oneshot};
-use papyrus_protobuf::consensus::{ConsensusMessage, Vote, VoteType};
+use futures::future::{self, Join3, Ready};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use papyrus_protobuf::consensus::{
+    ConsensusMessage,
+    EquivocationEvidence,
+    ProposalPart,
+    Vote,
+    VoteType,
+};
 use starknet_api::block::{BlockHash, BlockNumber};
-use tracing::{debug, info, instrument, trace};
+use starknet_api::crypto::utils::Signature;
+use starknet_api::transaction::Transaction;
+use tracing::{debug, info, instrument, trace, warn};
 
+use crate::config::TimeoutsConfig;
+use crate::proposal_stream::ProposalStreams;
+use crate::signing::ConsensusSigner;
 use crate::state_machine::{StateMachine, StateMachineEvent};
 use crate::types::{
     ConsensusBlock,
@@ -18,53 +33,221 @@ use crate::types::{
     ProposalInit,
     Round,
     ValidatorId,
+    VotingPower,
 };
+use crate::validator_set::ValidatorSet;
+use crate::wal::{ConsensusWal, OwnProposal, WalEntry};
+use crate::{report_peer, ReportSender, TransactionBatchWrapper};
+
+// A received proposal, which resolves once its validation completes and its fin arrives.
+type PendingProposal<BlockT> =
+    Join3<Ready<ProposalInit>, oneshot::Receiver<BlockT>, oneshot::Receiver<BlockHash>>;
+
+/// A received proposal, along with the block it was validated into and its fin. Either is an error
+/// if the proposal was invalid or never completed.
+pub(crate) type ValidatedProposal<BlockT> =
+    (ProposalInit, Result<BlockT, oneshot::Canceled>, Result<BlockHash, oneshot::Canceled>);
+
+/// A timeout requested by the state machine. The caller runs the task, and once its duration
+/// elapses, passes it back to SingleHeightConsensus via `handle_task`.
+#[derive(Debug, PartialEq)]
+pub struct ShcTask {
+    pub duration: Duration,
+    pub event: StateMachineEvent,
+}
 
-const ROUND_ZERO: Round = 0;
+impl ShcTask {
+    pub async fn run(self) -> Self {
+        tokio::time::sleep(self.duration).await;
+        self
+    }
+}
+
+/// The outcome of SingleHeightConsensus handling its input: either the tasks the caller should run,
+/// or the decision for this height.
+#[derive(Debug, PartialEq)]
+pub enum ShcReturn<BlockT: ConsensusBlock> {
+    Tasks(Vec<ShcTask>),
+    Decision(Decision<BlockT>),
+}
 
 /// Struct which represents a single height of consensus. Each height is expected to be begun with a
 /// call to `start`, which is relevant if we are the proposer for this height's first round.
 /// SingleHeightConsensus receives messages directly as parameters to function calls. It can send
 /// out messages "directly" to the network, and returning a decision to the caller.
+///
+/// Its inputs and its own proposals and votes are written to the write-ahead log, which `start`
+/// replays if the node restarted in the middle of the height.
 pub(crate) struct SingleHeightConsensus<BlockT: ConsensusBlock> {
     height: BlockNumber,
-    validators: Vec<ValidatorId>,
+    validators: ValidatorSet,
     id: ValidatorId,
+    signer: ConsensusSigner,
+    timeouts: TimeoutsConfig,
     state_machine: StateMachine,
-    proposals: HashMap<Round, BlockT>,
+    // The proposal of each round, which is `None` if the proposal was invalid.
+    proposals: HashMap<Round, Option<BlockHash>>,
+    // The proposed blocks, which a proposal may re-propose in a later round.
+    blocks: HashMap<BlockHash, BlockT>,
+    proposal_streams: ProposalStreams<BlockT::ProposalChunk>,
+    // The received proposals whose validation is still in progress.
+    pending_proposals: FuturesUnordered<PendingProposal<BlockT>>,
+    // While replaying the log, the received proposals are kept by round and proposer, until the
+    // log shows that their validation completed.
+    replayed_proposals: HashMap<(Round, ValidatorId), PendingProposal<BlockT>>,
+    // The peers which sent the received proposals, reported if their validation fails.
+    proposal_reporters: HashMap<(Round, ValidatorId), ReportSender>,
     prevotes: HashMap<(Round, ValidatorId), Vote>,
     precommits: HashMap<(Round, ValidatorId), Vote>,
+    wal: ConsensusWal,
+    // While replaying the log, its entries are not written to it again.
+    replaying: bool,
+    // The proposals and votes of this node which were logged before a restart. The node must not
+    // contradict them.
+    logged_proposals: HashMap<Round, OwnProposal>,
+    logged_votes: Vec<Vote>,
 }
 
-impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
-    pub(crate) fn new(height: BlockNumber, id: ValidatorId, validators: Vec<ValidatorId>) -> Self {
-        // TODO(matan): Use actual weights, not just `len`.
-        let state_machine = StateMachine::new(id, validators.len() as u32);
+// The proposals are sent to the network, and logged, as batches of transactions.
+impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT>
+where
+    TransactionBatchWrapper: Into<Vec<BlockT::ProposalChunk>>,
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
+{
+    pub(crate) fn new(
+        height: BlockNumber,
+        id: ValidatorId,
+        signer: ConsensusSigner,
+        validators: ValidatorSet,
+        timeouts: TimeoutsConfig,
+        wal: ConsensusWal,
+    ) -> Self {
+        let state_machine = StateMachine::new(id, &validators);
         Self {
             height,
             validators,
             id,
+            signer,
+            timeouts,
             state_machine,
             proposals: HashMap::new(),
+            blocks: HashMap::new(),
+            proposal_streams: ProposalStreams::new(),
+            pending_proposals: FuturesUnordered::new(),
+            replayed_proposals: HashMap::new(),
+            proposal_reporters: HashMap::new(),
             prevotes: HashMap::new(),
             precommits: HashMap::new(),
+            wal,
+            replaying: false,
+            logged_proposals: HashMap::new(),
+            logged_votes: Vec::new(),
         }
     }
 
+    pub(crate) fn validators(&self) -> &ValidatorSet {
+        &self.validators
+    }
+
+    /// Starts the height, and replays its write-ahead log, if the node already began it before a
+    /// restart.
     #[instrument(skip_all, fields(height=self.height.0), level = "debug")]
     pub(crate) async fn start<ContextT: ConsensusContext<Block = BlockT>>(
         &mut self,
         context: &mut ContextT,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
         info!("Starting consensus with validators {:?}", self.validators);
-        let leader_fn =
-            |_round: Round| -> ValidatorId { context.proposer(&self.validators, self.height) };
+        let entries = self
+            .wal
+            .entries(self.height)
+            .map_err(|err| ConsensusError::WriteAheadLog(err.to_string()))?;
+        for entry in &entries {
+            match entry {
+                WalEntry::OwnProposal(proposal) => {
+                    self.logged_proposals.insert(proposal.round, proposal.clone());
+                }
+                WalEntry::OwnVote(vote) => self.logged_votes.push(vote.clone()),
+                WalEntry::Received(_) | WalEntry::Timeout(_) | WalEntry::ProposalValidated(..) => {}
+            }
+        }
+
+        let leader_fn = |round: Round| -> ValidatorId {
+            context.proposer(&self.validators, self.height, round)
+        };
         let events = self.state_machine.start(&leader_fn);
-        self.handle_state_machine_events(context, events).await
+        let mut tasks = match self.handle_state_machine_events(context, events).await? {
+            ShcReturn::Decision(decision) => return Ok(ShcReturn::Decision(decision)),
+            ShcReturn::Tasks(tasks) => tasks,
+        };
+        if entries.is_empty() {
+            return Ok(ShcReturn::Tasks(tasks));
+        }
+
+        info!("Replaying {} entries of the write-ahead log.", entries.len());
+        self.replaying = true;
+        for entry in entries {
+            let shc_return = match entry {
+                WalEntry::Received(ConsensusMessage::ProposalPart(part)) => {
+                    self.handle_proposal_part(context, part, None).await?
+                }
+                WalEntry::Received(ConsensusMessage::Vote(vote)) => {
+                    self.handle_vote(context, vote).await?
+                }
+                WalEntry::Timeout(event) => {
+                    self.handle_task(context, ShcTask { duration: Duration::ZERO, event }).await?
+                }
+                WalEntry::ProposalValidated(round, proposer) => {
+                    // The log is only read, so a proposal which is missing from it cannot be
+                    // validated again, and its validation is skipped.
+                    let Some(proposal) = self.replayed_proposals.remove(&(round, proposer)) else {
+                        warn!(
+                            "Skipping the validation of the proposal of round {round} by \
+                             {proposer:?}, which is missing from the write-ahead log."
+                        );
+                        continue;
+                    };
+                    let proposal = proposal.await;
+                    self.handle_validated_proposal(context, proposal).await?
+                }
+                // Own proposals and votes are reproduced by replaying the inputs which led to them.
+                WalEntry::OwnProposal(_) | WalEntry::OwnVote(_) => continue,
+            };
+            match shc_return {
+                ShcReturn::Decision(decision) => {
+                    self.replaying = false;
+                    return Ok(ShcReturn::Decision(decision));
+                }
+                ShcReturn::Tasks(new_tasks) => tasks.extend(new_tasks),
+            }
+        }
+        self.replaying = false;
+        // The validation of the rest of the proposals was still in progress before the restart.
+        self.pending_proposals
+            .extend(self.replayed_proposals.drain().map(|(_, proposal)| proposal));
+        Ok(ShcReturn::Tasks(tasks))
+    }
+
+    /// Receive a part of a proposal from a peer node. The validation of the proposal starts once
+    /// its init part arrives, and its other parts are passed on to it as they arrive. The peer
+    /// which sent the part is reported through `report_sender` if its proposal is invalid.
+    pub(crate) async fn handle_proposal_part<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        part: ProposalPart,
+        report_sender: Option<ReportSender>,
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        self.log(WalEntry::Received(ConsensusMessage::ProposalPart(part.clone())));
+        match self.proposal_streams.handle_part(part) {
+            Some((init, content_receiver, fin_receiver)) => {
+                self.handle_proposal(context, init, content_receiver, fin_receiver, report_sender)
+                    .await
+            }
+            None => Ok(ShcReturn::Tasks(Vec::new())),
+        }
     }
 
-    /// Receive a proposal from a peer node. Returns only once the proposal has been fully received
-    /// and processed.
+    /// Receive a proposal from a peer node, and start validating it. Once the validation completes,
+    /// the proposal is returned by `next_validated_proposal`.
     #[instrument(
         skip_all,
         fields(height = %self.height),
@@ -76,67 +259,125 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         init: ProposalInit,
         p2p_messages_receiver: mpsc::Receiver<<BlockT as ConsensusBlock>::ProposalChunk>,
         fin_receiver: oneshot::Receiver<BlockHash>,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
+        report_sender: Option<ReportSender>,
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
         debug!(
-            "Received proposal: proposal_height={}, proposer={:?}",
-            init.height.0, init.proposer
+            "Received proposal: proposal_height={}, round={}, proposer={:?}, valid_round={:?}",
+            init.height.0, init.round, init.proposer, init.valid_round
         );
-        let proposer_id = context.proposer(&self.validators, self.height);
-        if init.height != self.height {
-            let msg = format!("invalid height: expected {:?}, got {:?}", self.height, init.height);
-            return Err(ConsensusError::InvalidProposal(proposer_id, self.height, msg));
-        }
-        if init.proposer != proposer_id {
-            let msg =
-                format!("invalid proposer: expected {:?}, got {:?}", proposer_id, init.proposer);
-            return Err(ConsensusError::InvalidProposal(proposer_id, self.height, msg));
+        let round = init.round;
+        let proposer_id = context.proposer(&self.validators, self.height, round);
+        let fault = if init.height != self.height {
+            Some(format!("invalid height: expected {:?}, got {:?}", self.height, init.height))
+        } else if init.proposer != proposer_id {
+            Some(format!("invalid proposer: expected {:?}, got {:?}", proposer_id, init.proposer))
+        } else if init.valid_round.is_some_and(|valid_round| valid_round >= round) {
+            Some(format!("invalid valid round {:?} for round {round}", init.valid_round))
+        } else {
+            None
+        };
+        // An invalid proposal is the fault of the peer which sent it, and is only ignored.
+        if let Some(fault) = fault {
+            warn!("Ignoring an invalid proposal: {fault}. {:?}", init);
+            if let Some(report_sender) = report_sender {
+                report_peer(report_sender);
+            }
+            return Ok(ShcReturn::Tasks(Vec::new()));
         }
 
+        // The validation runs while the rest of the proposal streams in.
         let block_receiver = context.validate_proposal(self.height, p2p_messages_receiver).await;
-        // TODO(matan): Actual Tendermint should handle invalid proposals.
-        let block = block_receiver.await.map_err(|_| {
-            ConsensusError::InvalidProposal(
-                proposer_id,
-                self.height,
-                "block validation failed".into(),
-            )
-        })?;
-        // TODO(matan): Actual Tendermint should handle invalid proposals.
-        let fin = fin_receiver.await.map_err(|_| {
-            ConsensusError::InvalidProposal(
-                proposer_id,
-                self.height,
-                "proposal fin never received".into(),
-            )
-        })?;
-        // TODO(matan): Switch to signature validation and handle invalid proposals.
-        if block.id() != fin {
-            return Err(ConsensusError::InvalidProposal(
-                proposer_id,
-                self.height,
-                "block signature doesn't match expected block hash".into(),
-            ));
+        let proposal = future::join3(future::ready(init), block_receiver, fin_receiver);
+        if let Some(report_sender) = report_sender {
+            self.proposal_reporters.insert((round, proposer_id), report_sender);
+        }
+        if self.replaying {
+            self.replayed_proposals.insert((round, proposer_id), proposal);
+        } else {
+            self.pending_proposals.push(proposal);
+        }
+        Ok(ShcReturn::Tasks(Vec::new()))
+    }
+
+    /// The next received proposal whose validation completes. Returns `None` if no proposal is
+    /// being validated.
+    pub(crate) async fn next_validated_proposal(&mut self) -> Option<ValidatedProposal<BlockT>> {
+        self.pending_proposals.next().await
+    }
+
+    /// Handle a received proposal whose validation completed.
+    #[instrument(
+        skip_all,
+        fields(height = %self.height),
+        level = "debug",
+    )]
+    pub(crate) async fn handle_validated_proposal<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        (init, block, fin): ValidatedProposal<BlockT>,
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        let round = init.round;
+        let proposer_id = init.proposer;
+        let report_sender = self.proposal_reporters.remove(&(round, proposer_id));
+        // The outcome of the validation changes the state whether the proposal is valid or not, and
+        // so it is logged either way.
+        self.log(WalEntry::ProposalValidated(round, proposer_id));
+        if self.proposals.contains_key(&round) {
+            // TODO(matan): Handle conflicting proposals.
+            debug!("Ignoring another proposal for round {round}.");
+            return Ok(ShcReturn::Tasks(Vec::new()));
         }
-        let sm_proposal = StateMachineEvent::Proposal(Some(block.id()), ROUND_ZERO);
-        // TODO(matan): Handle multiple rounds.
-        self.proposals.insert(ROUND_ZERO, block);
-        let leader_fn =
-            |_round: Round| -> ValidatorId { context.proposer(&self.validators, self.height) };
+        // An invalid proposal is the fault of its proposer, and the node prevotes nil on it.
+        let sm_proposal = match validated_block(block, fin) {
+            Ok(block) => {
+                let block_hash = block.id();
+                self.proposals.insert(round, Some(block_hash));
+                self.blocks.insert(block_hash, block);
+                StateMachineEvent::Proposal(Some(block_hash), round, init.valid_round)
+            }
+            Err(fault) => {
+                warn!("Invalid proposal of round {round} by {proposer_id:?}: {fault}.");
+                if let Some(report_sender) = report_sender {
+                    report_peer(report_sender);
+                }
+                self.proposals.insert(round, None);
+                StateMachineEvent::Proposal(None, round, None)
+            }
+        };
+        let leader_fn = |round: Round| -> ValidatorId {
+            context.proposer(&self.validators, self.height, round)
+        };
         let sm_events = self.state_machine.handle_event(sm_proposal, &leader_fn);
         self.handle_state_machine_events(context, sm_events).await
     }
 
+    /// Handle a task which the caller ran to completion, i.e., an expired timeout.
+    #[instrument(skip_all)]
+    pub(crate) async fn handle_task<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        task: ShcTask,
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        debug!("Timeout expired: {:?}", task.event);
+        self.log(WalEntry::Timeout(task.event.clone()));
+        let leader_fn = |round: Round| -> ValidatorId {
+            context.proposer(&self.validators, self.height, round)
+        };
+        let sm_events = self.state_machine.handle_event(task.event, &leader_fn);
+        self.handle_state_machine_events(context, sm_events).await
+    }
+
     /// Handle messages from peer nodes.
     #[instrument(skip_all)]
     pub(crate) async fn handle_message<ContextT: ConsensusContext<Block = BlockT>>(
         &mut self,
         context: &mut ContextT,
         message: ConsensusMessage,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
         debug!("Received message: {:?}", message);
         match message {
-            ConsensusMessage::Proposal(_) => {
-                unimplemented!("Proposals should use `handle_proposal` due to fake streaming")
+            ConsensusMessage::ProposalPart(_) => {
+                unimplemented!("Proposal parts should use `handle_proposal_part` to be reassembled")
             }
             ConsensusMessage::Vote(vote) => self.handle_vote(context, vote).await,
         }
@@ -147,31 +388,43 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         &mut self,
         context: &mut ContextT,
         vote: Vote,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        let voting_power = self.validators.voting_power(&vote.voter);
+        if voting_power == 0 {
+            debug!("Ignoring a vote from {:?}, which is not a validator.", vote.voter);
+            return Ok(ShcReturn::Tasks(Vec::new()));
+        }
         let (votes, sm_vote) = match vote.vote_type {
-            VoteType::Prevote => {
-                (&mut self.prevotes, StateMachineEvent::Prevote(vote.block_hash, ROUND_ZERO))
-            }
-            VoteType::Precommit => {
-                (&mut self.precommits, StateMachineEvent::Precommit(vote.block_hash, ROUND_ZERO))
-            }
+            VoteType::Prevote => (
+                &mut self.prevotes,
+                StateMachineEvent::Prevote(vote.block_hash, vote.round, voting_power),
+            ),
+            VoteType::Precommit => (
+                &mut self.precommits,
+                StateMachineEvent::Precommit(vote.block_hash, vote.round, voting_power),
+            ),
         };
-        if let Some(old) = votes.get(&(ROUND_ZERO, vote.voter)) {
+        if let Some(old) = votes.get(&(vote.round, vote.voter)) {
             if old.block_hash != vote.block_hash {
-                return Err(ConsensusError::Equivocation(
-                    self.height,
-                    ConsensusMessage::Vote(old.clone()),
-                    ConsensusMessage::Vote(vote),
-                ));
+                return Err(ConsensusError::Equivocation(Box::new(EquivocationEvidence {
+                    first_vote: old.clone(),
+                    second_vote: vote,
+                })));
             } else {
                 // Replay, ignore.
-                return Ok(None);
+                return Ok(ShcReturn::Tasks(Vec::new()));
             }
         }
 
-        votes.insert((ROUND_ZERO, vote.voter), vote);
-        let leader_fn =
-            |_round: Round| -> ValidatorId { context.proposer(&self.validators, self.height) };
+        self.log(WalEntry::Received(ConsensusMessage::Vote(vote.clone())));
+        let votes = match vote.vote_type {
+            VoteType::Prevote => &mut self.prevotes,
+            VoteType::Precommit => &mut self.precommits,
+        };
+        votes.insert((vote.round, vote.voter), vote);
+        let leader_fn = |round: Round| -> ValidatorId {
+            context.proposer(&self.validators, self.height, round)
+        };
         let sm_events = self.state_machine.handle_event(sm_vote, &leader_fn);
         self.handle_state_machine_events(context, sm_events).await
     }
@@ -182,7 +435,8 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         &mut self,
         context: &mut ContextT,
         mut events: VecDeque<StateMachineEvent>,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        let mut tasks = Vec::new();
         while let Some(event) = events.pop_front() {
             trace!("Handling event: {:?}", event);
             match event {
@@ -190,28 +444,43 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
                     events.append(
                         &mut self
                             .handle_state_machine_get_proposal(context, block_hash, round)
-                            .await,
+                            .await?,
                     );
                 }
-                StateMachineEvent::Proposal(_, _) => {
-                    // Ignore proposals sent by the StateMachine as SingleHeightConsensus already
-                    // sent this out when responding to a GetProposal.
-                    // TODO(matan): How do we handle this when validValue is set?
+                StateMachineEvent::Proposal(Some(block_hash), round, Some(valid_round)) => {
+                    self.handle_state_machine_re_proposal(context, block_hash, round, valid_round)
+                        .await?;
+                }
+                StateMachineEvent::Proposal(_, _, _) => {
+                    // Ignore new proposals sent by the StateMachine as SingleHeightConsensus
+                    // already sent them out when responding to a GetProposal.
                 }
                 StateMachineEvent::Decision(block_hash, round) => {
                     return self.handle_state_machine_decision(block_hash, round).await;
                 }
-                StateMachineEvent::Prevote(block_hash, round) => {
+                StateMachineEvent::Prevote(block_hash, round, _) => {
                     self.handle_state_machine_vote(context, block_hash, round, VoteType::Prevote)
                         .await?;
                 }
-                StateMachineEvent::Precommit(block_hash, round) => {
+                StateMachineEvent::Precommit(block_hash, round, _) => {
                     self.handle_state_machine_vote(context, block_hash, round, VoteType::Precommit)
                         .await?;
                 }
+                StateMachineEvent::TimeoutPropose(round) => {
+                    let duration = self.timeouts.proposal.at_round(round);
+                    tasks.push(ShcTask { duration, event });
+                }
+                StateMachineEvent::TimeoutPrevote(round) => {
+                    let duration = self.timeouts.prevote.at_round(round);
+                    tasks.push(ShcTask { duration, event });
+                }
+                StateMachineEvent::TimeoutPrecommit(round) => {
+                    let duration = self.timeouts.precommit.at_round(round);
+                    tasks.push(ShcTask { duration, event });
+                }
             }
         }
-        Ok(None)
+        Ok(ShcReturn::Tasks(tasks))
     }
 
     #[instrument(skip(self, context), level = "debug")]
@@ -220,16 +489,37 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         context: &mut ContextT,
         block_hash: Option<BlockHash>,
         round: Round,
-    ) -> VecDeque<StateMachineEvent> {
+    ) -> Result<VecDeque<StateMachineEvent>, ConsensusError> {
         assert!(
             block_hash.is_none(),
             "BlockHash must be None since the state machine is requesting a BlockHash"
         );
         debug!("Proposer");
 
+        let id = match self.logged_proposals.get(&round).cloned() {
+            // Building the proposal again may produce a different block, which would contradict
+            // the proposal sent before the restart, so that proposal is sent again instead.
+            Some(logged_proposal) => self.send_own_proposal(context, logged_proposal).await?,
+            None => self.build_proposal(context, round).await,
+        };
+        let leader_fn = |round: Round| -> ValidatorId {
+            context.proposer(&self.validators, self.height, round)
+        };
+        Ok(self
+            .state_machine
+            .handle_event(StateMachineEvent::GetProposal(Some(id), round), &leader_fn))
+    }
+
+    // Builds a new block and streams it out as the proposal of the round, returning the block's ID.
+    async fn build_proposal<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        round: Round,
+    ) -> BlockHash {
         let (p2p_messages_receiver, block_receiver) = context.build_proposal(self.height).await;
         let (fin_sender, fin_receiver) = oneshot::channel();
-        let init = ProposalInit { height: self.height, proposer: self.id };
+        let init =
+            ProposalInit { height: self.height, round, proposer: self.id, valid_round: None };
         // Peering is a permanent component, so if sending to it fails we cannot continue.
         context
             .propose(init, p2p_messages_receiver, fin_receiver)
@@ -237,17 +527,86 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
             .expect("Failed sending Proposal to Peering");
         let block = block_receiver.await.expect("Block building failed.");
         let id = block.id();
+        // Peers can only complete the proposal once its fin is sent, so logging it before then
+        // makes sure that the node sends no other proposal for this round.
+        self.log(WalEntry::OwnProposal(own_proposal(round, None, &block)));
         // If we choose to ignore this error, we should carefully consider how this affects
         // Tendermint. The partially synchronous model assumes all messages arrive at some point,
         // and this failure means this proposal will never arrive.
         //
         // TODO(matan): Switch this to the Proposal signature.
         fin_sender.send(id).expect("Failed to send ProposalFin to Peering.");
-        let old = self.proposals.insert(round, block);
+        let old = self.proposals.insert(round, Some(id));
         assert!(old.is_none(), "There should be no entry for this round.");
-        let leader_fn =
-            |_round: Round| -> ValidatorId { context.proposer(&self.validators, self.height) };
-        self.state_machine.handle_event(StateMachineEvent::GetProposal(Some(id), round), &leader_fn)
+        self.blocks.insert(id, block);
+        id
+    }
+
+    // Sends out the proposal of a block which had a prevote quorum in `valid_round`, instead of
+    // building a new one.
+    #[instrument(skip(self, context), level = "debug")]
+    async fn handle_state_machine_re_proposal<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        block_hash: BlockHash,
+        round: Round,
+        valid_round: Round,
+    ) -> Result<(), ConsensusError> {
+        debug!("Re-proposing a valid block");
+        let proposal = match self.logged_proposals.get(&round).cloned() {
+            Some(logged_proposal) if logged_proposal.block_hash != block_hash => {
+                return Err(ConsensusError::ContradictsOwnProposal(
+                    round,
+                    logged_proposal.block_hash,
+                    block_hash,
+                ));
+            }
+            // Sent before the restart. It is sent again, in case it did not reach the peers.
+            Some(logged_proposal) => logged_proposal,
+            None => {
+                let block =
+                    self.blocks.get(&block_hash).expect("The re-proposed block should be known.");
+                let proposal = own_proposal(round, Some(valid_round), block);
+                self.log(WalEntry::OwnProposal(proposal.clone()));
+                proposal
+            }
+        };
+        self.send_own_proposal(context, proposal).await?;
+        Ok(())
+    }
+
+    // Streams out a logged proposal of this node, as it is, and returns the ID of its block. A
+    // block which is not known, since it was built before the restart, is restored from the
+    // proposal's content.
+    async fn send_own_proposal<ContextT: ConsensusContext<Block = BlockT>>(
+        &mut self,
+        context: &mut ContextT,
+        OwnProposal { round, valid_round, block_hash, content }: OwnProposal,
+    ) -> Result<BlockHash, ConsensusError> {
+        if !self.blocks.contains_key(&block_hash) {
+            let block_receiver =
+                context.validate_proposal(self.height, content_channel(content.clone())).await;
+            let block = block_receiver.await.ok().filter(|block| block.id() == block_hash);
+            let Some(block) = block else {
+                let msg = format!(
+                    "the proposal of round {round}, which this node sent before restarting, is \
+                     invalid"
+                );
+                return Err(ConsensusError::InvalidProposal(self.id, self.height, msg));
+            };
+            self.blocks.insert(block_hash, block);
+        }
+        self.proposals.insert(round, Some(block_hash));
+
+        let (fin_sender, fin_receiver) = oneshot::channel();
+        fin_sender.send(block_hash).expect("The fin receiver is alive.");
+        let init = ProposalInit { height: self.height, round, proposer: self.id, valid_round };
+        // Peering is a permanent component, so if sending to it fails we cannot continue.
+        context
+            .propose(init, content_channel(content), fin_receiver)
+            .await
+            .expect("Failed sending Proposal to Peering");
+        Ok(block_hash)
     }
 
     #[instrument(skip_all)]
@@ -257,18 +616,40 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         block_hash: Option<BlockHash>,
         round: Round,
         vote_type: VoteType,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
-        let votes = match vote_type {
+    ) -> Result<(), ConsensusError> {
+        let vote = self.signer.sign_vote(Vote {
+            vote_type,
+            height: self.height.0,
+            round,
+            block_hash,
+            voter: self.id,
+            signature: Signature::default(),
+        });
+        let logged_vote = self
+            .logged_votes
+            .iter()
+            .find(|logged| logged.vote_type == vote.vote_type && logged.round == round);
+        match logged_vote {
+            Some(logged_vote) if logged_vote.block_hash != block_hash => {
+                return Err(ConsensusError::ContradictsOwnVote(
+                    Box::new(logged_vote.clone()),
+                    Box::new(vote),
+                ));
+            }
+            // Sent before the restart. It is sent again, in case it did not reach the peers.
+            Some(_) => {}
+            None => self.log(WalEntry::OwnVote(vote.clone())),
+        }
+        let votes = match vote.vote_type {
             VoteType::Prevote => &mut self.prevotes,
             VoteType::Precommit => &mut self.precommits,
         };
-        let vote = Vote { vote_type, height: self.height.0, round, block_hash, voter: self.id };
         if let Some(old) = votes.insert((round, self.id), vote.clone()) {
             // TODO(matan): Consider refactoring not to panic, rather log and return the error.
             panic!("State machine should not send repeat votes: old={:?}, new={:?}", old, vote);
         }
         context.broadcast(ConsensusMessage::Vote(vote)).await?;
-        Ok(None)
+        Ok(())
     }
 
     #[instrument(skip_all)]
@@ -276,23 +657,84 @@ impl<BlockT: ConsensusBlock> SingleHeightConsensus<BlockT> {
         &mut self,
         block_hash: BlockHash,
         round: Round,
-    ) -> Result<Option<Decision<BlockT>>, ConsensusError> {
-        let block =
-            self.proposals.remove(&round).expect("StateMachine arrived at an unknown decision");
-        assert_eq!(block.id(), block_hash, "StateMachine block hash should match the stored block");
+    ) -> Result<ShcReturn<BlockT>, ConsensusError> {
+        let proposal_id =
+            self.proposals.get(&round).expect("StateMachine arrived at an unknown decision");
+        assert_eq!(
+            *proposal_id,
+            Some(block_hash),
+            "StateMachine block hash should match the stored proposal"
+        );
+        let block = self.blocks.remove(&block_hash).expect("The decided block should be known.");
         let supporting_precommits: Vec<Vote> = self
             .validators
-            .iter()
+            .ids()
             .filter_map(|v| {
-                let vote = self.precommits.get(&(round, *v))?;
+                let vote = self.precommits.get(&(round, v))?;
                 if vote.block_hash != Some(block_hash) {
                     return None;
                 }
                 Some(vote.clone())
             })
             .collect();
-        // TODO(matan): Check actual weights.
-        assert!(supporting_precommits.len() >= self.state_machine.quorum_size() as usize);
-        Ok(Some(Decision { precommits: supporting_precommits, block }))
+        let supporting_voting_power: VotingPower = supporting_precommits
+            .iter()
+            .map(|vote| self.validators.voting_power(&vote.voter))
+            .sum();
+        assert!(supporting_voting_power >= self.state_machine.quorum_size());
+        Ok(ShcReturn::Decision(Decision { precommits: supporting_precommits, block }))
+    }
+
+    fn log(&self, entry: WalEntry) {
+        // The replayed inputs are already logged. The own proposals and votes are logged only if
+        // they were not logged before the restart, so they are logged during the replay as well.
+        if self.replaying && !matches!(entry, WalEntry::OwnProposal(_) | WalEntry::OwnVote(_)) {
+            return;
+        }
+        // A node which cannot log its actions must not take them.
+        self.wal
+            .append(self.height, &entry)
+            .expect("Failed to write to the consensus write-ahead log.");
+    }
+}
+
+// The proposal of this node, with the content of its block as it is sent to the network.
+fn own_proposal<BlockT: ConsensusBlock>(
+    round: Round,
+    valid_round: Option<Round>,
+    block: &BlockT,
+) -> OwnProposal
+where
+    Vec<BlockT::ProposalChunk>: Into<TransactionBatchWrapper>,
+{
+    let content: TransactionBatchWrapper = block.proposal_iter().collect::<Vec<_>>().into();
+    OwnProposal { round, valid_round, block_hash: block.id(), content: content.0 }
+}
+
+// A closed channel which holds the whole content of a proposal.
+fn content_channel<ChunkT>(content: Vec<Transaction>) -> mpsc::Receiver<ChunkT>
+where
+    TransactionBatchWrapper: Into<Vec<ChunkT>>,
+{
+    let chunks: Vec<ChunkT> = TransactionBatchWrapper(content).into();
+    let (mut content_sender, content_receiver) = mpsc::channel(chunks.len());
+    for chunk in chunks {
+        content_sender.try_send(chunk).expect("The channel has room for the whole block.");
+    }
+    content_sender.close_channel();
+    content_receiver
+}
+
+// The block of a completed proposal, or the reason the proposal is invalid.
+fn validated_block<BlockT: ConsensusBlock>(
+    block: Result<BlockT, oneshot::Canceled>,
+    fin: Result<BlockHash, oneshot::Canceled>,
+) -> Result<BlockT, &'static str> {
+    let block = block.map_err(|_| "block validation failed")?;
+    let fin = fin.map_err(|_| "proposal fin never received")?;
+    // TODO(matan): Switch to signature validation.
+    if block.id() != fin {
+        return Err("block signature doesn't match expected block hash");
     }
+    Ok(block)
 }
@@ -1,33 +1,58 @@
 use std::sync::{Arc, OnceLock};
 
 use futures::channel::{mpsc, oneshot};
-use papyrus_protobuf::consensus::{ConsensusMessage, Vote, VoteType};
+use futures::StreamExt;
+use papyrus_protobuf::consensus::{
+    ConsensusMessage,
+    EquivocationEvidence,
+    ProposalContent,
+    ProposalPart,
+    Vote,
+    VoteType,
+};
 use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::crypto::utils::Signature;
 use starknet_types_core::felt::Felt;
 use tokio;
 
-use super::SingleHeightConsensus;
-use crate::test_utils::{MockTestContext, TestBlock};
-use crate::types::{ConsensusBlock, ProposalInit, ValidatorId};
+use super::{ShcReturn, ShcTask, SingleHeightConsensus};
+use crate::config::TimeoutsConfig;
+use crate::signing::verify_message;
+use crate::state_machine::StateMachineEvent;
+use crate::test_utils::{
+    test_signer,
+    test_transaction,
+    test_validator_set,
+    test_wal,
+    MockTestContext,
+    TestBlock,
+    TEST_CHAIN_ID,
+};
+use crate::types::{ConsensusBlock, ConsensusError, ProposalInit, ValidatorId};
+use crate::wal::{OwnProposal, WalEntry};
+
+const NO_TASKS: ShcReturn<TestBlock> = ShcReturn::Tasks(Vec::new());
 
 fn prevote(block_hash: Option<BlockHash>, height: u64, voter: ValidatorId) -> ConsensusMessage {
-    ConsensusMessage::Vote(Vote {
+    ConsensusMessage::Vote(test_signer(voter).sign_vote(Vote {
         vote_type: VoteType::Prevote,
         height,
         round: 0,
         block_hash,
         voter,
-    })
+        signature: Signature::default(),
+    }))
 }
 
 fn precommit(block_hash: Option<BlockHash>, height: u64, voter: ValidatorId) -> ConsensusMessage {
-    ConsensusMessage::Vote(Vote {
+    ConsensusMessage::Vote(test_signer(voter).sign_vote(Vote {
         vote_type: VoteType::Precommit,
         height,
         round: 0,
         block_hash,
         voter,
-    })
+        signature: Signature::default(),
+    }))
 }
 
 #[tokio::test]
@@ -38,13 +63,17 @@ async fn proposer() {
     let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
     let block_id = block.id();
 
+    let (wal, _wal_dir) = test_wal();
     let mut shc = SingleHeightConsensus::new(
         BlockNumber(0),
         node_id,
-        vec![node_id, 2_u32.into(), 3_u32.into(), 4_u32.into()],
+        test_signer(node_id),
+        test_validator_set([node_id, 2_u32.into(), 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
     );
 
-    context.expect_proposer().returning(move |_, _| node_id);
+    context.expect_proposer().returning(move |_, _, _| node_id);
     let block_clone = block.clone();
     context.expect_build_proposal().returning(move |_| {
         let (_, content_receiver) = mpsc::channel(1);
@@ -66,11 +95,11 @@ async fn proposer() {
         .withf(move |msg: &ConsensusMessage| msg == &prevote(Some(block_id), 0, node_id))
         .returning(move |_| Ok(()));
     // Sends proposal and prevote.
-    assert!(matches!(shc.start(&mut context).await, Ok(None)));
+    assert_eq!(shc.start(&mut context).await, Ok(NO_TASKS));
 
     assert_eq!(
         shc.handle_message(&mut context, prevote(Some(block.id()), 0, 2_u32.into())).await,
-        Ok(None)
+        Ok(NO_TASKS)
     );
     // 3 of 4 Prevotes is enough to send a Precommit.
     context
@@ -79,7 +108,7 @@ async fn proposer() {
         .returning(move |_| Ok(()));
     assert_eq!(
         shc.handle_message(&mut context, prevote(Some(block.id()), 0, 3_u32.into())).await,
-        Ok(None)
+        Ok(NO_TASKS)
     );
 
     let precommits = vec![
@@ -88,9 +117,21 @@ async fn proposer() {
         precommit(Some(block.id()), 0, 2_u32.into()),
         precommit(Some(block.id()), 0, 3_u32.into()),
     ];
-    assert_eq!(shc.handle_message(&mut context, precommits[1].clone()).await, Ok(None));
-    assert_eq!(shc.handle_message(&mut context, precommits[2].clone()).await, Ok(None));
-    let decision = shc.handle_message(&mut context, precommits[3].clone()).await.unwrap().unwrap();
+    assert_eq!(shc.handle_message(&mut context, precommits[1].clone()).await, Ok(NO_TASKS));
+    // A precommit quorum, which does not agree on a value, starts the precommit timeout.
+    let precommit_timeout = ShcTask {
+        duration: TimeoutsConfig::default().precommit.base,
+        event: StateMachineEvent::TimeoutPrecommit(0),
+    };
+    assert_eq!(
+        shc.handle_message(&mut context, precommits[2].clone()).await,
+        Ok(ShcReturn::Tasks(vec![precommit_timeout]))
+    );
+    let ShcReturn::Decision(decision) =
+        shc.handle_message(&mut context, precommits[3].clone()).await.unwrap()
+    else {
+        panic!("Expected a decision.");
+    };
     assert_eq!(decision.block, block);
     assert!(
         decision
@@ -114,17 +155,21 @@ async fn validator() {
     let block_id = block.id();
 
     // Creation calls to `context.validators`.
+    let (wal, _wal_dir) = test_wal();
     let mut shc = SingleHeightConsensus::new(
         BlockNumber(0),
         node_id,
-        vec![node_id, proposer, 3_u32.into(), 4_u32.into()],
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
     );
 
     // Send the proposal from the peer.
     let (fin_sender, fin_receiver) = oneshot::channel();
     fin_sender.send(block.id()).unwrap();
 
-    context.expect_proposer().returning(move |_, _| proposer);
+    context.expect_proposer().returning(move |_, _, _| proposer);
     let block_clone = block.clone();
     context.expect_validate_proposal().returning(move |_, _| {
         let (block_sender, block_receiver) = oneshot::channel();
@@ -138,16 +183,20 @@ async fn validator() {
     let res = shc
         .handle_proposal(
             &mut context,
-            ProposalInit { height: BlockNumber(0), proposer },
+            ProposalInit { height: BlockNumber(0), round: 0, proposer, valid_round: None },
             mpsc::channel(1).1, // content - ignored by SHC.
             fin_receiver,
+            None,
         )
         .await;
-    assert_eq!(res, Ok(None));
+    assert_eq!(res, Ok(NO_TASKS));
+    // The node prevotes once the proposal is validated.
+    let proposal = shc.next_validated_proposal().await.unwrap();
+    assert_eq!(shc.handle_validated_proposal(&mut context, proposal).await, Ok(NO_TASKS));
 
     assert_eq!(
         shc.handle_message(&mut context, prevote(Some(block.id()), 0, 2_u32.into())).await,
-        Ok(None)
+        Ok(NO_TASKS)
     );
     // 3 of 4 Prevotes is enough to send a Precommit.
     context
@@ -156,7 +205,7 @@ async fn validator() {
         .returning(move |_| Ok(()));
     assert_eq!(
         shc.handle_message(&mut context, prevote(Some(block.id()), 0, 3_u32.into())).await,
-        Ok(None)
+        Ok(NO_TASKS)
     );
 
     let precommits = vec![
@@ -164,9 +213,18 @@ async fn validator() {
         precommit(Some(block.id()), 0, 3_u32.into()),
         precommit(Some(block.id()), 0, node_id),
     ];
-    assert_eq!(shc.handle_message(&mut context, precommits[0].clone()).await, Ok(None));
-    let decision = shc.handle_message(&mut context, precommits[1].clone()).await.unwrap().unwrap();
+    assert_eq!(shc.handle_message(&mut context, precommits[0].clone()).await, Ok(NO_TASKS));
+    let ShcReturn::Decision(decision) =
+        shc.handle_message(&mut context, precommits[1].clone()).await.unwrap()
+    else {
+        panic!("Expected a decision.");
+    };
     assert_eq!(decision.block, block);
+    // The precommits are signed, so they serve as a commit certificate.
+    assert!(decision.precommits.iter().all(|vote| {
+        let public_key = test_signer(vote.voter).public_key();
+        verify_message(&ConsensusMessage::Vote(vote.clone()), &public_key, &TEST_CHAIN_ID)
+    }));
     assert!(
         decision
             .precommits
@@ -174,3 +232,475 @@ async fn validator() {
             .all(|item| precommits.contains(&ConsensusMessage::Vote(item)))
     );
 }
+
+#[tokio::test]
+async fn validator_prevotes_nil_on_proposal_timeout() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let timeouts = TimeoutsConfig::default();
+    let (wal, _wal_dir) = test_wal();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        timeouts,
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| proposer);
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert_eq!(shc.start(&mut context).await, Ok(ShcReturn::Tasks(vec![proposal_timeout])));
+
+    // The proposal does not arrive before the timeout expires.
+    context
+        .expect_broadcast()
+        .withf(move |msg: &ConsensusMessage| msg == &prevote(None, 0, node_id))
+        .returning(move |_| Ok(()));
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert_eq!(shc.handle_task(&mut context, proposal_timeout).await, Ok(NO_TASKS));
+}
+
+#[tokio::test]
+async fn proposer_of_next_round_proposes() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
+    let (wal, _wal_dir) = test_wal();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
+    );
+
+    // The proposer of the first round is silent, and this node proposes in the second round.
+    context
+        .expect_proposer()
+        .returning(move |_, _, round| if round == 0 { proposer } else { node_id });
+    context.expect_broadcast().returning(move |_| Ok(()));
+    shc.start(&mut context).await.unwrap();
+    let proposal_timeout = ShcTask {
+        duration: TimeoutsConfig::default().proposal.base,
+        event: StateMachineEvent::TimeoutPropose(0),
+    };
+    shc.handle_task(&mut context, proposal_timeout).await.unwrap();
+    for voter in [proposer, 3_u32.into()] {
+        shc.handle_message(&mut context, prevote(None, 0, voter)).await.unwrap();
+    }
+
+    let block_clone = block.clone();
+    context.expect_build_proposal().times(1).returning(move |_| {
+        let (_, content_receiver) = mpsc::channel(1);
+        let (block_sender, block_receiver) = oneshot::channel();
+        block_sender.send(block_clone.clone()).unwrap();
+        (content_receiver, block_receiver)
+    });
+    let fin_receiver = Arc::new(OnceLock::new());
+    let fin_receiver_clone = Arc::clone(&fin_receiver);
+    context.expect_propose().times(1).return_once(move |init, _, fin_receiver| {
+        let expected_init =
+            ProposalInit { height: BlockNumber(0), round: 1, proposer: node_id, valid_round: None };
+        assert_eq!(init, expected_init);
+        fin_receiver_clone.set(fin_receiver).unwrap();
+        Ok(())
+    });
+    // A nil precommit quorum moves the validators to the next round.
+    for voter in [proposer, 3_u32.into()] {
+        shc.handle_message(&mut context, precommit(None, 0, voter)).await.unwrap();
+    }
+
+    let fin = Arc::into_inner(fin_receiver).unwrap().take().unwrap().await.unwrap();
+    assert_eq!(fin, block.id());
+}
+
+#[tokio::test]
+async fn proposer_of_next_round_re_proposes_valid_block() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
+    let block_id = block.id();
+    let timeouts = TimeoutsConfig::default();
+    let (wal, _wal_dir) = test_wal();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        timeouts,
+        wal,
+    );
+
+    context
+        .expect_proposer()
+        .returning(move |_, _, round| if round == 0 { proposer } else { node_id });
+    let block_clone = block.clone();
+    context.expect_validate_proposal().returning(move |_, _| {
+        let (block_sender, block_receiver) = oneshot::channel();
+        block_sender.send(block_clone.clone()).unwrap();
+        block_receiver
+    });
+    context.expect_broadcast().returning(move |_| Ok(()));
+    shc.start(&mut context).await.unwrap();
+
+    // The block of the first round has a prevote quorum, but is not decided.
+    let (fin_sender, fin_receiver) = oneshot::channel();
+    fin_sender.send(block_id).unwrap();
+    let init = ProposalInit { height: BlockNumber(0), round: 0, proposer, valid_round: None };
+    shc.handle_proposal(&mut context, init, mpsc::channel(1).1, fin_receiver, None).await.unwrap();
+    let proposal = shc.next_validated_proposal().await.unwrap();
+    shc.handle_validated_proposal(&mut context, proposal).await.unwrap();
+    for voter in [proposer, 3_u32.into()] {
+        shc.handle_message(&mut context, prevote(Some(block_id), 0, voter)).await.unwrap();
+    }
+
+    // The block is proposed again in the next round, without building a new one.
+    context.expect_build_proposal().never();
+    let proposed = Arc::new(OnceLock::new());
+    let proposed_clone = Arc::clone(&proposed);
+    context.expect_propose().times(1).return_once(move |init, content_receiver, fin_receiver| {
+        proposed_clone.set((init, content_receiver, fin_receiver)).unwrap();
+        Ok(())
+    });
+    let precommit_timeout = ShcTask {
+        duration: timeouts.precommit.base,
+        event: StateMachineEvent::TimeoutPrecommit(0),
+    };
+    shc.handle_task(&mut context, precommit_timeout).await.unwrap();
+
+    let (init, content_receiver, fin_receiver) = Arc::into_inner(proposed).unwrap().take().unwrap();
+    let expected_init =
+        ProposalInit { height: BlockNumber(0), round: 1, proposer: node_id, valid_round: Some(0) };
+    assert_eq!(init, expected_init);
+    assert_eq!(content_receiver.collect::<Vec<_>>().await, block.content);
+    assert_eq!(fin_receiver.await.unwrap(), block_id);
+}
+
+#[tokio::test]
+async fn restarted_validator_replays_its_votes() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
+    let block_id = block.id();
+    let validators =
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1)));
+    let timeouts = TimeoutsConfig::default();
+    let (wal, _wal_dir) = test_wal();
+
+    context.expect_proposer().returning(move |_, _, _| proposer);
+    context.expect_validate_proposal().returning(move |_, _| {
+        let (block_sender, block_receiver) = oneshot::channel();
+        block_sender.send(block.clone()).unwrap();
+        block_receiver
+    });
+    // Any nil prevote, which would contradict the prevote for the block, fails the test.
+    context
+        .expect_broadcast()
+        .withf(move |msg: &ConsensusMessage| msg == &prevote(Some(block_id), 0, node_id))
+        .returning(move |_| Ok(()));
+    let proposal_part = |sequence_number, content| ProposalPart {
+        height: 0,
+        round: 0,
+        proposer,
+        sequence_number,
+        content,
+        signature: Signature::default(),
+    };
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        validators.clone(),
+        timeouts,
+        wal.clone(),
+    );
+    shc.start(&mut context).await.unwrap();
+    let init = proposal_part(0, ProposalContent::Init { valid_round: None });
+    assert_eq!(shc.handle_proposal_part(&mut context, init, None).await, Ok(NO_TASKS));
+    let fin = proposal_part(1, ProposalContent::Fin { block_hash: block_id });
+    assert_eq!(shc.handle_proposal_part(&mut context, fin, None).await, Ok(NO_TASKS));
+    let proposal = shc.next_validated_proposal().await.unwrap();
+    assert_eq!(shc.handle_validated_proposal(&mut context, proposal).await, Ok(NO_TASKS));
+
+    // The node restarts, and replays the proposal, so it resends its prevote.
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        validators,
+        timeouts,
+        wal.clone(),
+    );
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert_eq!(shc.start(&mut context).await, Ok(ShcReturn::Tasks(vec![proposal_timeout])));
+    // The timeout of the proposal, which already arrived, is ignored.
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert_eq!(shc.handle_task(&mut context, proposal_timeout).await, Ok(NO_TASKS));
+    // The prevote was logged once, before it was first sent.
+    let own_votes = wal
+        .entries(BlockNumber(0))
+        .unwrap()
+        .into_iter()
+        .filter(|entry| matches!(entry, WalEntry::OwnVote(_)))
+        .count();
+    assert_eq!(own_votes, 1);
+}
+
+#[tokio::test]
+async fn restarted_validator_does_not_contradict_logged_vote() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let timeouts = TimeoutsConfig::default();
+    let (wal, _wal_dir) = test_wal();
+    // The node prevoted for a block before restarting, but the proposal was not logged.
+    let ConsensusMessage::Vote(logged_prevote) = prevote(Some(BlockHash(Felt::ONE)), 0, node_id)
+    else {
+        unreachable!();
+    };
+    wal.append(BlockNumber(0), &WalEntry::OwnVote(logged_prevote)).unwrap();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        timeouts,
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| proposer);
+    shc.start(&mut context).await.unwrap();
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert!(matches!(
+        shc.handle_task(&mut context, proposal_timeout).await,
+        Err(ConsensusError::ContradictsOwnVote(..))
+    ));
+}
+
+#[tokio::test]
+async fn restarted_proposer_resends_logged_proposal() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
+    let block_id = block.id();
+    let (wal, _wal_dir) = test_wal();
+    // The node proposed a block before restarting.
+    let logged_proposal = OwnProposal {
+        round: 0,
+        valid_round: None,
+        block_hash: block_id,
+        content: block.content.iter().copied().map(test_transaction).collect(),
+    };
+    wal.append(BlockNumber(0), &WalEntry::OwnProposal(logged_proposal)).unwrap();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, 2_u32.into(), 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| node_id);
+    // Building the proposal again may produce another block, so the logged one is restored.
+    context.expect_build_proposal().never();
+    let block_clone = block.clone();
+    context.expect_validate_proposal().return_once(move |_, _| {
+        let (block_sender, block_receiver) = oneshot::channel();
+        block_sender.send(block_clone).unwrap();
+        block_receiver
+    });
+    let proposed = Arc::new(OnceLock::new());
+    let proposed_clone = Arc::clone(&proposed);
+    context.expect_propose().times(1).return_once(move |init, content_receiver, fin_receiver| {
+        proposed_clone.set((init, content_receiver, fin_receiver)).unwrap();
+        Ok(())
+    });
+    context
+        .expect_broadcast()
+        .withf(move |msg: &ConsensusMessage| msg == &prevote(Some(block_id), 0, node_id))
+        .returning(move |_| Ok(()));
+    assert_eq!(shc.start(&mut context).await, Ok(NO_TASKS));
+
+    let (init, content_receiver, fin_receiver) = Arc::into_inner(proposed).unwrap().take().unwrap();
+    let expected_init =
+        ProposalInit { height: BlockNumber(0), round: 0, proposer: node_id, valid_round: None };
+    assert_eq!(init, expected_init);
+    assert_eq!(content_receiver.collect::<Vec<_>>().await, block.content);
+    assert_eq!(fin_receiver.await.unwrap(), block_id);
+}
+
+#[tokio::test]
+async fn replay_skips_validation_of_missing_proposal() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let timeouts = TimeoutsConfig::default();
+    let (wal, _wal_dir) = test_wal();
+    // The validation of a proposal was logged, but the proposal itself was not.
+    wal.append(BlockNumber(0), &WalEntry::ProposalValidated(0, proposer)).unwrap();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        timeouts,
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| proposer);
+    let proposal_timeout =
+        ShcTask { duration: timeouts.proposal.base, event: StateMachineEvent::TimeoutPropose(0) };
+    assert_eq!(shc.start(&mut context).await, Ok(ShcReturn::Tasks(vec![proposal_timeout])));
+}
+
+#[tokio::test]
+async fn invalid_proposal_init_is_reported() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let (wal, _wal_dir) = test_wal();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| proposer);
+    context.expect_validate_proposal().never();
+    let invalid_inits = [
+        ProposalInit { height: BlockNumber(1), round: 0, proposer, valid_round: None },
+        ProposalInit { height: BlockNumber(0), round: 0, proposer: node_id, valid_round: None },
+        ProposalInit { height: BlockNumber(0), round: 1, proposer, valid_round: Some(1) },
+    ];
+    for init in invalid_inits {
+        let (report_sender, report_receiver) = oneshot::channel();
+        let res = shc
+            .handle_proposal(
+                &mut context,
+                init,
+                mpsc::channel(1).1,
+                oneshot::channel().1,
+                Some(report_sender),
+            )
+            .await;
+        // The proposal is ignored, rather than ending consensus, and its sender is reported.
+        assert_eq!(res, Ok(NO_TASKS));
+        assert_eq!(report_receiver.await, Ok(()));
+    }
+}
+
+#[tokio::test]
+async fn invalid_proposal_is_prevoted_nil_and_reported() {
+    let node_id: ValidatorId = 1_u32.into();
+    let proposer: ValidatorId = 2_u32.into();
+    let block = TestBlock { content: vec![1, 2, 3], id: BlockHash(Felt::ONE) };
+    // A failed validation, a missing fin, and a fin which doesn't match the block.
+    let invalid_proposals = [
+        (None, Some(block.id())),
+        (Some(block.clone()), None),
+        (Some(block.clone()), Some(BlockHash(Felt::TWO))),
+    ];
+    for (validated_block, fin) in invalid_proposals {
+        let mut context = MockTestContext::new();
+        let (wal, _wal_dir) = test_wal();
+        let mut shc = SingleHeightConsensus::new(
+            BlockNumber(0),
+            node_id,
+            test_signer(node_id),
+            test_validator_set([node_id, proposer, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+            TimeoutsConfig::default(),
+            wal,
+        );
+
+        context.expect_proposer().returning(move |_, _, _| proposer);
+        context.expect_validate_proposal().return_once(move |_, _| {
+            let (block_sender, block_receiver) = oneshot::channel();
+            if let Some(validated_block) = validated_block {
+                block_sender.send(validated_block).unwrap();
+            }
+            block_receiver
+        });
+        let (fin_sender, fin_receiver) = oneshot::channel();
+        if let Some(fin) = fin {
+            fin_sender.send(fin).unwrap();
+        }
+        let (report_sender, report_receiver) = oneshot::channel();
+        let res = shc
+            .handle_proposal(
+                &mut context,
+                ProposalInit { height: BlockNumber(0), round: 0, proposer, valid_round: None },
+                mpsc::channel(1).1,
+                fin_receiver,
+                Some(report_sender),
+            )
+            .await;
+        assert_eq!(res, Ok(NO_TASKS));
+
+        // The proposal doesn't end consensus. The node prevotes nil, and reports the proposer.
+        context
+            .expect_broadcast()
+            .withf(move |msg: &ConsensusMessage| msg == &prevote(None, 0, node_id))
+            .times(1)
+            .returning(move |_| Ok(()));
+        let proposal = shc.next_validated_proposal().await.unwrap();
+        assert_eq!(shc.handle_validated_proposal(&mut context, proposal).await, Ok(NO_TASKS));
+        assert_eq!(report_receiver.await, Ok(()));
+    }
+}
+
+#[tokio::test]
+async fn conflicting_votes_are_evidence() {
+    let mut context = MockTestContext::new();
+
+    let node_id: ValidatorId = 1_u32.into();
+    let voter: ValidatorId = 2_u32.into();
+    let (wal, _wal_dir) = test_wal();
+    let mut shc = SingleHeightConsensus::new(
+        BlockNumber(0),
+        node_id,
+        test_signer(node_id),
+        test_validator_set([node_id, voter, 3_u32.into(), 4_u32.into()].map(|id| (id, 1))),
+        TimeoutsConfig::default(),
+        wal,
+    );
+
+    context.expect_proposer().returning(move |_, _, _| voter);
+    shc.start(&mut context).await.unwrap();
+    let first_vote = prevote(Some(BlockHash(Felt::ONE)), 0, voter);
+    let second_vote = prevote(None, 0, voter);
+    assert_eq!(shc.handle_message(&mut context, first_vote.clone()).await, Ok(NO_TASKS));
+    // The conflicting vote is not counted, but returned along with the first one as evidence.
+    let evidence = match (first_vote, second_vote.clone()) {
+        (ConsensusMessage::Vote(first_vote), ConsensusMessage::Vote(second_vote)) => {
+            EquivocationEvidence { first_vote, second_vote }
+        }
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        shc.handle_message(&mut context, second_vote).await,
+        Err(ConsensusError::Equivocation(Box::new(evidence)))
+    );
+}
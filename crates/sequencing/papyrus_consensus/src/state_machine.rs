@@ -9,29 +9,42 @@ mod state_machine_test;
 
 use std::collections::{HashMap, VecDeque};
 
+use serde::{Deserialize, Serialize};
 use starknet_api::block::BlockHash;
 use tracing::trace;
 
-use crate::types::{Round, ValidatorId};
+use crate::types::{Round, ValidatorId, VotingPower};
+use crate::validator_set::ValidatorSet;
 
 /// Events which the state machine sends/receives.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StateMachineEvent {
     /// Sent by the state machine when a block is required to propose (BlockHash is always None).
     /// While waiting for the response of GetProposal, the state machine will buffer all other
     /// events. The caller must respond with a valid block hash for this height to the state
     /// machine, and the same round sent out.
     GetProposal(Option<BlockHash>, Round),
-    /// Consensus message, can be both sent from and to the state machine.
-    Proposal(Option<BlockHash>, Round),
-    /// Consensus message, can be both sent from and to the state machine.
-    Prevote(Option<BlockHash>, Round),
-    /// Consensus message, can be both sent from and to the state machine.
-    Precommit(Option<BlockHash>, Round),
+    /// Consensus message, can be both sent from and to the state machine. Carries the valid round
+    /// of a re-proposed block, i.e. the round in which it had a prevote quorum.
+    Proposal(Option<BlockHash>, Round, Option<Round>),
+    /// Consensus message, can be both sent from and to the state machine. Carries the voting power
+    /// of the voter.
+    Prevote(Option<BlockHash>, Round, VotingPower),
+    /// Consensus message, can be both sent from and to the state machine. Carries the voting power
+    /// of the voter.
+    Precommit(Option<BlockHash>, Round, VotingPower),
     /// The state machine returns this event to the caller when a decision is reached. Not
     /// expected as an inbound message. We presume that the caller is able to recover the set of
     /// precommits which led to this decision from the information returned here.
     Decision(BlockHash, Round),
+    /// Timeout events, sent from the state machine to request that the caller schedule them, and
+    /// sent back to the state machine once they expire.
+    /// Waiting for the proposal of the round (LOC 21).
+    TimeoutPropose(Round),
+    /// Waiting for a prevote quorum on a single value (LOC 34).
+    TimeoutPrevote(Round),
+    /// Waiting for a precommit quorum on a single value (LOC 47).
+    TimeoutPrecommit(Round),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,42 +57,69 @@ pub enum Step {
 /// State Machine. Major assumptions:
 /// 1. SHC handles replays and conflicts.
 /// 2. SM must handle "out of order" messages (E.g. vote arrives before proposal).
-/// 3. No network failures.
+/// 3. SHC schedules the timeouts requested by the SM, and sends them back once they expire.
 pub struct StateMachine {
     id: ValidatorId,
+    voting_power: VotingPower,
     round: Round,
     step: Step,
-    quorum: u32,
-    proposals: HashMap<Round, Option<BlockHash>>,
-    // {round: {block_hash: vote_count}
-    prevotes: HashMap<Round, HashMap<Option<BlockHash>, u32>>,
-    precommits: HashMap<Round, HashMap<Option<BlockHash>, u32>>,
+    // More than 2/3 of the total voting power (2f+1).
+    quorum: VotingPower,
+    // More than 1/3 of the total voting power, which must include at least one honest validator
+    // (f+1).
+    round_skip_threshold: VotingPower,
+    // {round: (block_hash, valid_round)}
+    proposals: HashMap<Round, (Option<BlockHash>, Option<Round>)>,
+    // {round: {block_hash: voting_power}
+    prevotes: HashMap<Round, HashMap<Option<BlockHash>, VotingPower>>,
+    precommits: HashMap<Round, HashMap<Option<BlockHash>, VotingPower>>,
+    // The block this node precommitted on most recently, and the round it did so in.
+    locked_value: Option<(BlockHash, Round)>,
+    // The block most recently seen with a prevote quorum, and the round of the quorum. The leader
+    // re-proposes it instead of a new block.
+    valid_value: Option<(BlockHash, Round)>,
     // When true, the state machine will wait for a GetProposal event, buffering all other input
     // events in `events_queue`.
     awaiting_get_proposal: bool,
     events_queue: VecDeque<StateMachineEvent>,
+    // Whether the vote timeouts of the current round were already scheduled.
+    prevote_timeout_scheduled: bool,
+    precommit_timeout_scheduled: bool,
 }
 
 impl StateMachine {
-    /// total_weight - the total voting weight of all validators for this height.
-    pub fn new(id: ValidatorId, total_weight: u32) -> Self {
+    /// validators - the validators of this height, whose voting power determines the quorums.
+    pub fn new(id: ValidatorId, validators: &ValidatorSet) -> Self {
+        assert!(!validators.is_empty(), "Consensus requires at least one validator.");
+        let total_voting_power = validators.total_voting_power();
+        let quorum = validators.quorum_size();
         Self {
             id,
+            voting_power: validators.voting_power(&id),
             round: 0,
             step: Step::Propose,
-            quorum: (2 * total_weight / 3) + 1,
+            quorum,
+            round_skip_threshold: total_voting_power - quorum + 1,
             proposals: HashMap::new(),
             prevotes: HashMap::new(),
             precommits: HashMap::new(),
+            locked_value: None,
+            valid_value: None,
             awaiting_get_proposal: false,
             events_queue: VecDeque::new(),
+            prevote_timeout_scheduled: false,
+            precommit_timeout_scheduled: false,
         }
     }
 
-    pub fn quorum_size(&self) -> u32 {
+    pub fn quorum_size(&self) -> VotingPower {
         self.quorum
     }
 
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
     /// Starts the state machine, effectively calling `StartRound(0)` from the paper. This is
     /// needed to trigger the first leader to propose.
     /// See [`GetProposal`](StateMachineEvent::GetProposal)
@@ -143,9 +183,9 @@ impl StateMachine {
             // sent to self.
             for e in self.handle_event_internal(event, leader_fn) {
                 match e {
-                    StateMachineEvent::Proposal(_, _)
-                    | StateMachineEvent::Prevote(_, _)
-                    | StateMachineEvent::Precommit(_, _) => {
+                    StateMachineEvent::Proposal(_, _, _)
+                    | StateMachineEvent::Prevote(_, _, _)
+                    | StateMachineEvent::Precommit(_, _, _) => {
                         events_queue.push_back(e.clone());
                     }
                     StateMachineEvent::Decision(_, _) => {
@@ -156,6 +196,7 @@ impl StateMachine {
                 }
                 output_events.push_back(e);
             }
+            output_events.append(&mut self.schedule_vote_timeouts());
         }
         output_events
     }
@@ -172,20 +213,29 @@ impl StateMachine {
             StateMachineEvent::GetProposal(block_hash, round) => {
                 self.handle_get_proposal(block_hash, round)
             }
-            StateMachineEvent::Proposal(block_hash, round) => {
-                self.handle_proposal(block_hash, round, leader_fn)
+            StateMachineEvent::Proposal(block_hash, round, valid_round) => {
+                self.handle_proposal(block_hash, round, valid_round, leader_fn)
             }
-            StateMachineEvent::Prevote(block_hash, round) => {
-                self.handle_prevote(block_hash, round, leader_fn)
+            StateMachineEvent::Prevote(block_hash, round, voting_power) => {
+                self.handle_prevote(block_hash, round, voting_power, leader_fn)
             }
-            StateMachineEvent::Precommit(block_hash, round) => {
-                self.handle_precommit(block_hash, round, leader_fn)
+            StateMachineEvent::Precommit(block_hash, round, voting_power) => {
+                self.handle_precommit(block_hash, round, voting_power, leader_fn)
             }
             StateMachineEvent::Decision(_, _) => {
                 unimplemented!(
                     "If the caller knows of a decision, it can just drop the state machine."
                 )
             }
+            StateMachineEvent::TimeoutPropose(round) => {
+                self.handle_timeout_propose(round, leader_fn)
+            }
+            StateMachineEvent::TimeoutPrevote(round) => {
+                self.handle_timeout_prevote(round, leader_fn)
+            }
+            StateMachineEvent::TimeoutPrecommit(round) => {
+                self.handle_timeout_precommit(round, leader_fn)
+            }
         }
     }
 
@@ -199,7 +249,7 @@ impl StateMachine {
         assert_eq!(round, self.round);
         self.awaiting_get_proposal = false;
         assert!(block_hash.is_some(), "SHC should pass a valid block hash");
-        VecDeque::from([StateMachineEvent::Proposal(block_hash, round)])
+        VecDeque::from([StateMachineEvent::Proposal(block_hash, round, None)])
     }
 
     // A proposal from a peer (or self) node.
@@ -207,30 +257,73 @@ impl StateMachine {
         &mut self,
         block_hash: Option<BlockHash>,
         round: u32,
+        valid_round: Option<Round>,
         leader_fn: &LeaderFn,
     ) -> VecDeque<StateMachineEvent>
     where
         LeaderFn: Fn(Round) -> ValidatorId,
     {
-        let old = self.proposals.insert(round, block_hash);
+        let old = self.proposals.insert(round, (block_hash, valid_round));
         assert!(old.is_none(), "SHC should handle conflicts & replays");
-        self.process_proposal(block_hash, round, leader_fn)
+        // The votes may have arrived before the proposal (LOC 36 and 49).
+        if round != self.round {
+            return self.check_precommit_quorum(round, leader_fn);
+        }
+        match self.step {
+            Step::Propose => self.process_proposal(round, leader_fn),
+            Step::Prevote => self.check_prevote_quorum(round, leader_fn),
+            Step::Precommit => {
+                let mut output = self.check_prevote_quorum(round, leader_fn);
+                output.append(&mut self.check_precommit_quorum(round, leader_fn));
+                output
+            }
+        }
     }
 
+    // Prevotes on the proposal of the current round, according to the locked value (LOC 22 and 28).
     fn process_proposal<LeaderFn>(
         &mut self,
-        block_hash: Option<BlockHash>,
         round: u32,
         leader_fn: &LeaderFn,
     ) -> VecDeque<StateMachineEvent>
     where
         LeaderFn: Fn(Round) -> ValidatorId,
     {
-        if self.step != Step::Propose {
+        if self.step != Step::Propose || round != self.round {
             return VecDeque::new();
         }
+        let Some(&(block_hash, valid_round)) = self.proposals.get(&round) else {
+            return VecDeque::new();
+        };
 
-        let mut output = VecDeque::from([StateMachineEvent::Prevote(block_hash, round)]);
+        let vote = match (block_hash, valid_round) {
+            // An invalid block.
+            (None, _) => None,
+            (Some(block_hash), None) => match self.locked_value {
+                Some((locked_value, _)) => (locked_value == block_hash).then_some(block_hash),
+                None => Some(block_hash),
+            },
+            (Some(block_hash), Some(valid_round)) => {
+                // A re-proposed block is only accepted once its prevote quorum is seen.
+                let quorum_voting_power = self
+                    .prevotes
+                    .get(&valid_round)
+                    .and_then(|round_votes| round_votes.get(&Some(block_hash)))
+                    .copied()
+                    .unwrap_or(0);
+                if valid_round >= round || quorum_voting_power < self.quorum {
+                    return VecDeque::new();
+                }
+                let is_acceptable = match self.locked_value {
+                    Some((locked_value, locked_round)) => {
+                        locked_round <= valid_round || locked_value == block_hash
+                    }
+                    None => true,
+                };
+                is_acceptable.then_some(block_hash)
+            }
+        };
+        let mut output = VecDeque::from([self.prevote(vote, round)]);
         output.append(&mut self.advance_to_step(Step::Prevote, leader_fn));
         output
     }
@@ -240,16 +333,22 @@ impl StateMachine {
         &mut self,
         block_hash: Option<BlockHash>,
         round: u32,
+        voting_power: VotingPower,
         leader_fn: &LeaderFn,
     ) -> VecDeque<StateMachineEvent>
     where
         LeaderFn: Fn(Round) -> ValidatorId,
     {
-        let prevote_count = self.prevotes.entry(round).or_default().entry(block_hash).or_insert(0);
-        // TODO(matan): Use variable weight.
-        *prevote_count += 1;
+        *self.prevotes.entry(round).or_default().entry(block_hash).or_insert(0) += voting_power;
 
-        if self.step != Step::Prevote || round != self.round {
+        if round > self.round {
+            return self.check_round_skip(round, leader_fn);
+        }
+        // A quorum in an earlier round may complete a re-proposal of the current round (LOC 28).
+        if self.step == Step::Propose {
+            return self.process_proposal(self.round, leader_fn);
+        }
+        if round != self.round {
             return VecDeque::new();
         }
         self.check_prevote_quorum(round, leader_fn)
@@ -260,19 +359,107 @@ impl StateMachine {
         &mut self,
         block_hash: Option<BlockHash>,
         round: u32,
+        voting_power: VotingPower,
         leader_fn: &LeaderFn,
     ) -> VecDeque<StateMachineEvent>
     where
         LeaderFn: Fn(Round) -> ValidatorId,
     {
-        let precommit_count =
-            self.precommits.entry(round).or_default().entry(block_hash).or_insert(0);
-        // TODO(matan): Use variable weight.
-        *precommit_count += 1;
+        *self.precommits.entry(round).or_default().entry(block_hash).or_insert(0) += voting_power;
 
+        if round > self.round {
+            return self.check_round_skip(round, leader_fn);
+        }
         self.check_precommit_quorum(round, leader_fn)
     }
 
+    // LOC 57 in the paper.
+    fn handle_timeout_propose<LeaderFn>(
+        &mut self,
+        round: u32,
+        leader_fn: &LeaderFn,
+    ) -> VecDeque<StateMachineEvent>
+    where
+        LeaderFn: Fn(Round) -> ValidatorId,
+    {
+        if self.step != Step::Propose || round != self.round {
+            return VecDeque::new();
+        }
+        let mut output = VecDeque::from([self.prevote(None, round)]);
+        output.append(&mut self.advance_to_step(Step::Prevote, leader_fn));
+        output
+    }
+
+    // LOC 61 in the paper.
+    fn handle_timeout_prevote<LeaderFn>(
+        &mut self,
+        round: u32,
+        leader_fn: &LeaderFn,
+    ) -> VecDeque<StateMachineEvent>
+    where
+        LeaderFn: Fn(Round) -> ValidatorId,
+    {
+        if self.step != Step::Prevote || round != self.round {
+            return VecDeque::new();
+        }
+        self.send_precommit(None, round, leader_fn)
+    }
+
+    // LOC 65 in the paper.
+    fn handle_timeout_precommit<LeaderFn>(
+        &mut self,
+        round: u32,
+        leader_fn: &LeaderFn,
+    ) -> VecDeque<StateMachineEvent>
+    where
+        LeaderFn: Fn(Round) -> ValidatorId,
+    {
+        if round != self.round {
+            return VecDeque::new();
+        }
+        self.advance_to_round(round + 1, leader_fn)
+    }
+
+    // Requests timeouts for the votes of the current round, once a quorum has voted, regardless of
+    // whether they agree (LOC 34 and 47).
+    fn schedule_vote_timeouts(&mut self) -> VecDeque<StateMachineEvent> {
+        let mut output = VecDeque::new();
+        if self.step == Step::Prevote
+            && !self.prevote_timeout_scheduled
+            && total_votes(&self.prevotes, self.round) >= self.quorum
+        {
+            self.prevote_timeout_scheduled = true;
+            output.push_back(StateMachineEvent::TimeoutPrevote(self.round));
+        }
+        if !self.precommit_timeout_scheduled
+            && total_votes(&self.precommits, self.round) >= self.quorum
+        {
+            self.precommit_timeout_scheduled = true;
+            output.push_back(StateMachineEvent::TimeoutPrecommit(self.round));
+        }
+        output
+    }
+
+    // Skips to a future round once enough validators are known to have reached it, so that at
+    // least one of them is honest (LOC 55).
+    fn check_round_skip<LeaderFn>(
+        &mut self,
+        round: u32,
+        leader_fn: &LeaderFn,
+    ) -> VecDeque<StateMachineEvent>
+    where
+        LeaderFn: Fn(Round) -> ValidatorId,
+    {
+        // A validator votes at most once of each type per round, so the larger voting power is a
+        // lower bound on the voting power of the distinct voters.
+        let voting_power =
+            total_votes(&self.prevotes, round).max(total_votes(&self.precommits, round));
+        if voting_power < self.round_skip_threshold {
+            return VecDeque::new();
+        }
+        self.advance_to_round(round, leader_fn)
+    }
+
     fn advance_to_step<LeaderFn>(
         &mut self,
         step: Step,
@@ -305,18 +492,28 @@ impl StateMachine {
         if *count < self.quorum {
             return VecDeque::new();
         }
-        if block_hash.is_none() {
-            return self.send_precommit(*block_hash, round, leader_fn);
-        }
-        let Some(proposed_value) = self.proposals.get(&round) else {
+        let Some(block_hash) = *block_hash else {
+            // LOC 44.
+            if self.step != Step::Prevote {
+                return VecDeque::new();
+            }
+            return self.send_precommit(None, round, leader_fn);
+        };
+        let Some((proposed_value, _)) = self.proposals.get(&round) else {
             return VecDeque::new();
         };
-        if proposed_value != block_hash {
+        if *proposed_value != Some(block_hash) {
             // TODO(matan): This can be caused by a malicious leader double proposing.
             panic!("Proposal does not match quorum.");
         }
 
-        self.send_precommit(*block_hash, round, leader_fn)
+        // LOC 36.
+        self.valid_value = Some((block_hash, round));
+        if self.step != Step::Prevote {
+            return VecDeque::new();
+        }
+        self.locked_value = Some((block_hash, round));
+        self.send_precommit(Some(block_hash), round, leader_fn)
     }
 
     fn check_precommit_quorum<LeaderFn>(
@@ -333,22 +530,21 @@ impl StateMachine {
         if *count < self.quorum {
             return VecDeque::new();
         }
-        if block_hash.is_none() && round == self.round {
+        let Some(block_hash) = *block_hash else {
+            if round != self.round {
+                // NIL quorum reached on a different round.
+                return VecDeque::new();
+            }
             return self.advance_to_round(round + 1, leader_fn);
-        }
-        let Some(proposed_value) = self.proposals.get(&round) else {
+        };
+        let Some((proposed_value, _)) = self.proposals.get(&round) else {
             return VecDeque::new();
         };
-        if proposed_value != block_hash {
+        if *proposed_value != Some(block_hash) {
             // TODO(matan): This can be caused by a malicious leader double proposing.
             panic!("Proposal does not match quorum.");
         }
-        if let Some(block_hash) = block_hash {
-            VecDeque::from([StateMachineEvent::Decision(*block_hash, round)])
-        } else {
-            // NIL quorum reached on a different round.
-            VecDeque::new()
-        }
+        VecDeque::from([StateMachineEvent::Decision(block_hash, round)])
     }
 
     fn send_precommit<LeaderFn>(
@@ -360,11 +556,16 @@ impl StateMachine {
     where
         LeaderFn: Fn(Round) -> ValidatorId,
     {
-        let mut output = VecDeque::from([StateMachineEvent::Precommit(block_hash, round)]);
+        let mut output =
+            VecDeque::from([StateMachineEvent::Precommit(block_hash, round, self.voting_power)]);
         output.append(&mut self.advance_to_step(Step::Precommit, leader_fn));
         output
     }
 
+    fn prevote(&self, block_hash: Option<BlockHash>, round: u32) -> StateMachineEvent {
+        StateMachineEvent::Prevote(block_hash, round, self.voting_power)
+    }
+
     fn advance_to_round<LeaderFn>(
         &mut self,
         round: u32,
@@ -375,22 +576,37 @@ impl StateMachine {
     {
         self.round = round;
         self.step = Step::Propose;
+        self.prevote_timeout_scheduled = false;
+        self.precommit_timeout_scheduled = false;
         if self.id == leader_fn(self.round) {
+            // LOC 15: a block which may already be decided by others must be re-proposed.
+            if let Some((block_hash, valid_round)) = self.valid_value {
+                return VecDeque::from([StateMachineEvent::Proposal(
+                    Some(block_hash),
+                    round,
+                    Some(valid_round),
+                )]);
+            }
             self.awaiting_get_proposal = true;
-            // TODO(matan): Support re-proposing validValue.
             return VecDeque::from([StateMachineEvent::GetProposal(None, self.round)]);
         }
-        let Some(proposal) = self.proposals.get(&round) else {
-            return VecDeque::new();
-        };
-        self.process_proposal(*proposal, round, leader_fn)
+        let mut output = VecDeque::from([StateMachineEvent::TimeoutPropose(round)]);
+        output.append(&mut self.process_proposal(round, leader_fn));
+        output
     }
 }
 
+fn total_votes(
+    votes: &HashMap<u32, HashMap<Option<BlockHash>, VotingPower>>,
+    round: u32,
+) -> VotingPower {
+    votes.get(&round).map_or(0, |round_votes| round_votes.values().sum())
+}
+
 fn leading_vote(
-    votes: &HashMap<u32, HashMap<Option<BlockHash>, u32>>,
+    votes: &HashMap<u32, HashMap<Option<BlockHash>, VotingPower>>,
     round: u32,
-) -> Option<(&Option<BlockHash>, &u32)> {
+) -> Option<(&Option<BlockHash>, &VotingPower)> {
     // We don't care which value is chosen in the case of a tie, since consensus requires 2/3+1.
     votes.get(&round)?.iter().max_by(|a, b| a.1.cmp(b.1))
 }
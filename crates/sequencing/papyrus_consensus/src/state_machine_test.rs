@@ -1,3 +1,5 @@
+use std::collections::{HashMap, VecDeque};
+
 use lazy_static::lazy_static;
 use starknet_api::block::BlockHash;
 use starknet_types_core::felt::Felt;
@@ -5,49 +7,60 @@ use test_case::test_case;
 
 use super::Round;
 use crate::state_machine::{StateMachine, StateMachineEvent};
-use crate::types::ValidatorId;
+use crate::test_utils::test_validator_set;
+use crate::types::{ValidatorId, VotingPower};
+use crate::validator_set::ValidatorSet;
 
 lazy_static! {
     static ref PROPOSER_ID: ValidatorId = 0_u32.into();
     static ref VALIDATOR_ID: ValidatorId = 1_u32.into();
+    // 4 validators of equal voting power, so a quorum is any 3 of them.
+    static ref VALIDATORS: ValidatorSet = weighted_validators(&[1, 1, 1, 1]);
 }
 
 const BLOCK_HASH: Option<BlockHash> = Some(BlockHash(Felt::ONE));
+const OTHER_BLOCK_HASH: Option<BlockHash> = Some(BlockHash(Felt::TWO));
 const ROUND: Round = 0;
 
 #[test_case(true; "proposer")]
 #[test_case(false; "validator")]
 fn events_arrive_in_ideal_order(is_proposer: bool) {
     let id = if is_proposer { *PROPOSER_ID } else { *VALIDATOR_ID };
-    let mut state_machine = StateMachine::new(id, 4);
+    let mut state_machine = StateMachine::new(id, &VALIDATORS);
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
     if is_proposer {
         assert_eq!(events.pop_front().unwrap(), StateMachineEvent::GetProposal(None, ROUND));
         events = state_machine
             .handle_event(StateMachineEvent::GetProposal(BLOCK_HASH, ROUND), &leader_fn);
-        assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Proposal(BLOCK_HASH, ROUND));
+        assert_eq!(
+            events.pop_front().unwrap(),
+            StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None)
+        );
     } else {
+        assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
         assert!(events.is_empty(), "{:?}", events);
-        events =
-            state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND), &leader_fn);
+        events = state_machine
+            .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), &leader_fn);
     }
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
     assert!(events.is_empty(), "{:?}", events);
 
-    events = state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn);
+    events =
+        state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn);
     assert!(events.is_empty(), "{:?}", events);
 
-    events = state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND));
+    events =
+        state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1));
     assert!(events.is_empty(), "{:?}", events);
 
     events =
-        state_machine.handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn);
+        state_machine.handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn);
     assert!(events.is_empty(), "{:?}", events);
 
     events =
-        state_machine.handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn);
+        state_machine.handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn);
     assert_eq!(
         events.pop_front().unwrap(),
         StateMachineEvent::Decision(BLOCK_HASH.unwrap(), ROUND)
@@ -57,40 +70,47 @@ fn events_arrive_in_ideal_order(is_proposer: bool) {
 
 #[test]
 fn validator_receives_votes_first() {
-    let mut state_machine = StateMachine::new(*VALIDATOR_ID, 4);
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
 
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
     assert!(events.is_empty(), "{:?}", events);
 
     // Receives votes from all the other nodes first (more than minimum for a quorum).
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn),
+            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn),
+            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn),
+            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
+    // A precommit quorum on the round, even without the proposal, starts its precommit timeout.
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPrecommit(ROUND));
     assert!(events.is_empty(), "{:?}", events);
 
     // Finally the proposal arrives.
-    events = state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND));
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1));
     assert_eq!(
         events.pop_front().unwrap(),
         StateMachineEvent::Decision(BLOCK_HASH.unwrap(), ROUND)
@@ -101,89 +121,99 @@ fn validator_receives_votes_first() {
 #[test_case(BLOCK_HASH ; "valid_proposal")]
 #[test_case(None ; "invalid_proposal")]
 fn buffer_events_during_get_proposal(vote: Option<BlockHash>) {
-    let mut state_machine = StateMachine::new(*PROPOSER_ID, 4);
+    let mut state_machine = StateMachine::new(*PROPOSER_ID, &VALIDATORS);
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
     assert_eq!(events.pop_front().unwrap(), StateMachineEvent::GetProposal(None, 0));
     assert!(events.is_empty(), "{:?}", events);
 
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Prevote(vote, ROUND, 1), &leader_fn),
     );
     assert!(events.is_empty(), "{:?}", events);
 
     // Node finishes building the proposal.
     events =
         state_machine.handle_event(StateMachineEvent::GetProposal(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Proposal(BLOCK_HASH, ROUND));
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(vote, ROUND));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(vote, ROUND, 1));
     assert!(events.is_empty(), "{:?}", events);
 }
 
 #[test]
 fn only_send_precommit_with_prevote_quorum_and_proposal() {
-    let mut state_machine = StateMachine::new(*VALIDATOR_ID, 4);
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
     assert!(events.is_empty(), "{:?}", events);
 
     // Receives votes from all the other nodes first (more than minimum for a quorum).
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     assert!(events.is_empty(), "{:?}", events);
 
     // Finally the proposal arrives.
-    events = state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND));
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1));
     assert!(events.is_empty(), "{:?}", events);
 }
 
 #[test]
 fn only_decide_with_prcommit_quorum_and_proposal() {
-    let mut state_machine = StateMachine::new(*VALIDATOR_ID, 4);
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
     assert!(events.is_empty(), "{:?}", events);
 
     // Receives votes from all the other nodes first (more than minimum for a quorum).
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND), &leader_fn),
+        &mut state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn),
+            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND), &leader_fn),
+            .handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn),
     );
     assert!(events.is_empty(), "{:?}", events);
 
     // Finally the proposal arrives.
-    events = state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND));
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1));
     assert_eq!(
         events.pop_front().unwrap(),
         StateMachineEvent::Decision(BLOCK_HASH.unwrap(), ROUND)
@@ -193,29 +223,311 @@ fn only_decide_with_prcommit_quorum_and_proposal() {
 
 #[test]
 fn advance_to_the_next_round() {
-    let mut state_machine = StateMachine::new(*VALIDATOR_ID, 4);
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
 
     let leader_fn = |_: Round| *PROPOSER_ID;
     let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
     assert!(events.is_empty(), "{:?}", events);
 
-    events = state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND), &leader_fn);
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND));
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND, 1), &leader_fn),
     );
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND, 1), &leader_fn),
     );
     assert_eq!(state_machine.round, ROUND);
     events.append(
         &mut state_machine
-            .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND + 1), &leader_fn),
+            .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND + 1, None), &leader_fn),
     );
     assert!(events.is_empty(), "{:?}", events);
     events.append(
-        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND), &leader_fn),
+        &mut state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND, 1), &leader_fn),
     );
     // The Node sends Prevote after advancing to the next round.
-    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND + 1));
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 1, 1));
+}
+
+#[test]
+fn timeouts_vote_nil_and_advance_round() {
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
+    let leader_fn = |_: Round| *PROPOSER_ID;
+    let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
+
+    // The proposal does not arrive in time.
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPropose(ROUND), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(None, ROUND, 1));
+    assert!(events.is_empty(), "{:?}", events);
+
+    // A quorum prevotes, without agreeing on a value.
+    events =
+        state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn);
+    assert!(events.is_empty(), "{:?}", events);
+    events = state_machine.handle_event(StateMachineEvent::Prevote(None, ROUND, 1), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPrevote(ROUND));
+    assert!(events.is_empty(), "{:?}", events);
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrevote(ROUND), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(None, ROUND, 1));
+    assert!(events.is_empty(), "{:?}", events);
+
+    // A quorum precommits, without agreeing on a value.
+    events =
+        state_machine.handle_event(StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1), &leader_fn);
+    assert!(events.is_empty(), "{:?}", events);
+    events = state_machine.handle_event(StateMachineEvent::Precommit(None, ROUND, 1), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPrecommit(ROUND));
+    assert!(events.is_empty(), "{:?}", events);
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrecommit(ROUND), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND + 1));
+    assert!(events.is_empty(), "{:?}", events);
+    assert_eq!(state_machine.round(), ROUND + 1);
+
+    // Timeouts of previous rounds are ignored.
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrevote(ROUND), &leader_fn);
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn skip_to_round_reached_by_honest_validator() {
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
+    let leader_fn = |_: Round| *PROPOSER_ID;
+    let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
+
+    // A single validator may be faulty.
+    events = state_machine
+        .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 2, 1), &leader_fn);
+    assert!(events.is_empty(), "{:?}", events);
+    assert_eq!(state_machine.round(), ROUND);
+
+    events = state_machine
+        .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 2, 1), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND + 2));
+    assert!(events.is_empty(), "{:?}", events);
+    assert_eq!(state_machine.round(), ROUND + 2);
+}
+
+#[test]
+fn locked_validator_prevotes_nil_on_another_block() {
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
+    let leader_fn = |_: Round| *PROPOSER_ID;
+    let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
+
+    // Lock on the block of the first round.
+    lock_on_block(&mut state_machine, &leader_fn);
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrecommit(ROUND), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND + 1));
+    assert!(events.is_empty(), "{:?}", events);
+
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(OTHER_BLOCK_HASH, ROUND + 1, None), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(None, ROUND + 1, 1));
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn leader_re_proposes_valid_value() {
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
+    let leader_fn = |round: Round| if round == ROUND { *PROPOSER_ID } else { *VALIDATOR_ID };
+    let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
+
+    lock_on_block(&mut state_machine, &leader_fn);
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrecommit(ROUND), &leader_fn);
+    // Instead of asking for a new block, the leader proposes the block which had a quorum.
+    assert_eq!(
+        events.pop_front().unwrap(),
+        StateMachineEvent::Proposal(BLOCK_HASH, ROUND + 1, Some(ROUND))
+    );
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 1, 1));
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+#[test]
+fn re_proposal_is_accepted_once_its_quorum_is_seen() {
+    let mut state_machine = StateMachine::new(*VALIDATOR_ID, &VALIDATORS);
+    let leader_fn = |_: Round| *PROPOSER_ID;
+    let mut events = state_machine.start(&leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND));
+    events = state_machine.handle_event(StateMachineEvent::TimeoutPrecommit(ROUND), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::TimeoutPropose(ROUND + 1));
+
+    // The validator missed the prevotes of the first round.
+    events = state_machine
+        .handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND + 1, Some(ROUND)), &leader_fn);
+    assert!(events.is_empty(), "{:?}", events);
+
+    for _ in 0..2 {
+        events = state_machine
+            .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn);
+        assert!(events.is_empty(), "{:?}", events);
+    }
+    events =
+        state_machine.handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), &leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND + 1, 1));
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+// Receives the proposal of the first round and a prevote quorum on it, which locks the validator.
+fn lock_on_block<LeaderFn>(state_machine: &mut StateMachine, leader_fn: &LeaderFn)
+where
+    LeaderFn: Fn(Round) -> ValidatorId,
+{
+    let mut events =
+        state_machine.handle_event(StateMachineEvent::Proposal(BLOCK_HASH, ROUND, None), leader_fn);
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1));
+    for _ in 0..2 {
+        events.append(
+            &mut state_machine
+                .handle_event(StateMachineEvent::Prevote(BLOCK_HASH, ROUND, 1), leader_fn),
+        );
+    }
+    assert_eq!(events.pop_front().unwrap(), StateMachineEvent::Precommit(BLOCK_HASH, ROUND, 1));
+    assert!(events.is_empty(), "{:?}", events);
+}
+
+// Validator `i` has the `i`-th voting power.
+fn weighted_validators(voting_powers: &[VotingPower]) -> ValidatorSet {
+    test_validator_set(
+        voting_powers.iter().enumerate().map(|(i, power)| (ValidatorId::from(i as u64), *power)),
+    )
+}
+
+// A network of state machines, where the dropped validators neither send nor receive messages.
+// Timeouts only expire once no messages are in flight, as if the network was slower than them.
+struct SimulatedNetwork {
+    validators: Vec<ValidatorId>,
+    state_machines: HashMap<ValidatorId, StateMachine>,
+    messages: VecDeque<(ValidatorId, StateMachineEvent)>,
+    timeouts: VecDeque<(ValidatorId, StateMachineEvent)>,
+    decisions: HashMap<ValidatorId, (BlockHash, Round)>,
+}
+
+impl SimulatedNetwork {
+    fn new(validator_set: &ValidatorSet, dropped: &[ValidatorId]) -> Self {
+        let validators: Vec<ValidatorId> = validator_set.ids().collect();
+        let state_machines = validators
+            .iter()
+            .filter(|id| !dropped.contains(id))
+            .map(|id| (*id, StateMachine::new(*id, validator_set)))
+            .collect();
+        Self {
+            validators,
+            state_machines,
+            messages: VecDeque::new(),
+            timeouts: VecDeque::new(),
+            decisions: HashMap::new(),
+        }
+    }
+
+    // Runs until every live validator decides, or until no progress is possible.
+    fn run(mut self) -> HashMap<ValidatorId, (BlockHash, Round)> {
+        let validators = self.validators.clone();
+        let leader_fn = |round: Round| validators[round as usize % validators.len()];
+        let live_validators: Vec<ValidatorId> = self.state_machines.keys().copied().collect();
+        for id in &live_validators {
+            let events = self.state_machines.get_mut(id).unwrap().start(&leader_fn);
+            self.route(*id, events);
+        }
+
+        while self.decisions.len() < self.state_machines.len() {
+            let Some((id, event)) = self.messages.pop_front().or_else(|| self.timeouts.pop_front())
+            else {
+                break;
+            };
+            if self.decisions.contains_key(&id) {
+                continue;
+            }
+            let events = self.state_machines.get_mut(&id).unwrap().handle_event(event, &leader_fn);
+            self.route(id, events);
+        }
+        self.decisions
+    }
+
+    fn route(&mut self, from: ValidatorId, events: VecDeque<StateMachineEvent>) {
+        for event in events {
+            match event {
+                StateMachineEvent::GetProposal(None, round) => {
+                    let proposal = StateMachineEvent::GetProposal(BLOCK_HASH, round);
+                    self.messages.push_back((from, proposal));
+                }
+                StateMachineEvent::Proposal(_, _, _)
+                | StateMachineEvent::Prevote(..)
+                | StateMachineEvent::Precommit(..) => {
+                    for id in self.state_machines.keys().filter(|id| **id != from) {
+                        self.messages.push_back((*id, event.clone()));
+                    }
+                }
+                StateMachineEvent::TimeoutPropose(_)
+                | StateMachineEvent::TimeoutPrevote(_)
+                | StateMachineEvent::TimeoutPrecommit(_) => self.timeouts.push_back((from, event)),
+                StateMachineEvent::Decision(block_hash, round) => {
+                    self.decisions.insert(from, (block_hash, round));
+                }
+                StateMachineEvent::GetProposal(Some(_), _) => {
+                    panic!("The state machine should not send a block hash in GetProposal")
+                }
+            }
+        }
+    }
+}
+
+#[test_case(&[] ; "no_dropped_validators")]
+#[test_case(&[3_u32.into()] ; "dropped_validator")]
+fn simulated_network_decides_in_first_round(dropped: &[ValidatorId]) {
+    let decisions = SimulatedNetwork::new(&VALIDATORS, dropped).run();
+
+    assert_eq!(decisions.len(), 4 - dropped.len());
+    assert!(decisions.values().all(|decision| *decision == (BLOCK_HASH.unwrap(), ROUND)));
+}
+
+#[test]
+fn simulated_network_skips_silent_proposer() {
+    // The proposer of the first round.
+    let dropped = [*PROPOSER_ID];
+
+    let decisions = SimulatedNetwork::new(&VALIDATORS, &dropped).run();
+
+    assert_eq!(decisions.len(), 3);
+    assert!(decisions.values().all(|decision| *decision == (BLOCK_HASH.unwrap(), ROUND + 1)));
+}
+
+#[test]
+fn simulated_network_without_quorum_does_not_decide() {
+    let dropped = [*PROPOSER_ID, *VALIDATOR_ID];
+
+    let decisions = SimulatedNetwork::new(&VALIDATORS, &dropped).run();
+
+    assert!(decisions.is_empty(), "{:?}", decisions);
+}
+
+#[test]
+fn simulated_network_without_quorum_of_voting_power_does_not_decide() {
+    // Most of the validators are live, but they hold only half of the voting power.
+    let validators = weighted_validators(&[1, 1, 1, 3]);
+    let dropped = [3_u32.into()];
+
+    let decisions = SimulatedNetwork::new(&validators, &dropped).run();
+
+    assert!(decisions.is_empty(), "{:?}", decisions);
+}
+
+#[test]
+fn simulated_network_decides_with_quorum_of_voting_power() {
+    // Only half of the validators are live, but they hold more than 2/3 of the voting power. The
+    // proposers of the first two rounds are dropped.
+    let validators = weighted_validators(&[1, 1, 1, 4]);
+    let dropped = [0_u32.into(), 1_u32.into()];
+
+    let decisions = SimulatedNetwork::new(&validators, &dropped).run();
+
+    assert_eq!(decisions.len(), 2);
+    assert!(decisions.values().all(|decision| *decision == (BLOCK_HASH.unwrap(), ROUND + 2)));
 }
@@ -3,8 +3,23 @@ use futures::channel::{mpsc, oneshot};
 use mockall::mock;
 use papyrus_protobuf::consensus::ConsensusMessage;
 use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::core::{ChainId, Nonce};
+use starknet_api::transaction::{L1HandlerTransaction, Transaction};
+use starknet_types_core::felt::Felt;
 
-use crate::types::{ConsensusBlock, ConsensusContext, ConsensusError, ProposalInit, ValidatorId};
+use crate::signing::ConsensusSigner;
+use crate::types::{
+    ConsensusBlock,
+    ConsensusContext,
+    ConsensusError,
+    ProposalInit,
+    Round,
+    ValidatorId,
+    VotingPower,
+};
+use crate::validator_set::{Validator, ValidatorSet};
+use crate::wal::ConsensusWal;
+use crate::TransactionBatchWrapper;
 
 /// Define a consensus block which can be used to enable auto mocking Context.
 #[derive(Debug, PartialEq, Clone)]
@@ -45,9 +60,14 @@ mock! {
             content: mpsc::Receiver<u32>
         ) -> oneshot::Receiver<TestBlock>;
 
-        async fn validators(&self, height: BlockNumber) -> Vec<ValidatorId>;
+        async fn validators(&self, height: BlockNumber) -> Result<ValidatorSet, ConsensusError>;
 
-        fn proposer(&self, validators: &[ValidatorId], height: BlockNumber) -> ValidatorId;
+        fn proposer(
+            &self,
+            validators: &ValidatorSet,
+            height: BlockNumber,
+            round: Round,
+        ) -> ValidatorId;
 
         async fn broadcast(&mut self, message: ConsensusMessage) -> Result<(), ConsensusError>;
 
@@ -57,5 +77,68 @@ mock! {
             content_receiver: mpsc::Receiver<u32>,
             fin_receiver: oneshot::Receiver<BlockHash>,
         ) -> Result<(), ConsensusError>;
+
+        async fn sync_to(&mut self, height: BlockNumber) -> Result<(), ConsensusError>;
+
+        async fn synced_block_hash(
+            &self,
+            height: BlockNumber,
+        ) -> Result<Option<BlockHash>, ConsensusError>;
     }
 }
+
+// Test chunks are sent over the network as L1 handler transactions, whose nonce is the chunk.
+impl From<TransactionBatchWrapper> for Vec<u32> {
+    fn from(val: TransactionBatchWrapper) -> Self {
+        val.0
+            .into_iter()
+            .map(|tx| match tx {
+                Transaction::L1Handler(tx) => {
+                    let nonce = tx.nonce.0.to_bytes_be();
+                    u32::from_be_bytes(nonce[28..].try_into().expect("A nonce has 32 bytes."))
+                }
+                _ => panic!("Test chunks are sent as L1 handler transactions, got {tx:?}"),
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<u32>> for TransactionBatchWrapper {
+    fn from(chunks: Vec<u32>) -> Self {
+        TransactionBatchWrapper(chunks.into_iter().map(test_transaction).collect())
+    }
+}
+
+/// The transaction which carries the given test chunk over the network.
+pub fn test_transaction(chunk: u32) -> Transaction {
+    Transaction::L1Handler(L1HandlerTransaction {
+        nonce: Nonce(chunk.into()),
+        ..Default::default()
+    })
+}
+
+/// The chain on which the messages of the tests are signed.
+pub const TEST_CHAIN_ID: ChainId = ChainId::Sepolia;
+
+/// The signer of a validator in tests, whose private key is derived from the validator's ID.
+pub fn test_signer(validator: ValidatorId) -> ConsensusSigner {
+    ConsensusSigner::new(*validator.0.key() + Felt::ONE, TEST_CHAIN_ID)
+}
+
+/// A validator set of the given validators, each with its `test_signer`'s public key.
+pub fn test_validator_set(
+    voting_powers: impl IntoIterator<Item = (ValidatorId, VotingPower)>,
+) -> ValidatorSet {
+    ValidatorSet::new(voting_powers.into_iter().map(|(id, voting_power)| Validator {
+        id,
+        voting_power,
+        public_key: test_signer(id).public_key(),
+    }))
+    .unwrap()
+}
+
+/// A write-ahead log in a new temporary directory, which is removed once the returned guard drops.
+pub fn test_wal() -> (ConsensusWal, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    (ConsensusWal::open(dir.path().to_path_buf()).unwrap(), dir)
+}
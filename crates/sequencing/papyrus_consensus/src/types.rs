@@ -2,10 +2,12 @@ use std::fmt::Debug;
 
 use async_trait::async_trait;
 use futures::channel::{mpsc, oneshot};
-use papyrus_protobuf::consensus::{ConsensusMessage, Vote};
+use papyrus_protobuf::consensus::{ConsensusMessage, EquivocationEvidence, Vote};
 use starknet_api::block::{BlockHash, BlockNumber};
 use starknet_api::core::ContractAddress;
 
+use crate::validator_set::ValidatorSet;
+
 /// Used to identify the node by consensus.
 /// 1. This ID is derived from the id registered with Starknet's L2 staking contract.
 /// 2. We must be able to derive the public key associated with this ID for the sake of validating
@@ -13,6 +15,7 @@ use starknet_api::core::ContractAddress;
 // TODO(matan): Determine the actual type of NodeId.
 pub type ValidatorId = ContractAddress;
 pub type Round = u32;
+pub type VotingPower = u64;
 
 /// Interface that any concrete block type must implement to be used by consensus.
 ///
@@ -29,7 +32,8 @@ pub trait ConsensusBlock: Send {
     /// The chunks of content returned when iterating the proposal.
     // In practice I expect this to match the type sent to the network
     // (papyrus_protobuf::ConsensusMessage), and not to be specific to just the block's content.
-    type ProposalChunk;
+    // The chunks of a received proposal are passed on to its validation by a separate task.
+    type ProposalChunk: Send + 'static;
     /// Iterator for accessing the proposal's content.
     // An associated type is used instead of returning `impl Iterator` due to object safety.
     type ProposalIter: Iterator<Item = Self::ProposalChunk>;
@@ -114,14 +118,19 @@ pub trait ConsensusContext {
     ) -> oneshot::Receiver<Self::Block>;
 
     /// Get the set of validators for a given height. These are the nodes that can propose and vote
-    /// on blocks.
-    // TODO(matan): We expect this to change in the future to BTreeMap. Why?
-    // 1. Map - The nodes will have associated information (e.g. voting weight).
-    // 2. BTreeMap - We want a stable ordering of the nodes for deterministic leader selection.
-    async fn validators(&self, height: BlockNumber) -> Vec<ValidatorId>;
-
-    /// Calculates the ID of the Proposer based on the inputs.
-    fn proposer(&self, validators: &[ValidatorId], height: BlockNumber) -> ValidatorId;
+    /// on blocks, each with its voting power and the public key with which it signs its messages.
+    /// Fails if the validators of the height cannot be known, e.g. if it is not defined or its
+    /// state was pruned.
+    async fn validators(&self, height: BlockNumber) -> Result<ValidatorSet, ConsensusError>;
+
+    /// Calculates the ID of the Proposer based on the inputs. All nodes must agree on it, and it
+    /// should rotate among the validators across heights and rounds.
+    fn proposer(
+        &self,
+        validators: &ValidatorSet,
+        height: BlockNumber,
+        round: Round,
+    ) -> ValidatorId;
 
     async fn broadcast(&mut self, message: ConsensusMessage) -> Result<(), ConsensusError>;
 
@@ -134,10 +143,26 @@ pub trait ConsensusContext {
         content_receiver: mpsc::Receiver<<Self::Block as ConsensusBlock>::ProposalChunk>,
         fin_receiver: oneshot::Receiver<BlockHash>,
     ) -> Result<(), ConsensusError>;
+
+    /// This function is called by consensus once it falls behind the other validators, which
+    /// already decided the heights it has yet to decide. It returns once the node caught up on the
+    /// blocks they decided, through the sync, and consensus can resume at `height`.
+    ///
+    /// Params:
+    /// - `height`: The height at which consensus resumes. All the blocks below it are synced.
+    async fn sync_to(&mut self, height: BlockNumber) -> Result<(), ConsensusError>;
+
+    /// The hash of the block of the given height, if the sync already wrote it. Consensus checks
+    /// it against the precommits which decided the height.
+    async fn synced_block_hash(
+        &self,
+        height: BlockNumber,
+    ) -> Result<Option<BlockHash>, ConsensusError>;
 }
 
 #[derive(PartialEq)]
 pub struct Decision<BlockT: ConsensusBlock> {
+    /// The signed precommits for the block, which serve as its commit certificate.
     pub precommits: Vec<Vote>,
     pub block: BlockT,
 }
@@ -154,7 +179,10 @@ impl<BlockT: ConsensusBlock> Debug for Decision<BlockT> {
 #[derive(PartialEq, Debug, Clone)]
 pub struct ProposalInit {
     pub height: BlockNumber,
+    pub round: Round,
     pub proposer: ValidatorId,
+    /// The round in which the proposed block had a prevote quorum, if it is re-proposed.
+    pub valid_round: Option<Round>,
 }
 
 #[derive(thiserror::Error, PartialEq, Debug)]
@@ -165,6 +193,21 @@ pub enum ConsensusError {
     InvalidProposal(ValidatorId, BlockNumber, String),
     #[error(transparent)]
     SendError(#[from] mpsc::SendError),
-    #[error("Conflicting messages for block {0}. Old: {1:?}, New: {2:?}")]
-    Equivocation(BlockNumber, ConsensusMessage, ConsensusMessage),
+    #[error("A validator sent conflicting votes: {0:?}")]
+    Equivocation(Box<EquivocationEvidence>),
+    #[error("The vote {1:?} contradicts {0:?}, which this node sent before restarting.")]
+    ContradictsOwnVote(Box<Vote>, Box<Vote>),
+    #[error(
+        "The proposal of round {0} for block {2:?} contradicts the one for block {1:?}, which \
+         this node sent before restarting."
+    )]
+    ContradictsOwnProposal(Round, BlockHash, BlockHash),
+    #[error("Failed to access the consensus write-ahead log: {0}")]
+    WriteAheadLog(String),
+    #[error("Failed to get the validators of height {0}: {1}")]
+    Validators(BlockNumber, String),
+    #[error("Failed to read the synced block of height {0}: {1}")]
+    SyncedBlock(BlockNumber, String),
+    #[error("The synced block {2:?} of height {0} is not the decided block {1:?}.")]
+    SyncedBlockMismatch(BlockNumber, BlockHash, BlockHash),
 }
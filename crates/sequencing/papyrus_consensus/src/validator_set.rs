@@ -0,0 +1,311 @@
+//! The validators of each height, along with their voting power, and the providers which read them
+//! from a static file or from the state of the staking contract in storage.
+
+#[cfg(test)]
+#[path = "validator_set_test.rs"]
+mod validator_set_test;
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use papyrus_storage::state::StateStorageReader;
+use papyrus_storage::{StorageError, StorageReader};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::BlockNumber;
+use starknet_api::core::{ContractAddress, PatriciaKey, L2_ADDRESS_UPPER_BOUND};
+use starknet_api::crypto::utils::PublicKey;
+use starknet_api::hash::starknet_keccak_hash;
+use starknet_api::state::{StateNumber, StorageKey};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, StarkHash};
+
+use crate::types::{Round, ValidatorId, VotingPower};
+
+/// A validator, as it appears in the validator set of a height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validator {
+    pub id: ValidatorId,
+    pub voting_power: VotingPower,
+    /// The key with which the validator signs its consensus messages.
+    pub public_key: PublicKey,
+}
+
+/// The validators of a single height. Iteration is ordered by the validators' IDs, so that it is
+/// the same for all nodes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidatorSet {
+    validators: BTreeMap<ValidatorId, Validator>,
+    total_voting_power: VotingPower,
+}
+
+impl ValidatorSet {
+    /// Validators without voting power are left out, as they cannot take part in consensus.
+    ///
+    /// Fails if the total voting power does not fit in a [`VotingPower`], so that sums of the
+    /// voting power of any of the validators cannot overflow.
+    pub fn new(validators: impl IntoIterator<Item = Validator>) -> Result<Self, ValidatorSetError> {
+        let validators: BTreeMap<ValidatorId, Validator> = validators
+            .into_iter()
+            .filter(|validator| validator.voting_power > 0)
+            .map(|validator| (validator.id, validator))
+            .collect();
+        let total_voting_power = validators
+            .values()
+            .try_fold(0, |total: VotingPower, validator| total.checked_add(validator.voting_power))
+            .ok_or(ValidatorSetError::TotalVotingPowerOverflow)?;
+        Ok(Self { validators, total_voting_power })
+    }
+
+    pub fn get(&self, id: &ValidatorId) -> Option<&Validator> {
+        self.validators.get(id)
+    }
+
+    /// The voting power of the given validator, which is zero if it is not in the set.
+    pub fn voting_power(&self, id: &ValidatorId) -> VotingPower {
+        self.get(id).map_or(0, |validator| validator.voting_power)
+    }
+
+    pub fn total_voting_power(&self) -> VotingPower {
+        self.total_voting_power
+    }
+
+    /// The voting power required for a quorum, i.e. more than two thirds of the total.
+    pub fn quorum_size(&self) -> VotingPower {
+        // Widened, since twice the total voting power may overflow.
+        let quorum_size = 2 * u128::from(self.total_voting_power) / 3 + 1;
+        VotingPower::try_from(quorum_size)
+            .expect("The quorum size is at most the total voting power, or one if it is zero.")
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = ValidatorId> + '_ {
+        self.validators.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Validator> {
+        self.validators.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// The proposer of the given round of the given height, chosen by a weighted round-robin in
+    /// the style of Tendermint's proposer priority. At each step every validator's priority grows
+    /// by its weight, and the validator with the highest priority proposes and has its priority
+    /// reduced by the total weight. Ties go to the lowest ID.
+    ///
+    /// Priorities start at zero, and the rounds of a height continue the sequence where the height
+    /// starts. Within every total weight consecutive steps, each validator proposes exactly as
+    /// many times as its weight, after which the priorities return to zero. So the proposer
+    /// depends only on the validator set, the height and the round, and all nodes agree on it.
+    ///
+    /// The weights are the voting powers, normalized so that the schedule is short (see
+    /// [`MAX_PROPOSER_SCHEDULE_LENGTH`]).
+    pub fn proposer(&self, height: BlockNumber, round: Round) -> ValidatorId {
+        assert!(!self.is_empty(), "A proposer cannot be chosen from an empty validator set.");
+        let mut priorities: Vec<(&Validator, i128, i128)> = self
+            .proposer_weights()
+            .into_iter()
+            .map(|(validator, weight)| (validator, i128::from(weight), 0))
+            .collect();
+        let total_weight: i128 = priorities.iter().map(|(_, weight, _)| weight).sum();
+        let steps = (i128::from(height.0) + i128::from(round)) % total_weight;
+        let mut proposer = None;
+        for _ in 0..=steps {
+            for (_, weight, priority) in priorities.iter_mut() {
+                *priority += *weight;
+            }
+            let (validator, _, priority) = priorities
+                .iter_mut()
+                .max_by_key(|(validator, _, priority)| (*priority, Reverse(validator.id)))
+                .expect("The validator set is not empty.");
+            *priority -= total_weight;
+            proposer = Some(validator.id);
+        }
+        proposer.expect("At least one step is taken.")
+    }
+
+    // The weight of each validator in the proposer schedule. Common factors of the voting powers
+    // are divided out, which keeps the schedule's proportions exact. If the schedule is still too
+    // long, the weights are scaled down, with every validator keeping a weight of at least one so
+    // that it still proposes.
+    fn proposer_weights(&self) -> Vec<(&Validator, u64)> {
+        let gcd = self
+            .iter()
+            .fold(0, |gcd, validator| greatest_common_divisor(gcd, validator.voting_power));
+        let total_weight = self.total_voting_power / gcd;
+        self.iter()
+            .map(|validator| {
+                let weight = validator.voting_power / gcd;
+                if total_weight <= MAX_PROPOSER_SCHEDULE_LENGTH {
+                    return (validator, weight);
+                }
+                let scaled_weight = u128::from(weight) * u128::from(MAX_PROPOSER_SCHEDULE_LENGTH)
+                    / u128::from(total_weight);
+                let scaled_weight = u64::try_from(scaled_weight)
+                    .expect("A scaled weight is at most the schedule's maximal length.");
+                (validator, scaled_weight.max(1))
+            })
+            .collect()
+    }
+}
+
+/// The maximal length of the proposer schedule, after which it repeats, not counting a step for
+/// each validator whose weight is rounded up to one. Choosing a proposer is linear in the
+/// schedule's length, so voting power in fine units, e.g. the staked amount, is scaled to it.
+pub const MAX_PROPOSER_SCHEDULE_LENGTH: u64 = 1 << 12;
+
+fn greatest_common_divisor(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ValidatorSetError {
+    #[error("Failed to read the validator sets file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse the validator sets file: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("No validator set is defined for height {0}.")]
+    MissingHeight(BlockNumber),
+    #[error("The total voting power of the validator set overflows.")]
+    TotalVotingPowerOverflow,
+    #[error("The state before height {0} has not been synced yet.")]
+    StateNotSynced(BlockNumber),
+    #[error("The staking contract stores an invalid {field}: {value:#x}.")]
+    InvalidStakingContractValue { field: &'static str, value: Felt },
+    #[error("The staking contract stores {n_validators} validators, above the maximum of {max}.")]
+    TooManyValidators { n_validators: u64, max: u64 },
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// Provides the validator set of each height. The validator set may change between heights.
+pub trait ValidatorSetProvider: Send + Sync {
+    fn validator_set(&self, height: BlockNumber) -> Result<ValidatorSet, ValidatorSetError>;
+}
+
+/// Validator sets which are known in advance, each starting at a given height and lasting until
+/// the next one starts.
+#[derive(Debug, Clone, Default)]
+pub struct StaticValidatorSetProvider {
+    validator_sets: BTreeMap<BlockNumber, ValidatorSet>,
+}
+
+// An entry of the validator sets file.
+#[derive(Deserialize)]
+struct ValidatorSetEntry {
+    start_height: BlockNumber,
+    validators: Vec<Validator>,
+}
+
+impl StaticValidatorSetProvider {
+    pub fn new(validator_sets: BTreeMap<BlockNumber, ValidatorSet>) -> Self {
+        Self { validator_sets }
+    }
+
+    /// Reads the validator sets from a JSON file of the form:
+    /// `[{"start_height": 0, "validators": [{"id": "0x1", "voting_power": 1, "public_key":
+    /// "0x..."}]}]`.
+    pub fn from_file(path: &Path) -> Result<Self, ValidatorSetError> {
+        let entries: Vec<ValidatorSetEntry> =
+            serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        Ok(Self::new(
+            entries
+                .into_iter()
+                .map(|entry| Ok((entry.start_height, ValidatorSet::new(entry.validators)?)))
+                .collect::<Result<_, ValidatorSetError>>()?,
+        ))
+    }
+}
+
+impl ValidatorSetProvider for StaticValidatorSetProvider {
+    fn validator_set(&self, height: BlockNumber) -> Result<ValidatorSet, ValidatorSetError> {
+        self.validator_sets
+            .range(..=height)
+            .next_back()
+            .map(|(_, validator_set)| validator_set.clone())
+            .ok_or(ValidatorSetError::MissingHeight(height))
+    }
+}
+
+// The storage variables of the staking contract. The number of validators is stored in
+// `n_validators`, and the i-th validator in `validators(i)`, as the struct
+// `(id, voting_power, public_key)` in consecutive keys.
+const N_VALIDATORS_VAR: &str = "n_validators";
+const VALIDATORS_VAR: &str = "validators";
+
+/// The maximal number of validators read from the staking contract. Reading the validators is
+/// linear in their number, which is stored in the chain's state, so it is bounded.
+pub const MAX_VALIDATORS: u64 = 1 << 10;
+
+/// Reads the validator set of each height from the storage of the staking contract, as it was
+/// right before the height.
+pub struct StorageValidatorSetProvider {
+    storage_reader: StorageReader,
+    staking_contract_address: ContractAddress,
+}
+
+impl StorageValidatorSetProvider {
+    pub fn new(storage_reader: StorageReader, staking_contract_address: ContractAddress) -> Self {
+        Self { storage_reader, staking_contract_address }
+    }
+}
+
+impl ValidatorSetProvider for StorageValidatorSetProvider {
+    fn validator_set(&self, height: BlockNumber) -> Result<ValidatorSet, ValidatorSetError> {
+        let txn = self.storage_reader.begin_ro_txn()?;
+        if txn.get_state_marker()? < height {
+            return Err(ValidatorSetError::StateNotSynced(height));
+        }
+        let state_reader = txn.get_state_reader()?;
+        let state_number = StateNumber::right_before_block(height);
+        let read = |key: &StorageKey| {
+            state_reader.get_storage_at(state_number, &self.staking_contract_address, key)
+        };
+
+        let n_validators = read(&storage_var_address(N_VALIDATORS_VAR, &[], 0))?;
+        let n_validators = felt_to_u64(n_validators, "number of validators")?;
+        if n_validators > MAX_VALIDATORS {
+            return Err(ValidatorSetError::TooManyValidators { n_validators, max: MAX_VALIDATORS });
+        }
+        let mut validators = Vec::new();
+        for index in 0..n_validators {
+            let index = Felt::from(index);
+            let id = read(&storage_var_address(VALIDATORS_VAR, &[index], 0))?;
+            let id = ContractAddress::try_from(id).map_err(|_| {
+                ValidatorSetError::InvalidStakingContractValue { field: "validator id", value: id }
+            })?;
+            let voting_power = read(&storage_var_address(VALIDATORS_VAR, &[index], 1))?;
+            let voting_power = felt_to_u64(voting_power, "voting power")?;
+            let public_key = PublicKey(read(&storage_var_address(VALIDATORS_VAR, &[index], 2))?);
+            validators.push(Validator { id, voting_power, public_key });
+        }
+        ValidatorSet::new(validators)
+    }
+}
+
+// The key of a member of a storage variable, which is at the given offset from the variable's
+// address (see `get_storage_var_address` in the blockifier).
+fn storage_var_address(name: &str, args: &[Felt], offset: u8) -> StorageKey {
+    let address = args
+        .iter()
+        .fold(starknet_keccak_hash(name.as_bytes()), |address, arg| Pedersen::hash(&address, arg));
+    let (_, address) = address.div_rem(&L2_ADDRESS_UPPER_BOUND);
+    StorageKey(
+        PatriciaKey::try_from(address + Felt::from(offset))
+            .expect("A storage variable's address should be a valid storage key."),
+    )
+}
+
+fn felt_to_u64(value: Felt, field: &'static str) -> Result<u64, ValidatorSetError> {
+    u64::try_from(value.to_biguint())
+        .map_err(|_| ValidatorSetError::InvalidStakingContractValue { field, value })
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use indexmap::IndexMap;
+use papyrus_storage::state::StateStorageWriter;
+use papyrus_storage::test_utils::get_test_storage;
+use starknet_api::block::BlockNumber;
+use starknet_api::core::ContractAddress;
+use starknet_api::state::ThinStateDiff;
+use starknet_types_core::felt::Felt;
+use tempfile::NamedTempFile;
+use test_case::test_case;
+
+use super::{
+    storage_var_address,
+    StaticValidatorSetProvider,
+    StorageValidatorSetProvider,
+    Validator,
+    ValidatorSet,
+    ValidatorSetError,
+    ValidatorSetProvider,
+    MAX_PROPOSER_SCHEDULE_LENGTH,
+    MAX_VALIDATORS,
+    N_VALIDATORS_VAR,
+    VALIDATORS_VAR,
+};
+use crate::test_utils::{test_signer, test_validator_set};
+use crate::types::{ValidatorId, VotingPower};
+
+const STAKING_CONTRACT_ADDRESS: u64 = 0x100;
+
+fn validators(voting_powers: &[(u64, VotingPower)]) -> ValidatorSet {
+    test_validator_set(voting_powers.iter().map(|(id, power)| (ValidatorId::from(*id), *power)))
+}
+
+#[test]
+fn validator_set_voting_power() {
+    let validator_set = validators(&[(1, 3), (2, 1), (3, 0)]);
+
+    assert_eq!(validator_set.total_voting_power(), 4);
+    assert_eq!(validator_set.voting_power(&1_u64.into()), 3);
+    // Validators without voting power are not part of the set.
+    assert_eq!(validator_set.voting_power(&3_u64.into()), 0);
+    assert!(validator_set.get(&3_u64.into()).is_none());
+    assert_eq!(validator_set.ids().collect::<Vec<_>>(), vec![1_u64.into(), 2_u64.into()]);
+}
+
+#[test]
+fn quorum_size_of_large_voting_power() {
+    let validator_set = validators(&[(1, VotingPower::MAX / 2), (2, VotingPower::MAX / 2)]);
+
+    assert_eq!(validator_set.total_voting_power(), VotingPower::MAX - 1);
+    // More than two thirds of 18_446_744_073_709_551_614.
+    assert_eq!(validator_set.quorum_size(), 12_297_829_382_473_034_410);
+}
+
+#[test]
+fn validator_set_with_overflowing_voting_power_is_rejected() {
+    let public_key = test_signer(1_u64.into()).public_key();
+    let validators = [
+        Validator { id: 1_u64.into(), voting_power: VotingPower::MAX, public_key },
+        Validator { id: 2_u64.into(), voting_power: 1, public_key },
+    ];
+
+    assert!(matches!(
+        ValidatorSet::new(validators),
+        Err(ValidatorSetError::TotalVotingPowerOverflow)
+    ));
+}
+
+#[test]
+fn static_provider_uses_latest_validator_set() {
+    let provider = StaticValidatorSetProvider::new(BTreeMap::from([
+        (BlockNumber(1), validators(&[(1, 1)])),
+        (BlockNumber(5), validators(&[(1, 1), (2, 2)])),
+    ]));
+
+    assert!(matches!(
+        provider.validator_set(BlockNumber(0)),
+        Err(ValidatorSetError::MissingHeight(BlockNumber(0)))
+    ));
+    assert_eq!(provider.validator_set(BlockNumber(1)).unwrap(), validators(&[(1, 1)]));
+    assert_eq!(provider.validator_set(BlockNumber(4)).unwrap(), validators(&[(1, 1)]));
+    assert_eq!(provider.validator_set(BlockNumber(7)).unwrap(), validators(&[(1, 1), (2, 2)]));
+}
+
+#[test]
+fn static_provider_from_file() {
+    let public_key = test_signer(1_u64.into()).public_key();
+    let mut file = NamedTempFile::new().unwrap();
+    let content = serde_json::json!([
+        {
+            "start_height": 0,
+            "validators": [{"id": "0x1", "voting_power": 2, "public_key": public_key}],
+        },
+    ]);
+    file.write_all(content.to_string().as_bytes()).unwrap();
+
+    let provider = StaticValidatorSetProvider::from_file(file.path()).unwrap();
+
+    let expected =
+        ValidatorSet::new([Validator { id: 1_u64.into(), voting_power: 2, public_key }]).unwrap();
+    assert_eq!(provider.validator_set(BlockNumber(3)).unwrap(), expected);
+}
+
+// The storage diff which sets the staking contract's validators.
+fn staking_contract_diff(validator_set: &ValidatorSet) -> ThinStateDiff {
+    let mut storage = IndexMap::from([(
+        storage_var_address(N_VALIDATORS_VAR, &[], 0),
+        Felt::from(validator_set.len()),
+    )]);
+    for (index, validator) in validator_set.iter().enumerate() {
+        let index = Felt::from(index);
+        let values = [*validator.id.0.key(), validator.voting_power.into(), validator.public_key.0];
+        for (offset, value) in (0..).zip(values) {
+            storage.insert(storage_var_address(VALIDATORS_VAR, &[index], offset), value);
+        }
+    }
+    ThinStateDiff {
+        storage_diffs: IndexMap::from([(ContractAddress::from(STAKING_CONTRACT_ADDRESS), storage)]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn storage_provider_reads_staking_contract() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let first_validator_set = validators(&[(1, 1), (2, 1)]);
+    let second_validator_set = validators(&[(1, 1), (2, 5), (3, 2)]);
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), staking_contract_diff(&first_validator_set))
+        .unwrap()
+        .append_state_diff(BlockNumber(1), staking_contract_diff(&second_validator_set))
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let provider =
+        StorageValidatorSetProvider::new(reader, ContractAddress::from(STAKING_CONTRACT_ADDRESS));
+
+    // The validators of a height are those set by the state before it.
+    assert_eq!(provider.validator_set(BlockNumber(0)).unwrap(), ValidatorSet::default());
+    assert_eq!(provider.validator_set(BlockNumber(1)).unwrap(), first_validator_set);
+    assert_eq!(provider.validator_set(BlockNumber(2)).unwrap(), second_validator_set);
+    assert!(matches!(
+        provider.validator_set(BlockNumber(3)),
+        Err(ValidatorSetError::StateNotSynced(BlockNumber(3)))
+    ));
+}
+
+#[test]
+fn storage_provider_rejects_too_many_validators() {
+    let ((reader, mut writer), _temp_dir) = get_test_storage();
+    let n_validators = storage_var_address(N_VALIDATORS_VAR, &[], 0);
+    let storage = IndexMap::from([(n_validators, Felt::from(u64::MAX))]);
+    let state_diff = ThinStateDiff {
+        storage_diffs: IndexMap::from([(ContractAddress::from(STAKING_CONTRACT_ADDRESS), storage)]),
+        ..Default::default()
+    };
+    writer
+        .begin_rw_txn()
+        .unwrap()
+        .append_state_diff(BlockNumber(0), state_diff)
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    let provider =
+        StorageValidatorSetProvider::new(reader, ContractAddress::from(STAKING_CONTRACT_ADDRESS));
+
+    assert!(matches!(
+        provider.validator_set(BlockNumber(1)),
+        Err(ValidatorSetError::TooManyValidators { n_validators: u64::MAX, max: MAX_VALIDATORS })
+    ));
+}
+
+#[test_case(&[1, 1, 1, 1] ; "equal_voting_power")]
+#[test_case(&[1, 2, 3, 4] ; "weighted_voting_power")]
+fn proposer_rotation_is_fair(voting_powers: &[VotingPower]) {
+    let validator_set = validators(
+        &voting_powers.iter().enumerate().map(|(i, power)| (i as u64, *power)).collect::<Vec<_>>(),
+    );
+    let total_voting_power = validator_set.total_voting_power();
+
+    // Over any `total_voting_power` consecutive heights, each validator proposes as many times as
+    // its voting power.
+    for start in [0, 7, 1000] {
+        let mut proposals = BTreeMap::<ValidatorId, VotingPower>::new();
+        for height in start..start + total_voting_power {
+            *proposals.entry(validator_set.proposer(BlockNumber(height), 0)).or_default() += 1;
+        }
+        for validator in validator_set.iter() {
+            assert_eq!(proposals[&validator.id], validator.voting_power);
+        }
+    }
+}
+
+#[test]
+fn proposer_rotates_with_rounds() {
+    let validator_set = validators(&[(1, 1), (2, 3), (3, 1)]);
+
+    // The rounds of a height continue the rotation from the height's first proposer.
+    for height in 0..10 {
+        assert_eq!(
+            validator_set.proposer(BlockNumber(height), 2),
+            validator_set.proposer(BlockNumber(height + 2), 0)
+        );
+    }
+    // The heaviest validator does not propose twice in a row.
+    let proposers: Vec<ValidatorId> =
+        (0..5).map(|round| validator_set.proposer(BlockNumber(0), round)).collect();
+    assert_eq!(
+        proposers,
+        vec![2_u64.into(), 1_u64.into(), 2_u64.into(), 3_u64.into(), 2_u64.into()]
+    );
+}
+
+#[test]
+fn proposer_of_large_voting_power() {
+    // Voting power given in fine units, e.g. the staked amount.
+    const UNIT: VotingPower = 10_u64.pow(18);
+
+    // Common factors of the voting powers do not change the rotation.
+    let validator_set = validators(&[(1, UNIT), (2, 2 * UNIT), (3, 3 * UNIT)]);
+    let coarse_validator_set = validators(&[(1, 1), (2, 2), (3, 3)]);
+    for height in [0, 1, 7, u64::MAX] {
+        for round in [0, 1, 2, u32::MAX] {
+            assert_eq!(
+                validator_set.proposer(BlockNumber(height), round),
+                coarse_validator_set.proposer(BlockNumber(height), round)
+            );
+        }
+    }
+
+    // Otherwise the voting powers are scaled down, and each validator still proposes in
+    // proportion to its voting power, but at least once per schedule.
+    let validator_set = validators(&[(1, 3 * UNIT), (2, UNIT + 1), (3, 1)]);
+    let mut proposals = BTreeMap::<ValidatorId, u64>::new();
+    for height in 0..MAX_PROPOSER_SCHEDULE_LENGTH {
+        *proposals.entry(validator_set.proposer(BlockNumber(height), 0)).or_default() += 1;
+    }
+    let expected_proposals = BTreeMap::from([
+        (1_u64.into(), MAX_PROPOSER_SCHEDULE_LENGTH * 3 / 4 - 1),
+        (2_u64.into(), MAX_PROPOSER_SCHEDULE_LENGTH / 4),
+        (3_u64.into(), 1),
+    ]);
+    assert_eq!(proposals, expected_proposals);
+}
+
+#[test]
+fn all_nodes_agree_on_proposer() {
+    let validator_list: Vec<Validator> =
+        validators(&[(1, 2), (2, 5), (3, 1), (4, 3)]).iter().copied().collect();
+    // Nodes may learn the validators in a different order.
+    let validator_set = ValidatorSet::new(validator_list.clone()).unwrap();
+    let reversed_validator_set = ValidatorSet::new(validator_list.into_iter().rev()).unwrap();
+
+    for height in 0..50 {
+        for round in 0..3 {
+            assert_eq!(
+                validator_set.proposer(BlockNumber(height), round),
+                reversed_validator_set.proposer(BlockNumber(height), round)
+            );
+        }
+    }
+}
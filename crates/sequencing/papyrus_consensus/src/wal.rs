@@ -0,0 +1,156 @@
+//! A write-ahead log of the consensus of each height, which lets a validator that restarts in the
+//! middle of a height restore its state, instead of starting the height from scratch and possibly
+//! contradicting the votes it sent before the restart.
+//!
+//! Rather than the state itself (e.g. Tendermint's locked and valid values), the log holds the
+//! inputs which led to it, in order: the messages received from peers, the expired timeouts, the
+//! successful validations of proposals, and the node's own proposals and votes, which are written
+//! before they are sent. Replaying the log restores the state, and the own entries make sure that
+//! the replay does not propose or vote differently than before. Building a proposal again may
+//! produce a different block, so the own proposals are logged along with their content, and sent
+//! again as they are.
+//!
+//! Each height is logged to its own file of JSON lines, which is removed once the height is
+//! decided. Next to them, the log keeps the commit certificates of the decided heights whose blocks
+//! were not yet written by the sync, so that the blocks are checked against them even after a
+//! restart.
+
+#[cfg(test)]
+#[path = "wal_test.rs"]
+mod wal_test;
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use papyrus_protobuf::consensus::{ConsensusMessage, Vote};
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::transaction::Transaction;
+use tracing::warn;
+
+use crate::certificate::CommitCertificate;
+use crate::state_machine::StateMachineEvent;
+use crate::types::{Round, ValidatorId};
+
+const FILE_PREFIX: &str = "height_";
+const FILE_SUFFIX: &str = ".jsonl";
+const CERTIFICATES_FILE: &str = "certificates.json";
+
+/// An entry of the write-ahead log of a height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WalEntry {
+    /// A message received from a peer, once its signature was verified.
+    Received(ConsensusMessage),
+    /// A timeout which expired.
+    Timeout(StateMachineEvent),
+    /// The received proposal of the round, by the given proposer, was validated successfully. Its
+    /// outcome is reproduced by validating the proposal again, at the same point of the replay.
+    ProposalValidated(Round, ValidatorId),
+    /// A proposal of this node. Written before the proposal is completed, i.e. before its fin is
+    /// sent.
+    OwnProposal(OwnProposal),
+    /// A vote of this node. Written before the vote is sent.
+    OwnVote(Vote),
+}
+
+/// A proposal of this node, along with its content, so that the same proposal can be sent again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnProposal {
+    pub round: Round,
+    pub valid_round: Option<Round>,
+    pub block_hash: BlockHash,
+    /// The content of the proposed block, as it is sent to the network.
+    pub content: Vec<Transaction>,
+}
+
+/// The write-ahead log of the consensus, which is kept in a directory with a file per height.
+#[derive(Debug, Clone)]
+pub struct ConsensusWal {
+    dir: PathBuf,
+}
+
+impl ConsensusWal {
+    pub fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The entries logged for the given height, in the order they were appended.
+    pub fn entries(&self, height: BlockNumber) -> io::Result<Vec<WalEntry>> {
+        let path = self.path(height);
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut entries = Vec::new();
+        let mut valid_len = 0;
+        for line in content.split_inclusive(|byte| *byte == b'\n') {
+            // A crash in the middle of an append leaves a partial entry at the end of the file.
+            // Nothing was done based on it, so it is dropped.
+            if !line.ends_with(b"\n") {
+                break;
+            }
+            entries.push(serde_json::from_slice(line)?);
+            valid_len += line.len();
+        }
+        if valid_len < content.len() {
+            warn!("Dropping a partial entry from the write-ahead log of height {height}.");
+            OpenOptions::new().write(true).open(&path)?.set_len(valid_len as u64)?;
+        }
+        Ok(entries)
+    }
+
+    /// Appends an entry to the log of the given height. Returns once the entry is durable, so that
+    /// the node may act upon it.
+    pub fn append(&self, height: BlockNumber, entry: &WalEntry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(self.path(height))?;
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+
+    /// Removes the logs of all heights up to and including the decided one.
+    pub fn prune(&self, decided_height: BlockNumber) -> io::Result<()> {
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            let height = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.strip_prefix(FILE_PREFIX))
+                .and_then(|file_name| file_name.strip_suffix(FILE_SUFFIX))
+                .and_then(|height| height.parse::<u64>().ok());
+            if height.is_some_and(|height| height <= decided_height.0) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The commit certificates of the decided heights whose blocks were not checked yet.
+    pub fn certificates(&self) -> io::Result<Vec<CommitCertificate>> {
+        match fs::read(self.dir.join(CERTIFICATES_FILE)) {
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the kept commit certificates. Returns once the certificates are durable.
+    pub fn set_certificates(&self, certificates: &[CommitCertificate]) -> io::Result<()> {
+        // The certificates are written aside and then moved into place, so that a crash leaves
+        // either the old certificates or the new ones.
+        let temp_path = self.dir.join(format!("{CERTIFICATES_FILE}.tmp"));
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(&serde_json::to_vec(certificates)?)?;
+        file.sync_data()?;
+        fs::rename(temp_path, self.dir.join(CERTIFICATES_FILE))
+    }
+
+    fn path(&self, height: BlockNumber) -> PathBuf {
+        self.dir.join(format!("{FILE_PREFIX}{}{FILE_SUFFIX}", height.0))
+    }
+}
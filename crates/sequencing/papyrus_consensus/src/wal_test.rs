@@ -0,0 +1,96 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use papyrus_protobuf::consensus::{ConsensusMessage, Vote, VoteType};
+use starknet_api::block::{BlockHash, BlockNumber};
+use starknet_api::crypto::utils::Signature;
+use starknet_types_core::felt::Felt;
+
+use super::{OwnProposal, WalEntry, FILE_PREFIX, FILE_SUFFIX};
+use crate::certificate::CommitCertificate;
+use crate::state_machine::StateMachineEvent;
+use crate::test_utils::{test_transaction, test_wal};
+
+fn vote_entry(round: u32) -> WalEntry {
+    WalEntry::Received(ConsensusMessage::Vote(Vote {
+        vote_type: VoteType::Prevote,
+        height: 0,
+        round,
+        block_hash: Some(BlockHash(Felt::ONE)),
+        voter: 1_u32.into(),
+        signature: Signature::default(),
+    }))
+}
+
+#[test]
+fn entries_are_read_in_order() {
+    let (wal, _dir) = test_wal();
+    let entries = vec![
+        vote_entry(0),
+        WalEntry::Timeout(StateMachineEvent::TimeoutPropose(0)),
+        WalEntry::ProposalValidated(0, 2_u32.into()),
+        WalEntry::OwnProposal(OwnProposal {
+            round: 1,
+            valid_round: None,
+            block_hash: BlockHash(Felt::TWO),
+            content: vec![test_transaction(1), test_transaction(2)],
+        }),
+    ];
+    for entry in &entries {
+        wal.append(BlockNumber(0), entry).unwrap();
+    }
+    wal.append(BlockNumber(1), &vote_entry(2)).unwrap();
+
+    assert_eq!(wal.entries(BlockNumber(0)).unwrap(), entries);
+    assert_eq!(wal.entries(BlockNumber(1)).unwrap(), vec![vote_entry(2)]);
+    assert_eq!(wal.entries(BlockNumber(2)).unwrap(), Vec::new());
+}
+
+#[test]
+fn partial_entry_is_dropped() {
+    let (wal, dir) = test_wal();
+    wal.append(BlockNumber(0), &vote_entry(0)).unwrap();
+    // A crash in the middle of writing the second entry.
+    let path = dir.path().join(format!("{FILE_PREFIX}0{FILE_SUFFIX}"));
+    OpenOptions::new().append(true).open(path).unwrap().write_all(b"{\"Received\":").unwrap();
+
+    assert_eq!(wal.entries(BlockNumber(0)).unwrap(), vec![vote_entry(0)]);
+    // Later entries are appended after the last complete one.
+    wal.append(BlockNumber(0), &vote_entry(1)).unwrap();
+    assert_eq!(wal.entries(BlockNumber(0)).unwrap(), vec![vote_entry(0), vote_entry(1)]);
+}
+
+#[test]
+fn prune_removes_decided_heights() {
+    let (wal, _dir) = test_wal();
+    for height in 0..3 {
+        wal.append(BlockNumber(height), &vote_entry(0)).unwrap();
+    }
+
+    wal.prune(BlockNumber(1)).unwrap();
+
+    assert_eq!(wal.entries(BlockNumber(0)).unwrap(), Vec::new());
+    assert_eq!(wal.entries(BlockNumber(1)).unwrap(), Vec::new());
+    assert_eq!(wal.entries(BlockNumber(2)).unwrap(), vec![vote_entry(0)]);
+}
+
+#[test]
+fn certificates_are_replaced_and_kept_across_prunes() {
+    let (wal, _dir) = test_wal();
+    assert_eq!(wal.certificates().unwrap(), Vec::new());
+
+    let WalEntry::Received(ConsensusMessage::Vote(precommit)) = vote_entry(0) else {
+        unreachable!();
+    };
+    let certificate = CommitCertificate {
+        height: BlockNumber(0),
+        block_hash: BlockHash(Felt::ONE),
+        precommits: vec![Vote { vote_type: VoteType::Precommit, ..precommit }],
+    };
+    wal.set_certificates(&[certificate.clone()]).unwrap();
+    wal.prune(BlockNumber(1)).unwrap();
+    assert_eq!(wal.certificates().unwrap(), vec![certificate]);
+
+    wal.set_certificates(&[]).unwrap();
+    assert_eq!(wal.certificates().unwrap(), Vec::new());
+}
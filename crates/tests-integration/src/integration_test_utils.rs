@@ -17,6 +17,7 @@ use starknet_gateway::config::{
     StatelessTransactionValidatorConfig,
 };
 use starknet_gateway::errors::GatewayError;
+use starknet_gateway::transaction_status::AddTransactionReceipt;
 use starknet_mempool_node::config::MempoolNodeConfig;
 use tokio::net::TcpListener;
 
@@ -40,6 +41,8 @@ async fn create_gateway_config() -> GatewayConfig {
         stateless_tx_validator_config,
         stateful_tx_validator_config,
         compiler_config: gateway_compiler_config,
+        rate_limiter_config: Default::default(),
+        ..Default::default()
     }
 }
 
@@ -65,7 +68,8 @@ impl GatewayClient {
         let response = self.add_tx(tx).await;
         assert!(response.status().is_success());
 
-        response.json().await.unwrap()
+        let receipt: AddTransactionReceipt = response.json().await.unwrap();
+        receipt.tx_hash
     }
 
     // TODO: implement when usage eventually arises.
@@ -105,6 +105,9 @@ def run_test(changes_only: bool, commit_id: Optional[str], concurrency: bool):
     # If blockifier is to be tested, add the concurrency flag if requested.
     if concurrency and "blockifier" in tested_packages:
         cmd.extend(["--features", "concurrency"])
+    # If the committer is to be tested, test its mdbx storage as well.
+    if len(tested_packages) == 0 or "committer" in tested_packages:
+        cmd.extend(["--features", "committer/mdbx"])
 
     print("Running tests...")
     print(cmd, flush=True)